@@ -18,7 +18,7 @@ use libafl::{
     },
     observers::StdMapObserver,
     schedulers::QueueScheduler,
-    stages::{mutational::StdMutationalStage, GeneralizationStage},
+    stages::{grimoire_mutational_stage, mutational::StdMutationalStage, GeneralizationStage},
     state::{HasMetadata, StdState},
 };
 
@@ -138,21 +138,18 @@ pub fn main() {
 
     // Setup a mutational stage with a basic bytes mutator
     let mutator = StdScheduledMutator::with_max_stack_pow(havoc_mutations(), 2);
-    let grimoire_mutator = StdScheduledMutator::with_max_stack_pow(
-        tuple_list!(
-            GrimoireExtensionMutator::new(),
-            GrimoireRecursiveReplacementMutator::new(),
-            GrimoireStringReplacementMutator::new(),
-            // give more probability to avoid large inputs
-            GrimoireRandomDeleteMutator::new(),
-            GrimoireRandomDeleteMutator::new(),
-        ),
-        3,
-    );
+    let grimoire_stage = grimoire_mutational_stage(tuple_list!(
+        GrimoireExtensionMutator::new(),
+        GrimoireRecursiveReplacementMutator::new(),
+        GrimoireStringReplacementMutator::new(),
+        // give more probability to avoid large inputs
+        GrimoireRandomDeleteMutator::new(),
+        GrimoireRandomDeleteMutator::new(),
+    ));
     let mut stages = tuple_list!(
         generalization,
         StdMutationalStage::new(mutator),
-        StdMutationalStage::transforming(grimoire_mutator)
+        grimoire_stage
     );
 
     for input in initial_inputs {
@@ -1,5 +1,6 @@
 //! Compression of events passed between a broker and clients.
-//! Currently we use the gzip compression algorithm for its fast decompression performance.
+//! By default, we use the gzip compression algorithm for its fast decompression performance.
+//! With the `lz4` feature, the faster (but less dense) lz4 algorithm is available as well.
 
 use alloc::vec::Vec;
 use core::fmt::Debug;
@@ -54,6 +55,41 @@ impl GzipCompressor {
     }
 }
 
+/// Lz4 compression, faster than [`GzipCompressor`] at the cost of a lower compression ratio.
+#[cfg(feature = "lz4")]
+#[derive(Debug)]
+pub struct Lz4Compressor {
+    /// If less bytes than threshold are being passed to `compress`, the payload is not getting compressed.
+    threshold: usize,
+}
+
+#[cfg(feature = "lz4")]
+impl Lz4Compressor {
+    /// If the buffer is at least as large as the `threshold` value, we compress the buffer.
+    /// When given a `threshold` of `0`, the `Lz4Compressor` will always compress.
+    #[must_use]
+    pub fn new(threshold: usize) -> Self {
+        Self { threshold }
+    }
+
+    /// Compression.
+    /// If the buffer is smaller than the threshold of this compressor, `None` will be returned.
+    /// Else, the buffer is compressed, prepended by its uncompressed size.
+    pub fn compress(&self, buf: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        if buf.len() >= self.threshold {
+            Ok(Some(lz4_flex::compress_prepend_size(buf)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Decompression of a buffer previously compressed by [`Lz4Compressor::compress`].
+    #[allow(clippy::unused_self)]
+    pub fn decompress(&self, buf: &[u8]) -> Result<Vec<u8>, Error> {
+        lz4_flex::decompress_size_prepended(buf).map_err(|_| Error::compression())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::bolts::compress::GzipCompressor;
@@ -75,4 +111,18 @@ mod tests {
         assert!(compressor.compress(&[1u8; 1023]).unwrap().is_none());
         assert!(compressor.compress(&[1u8; 1024]).unwrap().is_some());
     }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_lz4_compression() {
+        use crate::bolts::compress::Lz4Compressor;
+
+        let compressor = Lz4Compressor::new(1024);
+        assert!(compressor.compress(&[1u8; 1023]).unwrap().is_none());
+
+        let payload = b"AAAABBBBCCCCDDDD".repeat(1024);
+        let compressed = compressor.compress(&payload).unwrap().unwrap();
+        assert!(compressed.len() < payload.len());
+        assert_eq!(compressor.decompress(&compressed).unwrap(), payload);
+    }
 }
@@ -538,6 +538,37 @@ fn write_crash<W: Write>(
     Ok(())
 }
 
+/// The program counter the given context faulted at
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+#[must_use]
+#[allow(clippy::cast_sign_loss)]
+pub fn crash_pc(ucontext: &ucontext_t) -> Option<u64> {
+    Some(ucontext.uc_mcontext.gregs[libc::REG_RIP as usize] as u64)
+}
+
+/// The program counter the given context faulted at
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    target_arch = "aarch64"
+))]
+#[must_use]
+pub fn crash_pc(ucontext: &ucontext_t) -> Option<u64> {
+    Some(ucontext.uc_mcontext.pc)
+}
+
+/// The program counter the given context faulted at, not yet supported on this platform
+#[cfg(not(any(
+    all(target_os = "linux", target_arch = "x86_64"),
+    all(
+        any(target_os = "linux", target_os = "android"),
+        target_arch = "aarch64"
+    )
+)))]
+#[must_use]
+pub fn crash_pc(_ucontext: &ucontext_t) -> Option<u64> {
+    None
+}
+
 /// Generates a mini-BSOD given a signal and context.
 #[cfg(unix)]
 #[allow(clippy::non_ascii_literal)]
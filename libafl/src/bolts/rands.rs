@@ -104,6 +104,36 @@ default_rand!(Lehmer64Rand);
 default_rand!(RomuTrioRand);
 default_rand!(RomuDuoJrRand);
 
+/// Shuffles the given items in place, in an order that only depends on the given `key`.
+///
+/// In contrast to [`Rand::choose`] and friends, this does not use (or advance) any [`Rand`],
+/// so the same items will always end up in the same pseudo-random order for the same `key`,
+/// for example to load seeds in a reproducible order for benchmarking.
+/// To key the order by a name, hash it first, for example using `xxh3_64(name.as_bytes())`.
+pub fn reproducible_shuffle<T>(items: &mut [T], key: u64) {
+    // splitmix64, see <https://prng.di.unimi.it/splitmix64.c>
+    let mut state = key;
+    let mut next = || {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    };
+
+    // Fisher-Yates, drawing unbiased indices like [`Rand::below`]
+    for i in (1..items.len()).rev() {
+        let upper_bound_excl = (i + 1) as u64;
+        let j = loop {
+            let rnd = next();
+            if rnd < (u64::MAX - (u64::MAX % upper_bound_excl)) {
+                break rnd % upper_bound_excl;
+            }
+        };
+        items.swap(i, j as usize);
+    }
+}
+
 /// Initialize Rand types from a source of randomness.
 ///
 /// Default implementations are provided with the "std" feature enabled, using system time in
@@ -378,9 +408,11 @@ impl XkcdRand {
 #[cfg(test)]
 mod tests {
     //use xxhash_rust::xxh3::xxh3_64_with_seed;
+    use alloc::vec::Vec;
 
     use crate::bolts::rands::{
-        Rand, RomuDuoJrRand, RomuTrioRand, StdRand, XorShift64Rand, Xoshiro256StarRand,
+        reproducible_shuffle, Rand, RomuDuoJrRand, RomuTrioRand, StdRand, XorShift64Rand,
+        Xoshiro256StarRand,
     };
 
     fn test_single_rand<R: Rand>(rand: &mut R) {
@@ -401,6 +433,26 @@ mod tests {
         test_single_rand(&mut Xoshiro256StarRand::with_seed(0));
     }
 
+    #[test]
+    fn test_reproducible_shuffle() {
+        let orig: Vec<u32> = (0..64).collect();
+
+        let mut first = orig.clone();
+        reproducible_shuffle(&mut first, 1337);
+        let mut second = orig.clone();
+        reproducible_shuffle(&mut second, 1337);
+        assert_eq!(first, second);
+        assert_ne!(first, orig);
+
+        let mut other = orig.clone();
+        reproducible_shuffle(&mut other, 1338);
+        assert_ne!(first, other);
+
+        // Still a permutation
+        other.sort_unstable();
+        assert_eq!(other, orig);
+    }
+
     #[cfg(feature = "std")]
     #[test]
     fn test_random_seed() {
@@ -259,6 +259,12 @@ macro_rules! create_serde_registry_for_trait {
                     self.map.insert(unpack_type_id(TypeId::of::<T>()), t);
                 }
 
+                /// Moves all elements of `other` into this map, replacing the elements of the same types.
+                #[inline]
+                pub fn extend(&mut self, other: SerdeAnyMap) {
+                    self.map.extend(other.map);
+                }
+
                 /// Returns the count of elements in this map.
                 #[must_use]
                 #[inline]
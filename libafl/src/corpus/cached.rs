@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     corpus::{
         ondisk::{OnDiskCorpus, OnDiskMetadataFormat},
-        Corpus, CorpusId, Testcase,
+        Corpus, CorpusId, CorpusSizeStats, Testcase,
     },
     inputs::{Input, UsesInput},
     Error,
@@ -126,6 +126,11 @@ where
     fn nth(&self, nth: usize) -> CorpusId {
         self.inner.nth(nth)
     }
+
+    #[inline]
+    fn size_stats(&self) -> Option<&CorpusSizeStats> {
+        self.inner.size_stats()
+    }
 }
 
 impl<I> CachedOnDiskCorpus<I>
@@ -6,7 +6,7 @@ use core::cell::RefCell;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    corpus::{Corpus, CorpusId, Testcase},
+    corpus::{Corpus, CorpusId, CorpusSizeStats, Testcase},
     inputs::{Input, UsesInput},
     Error,
 };
@@ -293,6 +293,8 @@ where
 {
     storage: TestcaseStorage<I>,
     current: Option<CorpusId>,
+    #[serde(default)]
+    size_stats: CorpusSizeStats,
 }
 
 impl<I> UsesInput for InMemoryCorpus<I>
@@ -315,24 +317,39 @@ where
     /// Add an entry to the corpus and return its index
     #[inline]
     fn add(&mut self, testcase: Testcase<I>) -> Result<CorpusId, Error> {
+        if let Some(len) = testcase.input().as_ref().and_then(Input::len_hint) {
+            self.size_stats.add(len);
+        }
         Ok(self.storage.insert(RefCell::new(testcase)))
     }
 
     /// Replaces the testcase at the given idx
     #[inline]
     fn replace(&mut self, idx: CorpusId, testcase: Testcase<I>) -> Result<Testcase<I>, Error> {
-        self.storage
+        let len = testcase.input().as_ref().and_then(Input::len_hint);
+        let prev = self
+            .storage
             .replace(idx, testcase)
-            .ok_or_else(|| Error::key_not_found(format!("Index {idx} not found")))
+            .ok_or_else(|| Error::key_not_found(format!("Index {idx} not found")))?;
+        self.size_stats
+            .remove(prev.input().as_ref().and_then(Input::len_hint));
+        if let Some(len) = len {
+            self.size_stats.add(len);
+        }
+        Ok(prev)
     }
 
     /// Removes an entry from the corpus, returning it if it was present.
     #[inline]
     fn remove(&mut self, idx: CorpusId) -> Result<Testcase<I>, Error> {
-        self.storage
+        let testcase = self
+            .storage
             .remove(idx)
             .map(|x| x.take())
-            .ok_or_else(|| Error::key_not_found(format!("Index {idx} not found")))
+            .ok_or_else(|| Error::key_not_found(format!("Index {idx} not found")))?;
+        self.size_stats
+            .remove(testcase.input().as_ref().and_then(Input::len_hint));
+        Ok(testcase)
     }
 
     /// Get by id
@@ -379,6 +396,11 @@ where
     fn nth(&self, nth: usize) -> CorpusId {
         self.storage.keys[nth]
     }
+
+    #[inline]
+    fn size_stats(&self) -> Option<&CorpusSizeStats> {
+        Some(&self.size_stats)
+    }
 }
 
 impl<I> InMemoryCorpus<I>
@@ -392,6 +414,7 @@ where
         Self {
             storage: TestcaseStorage::new(),
             current: None,
+            size_stats: CorpusSizeStats::new(),
         }
     }
 }
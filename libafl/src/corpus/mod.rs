@@ -18,8 +18,10 @@ pub use cached::CachedOnDiskCorpus;
 
 #[cfg(feature = "cmin")]
 pub mod minimizer;
+use alloc::vec::Vec;
 use core::{cell::RefCell, fmt};
 
+use hashbrown::HashMap;
 #[cfg(feature = "cmin")]
 pub use minimizer::*;
 use serde::{Deserialize, Serialize};
@@ -59,6 +61,78 @@ macro_rules! random_corpus_id {
     }};
 }
 
+/// Statistics on the lengths of the inputs in a [`Corpus`], updated incrementally on `add`, `replace` and `remove`.
+/// Only inputs returning a [`crate::inputs::Input::len_hint`] count.
+///
+/// The statistics are lossy on removal: `min` and `max` keep the bounds over all inputs ever added,
+/// and if the length of a removed input is unknown (for example, because it is not loaded),
+/// the mean gets approximated by assuming the removed input had the mean length.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CorpusSizeStats {
+    count: usize,
+    mean: f64,
+    min: Option<usize>,
+    max: Option<usize>,
+}
+
+impl CorpusSizeStats {
+    /// Creates empty [`CorpusSizeStats`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an added input of the given length
+    #[allow(clippy::cast_precision_loss)]
+    pub fn add(&mut self, len: usize) {
+        self.count += 1;
+        self.mean += (len as f64 - self.mean) / self.count as f64;
+        self.min = Some(self.min.map_or(len, |min| min.min(len)));
+        self.max = Some(self.max.map_or(len, |max| max.max(len)));
+    }
+
+    /// Records a removed input of the given length, or of unknown length if `None`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn remove(&mut self, len: Option<usize>) {
+        match self.count {
+            0 => {}
+            1 => {
+                self.count = 0;
+                self.mean = 0.0;
+            }
+            _ => {
+                let len = len.map_or(self.mean, |len| len as f64);
+                self.mean = (self.mean * self.count as f64 - len) / (self.count - 1) as f64;
+                self.count -= 1;
+            }
+        }
+    }
+
+    /// The number of inputs the statistics cover
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The mean input length, `None` if there are no inputs
+    #[must_use]
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// The length of the shortest input ever added
+    #[must_use]
+    pub fn min(&self) -> Option<usize> {
+        self.min
+    }
+
+    /// The length of the longest input ever added
+    #[must_use]
+    pub fn max(&self) -> Option<usize> {
+        self.max
+    }
+}
+
 /// Corpus with all current testcases
 pub trait Corpus: UsesInput + Serialize + for<'de> Deserialize<'de> {
     /// Returns the number of elements
@@ -103,7 +177,8 @@ pub trait Corpus: UsesInput + Serialize + for<'de> Deserialize<'de> {
     /// Get the last inserted corpus id
     fn last(&self) -> Option<CorpusId>;
 
-    /// An iterator over very active corpus id
+    /// An iterator over every active corpus id, in insertion order.
+    /// Removed entries are skipped.
     fn ids(&self) -> CorpusIdIterator<'_, Self> {
         CorpusIdIterator {
             corpus: self,
@@ -118,6 +193,76 @@ pub trait Corpus: UsesInput + Serialize + for<'de> Deserialize<'de> {
             .nth(nth)
             .expect("Failed to get the {nth} CorpusId")
     }
+
+    /// The [`CorpusSizeStats`] of the inputs in this corpus, if it tracks them
+    fn size_stats(&self) -> Option<&CorpusSizeStats> {
+        None
+    }
+
+    /// Appends a clone of every entry of `other`, in order, under fresh ids, e.g. to combine the findings of several nodes.
+    /// Returns the id each entry of `other` got in this corpus, to fix up references to the old ids, like parent links in metadata.
+    /// Duplicates are not detected. The inputs of entries `other` keeps on disk get loaded, and the copies forget
+    /// the files of `other`, so that this corpus stores them on its own.
+    /// The scheduler does not know about the new entries, call its `on_add` for each one.
+    fn merge_from<C>(&mut self, other: &C) -> Result<HashMap<CorpusId, CorpusId>, Error>
+    where
+        C: Corpus<Input = Self::Input>,
+    {
+        let mut mapping = HashMap::with_capacity(other.count());
+        for id in other.ids() {
+            let mut testcase = other.get(id)?.borrow().clone();
+            if testcase.input().is_none() && testcase.filename().is_some() {
+                testcase.load_input()?;
+            }
+            *testcase.filename_mut() = None;
+            mapping.insert(id, self.add(testcase)?);
+        }
+        Ok(mapping)
+    }
+
+    /// Removes every entry `f` returns `false` for, returning the number of removed entries.
+    /// Corpora storing their entries on disk delete the files of the removed ones.
+    /// If the current entry gets removed, the nearest kept entry before it becomes the current one,
+    /// so a scheduler walking the corpus in order continues after it.
+    /// The scheduler does not know about the removed entries, see [`crate::schedulers::retain_corpus`] instead.
+    fn retain<F>(&mut self, f: F) -> Result<usize, Error>
+    where
+        F: FnMut(&Testcase<Self::Input>) -> bool,
+    {
+        Ok(self.retain_collect(f)?.len())
+    }
+
+    /// Like [`Corpus::retain`], but returns the ids and testcases of the removed entries, in insertion order.
+    #[allow(clippy::type_complexity)]
+    fn retain_collect<F>(
+        &mut self,
+        mut f: F,
+    ) -> Result<Vec<(CorpusId, Testcase<Self::Input>)>, Error>
+    where
+        F: FnMut(&Testcase<Self::Input>) -> bool,
+    {
+        let current = *self.current();
+        let mut new_current = current;
+        let mut last_kept = None;
+        let mut rejected = Vec::new();
+        for id in self.ids() {
+            if f(&self.get(id)?.borrow()) {
+                last_kept = Some(id);
+            } else {
+                if current == Some(id) {
+                    new_current = last_kept;
+                }
+                rejected.push(id);
+            }
+        }
+
+        let mut removed = Vec::with_capacity(rejected.len());
+        for id in rejected {
+            removed.push((id, self.remove(id)?));
+        }
+        *self.current_mut() = new_current;
+        Ok(removed)
+    }
 }
 
 /// `Iterator` over the ids of a `Corpus`
@@ -139,7 +284,13 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(cur) = self.cur {
-            self.cur = self.corpus.next(cur);
+            if self.cur_back == Some(cur) {
+                // Met the back of the iterator
+                self.cur = None;
+                self.cur_back = None;
+            } else {
+                self.cur = self.corpus.next(cur);
+            }
             Some(cur)
         } else {
             None
@@ -153,7 +304,13 @@ where
 {
     fn next_back(&mut self) -> Option<Self::Item> {
         if let Some(cur_back) = self.cur_back {
-            self.cur_back = self.corpus.prev(cur_back);
+            if self.cur == Some(cur_back) {
+                // Met the front of the iterator
+                self.cur = None;
+                self.cur_back = None;
+            } else {
+                self.cur_back = self.corpus.prev(cur_back);
+            }
             Some(cur_back)
         } else {
             None
@@ -161,6 +318,110 @@ where
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase},
+        inputs::BytesInput,
+    };
+
+    #[test]
+    fn test_corpus_ids() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        let ids = (0..5_u8)
+            .map(|i| corpus.add(Testcase::new(BytesInput::new(vec![i]))).unwrap())
+            .collect::<Vec<_>>();
+        corpus.remove(ids[1]).unwrap();
+        corpus.remove(ids[4]).unwrap();
+
+        let expected = [ids[0], ids[2], ids[3]];
+        assert!(corpus.ids().eq(expected));
+        assert!(corpus.ids().rev().eq(expected.into_iter().rev()));
+
+        // Interleaving both ends yields every id exactly once
+        let mut iter = corpus.ids();
+        assert_eq!(iter.next(), Some(ids[0]));
+        assert_eq!(iter.next_back(), Some(ids[3]));
+        assert_eq!(iter.next(), Some(ids[2]));
+        assert_eq!(iter.next_back(), None);
+        assert_eq!(iter.next(), None);
+
+        // The ids stay valid across `get`
+        for id in corpus.ids() {
+            assert!(corpus.get(id).is_ok());
+        }
+        assert_eq!(
+            corpus.ids().collect::<Vec<CorpusId>>().len(),
+            corpus.count()
+        );
+    }
+
+    #[test]
+    fn test_merge_from() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        let mut other = InMemoryCorpus::<BytesInput>::new();
+        for i in 0..3_u8 {
+            corpus.add(Testcase::new(BytesInput::new(vec![i]))).unwrap();
+        }
+        let other_ids = (10..13_u8)
+            .map(|i| other.add(Testcase::new(BytesInput::new(vec![i]))).unwrap())
+            .collect::<Vec<_>>();
+        other.remove(other_ids[1]).unwrap();
+
+        let mapping = corpus.merge_from(&other).unwrap();
+        assert_eq!(mapping.len(), 2);
+        assert_eq!(corpus.count(), 5);
+        for id in [other_ids[0], other_ids[2]] {
+            assert_eq!(
+                corpus.get(mapping[&id]).unwrap().borrow().input(),
+                other.get(id).unwrap().borrow().input()
+            );
+        }
+        assert!(!mapping.contains_key(&other_ids[1]));
+        assert!(corpus
+            .ids()
+            .skip(3)
+            .eq([mapping[&other_ids[0]], mapping[&other_ids[2]]]));
+    }
+
+    #[test]
+    fn test_size_stats() {
+        let mut corpus = InMemoryCorpus::<BytesInput>::new();
+        assert_eq!(corpus.size_stats().unwrap().mean(), None);
+
+        let ids = [2, 4, 9].map(|len| {
+            corpus
+                .add(Testcase::new(BytesInput::new(vec![0; len])))
+                .unwrap()
+        });
+        let stats = corpus.size_stats().unwrap();
+        assert_eq!(stats.count(), 3);
+        assert_eq!(stats.mean(), Some(5.0));
+        assert_eq!(stats.min(), Some(2));
+        assert_eq!(stats.max(), Some(9));
+
+        corpus
+            .replace(ids[1], Testcase::new(BytesInput::new(vec![0; 7])))
+            .unwrap();
+        assert_eq!(corpus.size_stats().unwrap().mean(), Some(6.0));
+
+        // The bounds are kept on removal, the mean gets updated
+        corpus.remove(ids[2]).unwrap();
+        let stats = corpus.size_stats().unwrap();
+        assert_eq!(stats.count(), 2);
+        assert_eq!(stats.mean(), Some(4.5));
+        assert_eq!(stats.max(), Some(9));
+
+        // Removing an input of unknown length keeps the mean
+        let mut stats = *stats;
+        stats.remove(None);
+        assert_eq!(stats.count(), 1);
+        assert_eq!(stats.mean(), Some(4.5));
+    }
+}
+
 /// `Corpus` Python bindings
 #[cfg(feature = "python")]
 #[allow(missing_docs)]
@@ -3,9 +3,14 @@
 //! For a lower memory footprint, consider using [`crate::corpus::CachedOnDiskCorpus`]
 //! which only stores a certain number of testcases and removes additional ones in a FIFO manner.
 
+use alloc::vec::Vec;
 use core::{cell::RefCell, time::Duration};
 #[cfg(feature = "std")]
-use std::{fs, fs::File, io::Write};
+use std::{
+    fs,
+    fs::File,
+    io::{Read, Write},
+};
 use std::{
     fs::OpenOptions,
     path::{Path, PathBuf},
@@ -16,8 +21,8 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "gzip")]
 use crate::bolts::compress::GzipCompressor;
 use crate::{
-    bolts::serdeany::SerdeAnyMap,
-    corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase},
+    bolts::{fs::write_file_atomic, serdeany::SerdeAnyMap},
+    corpus::{Corpus, CorpusId, CorpusSizeStats, InMemoryCorpus, Testcase},
     inputs::{Input, UsesInput},
     state::HasMetadata,
     Error,
@@ -38,6 +43,102 @@ pub enum OnDiskMetadataFormat {
     JsonGzip,
 }
 
+/// The compression of the [`Testcase`] inputs an [`OnDiskCorpus`] stores to disk
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+    /// The inputs are stored as they are
+    #[default]
+    None,
+    /// The inputs are compressed using zstd
+    #[cfg(feature = "zstd")]
+    Zstd {
+        /// The zstd compression level, `0` being zstd's default level
+        level: i32,
+    },
+}
+
+#[cfg(feature = "std")]
+impl Compression {
+    /// Compresses the given bytes
+    #[allow(clippy::unnecessary_wraps)]
+    fn compress(self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Compression::None => Ok(bytes.to_vec()),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => Ok(zstd::encode_all(bytes, level)?),
+        }
+    }
+
+    /// Decompresses the given bytes
+    #[allow(clippy::unnecessary_wraps)]
+    fn decompress(self, bytes: &[u8]) -> Result<Vec<u8>, Error> {
+        match self {
+            Compression::None => Ok(bytes.to_vec()),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { .. } => Ok(zstd::decode_all(bytes)?),
+        }
+    }
+
+    /// The compression of stored bytes starting with the given `magic` number.
+    /// The compression level can not be detected, and is only used for compressing anyway.
+    #[must_use]
+    #[cfg_attr(not(feature = "zstd"), allow(unused_variables))]
+    pub fn detect(magic: &[u8]) -> Self {
+        #[cfg(feature = "zstd")]
+        if magic.starts_with(&ZSTD_MAGIC) {
+            return Compression::Zstd { level: 0 };
+        }
+        Compression::None
+    }
+
+    /// Loads an input stored with this compression, decompressing it in memory
+    pub fn read_input<I, P>(self, path: P) -> Result<I, Error>
+    where
+        I: Input,
+        P: AsRef<Path>,
+    {
+        if self == Compression::None {
+            return I::from_file(path);
+        }
+        I::from_file_bytes(&self.decompress(&fs::read(path)?)?)
+    }
+}
+
+/// The magic number every zstd frame starts with
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Loads an input from a file an [`OnDiskCorpus`] stored, decompressing it if it was stored compressed.
+/// Without its [`OnDiskCompressionMetadata`] at hand, the compression gets detected from the file itself,
+/// so that compressed corpora can be used as initial inputs, too.
+#[cfg(feature = "std")]
+pub fn load_stored_input<I, P>(path: P) -> Result<I, Error>
+where
+    I: Input,
+    P: AsRef<Path>,
+{
+    let mut magic = [0_u8; 4];
+    let compression = match File::open(path.as_ref())?.read_exact(&mut magic) {
+        Ok(()) => Compression::detect(&magic),
+        Err(_) => Compression::None,
+    };
+    compression.read_input(path)
+}
+
+/// A [`Testcase`] metadata holding the [`Compression`] the input of the [`Testcase`] was stored with.
+/// As all metadata, it gets written to the `.<filename>.metadata` file,
+/// so that corpora stored with different compressions still load.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct OnDiskCompressionMetadata {
+    /// The compression of the stored input
+    pub compression: Compression,
+}
+
+#[cfg(feature = "std")]
+crate::impl_serdeany!(OnDiskCompressionMetadata);
+
 /// The [`Testcase`] metadata that'll be stored to disk
 #[cfg(feature = "std")]
 #[derive(Debug, Serialize)]
@@ -50,6 +151,7 @@ pub struct OnDiskMetadata<'a> {
 /// A corpus able to store [`Testcase`]s to disk, and load them from disk, when they are being used.
 ///
 /// Metadata is written to a `.<filename>.metadata` file in the same folder by default.
+/// The inputs can be compressed on disk, see [`OnDiskCorpus::with_compression`].
 #[cfg(feature = "std")]
 #[derive(Default, Serialize, Deserialize, Clone, Debug)]
 #[serde(bound = "I: serde::de::DeserializeOwned")]
@@ -60,6 +162,7 @@ where
     inner: InMemoryCorpus<I>,
     dir_path: PathBuf,
     meta_format: Option<OnDiskMetadataFormat>,
+    compression: Compression,
 }
 
 impl<I> UsesInput for OnDiskCorpus<I>
@@ -146,6 +249,11 @@ where
     fn nth(&self, nth: usize) -> CorpusId {
         self.inner.nth(nth)
     }
+
+    #[inline]
+    fn size_stats(&self) -> Option<&CorpusSizeStats> {
+        self.inner.size_stats()
+    }
 }
 
 impl<I> OnDiskCorpus<I>
@@ -201,9 +309,24 @@ where
             inner: InMemoryCorpus::new(),
             dir_path: dir_path.into(),
             meta_format,
+            compression: Compression::None,
         })
     }
 
+    /// Sets the [`Compression`] of the inputs stored from now on.
+    /// Inputs stored before keep their compression.
+    #[must_use]
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// The [`Compression`] of the inputs stored from now on
+    #[must_use]
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
     fn save_testcase(&self, testcase: &mut Testcase<I>, idx: CorpusId) -> Result<(), Error> {
         if testcase.filename().is_none() {
             // TODO walk entry metadata to ask for pieces of filename (e.g. :havoc in AFL)
@@ -231,6 +354,17 @@ where
             let filename_str = filename.to_str().expect("Invalid Path");
             testcase.set_filename(filename_str.into());
         };
+        if self.compression == Compression::None {
+            drop(
+                testcase
+                    .metadata_mut()
+                    .remove::<OnDiskCompressionMetadata>(),
+            );
+        } else {
+            testcase.add_metadata(OnDiskCompressionMetadata {
+                compression: self.compression,
+            });
+        }
         if self.meta_format.is_some() {
             let mut filename = PathBuf::from(testcase.filename().as_ref().unwrap());
             filename.set_file_name(format!(
@@ -263,9 +397,17 @@ where
             tmpfile.write_all(&serialized)?;
             fs::rename(&tmpfile_name, &filename)?;
         }
-        testcase
-            .store_input()
-            .expect("Could not save testcase to disk");
+        if self.compression == Compression::None {
+            testcase
+                .store_input()
+                .expect("Could not save testcase to disk");
+        } else if let Some(input) = testcase.input_mut().take() {
+            // Written once, already compressed, like `store_input` the input leaves the memory
+            write_file_atomic(
+                testcase.filename().as_ref().unwrap(),
+                &self.compression.compress(&input.to_file_bytes()?)?,
+            )?;
+        }
         Ok(())
     }
 
@@ -285,6 +427,112 @@ where
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::OnDiskCorpus;
+    #[cfg(feature = "zstd")]
+    use super::{load_stored_input, Compression};
+    use crate::{
+        corpus::{Corpus, Testcase},
+        inputs::{BytesInput, HasBytesVec},
+    };
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_compression() {
+        let dir = "target/.test/ondisk_compression";
+        let seed = vec![b'a'; 4096];
+        let mut corpus = OnDiskCorpus::<BytesInput>::new(dir).unwrap();
+        let plain = corpus
+            .add(Testcase::new(BytesInput::new(b"plain".to_vec())))
+            .unwrap();
+        let mut corpus = corpus.with_compression(Compression::Zstd { level: 3 });
+        let compressed = corpus
+            .add(Testcase::new(BytesInput::new(seed.clone())))
+            .unwrap();
+
+        let filename = corpus
+            .get(compressed)
+            .unwrap()
+            .borrow()
+            .filename()
+            .clone()
+            .unwrap();
+        assert!((fs::metadata(&filename).unwrap().len() as usize) < seed.len());
+        // Getting an entry does not load it
+        assert!(corpus.get(compressed).unwrap().borrow().input().is_none());
+
+        // Both the compressed and the uncompressed entries load
+        assert_eq!(
+            corpus
+                .get(compressed)
+                .unwrap()
+                .borrow_mut()
+                .load_input()
+                .unwrap()
+                .bytes(),
+            &seed
+        );
+        assert_eq!(
+            corpus
+                .get(plain)
+                .unwrap()
+                .borrow_mut()
+                .load_input()
+                .unwrap()
+                .bytes(),
+            b"plain"
+        );
+
+        // Compressed entries also load as initial inputs, without their metadata
+        let input: BytesInput = load_stored_input(&filename).unwrap();
+        assert_eq!(input.bytes(), &seed);
+        let plain_filename = corpus.get(plain).unwrap().borrow().filename().clone();
+        let input: BytesInput = load_stored_input(plain_filename.unwrap()).unwrap();
+        assert_eq!(input.bytes(), b"plain");
+        // Nothing but the inputs, their metadata and lock files got written
+        for entry in fs::read_dir(dir).unwrap() {
+            let name = entry.unwrap().file_name().into_string().unwrap();
+            assert!(
+                !name.starts_with('.')
+                    || name.ends_with(".metadata")
+                    || name.ends_with(".lafl_lock")
+            );
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_merge_from() {
+        let dir = "target/.test/ondisk_merge_into";
+        let other_dir = "target/.test/ondisk_merge_from";
+        let mut corpus = OnDiskCorpus::<BytesInput>::new(dir).unwrap();
+        let mut other = OnDiskCorpus::<BytesInput>::new(other_dir).unwrap();
+        let other_ids = [b"foo", b"bar"].map(|bytes| {
+            other
+                .add(Testcase::new(BytesInput::new(bytes.to_vec())))
+                .unwrap()
+        });
+        // The entries of `other` are only on disk
+        assert!(other.get(other_ids[0]).unwrap().borrow().input().is_none());
+
+        let mapping = corpus.merge_from(&other).unwrap();
+        fs::remove_dir_all(other_dir).unwrap();
+
+        // The merged entries got their own files, which outlive `other`
+        for (id, bytes) in other_ids.iter().zip([b"foo", b"bar"]) {
+            let mut testcase = corpus.get(mapping[id]).unwrap().borrow_mut();
+            assert!(testcase.filename().as_ref().unwrap().starts_with(dir));
+            assert_eq!(testcase.load_input().unwrap().bytes(), bytes);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
+
 #[cfg(feature = "python")]
 /// `OnDiskCorpus` Python bindings
 pub mod pybind {
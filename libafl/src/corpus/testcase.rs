@@ -6,6 +6,8 @@ use core::{default::Default, option::Option, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "std")]
+use crate::corpus::ondisk::OnDiskCompressionMetadata;
 use crate::{
     bolts::{serdeany::SerdeAnyMap, HasLen},
     inputs::Input,
@@ -36,6 +38,9 @@ where
     fuzz_level: usize,
     /// If it has been fuzzed
     fuzzed: bool,
+    /// If it is disabled, i.e., kept in the corpus but not scheduled anymore
+    #[serde(default)]
+    disabled: bool,
 }
 
 impl<I> HasMetadata for Testcase<I>
@@ -60,10 +65,19 @@ impl<I> Testcase<I>
 where
     I: Input,
 {
-    /// Returns this testcase with a loaded input
+    /// Returns this testcase with a loaded input.
+    /// Inputs stored compressed (see [`crate::corpus::ondisk::OnDiskCompressionMetadata`]) get decompressed in memory.
     pub fn load_input(&mut self) -> Result<&I, Error> {
         if self.input.is_none() {
-            self.input = Some(I::from_file(self.filename.as_ref().unwrap())?);
+            let filename = self.filename.as_ref().unwrap();
+            #[cfg(feature = "std")]
+            let input = match self.metadata.get::<OnDiskCompressionMetadata>() {
+                Some(meta) => meta.compression.read_input(filename)?,
+                None => I::from_file(filename)?,
+            };
+            #[cfg(not(feature = "std"))]
+            let input = I::from_file(filename)?;
+            self.input = Some(input);
         }
         Ok(self.input.as_ref().unwrap())
     }
@@ -181,6 +195,18 @@ where
         self.fuzzed = fuzzed;
     }
 
+    /// Get if it is disabled, i.e., kept in the corpus but not scheduled anymore
+    #[inline]
+    pub fn disabled(&self) -> bool {
+        self.disabled
+    }
+
+    /// Set if it is disabled. Disabled testcases stay in the corpus, but are skipped by the stock [`crate::schedulers::Scheduler`]s.
+    #[inline]
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+
     /// Create a new Testcase instance given an input
     #[inline]
     pub fn new(input: I) -> Self {
@@ -231,6 +257,7 @@ where
             fuzz_level: 0,
             executions: 0,
             fuzzed: false,
+            disabled: false,
         }
     }
 }
@@ -424,6 +451,11 @@ pub mod pybind {
             self.inner.as_ref().fuzzed()
         }
 
+        #[getter]
+        fn disabled(&self) -> bool {
+            self.inner.as_ref().disabled()
+        }
+
         fn metadata(&mut self) -> PyObject {
             let meta = self.inner.as_mut().metadata_mut();
             if !meta.contains::<PythonMetadata>() {
@@ -18,17 +18,18 @@ use serde::{de::DeserializeOwned, Serialize};
 use typed_builder::TypedBuilder;
 
 use super::{CustomBufEventResult, CustomBufHandlerFn};
+#[cfg(all(feature = "llmp_compression", not(feature = "llmp_compression_lz4")))]
+use crate::bolts::compress::GzipCompressor;
+#[cfg(feature = "llmp_compression_lz4")]
+use crate::bolts::compress::Lz4Compressor;
 #[cfg(feature = "std")]
 use crate::bolts::core_affinity::CoreId;
+#[cfg(feature = "llmp_compression")]
+use crate::bolts::llmp::{LLMP_FLAG_COMPRESSED, LLMP_FLAG_INITIALIZED};
 #[cfg(all(feature = "std", any(windows, not(feature = "fork"))))]
 use crate::bolts::os::startable_self;
 #[cfg(all(feature = "std", feature = "fork", unix))]
 use crate::bolts::os::{fork, ForkResult};
-#[cfg(feature = "llmp_compression")]
-use crate::bolts::{
-    compress::GzipCompressor,
-    llmp::{LLMP_FLAG_COMPRESSED, LLMP_FLAG_INITIALIZED},
-};
 #[cfg(feature = "std")]
 use crate::bolts::{llmp::LlmpConnection, shmem::StdShMemProvider, staterestore::StateRestorer};
 #[cfg(all(unix, feature = "std"))]
@@ -67,6 +68,15 @@ const _LLMP_TAG_NO_RESTART: Tag = 0x57A7EE71;
 #[cfg(feature = "llmp_compression")]
 const COMPRESS_THRESHOLD: usize = 1024;
 
+/// The compressor for LLMP IPC messages.
+/// Compressed messages are marked with [`LLMP_FLAG_COMPRESSED`], so the receiver knows to decompress them.
+#[cfg(all(feature = "llmp_compression", not(feature = "llmp_compression_lz4")))]
+type LlmpCompressor = GzipCompressor;
+/// The compressor for LLMP IPC messages, using lz4.
+/// Compressed messages are marked with [`LLMP_FLAG_COMPRESSED`], so the receiver knows to decompress them.
+#[cfg(feature = "llmp_compression_lz4")]
+type LlmpCompressor = Lz4Compressor;
+
 /// An LLMP-backed event manager for scalable multi-processed fuzzing
 #[derive(Debug)]
 pub struct LlmpEventBroker<I, MT, SP>
@@ -79,7 +89,7 @@ where
     monitor: MT,
     llmp: llmp::LlmpBroker<SP>,
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: LlmpCompressor,
     phantom: PhantomData<I>,
 }
 
@@ -95,7 +105,7 @@ where
             monitor,
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(COMPRESS_THRESHOLD),
             phantom: PhantomData,
         })
     }
@@ -108,7 +118,7 @@ where
             monitor,
             llmp: llmp::LlmpBroker::create_attach_to_tcp(shmem_provider, port)?,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(COMPRESS_THRESHOLD),
             phantom: PhantomData,
         })
     }
@@ -302,7 +312,7 @@ where
     /// The custom buf handler
     custom_buf_handlers: Vec<Box<CustomBufHandlerFn<S>>>,
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: LlmpCompressor,
     configuration: EventConfig,
     phantom: PhantomData<S>,
 }
@@ -346,7 +356,7 @@ where
         Ok(Self {
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(COMPRESS_THRESHOLD),
             configuration,
             phantom: PhantomData,
             custom_buf_handlers: vec![],
@@ -365,7 +375,7 @@ where
         Ok(Self {
             llmp: LlmpClient::create_attach_to_tcp(shmem_provider, port)?,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(COMPRESS_THRESHOLD),
             configuration,
             phantom: PhantomData,
             custom_buf_handlers: vec![],
@@ -382,7 +392,7 @@ where
         Ok(Self {
             llmp: LlmpClient::on_existing_from_env(shmem_provider, env_name)?,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(COMPRESS_THRESHOLD),
             configuration,
             phantom: PhantomData,
             custom_buf_handlers: vec![],
@@ -403,7 +413,7 @@ where
         Ok(Self {
             llmp: LlmpClient::existing_client_from_description(shmem_provider, description)?,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(COMPRESS_THRESHOLD),
             configuration,
             phantom: PhantomData,
             custom_buf_handlers: vec![],
@@ -1071,7 +1081,7 @@ where
     /// The custom buf handler
     custom_buf_handlers: Vec<Box<CustomBufHandlerFn<S>>>,
     #[cfg(feature = "llmp_compression")]
-    compressor: GzipCompressor,
+    compressor: LlmpCompressor,
     converter: Option<IC>,
     converter_back: Option<ICB>,
     phantom: PhantomData<S>,
@@ -1116,7 +1126,7 @@ where
         Ok(Self {
             llmp,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(COMPRESS_THRESHOLD),
             converter,
             converter_back,
             phantom: PhantomData,
@@ -1135,7 +1145,7 @@ where
         Ok(Self {
             llmp: LlmpClient::create_attach_to_tcp(shmem_provider, port)?,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(COMPRESS_THRESHOLD),
             converter,
             converter_back,
             phantom: PhantomData,
@@ -1154,7 +1164,7 @@ where
         Ok(Self {
             llmp: LlmpClient::on_existing_from_env(shmem_provider, env_name)?,
             #[cfg(feature = "llmp_compression")]
-            compressor: GzipCompressor::new(COMPRESS_THRESHOLD),
+            compressor: LlmpCompressor::new(COMPRESS_THRESHOLD),
             phantom: PhantomData,
             converter,
             converter_back,
@@ -1523,4 +1533,38 @@ mod tests {
                 .unwrap();
         }
     }
+
+    #[test]
+    #[cfg(feature = "llmp_compression")]
+    fn test_event_compression() {
+        use super::{LlmpCompressor, COMPRESS_THRESHOLD};
+        use crate::{
+            bolts::current_time,
+            events::{Event, EventConfig},
+            inputs::HasBytesVec,
+        };
+
+        let bytes = vec![0x41; 64 * 1024];
+        let event = Event::NewTestcase {
+            input: BytesInput::new(bytes.clone()),
+            observers_buf: None,
+            exit_kind: ExitKind::Ok,
+            corpus_size: 1,
+            client_config: EventConfig::AlwaysUnique,
+            time: current_time(),
+            executions: 0,
+        };
+        let serialized = postcard::to_allocvec(&event).unwrap();
+
+        let compressor = LlmpCompressor::new(COMPRESS_THRESHOLD);
+        let compressed = compressor.compress(&serialized).unwrap().unwrap();
+        assert!(compressed.len() < serialized.len());
+
+        let decompressed = compressor.decompress(&compressed).unwrap();
+        assert_eq!(decompressed, serialized);
+        match postcard::from_bytes::<Event<BytesInput>>(&decompressed).unwrap() {
+            Event::NewTestcase { input, .. } => assert_eq!(input.bytes(), &bytes),
+            _ => panic!("mismatch"),
+        }
+    }
 }
@@ -614,6 +614,11 @@ mod unix_signal_handler {
         inputs::UsesInput,
         state::{HasClientPerfMonitor, HasSolutions},
     };
+    #[cfg(all(feature = "std", unix))]
+    use crate::{
+        bolts::tuples::MatchName,
+        observers::{CrashPcObserver, CRASH_PC_OBSERVER_NAME},
+    };
 
     pub(crate) type HandlerFuncPtr =
         unsafe fn(Signal, siginfo_t, &mut ucontext_t, data: &mut InProcessExecutorHandlerData);
@@ -787,6 +792,18 @@ mod unix_signal_handler {
                 crate::bolts::minibsod::generate_minibsod(&mut writer, signal, _info, _context)
                     .unwrap();
                 writer.flush().unwrap();
+
+                // Unless the harness already set it, record where we crashed
+                if let Some(observer) = executor
+                    .observers_mut()
+                    .match_name_mut::<CrashPcObserver>(CRASH_PC_OBSERVER_NAME)
+                {
+                    if observer.pc().is_none() {
+                        if let Some(pc) = crate::bolts::minibsod::crash_pc(_context) {
+                            observer.set_pc(pc);
+                        }
+                    }
+                }
             }
 
             run_observers_and_save_state::<E, EM, OF, Z>(
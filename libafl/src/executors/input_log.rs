@@ -0,0 +1,276 @@
+//! The [`InputLogExecutor`] keeps the last inputs executed by the [`Executor`] it wraps in a ring buffer.
+//! If an execution does not end with [`ExitKind::Ok`], the ring is written to disk for post-crash forensics.
+//!
+//! Note that the ring can only be written if the wrapped executor returns from the crashing run,
+//! as the forkserver or the in-process fork executors do.
+
+use alloc::{collections::VecDeque, vec::Vec};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    bolts::{fs::write_file_atomic, AsSlice},
+    executors::{Executor, ExitKind, HasObservers},
+    inputs::{HasTargetBytes, Input},
+    observers::UsesObservers,
+    state::UsesState,
+    Error,
+};
+
+/// The default maximum number of bytes kept in the ring of an [`InputLogExecutor`]
+pub const DEFAULT_INPUT_LOG_MAX_BYTES: usize = 1024 * 1024;
+
+/// A wrapper for any [`Executor`] logging the last `max_inputs` inputs it executed.
+/// Once an execution ends with anything but [`ExitKind::Ok`], the inputs executed before it,
+/// oldest first, get written to `.<input name>.inputlog` in the `log_dir`.
+/// Pass the directory of the solutions corpus as `log_dir` to keep each log next to its solution.
+#[derive(Debug)]
+pub struct InputLogExecutor<E> {
+    executor: E,
+    /// The last inputs, oldest first
+    ring: VecDeque<Vec<u8>>,
+    /// The number of bytes currently held in the ring
+    ring_bytes: usize,
+    /// The maximum number of inputs in the ring
+    max_inputs: usize,
+    /// The maximum number of bytes held in the ring
+    max_bytes: usize,
+    /// The directory the rings get written to
+    log_dir: PathBuf,
+}
+
+impl<E> InputLogExecutor<E> {
+    /// Wraps the given `executor`, logging its last `max_inputs` inputs,
+    /// up to [`DEFAULT_INPUT_LOG_MAX_BYTES`] bytes in total, to `log_dir`.
+    pub fn new<P>(executor: E, max_inputs: usize, log_dir: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self::with_max_bytes(executor, max_inputs, DEFAULT_INPUT_LOG_MAX_BYTES, log_dir)
+    }
+
+    /// Wraps the given `executor`, logging its last `max_inputs` inputs,
+    /// up to `max_bytes` bytes in total, to `log_dir`.
+    pub fn with_max_bytes<P>(executor: E, max_inputs: usize, max_bytes: usize, log_dir: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            executor,
+            ring: VecDeque::with_capacity(max_inputs),
+            ring_bytes: 0,
+            max_inputs,
+            max_bytes,
+            log_dir: log_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Retrieve the wrapped [`Executor`]
+    pub fn executor(&mut self) -> &mut E {
+        &mut self.executor
+    }
+
+    /// The inputs currently in the ring, oldest first
+    pub fn ring(&self) -> impl Iterator<Item = &[u8]> {
+        self.ring.iter().map(Vec::as_slice)
+    }
+
+    /// Loads the inputs, oldest first, from a ring previously written by an [`InputLogExecutor`]
+    pub fn load_ring<P>(path: P) -> Result<Vec<Vec<u8>>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(postcard::from_bytes(&fs::read(path)?)?)
+    }
+
+    /// Adds the given input bytes to the ring, evicting the oldest inputs as needed.
+    /// Evicted buffers get reused, so that a full ring does not allocate.
+    fn push(&mut self, bytes: &[u8]) {
+        if self.max_inputs == 0 {
+            return;
+        }
+        let bytes = &bytes[..bytes.len().min(self.max_bytes)];
+
+        let mut buf = None;
+        while self.ring.len() >= self.max_inputs
+            || (!self.ring.is_empty() && self.ring_bytes + bytes.len() > self.max_bytes)
+        {
+            let evicted = self.ring.pop_front().unwrap();
+            self.ring_bytes -= evicted.len();
+            buf = Some(evicted);
+        }
+
+        let mut buf = buf.unwrap_or_default();
+        buf.clear();
+        buf.extend_from_slice(bytes);
+        self.ring_bytes += buf.len();
+        self.ring.push_back(buf);
+    }
+
+    /// Writes the ring for the given (crashing) input to the `log_dir`
+    fn dump<I>(&self, input: &I) -> Result<(), Error>
+    where
+        I: Input,
+    {
+        fs::create_dir_all(&self.log_dir)?;
+        let path = self
+            .log_dir
+            .join(format!(".{}.inputlog", input.generate_name(0)));
+        write_file_atomic(path, &postcard::to_allocvec(&self.ring)?)
+    }
+}
+
+impl<E, EM, Z> Executor<EM, Z> for InputLogExecutor<E>
+where
+    E: Executor<EM, Z>,
+    E::Input: HasTargetBytes,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        input: &Self::Input,
+    ) -> Result<ExitKind, Error> {
+        let ret = self.executor.run_target(fuzzer, state, mgr, input)?;
+        if ret != ExitKind::Ok {
+            self.dump(input)?;
+        }
+        self.push(input.target_bytes().as_slice());
+        Ok(ret)
+    }
+
+    fn post_run_reset(&mut self) {
+        self.executor.post_run_reset();
+    }
+}
+
+impl<E> UsesState for InputLogExecutor<E>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E> UsesObservers for InputLogExecutor<E>
+where
+    E: UsesObservers,
+{
+    type Observers = E::Observers;
+}
+
+impl<E> HasObservers for InputLogExecutor<E>
+where
+    E: HasObservers,
+{
+    #[inline]
+    fn observers(&self) -> &Self::Observers {
+        self.executor.observers()
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut Self::Observers {
+        self.executor.observers_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+    use std::{fs, path::PathBuf};
+
+    use super::InputLogExecutor;
+    use crate::{
+        bolts::AsSlice,
+        events::NopEventManager,
+        executors::{Executor, ExitKind},
+        inputs::{BytesInput, HasTargetBytes, Input, UsesInput},
+        state::{NopState, UsesState},
+        Error, NopFuzzer,
+    };
+
+    /// Crashes on inputs starting with `!`
+    #[derive(Debug)]
+    struct CrashingExecutor<S> {
+        phantom: PhantomData<S>,
+    }
+
+    impl<S> UsesState for CrashingExecutor<S>
+    where
+        S: UsesInput,
+    {
+        type State = S;
+    }
+
+    impl<EM, S, Z> Executor<EM, Z> for CrashingExecutor<S>
+    where
+        EM: UsesState<State = S>,
+        S: UsesInput + core::fmt::Debug,
+        S::Input: HasTargetBytes,
+        Z: UsesState<State = S>,
+    {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut Self::State,
+            _mgr: &mut EM,
+            input: &Self::Input,
+        ) -> Result<ExitKind, Error> {
+            if input.target_bytes().as_slice().first() == Some(&b'!') {
+                Ok(ExitKind::Crash)
+            } else {
+                Ok(ExitKind::Ok)
+            }
+        }
+    }
+
+    #[test]
+    fn test_input_log() {
+        let log_dir = PathBuf::from("target/.test/inputlog");
+        let mut executor = InputLogExecutor::new(
+            CrashingExecutor {
+                phantom: PhantomData,
+            },
+            3,
+            &log_dir,
+        );
+        let mut fuzzer = NopFuzzer::new();
+        let mut state = NopState::new();
+        let mut mgr = NopEventManager::new();
+
+        for input in [&b"a"[..], b"b", b"c", b"d", b"e"] {
+            let ret = executor
+                .run_target(&mut fuzzer, &mut state, &mut mgr, &BytesInput::from(input))
+                .unwrap();
+            assert_eq!(ret, ExitKind::Ok);
+        }
+        let crash = BytesInput::from(&b"!crash"[..]);
+        let ret = executor
+            .run_target(&mut fuzzer, &mut state, &mut mgr, &crash)
+            .unwrap();
+        assert_eq!(ret, ExitKind::Crash);
+
+        let path = log_dir.join(format!(".{}.inputlog", crash.generate_name(0)));
+        let ring = InputLogExecutor::<()>::load_ring(&path).unwrap();
+        assert_eq!(ring, vec![b"c".to_vec(), b"d".to_vec(), b"e".to_vec()]);
+
+        fs::remove_dir_all(&log_dir).unwrap();
+    }
+
+    #[test]
+    fn test_input_log_max_bytes() {
+        let mut executor = InputLogExecutor::with_max_bytes((), 8, 5, "target/.test/unused");
+        for input in [&b"aa"[..], b"bb", b"cc", b"toolongforthering"] {
+            executor.push(input);
+        }
+        assert!(executor.ring().eq([&b"toolo"[..]]));
+
+        executor.push(b"d");
+        executor.push(b"e");
+        assert!(executor.ring().eq([&b"d"[..], b"e"]));
+    }
+}
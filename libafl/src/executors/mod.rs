@@ -29,6 +29,14 @@ pub use shadow::ShadowExecutor;
 pub mod with_observers;
 pub use with_observers::WithObservers;
 
+pub mod retry;
+pub use retry::RetryExecutor;
+
+#[cfg(feature = "std")]
+pub mod input_log;
+#[cfg(feature = "std")]
+pub use input_log::InputLogExecutor;
+
 #[cfg(all(feature = "std", any(unix, doc)))]
 pub mod command;
 use core::{fmt::Debug, marker::PhantomData};
@@ -63,6 +71,9 @@ pub enum ExitKind {
         /// The exitkind of the secondary executor
         secondary: DiffExitKind,
     },
+    /// The target detected a logic error that is not a crash, with an optional error code.
+    /// Reported by the harness, see [`crate::feedbacks::SoftFailFeedback`].
+    SoftFail(Option<u32>),
     // The run resulted in a custom `ExitKind`.
     // Custom(Box<dyn SerdeAny>),
 }
@@ -80,12 +91,46 @@ pub enum DiffExitKind {
     Timeout,
     /// One of the executors itelf repots a differential, we can't go into further details.
     Diff,
+    /// The target detected a logic error that is not a crash, with an optional error code.
+    SoftFail(Option<u32>),
     // The run resulted in a custom `ExitKind`.
     // Custom(Box<dyn SerdeAny>),
 }
 
 crate::impl_serdeany!(ExitKind);
 
+impl ExitKind {
+    /// The process exit code for this [`ExitKind`], for example to exit a CLI replaying an input with.
+    /// The codes follow the shell conventions: [`ExitKind::Ok`] is `0`, [`ExitKind::Crash`] is `134` (`SIGABRT`),
+    /// [`ExitKind::Oom`] is `137` (`SIGKILL`, as sent by the OOM killer), and [`ExitKind::Timeout`] is `124`,
+    /// like the `timeout` command. [`ExitKind::SoftFail`] is `1`, and [`ExitKind::Diff`] is `2`.
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ExitKind::Ok => 0,
+            ExitKind::SoftFail(_) => 1,
+            ExitKind::Diff { .. } => 2,
+            ExitKind::Timeout => 124,
+            ExitKind::Crash => 134,
+            ExitKind::Oom => 137,
+        }
+    }
+
+    /// The [`ExitKind`] for a process exit code, the inverse of [`ExitKind::exit_code`].
+    /// Returns `None` for the codes of [`ExitKind::SoftFail`] and [`ExitKind::Diff`], as they lost their details,
+    /// and for any other code.
+    #[must_use]
+    pub fn from_exit_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(ExitKind::Ok),
+            124 => Some(ExitKind::Timeout),
+            134 => Some(ExitKind::Crash),
+            137 => Some(ExitKind::Oom),
+            _ => None,
+        }
+    }
+}
+
 impl From<ExitKind> for DiffExitKind {
     fn from(exitkind: ExitKind) -> Self {
         match exitkind {
@@ -94,6 +139,7 @@ impl From<ExitKind> for DiffExitKind {
             ExitKind::Oom => DiffExitKind::Oom,
             ExitKind::Timeout => DiffExitKind::Timeout,
             ExitKind::Diff { .. } => DiffExitKind::Diff,
+            ExitKind::SoftFail(code) => DiffExitKind::SoftFail(code),
         }
     }
 }
@@ -181,9 +227,33 @@ where
 mod test {
     use core::marker::PhantomData;
 
-    use super::{Executor, NopExecutor};
+    use super::{DiffExitKind, Executor, ExitKind, NopExecutor};
     use crate::{events::NopEventManager, inputs::BytesInput, state::NopState, NopFuzzer};
 
+    #[test]
+    fn exit_codes() {
+        for exit_kind in [
+            ExitKind::Ok,
+            ExitKind::Crash,
+            ExitKind::Oom,
+            ExitKind::Timeout,
+        ] {
+            assert_eq!(
+                ExitKind::from_exit_code(exit_kind.exit_code()),
+                Some(exit_kind)
+            );
+        }
+
+        let diff = ExitKind::Diff {
+            primary: DiffExitKind::Ok,
+            secondary: DiffExitKind::Crash,
+        };
+        assert_eq!(ExitKind::SoftFail(Some(3)).exit_code(), 1);
+        assert_eq!(ExitKind::from_exit_code(diff.exit_code()), None);
+        assert_eq!(ExitKind::from_exit_code(1), None);
+        assert_eq!(ExitKind::from_exit_code(42), None);
+    }
+
     #[test]
     fn nop_executor() {
         let empty_input = BytesInput::new(vec![]);
@@ -275,6 +345,11 @@ pub mod pybind {
             self.inner == ExitKind::Timeout
         }
 
+        #[must_use]
+        fn is_soft_fail(&self) -> bool {
+            matches!(self.inner, ExitKind::SoftFail(_))
+        }
+
         #[staticmethod]
         #[must_use]
         fn ok() -> Self {
@@ -306,6 +381,14 @@ pub mod pybind {
                 inner: ExitKind::Timeout,
             }
         }
+
+        #[staticmethod]
+        #[must_use]
+        fn soft_fail(code: Option<u32>) -> Self {
+            Self {
+                inner: ExitKind::SoftFail(code),
+            }
+        }
     }
 
     #[derive(Clone, Debug)]
@@ -0,0 +1,253 @@
+//! The [`RetryExecutor`] re-runs inputs that timed out in the [`Executor`] it wraps,
+//! to filter out transient timeouts, for example due to the JIT warmup of QEMU.
+
+use crate::{
+    executors::{Executor, ExitKind, HasObservers},
+    observers::{ObserversTuple, UsesObservers},
+    state::{HasExecutions, UsesState},
+    Error,
+};
+
+/// A wrapper for any [`Executor`] re-running an input up to `max_retries` times if it ends with an
+/// [`ExitKind::Timeout`]. It only reports the timeout if all retries time out as well, and reports the
+/// result of the first retry that does not time out otherwise. Other exit kinds, like crashes, never get retried.
+/// The observers get reset before each retry, so they only see the run that gets reported,
+/// and each retry counts as an execution.
+#[derive(Debug)]
+pub struct RetryExecutor<E> {
+    executor: E,
+    max_retries: usize,
+    /// The number of timeouts a retry did not reproduce
+    rescued_timeouts: usize,
+}
+
+impl<E> RetryExecutor<E> {
+    /// Wraps the given `executor`, retrying timeouts up to `max_retries` times
+    pub fn new(executor: E, max_retries: usize) -> Self {
+        Self {
+            executor,
+            max_retries,
+            rescued_timeouts: 0,
+        }
+    }
+
+    /// Retrieve the wrapped [`Executor`]
+    pub fn executor(&mut self) -> &mut E {
+        &mut self.executor
+    }
+
+    /// The maximum number of times a timed out input gets re-run
+    #[must_use]
+    pub fn max_retries(&self) -> usize {
+        self.max_retries
+    }
+
+    /// The number of timeouts that did not persist when the input got re-run, and were not reported
+    #[must_use]
+    pub fn rescued_timeouts(&self) -> usize {
+        self.rescued_timeouts
+    }
+}
+
+impl<E, EM, Z> Executor<EM, Z> for RetryExecutor<E>
+where
+    E: Executor<EM, Z> + HasObservers,
+    E::State: HasExecutions,
+    EM: UsesState<State = E::State>,
+    Z: UsesState<State = E::State>,
+{
+    fn run_target(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut Self::State,
+        mgr: &mut EM,
+        input: &Self::Input,
+    ) -> Result<ExitKind, Error> {
+        let mut ret = self.executor.run_target(fuzzer, state, mgr, input)?;
+        for _ in 0..self.max_retries {
+            if ret != ExitKind::Timeout {
+                break;
+            }
+            self.executor.post_run_reset();
+            self.executor.observers_mut().pre_exec_all(state, input)?;
+            ret = self.executor.run_target(fuzzer, state, mgr, input)?;
+            *state.executions_mut() += 1;
+            if ret != ExitKind::Timeout {
+                self.rescued_timeouts += 1;
+            }
+        }
+        Ok(ret)
+    }
+
+    fn post_run_reset(&mut self) {
+        self.executor.post_run_reset();
+    }
+}
+
+impl<E> UsesState for RetryExecutor<E>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E> UsesObservers for RetryExecutor<E>
+where
+    E: UsesObservers,
+{
+    type Observers = E::Observers;
+}
+
+impl<E> HasObservers for RetryExecutor<E>
+where
+    E: HasObservers,
+{
+    #[inline]
+    fn observers(&self) -> &Self::Observers {
+        self.executor.observers()
+    }
+
+    #[inline]
+    fn observers_mut(&mut self) -> &mut Self::Observers {
+        self.executor.observers_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::marker::PhantomData;
+
+    use super::RetryExecutor;
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list, AsSlice},
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::{Executor, ExitKind, HasObservers},
+        feedbacks::ConstFeedback,
+        inputs::{BytesInput, HasTargetBytes, UsesInput},
+        observers::{MapObserver, ObserversTuple, StdMapObserver, UsesObservers},
+        schedulers::QueueScheduler,
+        state::{HasExecutions, StdState, UsesState},
+        Error, StdFuzzer,
+    };
+
+    type TestObservers = (StdMapObserver<'static, u8, false>, ());
+
+    /// Times out for the first `timeouts` runs of each input, and crashes on inputs starting with `!`.
+    /// Each run bumps the first entry of the map.
+    #[derive(Debug)]
+    struct FlakyExecutor<S> {
+        timeouts: usize,
+        runs: usize,
+        observers: TestObservers,
+        phantom: PhantomData<S>,
+    }
+
+    impl<S> UsesState for FlakyExecutor<S>
+    where
+        S: UsesInput,
+    {
+        type State = S;
+    }
+
+    impl<S> UsesObservers for FlakyExecutor<S>
+    where
+        S: UsesInput,
+        TestObservers: ObserversTuple<S>,
+    {
+        type Observers = TestObservers;
+    }
+
+    impl<S> HasObservers for FlakyExecutor<S>
+    where
+        S: UsesInput,
+        TestObservers: ObserversTuple<S>,
+    {
+        fn observers(&self) -> &TestObservers {
+            &self.observers
+        }
+
+        fn observers_mut(&mut self) -> &mut TestObservers {
+            &mut self.observers
+        }
+    }
+
+    impl<EM, S, Z> Executor<EM, Z> for FlakyExecutor<S>
+    where
+        EM: UsesState<State = S>,
+        S: UsesInput + core::fmt::Debug,
+        S::Input: HasTargetBytes,
+        Z: UsesState<State = S>,
+    {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut Self::State,
+            _mgr: &mut EM,
+            input: &Self::Input,
+        ) -> Result<ExitKind, Error> {
+            self.runs += 1;
+            *self.observers.0.get_mut(0) += 1;
+            if input.target_bytes().as_slice().first() == Some(&b'!') {
+                Ok(ExitKind::Crash)
+            } else if self.runs <= self.timeouts {
+                Ok(ExitKind::Timeout)
+            } else {
+                Ok(ExitKind::Ok)
+            }
+        }
+    }
+
+    #[test]
+    fn test_retry_executor() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut fuzzer =
+            StdFuzzer::<_, _, _, TestObservers>::new(QueueScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let mut run = |timeouts, input: &[u8]| {
+            let mut executor = RetryExecutor::new(
+                FlakyExecutor {
+                    timeouts,
+                    runs: 0,
+                    observers: tuple_list!(StdMapObserver::new_owned("map", vec![0_u8; 4])),
+                    phantom: PhantomData,
+                },
+                2,
+            );
+            let input = BytesInput::from(input);
+            let executions = *state.executions();
+            executor
+                .observers_mut()
+                .pre_exec_all(&mut state, &input)
+                .unwrap();
+            let ret = executor
+                .run_target(&mut fuzzer, &mut state, &mut mgr, &input)
+                .unwrap();
+            // The fuzzer counts the first run itself
+            assert_eq!(
+                *state.executions() - executions + 1,
+                executor.executor().runs
+            );
+            // The map only holds the reported run
+            assert_eq!(*executor.observers().0.get(0), 1);
+            (ret, executor.executor().runs, executor.rescued_timeouts())
+        };
+
+        assert_eq!(run(0, b"a"), (ExitKind::Ok, 1, 0));
+        // Rescued by the second retry
+        assert_eq!(run(2, b"a"), (ExitKind::Ok, 3, 1));
+        // Persisting timeouts get reported
+        assert_eq!(run(3, b"a"), (ExitKind::Timeout, 3, 0));
+        // Crashes never get retried
+        assert_eq!(run(3, b"!"), (ExitKind::Crash, 1, 0));
+    }
+}
@@ -0,0 +1,93 @@
+//! Edge hits feedback, counting how often each edge of a [`MapObserver`] got hit over all executions,
+//! for use with the [`crate::schedulers::RarenessScheduler`].
+//! This feedback should be used in combination with another feedback as this feedback always considers testcases
+//! to be not interesting.
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::{fmt::Debug, marker::PhantomData};
+
+use crate::{
+    bolts::tuples::Named,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverName},
+    inputs::UsesInput,
+    observers::{MapObserver, ObserversTuple},
+    schedulers::rareness::EdgeHitsMetadata,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// The edge hits feedback. After each execution, it increments the global hit count
+/// of each set entry of a [`MapObserver`] in the [`struct@EdgeHitsMetadata`] of the state.
+/// This feedback should be used in combination with another feedback as this feedback always considers testcases
+/// to be not interesting.
+#[derive(Debug)]
+pub struct EdgeHitsFeedback<O, S> {
+    name: String,
+    /// The set indexes of the last execution, kept to reuse the allocation
+    indexes: Vec<usize>,
+    phantom: PhantomData<(O, S)>,
+}
+
+impl<O, S> EdgeHitsFeedback<O, S>
+where
+    O: MapObserver,
+{
+    /// Creates an edge hits feedback from an observer
+    #[must_use]
+    pub fn new(observer: &O) -> Self {
+        Self {
+            name: observer.name().to_owned(),
+            indexes: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<O, S> Named for EdgeHitsFeedback<O, S> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<O, S> HasObserverName for EdgeHitsFeedback<O, S> {
+    fn observer_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<O, S> Feedback<S> for EdgeHitsFeedback<O, S>
+where
+    O: MapObserver,
+    S: UsesInput + Debug + HasClientPerfMonitor + HasMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        if !state.has_metadata::<EdgeHitsMetadata>() {
+            state.add_metadata(EdgeHitsMetadata::new());
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &<S as UsesInput>::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let observer = observers.match_name::<O>(&self.name).unwrap();
+        observer.set_indexes(&mut self.indexes);
+        state
+            .metadata_mut()
+            .get_mut::<EdgeHitsMetadata>()
+            .ok_or_else(|| Error::key_not_found("EdgeHitsMetadata not found".to_owned()))?
+            .record(&self.indexes);
+        Ok(false)
+    }
+}
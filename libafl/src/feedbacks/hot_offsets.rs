@@ -0,0 +1,181 @@
+//! The [`HotOffsetsFeedback`] records the byte offsets in which an interesting input differs from its parent,
+//! so that the [`crate::mutators::HotOffsetsMutator`] can focus on them later.
+//! This feedback should be used in combination with another feedback as this feedback always considers testcases
+//! to be not interesting.
+
+use alloc::vec::Vec;
+use core::{fmt::Debug, marker::PhantomData};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::Named,
+    corpus::{Corpus, Testcase},
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::Feedback,
+    inputs::{HasBytesVec, UsesInput},
+    observers::ObserversTuple,
+    state::{HasClientPerfMonitor, HasCorpus, HasMetadata},
+    Error,
+};
+
+/// A testcase metadata holding the (sorted) byte offsets in which a testcase differs from its parent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HotOffsetsMetadata {
+    /// The changed offsets, in ascending order
+    pub offsets: Vec<usize>,
+}
+
+crate::impl_serdeany!(HotOffsetsMetadata);
+
+impl HotOffsetsMetadata {
+    /// Creates a new [`struct@HotOffsetsMetadata`] from the diff between a `parent` and its `child`.
+    /// Offsets past the end of the `parent` count as changed.
+    #[must_use]
+    pub fn from_diff(parent: &[u8], child: &[u8]) -> Self {
+        let offsets = child
+            .iter()
+            .enumerate()
+            .filter(|(i, b)| parent.get(*i) != Some(*b))
+            .map(|(i, _)| i)
+            .collect();
+        Self { offsets }
+    }
+}
+
+/// The hot offsets feedback. Attaches a [`struct@HotOffsetsMetadata`] to new testcases,
+/// listing the offsets changed with respect to the corpus entry currently being fuzzed.
+#[derive(Debug)]
+pub struct HotOffsetsFeedback<S> {
+    phantom: PhantomData<S>,
+}
+
+impl<S> HotOffsetsFeedback<S> {
+    /// Creates a new [`HotOffsetsFeedback`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> Default for HotOffsetsFeedback<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Named for HotOffsetsFeedback<S> {
+    fn name(&self) -> &str {
+        "HotOffsetsFeedback"
+    }
+}
+
+impl<S> Feedback<S> for HotOffsetsFeedback<S>
+where
+    S: UsesInput + HasCorpus + HasClientPerfMonitor + Debug,
+    S::Input: HasBytesVec,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &<S as UsesInput>::Input,
+        _observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        Ok(false)
+    }
+
+    fn append_metadata(
+        &mut self,
+        state: &mut S,
+        testcase: &mut Testcase<<S as UsesInput>::Input>,
+    ) -> Result<(), Error> {
+        // Initial inputs have no parent
+        let Some(parent_idx) = *state.corpus().current() else {
+            return Ok(());
+        };
+        let mut parent = state.corpus().get(parent_idx)?.borrow_mut();
+        let meta = HotOffsetsMetadata::from_diff(
+            parent.load_input()?.bytes(),
+            testcase.load_input()?.bytes(),
+        );
+        testcase.add_metadata(meta);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::{ConstFeedback, Feedback, HotOffsetsFeedback, HotOffsetsMetadata},
+        inputs::{BytesInput, HasBytesVec},
+        mutators::{HotOffsetsMutator, MutationResult, Mutator},
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_hot_offsets() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let parent = b"abcdefgh";
+        let parent_idx = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(parent.to_vec())))
+            .unwrap();
+        *state.corpus_mut().current_mut() = Some(parent_idx);
+
+        let child = b"aXcdeYghZZ";
+        let mut testcase = Testcase::new(BytesInput::new(child.to_vec()));
+        HotOffsetsFeedback::new()
+            .append_metadata(&mut state, &mut testcase)
+            .unwrap();
+
+        let offsets = &testcase
+            .metadata()
+            .get::<HotOffsetsMetadata>()
+            .unwrap()
+            .offsets;
+        let diff = (0..child.len())
+            .filter(|i| parent.get(*i) != Some(&child[*i]))
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, &diff);
+        assert_eq!(offsets, &[1, 5, 8, 9]);
+
+        // The mutator only touches the hot offsets of the current testcase
+        let child_idx = state.corpus_mut().add(testcase).unwrap();
+        *state.corpus_mut().current_mut() = Some(child_idx);
+        let mut mutator = HotOffsetsMutator::new();
+        for _ in 0..64 {
+            let mut input = BytesInput::new(child.to_vec());
+            assert_eq!(
+                mutator.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Mutated
+            );
+            for (i, (a, b)) in input.bytes().iter().zip(child).enumerate() {
+                assert!(a == b || diff.contains(&i));
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Scoring of corpus entries by the insertion points of their own generalization, to bias the corpus
+//! towards entries with a richer structure for Grimoire. The [`crate::stages::GeneralizationStage`]
+//! scores each entry it generalizes with [`record_max_gaps`], so that an entry is never scored with
+//! the generalization of the entry it got derived from.
+
+use alloc::string::ToString;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId},
+    inputs::{GeneralizedInputMetadata, GeneralizedItem},
+    state::{HasCorpus, HasMetadata},
+    Error,
+};
+
+/// A state metadata holding the highest number of insertion points of a generalized entry so far
+#[derive(Default, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct MaxGapsMetadata {
+    /// The highest number of insertion points seen so far
+    pub max_gaps: usize,
+}
+
+crate::impl_serdeany!(MaxGapsMetadata);
+
+/// A testcase metadata marking an entry that had more insertion points than any entry generalized before it
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct MaxGapsRecordMetadata {
+    /// The number of insertion points of the entry
+    pub gaps: usize,
+}
+
+crate::impl_serdeany!(MaxGapsRecordMetadata);
+
+/// The number of insertion points of a generalization. A [`GeneralizedItem::GapRun`] counts with its length.
+#[must_use]
+pub fn gap_count(meta: &GeneralizedInputMetadata) -> usize {
+    meta.generalized()
+        .iter()
+        .filter_map(GeneralizedItem::gap_len)
+        .sum()
+}
+
+/// Scores the corpus entry at `idx` by the insertion points of its own [`GeneralizedInputMetadata`].
+/// If it has more of them than any entry scored before, the maximum in the [`MaxGapsMetadata`] of the state
+/// grows, the entry gets marked with a [`MaxGapsRecordMetadata`], and this returns `true`.
+/// Entries without a generalization are not scored.
+pub fn record_max_gaps<S>(state: &mut S, idx: CorpusId) -> Result<bool, Error>
+where
+    S: HasCorpus + HasMetadata,
+{
+    let Some(gaps) = state
+        .corpus()
+        .get(idx)?
+        .borrow()
+        .metadata()
+        .get::<GeneralizedInputMetadata>()
+        .map(gap_count)
+    else {
+        return Ok(false);
+    };
+    if state.metadata().get::<MaxGapsMetadata>().is_none() {
+        state.add_metadata(MaxGapsMetadata::default());
+    }
+    let seen = state
+        .metadata_mut()
+        .get_mut::<MaxGapsMetadata>()
+        .ok_or_else(|| Error::key_not_found("MaxGapsMetadata not found".to_string()))?;
+    if gaps <= seen.max_gaps {
+        return Ok(false);
+    }
+    seen.max_gaps = gaps;
+    state
+        .corpus()
+        .get(idx)?
+        .borrow_mut()
+        .add_metadata(MaxGapsRecordMetadata { gaps });
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::{
+            max_gaps::MaxGapsRecordMetadata, record_max_gaps, ConstFeedback, MaxGapsMetadata,
+        },
+        inputs::{BytesInput, GeneralizedInputMetadata},
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    fn test_record_max_gaps() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut add = |bytes: &[u8], generalized: Option<&[Option<u8>]>| {
+            let mut testcase = Testcase::new(BytesInput::new(bytes.to_vec()));
+            if let Some(generalized) = generalized {
+                testcase.add_metadata(GeneralizedInputMetadata::generalized_from_options(
+                    generalized,
+                ));
+            }
+            state.corpus_mut().add(testcase).unwrap()
+        };
+        let parent = add(
+            b"ab",
+            Some(&[None, Some(b'a'), None, None, Some(b'b'), None]),
+        );
+        // Derived from the parent, but with fewer insertion points of its own
+        let child = add(
+            b"abc",
+            Some(&[None, Some(b'a'), Some(b'b'), Some(b'c'), None]),
+        );
+        // Derived from the parent, with more insertion points than it
+        let rich_child = add(
+            b"abc",
+            Some(&[
+                None,
+                None,
+                Some(b'a'),
+                None,
+                None,
+                Some(b'b'),
+                None,
+                None,
+                Some(b'c'),
+                None,
+            ]),
+        );
+        let flat = add(
+            b"abcd",
+            Some(&[Some(b'a'), Some(b'b'), Some(b'c'), Some(b'd')]),
+        );
+        let plain = add(b"abcd", None);
+
+        let is_record = |state: &TestState, idx| {
+            state
+                .corpus()
+                .get(idx)
+                .unwrap()
+                .borrow()
+                .metadata()
+                .contains::<MaxGapsRecordMetadata>()
+        };
+        let max_gaps =
+            |state: &TestState| state.metadata().get::<MaxGapsMetadata>().unwrap().max_gaps;
+
+        assert!(record_max_gaps(&mut state, parent).unwrap());
+        assert_eq!(max_gaps(&state), 4);
+        assert!(!record_max_gaps(&mut state, child).unwrap());
+        assert!(!is_record(&state, child));
+        assert!(record_max_gaps(&mut state, rich_child).unwrap());
+        assert!(is_record(&state, rich_child));
+        assert_eq!(max_gaps(&state), 7);
+
+        assert!(!record_max_gaps(&mut state, flat).unwrap());
+        assert!(!record_max_gaps(&mut state, plain).unwrap());
+        assert!(!record_max_gaps(&mut state, parent).unwrap());
+        assert_eq!(max_gaps(&state), 7);
+    }
+}
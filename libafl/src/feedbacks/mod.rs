@@ -9,6 +9,22 @@ pub use map::*;
 
 pub mod differential;
 pub use differential::DiffFeedback;
+
+pub mod near_miss;
+pub use near_miss::NearMissFeedback;
+
+pub mod edge_hits;
+pub use edge_hits::EdgeHitsFeedback;
+
+pub mod hot_offsets;
+pub use hot_offsets::{HotOffsetsFeedback, HotOffsetsMetadata};
+
+pub mod max_gaps;
+pub use max_gaps::{record_max_gaps, MaxGapsMetadata};
+
+pub mod windowed_novelty;
+pub use windowed_novelty::{WindowedNoveltyFeedback, WindowedNoveltyMetadata};
+
 #[cfg(feature = "std")]
 pub mod concolic;
 #[cfg(feature = "std")]
@@ -17,6 +33,8 @@ pub use concolic::ConcolicFeedback;
 #[cfg(feature = "std")]
 pub mod new_hash_feedback;
 #[cfg(feature = "std")]
+pub use new_hash_feedback::NewCrashPcFeedback;
+#[cfg(feature = "std")]
 pub use new_hash_feedback::NewHashFeedback;
 #[cfg(feature = "std")]
 pub use new_hash_feedback::NewHashFeedbackMetadata;
@@ -878,6 +896,59 @@ impl Default for TimeoutFeedback {
 /// A feedback factory for timeout feedbacks
 pub type TimeoutFeedbackFactory = DefaultFeedbackFactory<TimeoutFeedback>;
 
+/// A [`SoftFailFeedback`] reports runs in which the target detected a logic error, see [`ExitKind::SoftFail`].
+/// If it only accepts a single error code, other soft failures are not interesting.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SoftFailFeedback {
+    code: Option<u32>,
+}
+
+impl<S> Feedback<S> for SoftFailFeedback
+where
+    S: UsesInput + HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        _observers: &OT,
+        exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        if let ExitKind::SoftFail(code) = exit_kind {
+            Ok(self.code.is_none() || self.code == *code)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl Named for SoftFailFeedback {
+    #[inline]
+    fn name(&self) -> &str {
+        "SoftFailFeedback"
+    }
+}
+
+impl SoftFailFeedback {
+    /// Returns a new [`SoftFailFeedback`], reporting all soft failures.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { code: None }
+    }
+
+    /// Returns a new [`SoftFailFeedback`], only reporting soft failures with the given error `code`.
+    #[must_use]
+    pub fn with_code(code: u32) -> Self {
+        Self { code: Some(code) }
+    }
+}
+
 /// Nop feedback that annotates execution time in the new testcase, if any
 /// for this Feedback, the testcase is never interesting (use with an OR).
 /// It decides, if the given [`TimeObserver`] value of a run is interesting.
@@ -0,0 +1,104 @@
+//! Near-miss feedback, attaching a [`NearMissMetadata`] score to the testcase,
+//! for use with the [`crate::schedulers::NearMissScheduler`].
+//! This feedback should be used in combination with another feedback as this feedback always considers testcases
+//! to be not interesting.
+use alloc::{borrow::ToOwned, string::String};
+use core::{fmt::Debug, marker::PhantomData};
+
+use crate::{
+    bolts::tuples::Named,
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverName},
+    inputs::UsesInput,
+    observers::{MapObserver, ObserversTuple},
+    schedulers::near_miss_sched::NearMissMetadata,
+    state::{HasClientPerfMonitor, HasMetadata},
+    Error,
+};
+
+/// The near-miss feedback. It reads a [`MapObserver`] in which each set entry is a near-miss
+/// (for example, a comparison towards an uncovered branch that almost succeeded),
+/// and attaches the number of near-misses of the run to the testcase as its [`NearMissMetadata`] score.
+/// This feedback should be used in combination with another feedback as this feedback always considers testcases
+/// to be not interesting.
+#[derive(Debug)]
+pub struct NearMissFeedback<O, S> {
+    name: String,
+    score: Option<f64>,
+    phantom: PhantomData<(O, S)>,
+}
+
+impl<O, S> NearMissFeedback<O, S>
+where
+    O: MapObserver,
+{
+    /// Creates a near-miss feedback from an observer
+    #[must_use]
+    pub fn new(observer: &O) -> Self {
+        Self {
+            name: observer.name().to_owned(),
+            score: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<O, S> Named for NearMissFeedback<O, S> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<O, S> HasObserverName for NearMissFeedback<O, S> {
+    fn observer_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<O, S> Feedback<S> for NearMissFeedback<O, S>
+where
+    O: MapObserver,
+    S: UsesInput + Debug + HasClientPerfMonitor,
+{
+    #[allow(clippy::wrong_self_convention)]
+    #[allow(clippy::cast_precision_loss)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        _state: &mut S,
+        _manager: &mut EM,
+        _input: &<S as UsesInput>::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        self.score = observers
+            .match_name::<O>(&self.name)
+            .map(|observer| observer.count_bytes() as f64);
+        Ok(false)
+    }
+
+    fn append_metadata(
+        &mut self,
+        _state: &mut S,
+        testcase: &mut Testcase<<S as UsesInput>::Input>,
+    ) -> Result<(), Error> {
+        if let Some(score) = self.score.take() {
+            testcase.add_metadata(NearMissMetadata::new(score));
+        }
+        Ok(())
+    }
+
+    fn discard_metadata(
+        &mut self,
+        _state: &mut S,
+        _input: &<S as UsesInput>::Input,
+    ) -> Result<(), Error> {
+        self.score = None;
+        Ok(())
+    }
+}
@@ -12,7 +12,7 @@ use crate::{
     executors::ExitKind,
     feedbacks::{Feedback, HasObserverName},
     inputs::UsesInput,
-    observers::{ObserverWithHashField, ObserversTuple},
+    observers::{CrashPcObserver, ObserverWithHashField, ObserversTuple},
     state::{HasClientPerfMonitor, HasNamedMetadata},
     Error,
 };
@@ -66,6 +66,10 @@ impl HashSetState<u64> for NewHashFeedbackMetadata {
     }
 }
 
+/// A [`NewHashFeedback`] on the faulting program counter of a [`CrashPcObserver`].
+/// Used as objective, it only saves crashes at program counters that did not crash before.
+pub type NewCrashPcFeedback<S> = NewHashFeedback<CrashPcObserver, S>;
+
 /// A [`NewHashFeedback`] maintains a hashset of already seen stacktraces and considers interesting unseen ones
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct NewHashFeedback<O, S> {
@@ -160,3 +164,60 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{ConstFeedback, NewCrashPcFeedback},
+        fuzzer::{ExecutionProcessor, StdFuzzer},
+        inputs::BytesInput,
+        observers::{CrashPcObserver, Observer},
+        schedulers::QueueScheduler,
+        state::{HasSolutions, StdState},
+    };
+
+    #[test]
+    fn test_new_crash_pc() {
+        let mut observers = tuple_list!(CrashPcObserver::default());
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = NewCrashPcFeedback::new(&observers.0);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+
+        for (i, pc) in [0x1000, 0x1000, 0x2000].into_iter().enumerate() {
+            let input = BytesInput::new(vec![i as u8]);
+            observers.0.pre_exec(&mut state, &input).unwrap();
+            // The crash handler records the faulting pc
+            observers.0.set_pc(pc);
+            observers
+                .0
+                .post_exec(&mut state, &input, &ExitKind::Crash)
+                .unwrap();
+            fuzzer
+                .process_execution(
+                    &mut state,
+                    &mut mgr,
+                    input,
+                    &observers,
+                    &ExitKind::Crash,
+                    false,
+                )
+                .unwrap();
+        }
+
+        // The second crash faulted at a known pc
+        assert_eq!(state.solutions().count(), 2);
+    }
+}
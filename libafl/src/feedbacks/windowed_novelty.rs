@@ -0,0 +1,196 @@
+//! The [`WindowedNoveltyFeedback`] considers an edge of a [`MapObserver`] as new if it was not hit
+//! within a sliding time window, to focus late campaigns on recently (re)discovered behavior.
+
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use core::{fmt::Debug, marker::PhantomData, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{current_time, tuples::Named},
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverName},
+    inputs::UsesInput,
+    observers::{MapObserver, ObserversTuple},
+    state::{HasClientPerfMonitor, HasNamedMetadata},
+    Error,
+};
+
+/// The prefix of the name of a [`WindowedNoveltyFeedback`], and of its [`WindowedNoveltyMetadata`],
+/// followed by the name of the observer
+pub const WINDOWED_NOVELTY_PREFIX: &str = "windowed_novelty_";
+
+/// The state of a [`WindowedNoveltyFeedback`], the time each edge got hit last
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WindowedNoveltyMetadata {
+    /// The time each edge got hit last, indexed by map index, `None` if it never got hit
+    pub last_seen: Vec<Option<Duration>>,
+}
+
+crate::impl_serdeany!(WindowedNoveltyMetadata);
+
+impl WindowedNoveltyMetadata {
+    /// Records the edges at the given map indexes as hit at `now`.
+    /// Returns `true` if any of them was not hit within the `window` before.
+    pub fn record(&mut self, indexes: &[usize], now: Duration, window: Duration) -> bool {
+        let mut novel = false;
+        for idx in indexes {
+            if *idx >= self.last_seen.len() {
+                self.last_seen.resize(idx + 1, None);
+            }
+            let last = &mut self.last_seen[*idx];
+            if last.map_or(true, |last| now.saturating_sub(last) > window) {
+                novel = true;
+            }
+            *last = Some(now);
+        }
+        novel
+    }
+}
+
+/// A [`Feedback`] reporting an input as interesting if it hits an edge of a [`MapObserver`]
+/// that no execution hit within the last `window`, or ever.
+/// Unlike for a [`crate::feedbacks::MapFeedback`], old edges become new again once they were not hit for long enough.
+/// The time each edge got hit last is kept in the [`WindowedNoveltyMetadata`] of the state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowedNoveltyFeedback<O, S> {
+    name: String,
+    observer_name: String,
+    window: Duration,
+    /// The set indexes of the last execution, kept to reuse the allocation
+    #[serde(skip)]
+    indexes: Vec<usize>,
+    phantom: PhantomData<(O, S)>,
+}
+
+impl<O, S> WindowedNoveltyFeedback<O, S>
+where
+    O: MapObserver,
+{
+    /// Creates a new [`WindowedNoveltyFeedback`] for the given observer, with the given time `window`
+    #[must_use]
+    pub fn new(observer: &O, window: Duration) -> Self {
+        Self {
+            name: WINDOWED_NOVELTY_PREFIX.to_owned() + observer.name(),
+            observer_name: observer.name().to_owned(),
+            window,
+            indexes: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The time window within which a hit edge is not new
+    #[must_use]
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+}
+
+impl<O, S> Named for WindowedNoveltyFeedback<O, S> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<O, S> HasObserverName for WindowedNoveltyFeedback<O, S> {
+    fn observer_name(&self) -> &str {
+        &self.observer_name
+    }
+}
+
+impl<O, S> Feedback<S> for WindowedNoveltyFeedback<O, S>
+where
+    O: MapObserver,
+    S: UsesInput + Debug + HasClientPerfMonitor + HasNamedMetadata,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        state.add_named_metadata(WindowedNoveltyMetadata::default(), &self.name);
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &<S as UsesInput>::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let observer = observers
+            .match_name::<O>(&self.observer_name)
+            .ok_or_else(|| Error::key_not_found("MapObserver not found".to_owned()))?;
+        observer.set_indexes(&mut self.indexes);
+        let meta = state
+            .named_metadata_mut()
+            .get_mut::<WindowedNoveltyMetadata>(&self.name)
+            .ok_or_else(|| Error::key_not_found("WindowedNoveltyMetadata not found".to_owned()))?;
+        Ok(meta.record(&self.indexes, current_time(), self.window))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{ConstFeedback, Feedback, WindowedNoveltyFeedback, WindowedNoveltyMetadata},
+        inputs::BytesInput,
+        observers::{MapObserver, StdMapObserver},
+        state::{HasNamedMetadata, StdState},
+    };
+
+    #[test]
+    fn test_windowed_novelty_feedback() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut mgr = NopEventManager::new();
+
+        let mut observer = StdMapObserver::new_owned("map", vec![0_u8; 16]);
+        let mut feedback = WindowedNoveltyFeedback::new(&observer, Duration::from_secs(3600));
+        feedback.init_state(&mut state).unwrap();
+        let input = BytesInput::new(vec![]);
+
+        *observer.get_mut(3) = 1;
+        let mut observers = tuple_list!(observer);
+        let mut is_interesting = |state: &mut _, observers: &_| {
+            feedback
+                .is_interesting(state, &mut mgr, &input, observers, &ExitKind::Ok)
+                .unwrap()
+        };
+        assert!(is_interesting(&mut state, &observers));
+        // Hit again within the window
+        assert!(!is_interesting(&mut state, &observers));
+
+        // A new edge is novel, the old one alongside it does not matter
+        *observers.0.get_mut(5) = 1;
+        assert!(is_interesting(&mut state, &observers));
+        assert!(!is_interesting(&mut state, &observers));
+
+        // Edges not hit within the window become novel again
+        let meta = state
+            .named_metadata_mut()
+            .get_mut::<WindowedNoveltyMetadata>("windowed_novelty_map")
+            .unwrap();
+        meta.last_seen[5] = Some(Duration::ZERO);
+        assert!(is_interesting(&mut state, &observers));
+        assert!(!is_interesting(&mut state, &observers));
+    }
+}
@@ -5,22 +5,20 @@ use core::{fmt::Debug, marker::PhantomData, time::Duration};
 
 use serde::{de::DeserializeOwned, Serialize};
 
-#[cfg(test)]
-use crate::inputs::Input;
 #[cfg(feature = "introspection")]
 use crate::monitors::PerfFeature;
 #[cfg(test)]
 use crate::state::NopState;
 use crate::{
-    bolts::current_time,
+    bolts::{current_time, serdeany::SerdeAnyMap},
     corpus::{Corpus, CorpusId, Testcase},
     events::{Event, EventConfig, EventFirer, EventProcessor, ProgressReporter},
     executors::{Executor, ExitKind, HasObservers},
     feedbacks::Feedback,
-    inputs::UsesInput,
+    inputs::{EmptyInputPolicy, Input, UsesInput},
     mark_feature_time,
     observers::ObserversTuple,
-    schedulers::Scheduler,
+    schedulers::{self, Scheduler},
     stages::StagesTuple,
     start_timer,
     state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, HasSolutions, UsesState},
@@ -136,6 +134,17 @@ where
         send_events: bool,
     ) -> Result<(ExecuteInputResult, Option<CorpusId>), Error>;
 
+    /// Like [`Evaluator::evaluate_input`], but moves the given `metadata` into the new testcase, if any,
+    /// before the scheduler sees it in `on_add`. Entries of the same type the feedback appends win.
+    fn evaluate_input_with_metadata(
+        &mut self,
+        state: &mut Self::State,
+        executor: &mut E,
+        manager: &mut EM,
+        input: <Self::State as UsesInput>::Input,
+        metadata: SerdeAnyMap,
+    ) -> Result<(ExecuteInputResult, Option<CorpusId>), Error>;
+
     /// Runs the input and triggers observers and feedback.
     /// Adds an input, to the corpus even if it's not considered `interesting` by the `feedback`.
     /// Returns the `index` of the new testcase in the corpus.
@@ -146,6 +155,19 @@ where
         executor: &mut E,
         manager: &mut EM,
         input: <Self::State as UsesInput>::Input,
+    ) -> Result<CorpusId, Error> {
+        self.add_input_with_metadata(state, executor, manager, input, SerdeAnyMap::new())
+    }
+
+    /// Like [`Evaluator::add_input`], but moves the given `metadata` into the new testcase
+    /// before the scheduler sees it in `on_add`. Entries of the same type the feedback appends win.
+    fn add_input_with_metadata(
+        &mut self,
+        state: &mut Self::State,
+        executor: &mut E,
+        manager: &mut EM,
+        input: <Self::State as UsesInput>::Input,
+        metadata: SerdeAnyMap,
     ) -> Result<CorpusId, Error>;
 }
 
@@ -254,6 +276,7 @@ where
     scheduler: CS,
     feedback: F,
     objective: OF,
+    empty_input_policy: EmptyInputPolicy,
     phantom: PhantomData<OT>,
 }
 
@@ -327,7 +350,7 @@ where
     F: Feedback<CS::State>,
     OF: Feedback<CS::State>,
     OT: ObserversTuple<CS::State> + Serialize + DeserializeOwned,
-    CS::State: HasCorpus + HasSolutions + HasClientPerfMonitor + HasExecutions,
+    CS::State: HasCorpus + HasSolutions + HasClientPerfMonitor + HasExecutions + HasMetadata,
 {
     /// Evaluate if a set of observation channels has an interesting state
     fn process_execution<EM>(
@@ -341,6 +364,41 @@ where
     ) -> Result<(ExecuteInputResult, Option<CorpusId>), Error>
     where
         EM: EventFirer<State = Self::State>,
+    {
+        self.process_execution_with_metadata(
+            state,
+            manager,
+            input,
+            observers,
+            exit_kind,
+            send_events,
+            SerdeAnyMap::new(),
+        )
+    }
+}
+
+impl<CS, F, OF, OT> StdFuzzer<CS, F, OF, OT>
+where
+    CS: Scheduler,
+    F: Feedback<CS::State>,
+    OF: Feedback<CS::State>,
+    OT: ObserversTuple<CS::State> + Serialize + DeserializeOwned,
+    CS::State: HasCorpus + HasSolutions + HasClientPerfMonitor + HasExecutions + HasMetadata,
+{
+    /// Like `process_execution`, moving the given `metadata` into the new corpus entry, if any
+    #[allow(clippy::too_many_arguments)]
+    fn process_execution_with_metadata<EM>(
+        &mut self,
+        state: &mut CS::State,
+        manager: &mut EM,
+        input: <CS::State as UsesInput>::Input,
+        observers: &OT,
+        exit_kind: &ExitKind,
+        send_events: bool,
+        metadata: SerdeAnyMap,
+    ) -> Result<(ExecuteInputResult, Option<CorpusId>), Error>
+    where
+        EM: EventFirer<State = CS::State>,
     {
         let mut res = ExecuteInputResult::None;
 
@@ -384,6 +442,7 @@ where
 
                 // Add the input to the main corpus
                 let mut testcase = Testcase::with_executions(input.clone(), *state.executions());
+                testcase.metadata_mut().extend(metadata);
                 self.feedback_mut().append_metadata(state, &mut testcase)?;
                 let idx = state.corpus_mut().add(testcase)?;
                 self.scheduler_mut().on_add(state, idx)?;
@@ -440,7 +499,7 @@ where
     OT: ObserversTuple<CS::State> + Serialize + DeserializeOwned,
     F: Feedback<CS::State>,
     OF: Feedback<CS::State>,
-    CS::State: HasCorpus + HasSolutions + HasClientPerfMonitor + HasExecutions,
+    CS::State: HasCorpus + HasSolutions + HasClientPerfMonitor + HasExecutions + HasMetadata,
 {
     /// Process one input, adding to the respective corpora if needed and firing the right events
     #[inline]
@@ -456,6 +515,10 @@ where
         E: Executor<EM, Self> + HasObservers<Observers = OT, State = Self::State>,
         EM: EventFirer<State = Self::State>,
     {
+        let mut input = input;
+        if !input.apply_empty_input_policy(self.empty_input_policy) {
+            return Ok((ExecuteInputResult::None, None));
+        }
         let exit_kind = self.execute_input(state, executor, manager, &input)?;
         let observers = executor.observers();
         self.process_execution(state, manager, input, observers, &exit_kind, send_events)
@@ -470,7 +533,7 @@ where
     F: Feedback<CS::State>,
     OF: Feedback<CS::State>,
     OT: ObserversTuple<CS::State> + Serialize + DeserializeOwned,
-    CS::State: HasCorpus + HasSolutions + HasClientPerfMonitor + HasExecutions,
+    CS::State: HasCorpus + HasSolutions + HasClientPerfMonitor + HasExecutions + HasMetadata,
 {
     /// Process one input, adding to the respective corpora if needed and firing the right events
     #[inline]
@@ -485,14 +548,41 @@ where
         self.evaluate_input_with_observers(state, executor, manager, input, send_events)
     }
 
+    /// Process one input like `evaluate_input`, moving the `metadata` into the new corpus entry, if any
+    fn evaluate_input_with_metadata(
+        &mut self,
+        state: &mut CS::State,
+        executor: &mut E,
+        manager: &mut EM,
+        input: <CS::State as UsesInput>::Input,
+        metadata: SerdeAnyMap,
+    ) -> Result<(ExecuteInputResult, Option<CorpusId>), Error> {
+        let mut input = input;
+        if !input.apply_empty_input_policy(self.empty_input_policy) {
+            return Ok((ExecuteInputResult::None, None));
+        }
+        let exit_kind = self.execute_input(state, executor, manager, &input)?;
+        let observers = executor.observers();
+        self.process_execution_with_metadata(
+            state, manager, input, observers, &exit_kind, true, metadata,
+        )
+    }
+
     /// Adds an input, even if it's not considered `interesting` by any of the executors
-    fn add_input(
+    fn add_input_with_metadata(
         &mut self,
         state: &mut CS::State,
         executor: &mut E,
         manager: &mut EM,
         input: <CS::State as UsesInput>::Input,
+        metadata: SerdeAnyMap,
     ) -> Result<CorpusId, Error> {
+        let mut input = input;
+        if !input.apply_empty_input_policy(self.empty_input_policy) {
+            return Err(Error::illegal_argument(
+                "Can not add an empty input, the EmptyInputPolicy skips them",
+            ));
+        }
         let exit_kind = self.execute_input(state, executor, manager, &input)?;
         let observers = executor.observers();
         // Always consider this to be "interesting"
@@ -502,6 +592,7 @@ where
 
         // Add the input to the main corpus
         let mut testcase = Testcase::with_executions(input.clone(), *state.executions());
+        testcase.metadata_mut().extend(metadata);
         self.feedback_mut().append_metadata(state, &mut testcase)?;
         let idx = state.corpus_mut().add(testcase)?;
         self.scheduler_mut().on_add(state, idx)?;
@@ -534,7 +625,7 @@ where
     EM: ProgressReporter + EventProcessor<E, Self, State = CS::State>,
     F: Feedback<CS::State>,
     OF: Feedback<CS::State>,
-    CS::State: HasClientPerfMonitor + HasExecutions + HasMetadata,
+    CS::State: HasClientPerfMonitor + HasExecutions + HasMetadata + HasCorpus,
     ST: StagesTuple<E, EM, CS::State, Self>,
 {
     fn fuzz_one(
@@ -548,8 +639,8 @@ where
         #[cfg(feature = "introspection")]
         state.introspection_monitor_mut().start_timer();
 
-        // Get the next index from the scheduler
-        let idx = self.scheduler.next(state)?;
+        // Get the next (enabled) index from the scheduler
+        let idx = self.next_enabled(state)?;
 
         // Mark the elapsed time for the scheduler
         #[cfg(feature = "introspection")]
@@ -590,10 +681,25 @@ where
             scheduler,
             feedback,
             objective,
+            empty_input_policy: EmptyInputPolicy::default(),
             phantom: PhantomData,
         }
     }
 
+    /// The [`EmptyInputPolicy`] applied to the inputs before they get evaluated
+    pub fn empty_input_policy(&self) -> EmptyInputPolicy {
+        self.empty_input_policy
+    }
+
+    /// Sets the [`EmptyInputPolicy`] applied to the inputs before they get evaluated,
+    /// to skip empty inputs or to coerce them to a single byte.
+    /// It applies to the inputs evaluated or added through the [`Evaluator`] and [`EvaluatorObservers`] methods,
+    /// where [`Evaluator::add_input`] errors on skipped inputs. Inputs run with [`Self::execute_input`] directly,
+    /// as by the calibration and minimization stages, or executed for the push stages, are not affected.
+    pub fn set_empty_input_policy(&mut self, policy: EmptyInputPolicy) {
+        self.empty_input_policy = policy;
+    }
+
     /// Runs the input and triggers observers and feedback
     pub fn execute_input<E, EM>(
         &mut self,
@@ -627,6 +733,21 @@ where
     }
 }
 
+impl<CS, F, OF, OT> StdFuzzer<CS, F, OF, OT>
+where
+    CS: Scheduler,
+    F: Feedback<CS::State>,
+    OF: Feedback<CS::State>,
+    CS::State: HasClientPerfMonitor + HasCorpus,
+{
+    /// Gets the next entry from the scheduler, skipping disabled [`Testcase`]s.
+    /// The stock schedulers already skip them, this also covers custom [`Scheduler`]s.
+    /// Errors with [`Error::Empty`] if all entries in the corpus are disabled.
+    pub fn next_enabled(&mut self, state: &mut CS::State) -> Result<CorpusId, Error> {
+        schedulers::next_enabled(state, |state| self.scheduler.next(state))
+    }
+}
+
 /// Structs with this trait will execute an input
 pub trait ExecutesInput<E, EM>: UsesState
 where
@@ -722,6 +843,248 @@ where
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use crate::{
+        bolts::{rands::StdRand, serdeany::SerdeAnyMap, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        feedbacks::ConstFeedback,
+        fuzzer::{Evaluator, ExecuteInputResult, Fuzzer},
+        inputs::{BytesInput, EmptyInputPolicy, HasBytesVec},
+        schedulers::{fairness::FamilyMetadata, QueueScheduler, Scheduler},
+        state::{HasCorpus, HasExecutions, HasMetadata, StdState},
+        StdFuzzer,
+    };
+
+    #[test]
+    fn test_skip_disabled() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let scheduler = QueueScheduler::new();
+        let mut ids = vec![];
+        for i in 0..3_u8 {
+            let idx = state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(vec![i])))
+                .unwrap();
+            scheduler.on_add(&mut state, idx).unwrap();
+            ids.push(idx);
+        }
+        state
+            .corpus()
+            .get(ids[1])
+            .unwrap()
+            .borrow_mut()
+            .set_disabled(true);
+
+        let mut fuzzer = StdFuzzer::<_, _, _, ()>::new(scheduler, feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        let mut counts = [0; 3];
+        for _ in 0..30 {
+            let idx = fuzzer
+                .fuzz_one(&mut (), &mut executor, &mut state, &mut mgr)
+                .unwrap();
+            counts[ids.iter().position(|id| *id == idx).unwrap()] += 1;
+        }
+        assert_eq!(counts, [15, 0, 15]);
+
+        // The disabled entry is still in the corpus, and with all entries disabled, fuzzing errors out
+        assert_eq!(state.corpus().count(), 3);
+        for idx in &ids {
+            state
+                .corpus()
+                .get(*idx)
+                .unwrap()
+                .borrow_mut()
+                .set_disabled(true);
+        }
+        assert!(fuzzer
+            .fuzz_one(&mut (), &mut executor, &mut state, &mut mgr)
+            .is_err());
+    }
+
+    #[test]
+    fn test_input_with_metadata() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut fuzzer = StdFuzzer::<_, _, _, ()>::new(QueueScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+        let metadata = |family| {
+            let mut metadata = SerdeAnyMap::new();
+            metadata.insert(FamilyMetadata::new(family));
+            metadata
+        };
+
+        // The metadata of an uninteresting input gets dropped along with it
+        let res = fuzzer
+            .evaluate_input_with_metadata(
+                &mut state,
+                &mut executor,
+                &mut mgr,
+                BytesInput::new(vec![b'a']),
+                metadata(1),
+            )
+            .unwrap();
+        assert_eq!(res, (ExecuteInputResult::None, None));
+
+        let idx = fuzzer
+            .add_input_with_metadata(
+                &mut state,
+                &mut executor,
+                &mut mgr,
+                BytesInput::new(vec![b'b']),
+                metadata(2),
+            )
+            .unwrap();
+        let idx_plain = fuzzer
+            .add_input(
+                &mut state,
+                &mut executor,
+                &mut mgr,
+                BytesInput::new(vec![b'c']),
+            )
+            .unwrap();
+        let family = |idx| {
+            state
+                .corpus()
+                .get(idx)
+                .unwrap()
+                .borrow()
+                .metadata()
+                .get::<FamilyMetadata>()
+                .map(|meta| meta.family)
+        };
+        assert_eq!(family(idx), Some(2));
+        assert_eq!(family(idx_plain), None);
+        assert!(state.metadata().is_empty());
+    }
+
+    #[test]
+    fn test_empty_input_policy() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut fuzzer = StdFuzzer::<_, _, _, ()>::new(QueueScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let delivered = RefCell::new(vec![]);
+        let mut harness = |input: &BytesInput| {
+            delivered.borrow_mut().push(input.bytes().to_vec());
+            ExitKind::Ok
+        };
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        // By default, empty inputs get executed as they are
+        assert_eq!(fuzzer.empty_input_policy(), EmptyInputPolicy::Execute);
+        fuzzer
+            .evaluate_input(&mut state, &mut executor, &mut mgr, BytesInput::new(vec![]))
+            .unwrap();
+        assert_eq!(*state.executions(), 1);
+
+        fuzzer.set_empty_input_policy(EmptyInputPolicy::Skip);
+        let res = fuzzer
+            .evaluate_input(&mut state, &mut executor, &mut mgr, BytesInput::new(vec![]))
+            .unwrap();
+        assert_eq!(res, (ExecuteInputResult::None, None));
+        assert_eq!(*state.executions(), 1);
+        assert!(fuzzer
+            .add_input(&mut state, &mut executor, &mut mgr, BytesInput::new(vec![]))
+            .is_err());
+        assert_eq!(*state.executions(), 1);
+
+        fuzzer.set_empty_input_policy(EmptyInputPolicy::Coerce(b'A'));
+        fuzzer
+            .evaluate_input(&mut state, &mut executor, &mut mgr, BytesInput::new(vec![]))
+            .unwrap();
+        // Non-empty inputs are never touched
+        fuzzer
+            .evaluate_input(
+                &mut state,
+                &mut executor,
+                &mut mgr,
+                BytesInput::new(vec![b'x']),
+            )
+            .unwrap();
+        assert_eq!(*state.executions(), 3);
+
+        // Added inputs get coerced, too
+        let idx = fuzzer
+            .add_input(&mut state, &mut executor, &mut mgr, BytesInput::new(vec![]))
+            .unwrap();
+        assert_eq!(
+            state
+                .corpus()
+                .get(idx)
+                .unwrap()
+                .borrow()
+                .input()
+                .as_ref()
+                .unwrap()
+                .bytes(),
+            b"A"
+        );
+        assert_eq!(
+            *delivered.borrow(),
+            vec![vec![], vec![b'A'], vec![b'x'], vec![b'A']]
+        );
+    }
+}
+
 #[cfg(feature = "python")]
 #[allow(missing_docs)]
 /// `Fuzzer` Python bindings
@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 use crate::{bolts::fs::write_file_atomic, Error};
 use crate::{
     bolts::{ownedref::OwnedSlice, HasLen},
-    inputs::{HasBytesVec, HasTargetBytes, Input},
+    inputs::{EmptyInputPolicy, HasBytesVec, HasTargetBytes, Input},
 };
 
 /// A bytes input is the basic input
@@ -45,12 +45,34 @@ impl Input for BytesInput {
         Ok(BytesInput::new(bytes))
     }
 
+    /// The raw bytes of this input
+    #[cfg(feature = "std")]
+    fn to_file_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.bytes.clone())
+    }
+
+    /// Takes the raw bytes as they are
+    #[cfg(feature = "std")]
+    fn from_file_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(BytesInput::new(bytes.to_vec()))
+    }
+
     /// Generate a name for this input
     fn generate_name(&self, _idx: usize) -> String {
         let mut hasher = AHasher::new_with_keys(0, 0);
         hasher.write(self.bytes());
         format!("{:016x}", hasher.finish())
     }
+
+    /// Skips or coerces this input, if it is empty
+    fn apply_empty_input_policy(&mut self, policy: EmptyInputPolicy) -> bool {
+        policy.apply_to_bytes(&mut self.bytes)
+    }
+
+    #[inline]
+    fn len_hint(&self) -> Option<usize> {
+        Some(self.len())
+    }
 }
 
 /// Rc Ref-cell from Input
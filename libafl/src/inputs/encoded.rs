@@ -205,6 +205,11 @@ impl Input for EncodedInput {
         }
         format!("{:016x}", hasher.finish())
     }
+
+    #[inline]
+    fn len_hint(&self) -> Option<usize> {
+        Some(self.len())
+    }
 }
 
 /// Rc Ref-cell from Input
@@ -0,0 +1,207 @@
+//! The `FixedLenBytesInput` is a bytes input for targets that only accept inputs of exactly `N` bytes.
+//! Mutators may freely grow and shrink the bytes, but the target always gets them padded or truncated to `N` bytes.
+//! On disk, the input is stored as the exactly `N` bytes the target gets, like a [`crate::inputs::BytesInput`],
+//! so that files can be shared with other tools, and loaded as seeds.
+
+use alloc::{borrow::ToOwned, rc::Rc, string::String, vec::Vec};
+use core::{cell::RefCell, convert::From, hash::Hasher};
+
+use ahash::AHasher;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::{bolts::AsSlice, Error};
+use crate::{
+    bolts::{ownedref::OwnedSlice, HasLen},
+    inputs::{HasBytesVec, HasTargetBytes, Input},
+};
+
+/// A bytes input that is always delivered to the target as exactly `N` bytes.
+/// Shorter inputs get padded with the `padding` byte, longer inputs get truncated.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FixedLenBytesInput<const N: usize> {
+    /// The raw input bytes, of any length
+    bytes: Vec<u8>,
+    /// The byte used to pad inputs shorter than `N`
+    padding: u8,
+}
+
+impl<const N: usize> Input for FixedLenBytesInput<N> {
+    /// The exactly `N` bytes the target gets
+    #[cfg(feature = "std")]
+    fn to_file_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.target_bytes().as_slice().to_vec())
+    }
+
+    /// Takes the raw bytes as they are, of any length, padding with `0`
+    #[cfg(feature = "std")]
+    fn from_file_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::new(bytes.to_vec()))
+    }
+
+    /// Generate a name for this input, based on its raw bytes
+    fn generate_name(&self, _idx: usize) -> String {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write(self.bytes());
+        format!("{:016x}", hasher.finish())
+    }
+
+    #[inline]
+    fn len_hint(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+/// Rc Ref-cell from Input
+impl<const N: usize> From<FixedLenBytesInput<N>> for Rc<RefCell<FixedLenBytesInput<N>>> {
+    fn from(input: FixedLenBytesInput<N>) -> Self {
+        Rc::new(RefCell::new(input))
+    }
+}
+
+impl<const N: usize> HasBytesVec for FixedLenBytesInput<N> {
+    #[inline]
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    #[inline]
+    fn bytes_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.bytes
+    }
+}
+
+impl<const N: usize> HasTargetBytes for FixedLenBytesInput<N> {
+    /// The bytes of this input, padded or truncated to exactly `N` bytes
+    #[inline]
+    fn target_bytes(&self) -> OwnedSlice<u8> {
+        if self.bytes.len() >= N {
+            OwnedSlice::from(&self.bytes[..N])
+        } else {
+            let mut bytes = self.bytes.clone();
+            bytes.resize(N, self.padding);
+            OwnedSlice::from(bytes)
+        }
+    }
+}
+
+impl<const N: usize> HasLen for FixedLenBytesInput<N> {
+    /// The length of the raw bytes, before they get padded or truncated to `N` bytes
+    #[inline]
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+impl<const N: usize> From<Vec<u8>> for FixedLenBytesInput<N> {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl<const N: usize> From<&[u8]> for FixedLenBytesInput<N> {
+    fn from(bytes: &[u8]) -> Self {
+        Self::new(bytes.to_owned())
+    }
+}
+
+impl<const N: usize> FixedLenBytesInput<N> {
+    /// Creates a new fixed-length input using the given bytes, padding with `0`
+    #[must_use]
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self::with_padding(bytes, 0)
+    }
+
+    /// Creates a new fixed-length input using the given bytes, padding with the given byte
+    #[must_use]
+    pub fn with_padding(bytes: Vec<u8>, padding: u8) -> Self {
+        Self { bytes, padding }
+    }
+
+    /// The byte used to pad inputs shorter than `N`
+    #[must_use]
+    pub fn padding(&self) -> u8 {
+        self.padding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list, AsSlice, HasLen},
+        corpus::InMemoryCorpus,
+        feedbacks::ConstFeedback,
+        inputs::{FixedLenBytesInput, HasBytesVec, HasTargetBytes},
+        mutators::{BytesDeleteMutator, BytesExpandMutator, BytesInsertMutator, MutatorsTuple},
+        state::StdState,
+    };
+
+    #[test]
+    fn test_fixed_len_delivery() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<FixedLenBytesInput<8>>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut mutations = tuple_list!(
+            BytesDeleteMutator::new(),
+            BytesExpandMutator::new(),
+            BytesInsertMutator::new()
+        );
+
+        let mut input = FixedLenBytesInput::<8>::with_padding(b"abcd".to_vec(), b'P');
+        assert_eq!(input.target_bytes().as_slice(), b"abcdPPPP");
+
+        let mut lens = vec![];
+        for i in 0..64 {
+            mutations
+                .get_and_mutate((i % 3).into(), &mut state, &mut input, 0)
+                .unwrap();
+            lens.push(input.bytes().len());
+            assert_eq!(input.target_bytes().as_slice().len(), 8);
+        }
+        // The mutators were free to create both shorter and longer inputs
+        assert!(lens.iter().any(|len| *len < 8));
+        assert!(lens.iter().any(|len| *len > 8));
+
+        input.bytes_mut().clear();
+        input.bytes_mut().extend_from_slice(b"0123456789");
+        assert_eq!(input.target_bytes().as_slice(), b"01234567");
+        assert_eq!(input.len(), 10);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_fixed_len_file_roundtrip() {
+        use crate::inputs::Input;
+
+        let path = std::env::temp_dir().join("libafl_test_fixed_len_roundtrip");
+        for (bytes, stored) in [
+            (b"ab".as_slice(), b"abPPPPPP"),
+            (b"0123456789", b"01234567"),
+        ] {
+            let input = FixedLenBytesInput::<8>::with_padding(bytes.to_vec(), b'P');
+            input.to_file(&path).unwrap();
+            // The file holds exactly the bytes the target gets
+            assert_eq!(std::fs::read(&path).unwrap(), stored);
+            let loaded = FixedLenBytesInput::<8>::from_file(&path).unwrap();
+            assert_eq!(loaded.bytes(), stored);
+            assert_eq!(
+                loaded.target_bytes().as_slice(),
+                input.target_bytes().as_slice()
+            );
+        }
+
+        // Raw seeds of any length load as they are
+        std::fs::write(&path, b"abc").unwrap();
+        let loaded = FixedLenBytesInput::<8>::from_file(&path).unwrap();
+        assert_eq!(loaded.target_bytes().as_slice(), b"abc\0\0\0\0\0");
+        std::fs::remove_file(path).unwrap();
+    }
+}
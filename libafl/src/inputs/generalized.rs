@@ -1,13 +1,20 @@
-//! The `GeneralizedInput` is an input that ca be generalized to represent a rule, used by Grimoire
+//! The `GeneralizedInput` is an input that can be generalized to represent a rule, used by Grimoire
 
-use alloc::vec::Vec;
+use alloc::{rc::Rc, string::String, vec::Vec};
+use core::{
+    cell::RefCell,
+    fmt::{self, Write},
+    hash::Hasher,
+};
 
+use ahash::AHasher;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    bolts::{ownedref::OwnedSlice, rands::Rand, HasLen},
     corpus::{Corpus, CorpusId, Testcase},
     impl_serdeany,
-    inputs::BytesInput,
+    inputs::{BytesInput, EmptyInputPolicy, HasBytesVec, HasTargetBytes, Input},
     stages::mutational::{MutatedTransform, MutatedTransformPost},
     state::{HasCorpus, HasMetadata},
     Error,
@@ -16,12 +23,51 @@ use crate::{
 /// An item of the generalized input
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum GeneralizedItem {
-    /// Real bytes
-    Bytes(Vec<u8>),
+    /// Real bytes, shared between the clones of the item, so that cloning a generalization is cheap.
+    /// Mutations replace the whole run.
+    Bytes(Rc<[u8]>),
     /// An insertion point
     Gap,
+    /// A run of the given number of consecutive insertion points.
+    /// A run of `1` is equivalent to a [`GeneralizedItem::Gap`].
+    GapRun(usize),
+}
+
+impl GeneralizedItem {
+    /// Returns `true` if this item is a [`GeneralizedItem::Gap`] or a [`GeneralizedItem::GapRun`]
+    #[must_use]
+    pub fn is_gap(&self) -> bool {
+        self.gap_len().is_some()
+    }
+
+    /// The number of insertion points of this item, or `None` for [`GeneralizedItem::Bytes`]
+    #[must_use]
+    pub fn gap_len(&self) -> Option<usize> {
+        match self {
+            GeneralizedItem::Bytes(_) => None,
+            GeneralizedItem::Gap => Some(1),
+            GeneralizedItem::GapRun(len) => Some(*len),
+        }
+    }
+
+    /// Creates a gap of `len` insertion points, a [`GeneralizedItem::Gap`] if `len` is `1`
+    #[must_use]
+    pub fn gap(len: usize) -> Self {
+        if len == 1 {
+            GeneralizedItem::Gap
+        } else {
+            GeneralizedItem::GapRun(len)
+        }
+    }
 }
 
+/// The default maximum number of [`GeneralizedItem`]s the Grimoire mutators and the
+/// [`crate::stages::GeneralizationStage`] keep, see [`GeneralizedInputMetadata::truncate_to`]
+pub const DEFAULT_MAX_GENERALIZED_ITEMS: usize = 4096;
+
+/// The number of bytes of a byte run [`GeneralizedInputMetadata::summary`] shows before truncating it
+pub const SUMMARY_MAX_RUN_BYTES: usize = 16;
+
 /// Metadata regarding the generalised content of an input
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct GeneralizedInputMetadata {
@@ -30,12 +76,46 @@ pub struct GeneralizedInputMetadata {
 
 impl_serdeany!(GeneralizedInputMetadata);
 
+/// A testcase metadata holding the index of the stage that stored the [`GeneralizedInputMetadata`]
+/// of the testcase in its `post_exec`. Lets later stages skip the testcases another stage already processed.
+/// Missing if the generalization was not stored by a mutational stage, e.g. by the generalization stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneralizedStageMetadata {
+    /// The index of the stage that stored the generalization
+    pub stage_idx: i32,
+}
+
+impl_serdeany!(GeneralizedStageMetadata);
+
+impl GeneralizedStageMetadata {
+    /// Creates a new [`struct@GeneralizedStageMetadata`]
+    #[must_use]
+    pub fn new(stage_idx: i32) -> Self {
+        Self { stage_idx }
+    }
+}
+
+impl From<&[Option<u8>]> for GeneralizedInputMetadata {
+    fn from(v: &[Option<u8>]) -> Self {
+        Self::generalized_from_options(v)
+    }
+}
+
+/// Shows the [`GeneralizedInputMetadata::summary`], capped at the precision of the formatter (as in `{meta:.40}`), if any
+impl fmt::Display for GeneralizedInputMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.summary(f.precision().unwrap_or(usize::MAX)))
+    }
+}
+
 impl GeneralizedInputMetadata {
-    /// Fill the generalized vector from a slice of option (None -> Gap)
+    /// Fill the generalized vector from a slice of option (None -> Gap).
+    /// Consecutive `None` entries get coalesced into a single [`GeneralizedItem::GapRun`].
     #[must_use]
     pub fn generalized_from_options(v: &[Option<u8>]) -> Self {
         let mut generalized = vec![];
         let mut bytes = vec![];
+        let mut gap_len = 0;
         if v.first() != Some(&None) {
             generalized.push(GeneralizedItem::Gap);
         }
@@ -43,25 +123,48 @@ impl GeneralizedInputMetadata {
             match e {
                 None => {
                     if !bytes.is_empty() {
-                        generalized.push(GeneralizedItem::Bytes(bytes.clone()));
-                        bytes.clear();
+                        generalized
+                            .push(GeneralizedItem::Bytes(core::mem::take(&mut bytes).into()));
                     }
-                    generalized.push(GeneralizedItem::Gap);
+                    gap_len += 1;
                 }
                 Some(b) => {
+                    if gap_len > 0 {
+                        generalized.push(GeneralizedItem::gap(gap_len));
+                        gap_len = 0;
+                    }
                     bytes.push(*b);
                 }
             }
         }
         if !bytes.is_empty() {
-            generalized.push(GeneralizedItem::Bytes(bytes));
+            generalized.push(GeneralizedItem::Bytes(bytes.into()));
+        }
+        if gap_len > 0 {
+            generalized.push(GeneralizedItem::gap(gap_len));
         }
-        if generalized.last() != Some(&GeneralizedItem::Gap) {
+        if !generalized.last().map_or(false, GeneralizedItem::is_gap) {
             generalized.push(GeneralizedItem::Gap);
         }
         Self { generalized }
     }
 
+    /// The inverse of [`GeneralizedInputMetadata::generalized_from_options`]:
+    /// each insertion point becomes a `None`, each byte a `Some`.
+    #[must_use]
+    pub fn to_options(&self) -> Vec<Option<u8>> {
+        let mut options = Vec::with_capacity(self.generalized_len());
+        for item in &self.generalized {
+            match item {
+                GeneralizedItem::Bytes(bytes) => options.extend(bytes.iter().copied().map(Some)),
+                GeneralizedItem::Gap | GeneralizedItem::GapRun(_) => {
+                    options.resize(options.len() + item.gap_len().unwrap(), None);
+                }
+            }
+        }
+        options
+    }
+
     /// Get the size of the generalized
     #[must_use]
     pub fn generalized_len(&self) -> usize {
@@ -70,23 +173,305 @@ impl GeneralizedInputMetadata {
             match item {
                 GeneralizedItem::Bytes(b) => size += b.len(),
                 GeneralizedItem::Gap => size += 1,
+                GeneralizedItem::GapRun(len) => size += len,
             }
         }
         size
     }
 
+    /// The share of the items that are gaps, `0.0` if there are no items.
+    /// A [`GeneralizedItem::GapRun`] counts as a single item.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use libafl::inputs::{GeneralizedInputMetadata, GeneralizedItem};
+    /// let mut meta = GeneralizedInputMetadata::default();
+    /// assert_eq!(meta.gap_density(), 0.0);
+    ///
+    /// meta.generalized_mut().extend([
+    ///     GeneralizedItem::Bytes(b"ab".as_slice().into()),
+    ///     GeneralizedItem::Gap,
+    ///     GeneralizedItem::Bytes(b"c".as_slice().into()),
+    /// ]);
+    /// assert_eq!(meta.gap_density(), 1.0 / 3.0);
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn gap_density(&self) -> f64 {
+        if self.generalized.is_empty() {
+            return 0.0;
+        }
+        let gaps = self.generalized.iter().filter(|item| item.is_gap()).count();
+        gaps as f64 / self.generalized.len() as f64
+    }
+
+    /// The share of concrete bytes in the [`Self::generalized_len`], `0.0` if it is `0`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use libafl::inputs::{GeneralizedInputMetadata, GeneralizedItem};
+    /// let mut meta = GeneralizedInputMetadata::default();
+    /// assert_eq!(meta.byte_ratio(), 0.0);
+    ///
+    /// meta.generalized_mut().extend([
+    ///     GeneralizedItem::Bytes(b"ab".as_slice().into()),
+    ///     GeneralizedItem::Gap,
+    ///     GeneralizedItem::Bytes(b"c".as_slice().into()),
+    /// ]);
+    /// assert_eq!(meta.byte_ratio(), 3.0 / 4.0);
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn byte_ratio(&self) -> f64 {
+        let len = self.generalized_len();
+        if len == 0 {
+            return 0.0;
+        }
+        let bytes: usize = self.byte_runs().map(<[u8]>::len).sum();
+        bytes as f64 / len as f64
+    }
+
     /// Convert generalized to bytes
     #[must_use]
     pub fn generalized_to_bytes(&self) -> Vec<u8> {
-        self.generalized
-            .iter()
+        self.byte_runs().flatten().copied().collect()
+    }
+
+    /// Compares only the bytes of both generalizations, ignoring where the gaps are.
+    /// Unlike `==`, two generalizations of the same input are always structurally equal.
+    #[must_use]
+    pub fn structurally_eq(&self, other: &Self) -> bool {
+        self.byte_runs().flatten().eq(other.byte_runs().flatten())
+    }
+
+    /// A hash of the bytes of this generalization, ignoring the gaps,
+    /// consistent with [`GeneralizedInputMetadata::structurally_eq`].
+    /// Useful as a key to deduplicate corpus entries.
+    #[must_use]
+    pub fn byte_signature_hash(&self) -> u64 {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write(&self.generalized_to_bytes());
+        hasher.finish()
+    }
+
+    /// The byte runs of this generalization, in order
+    fn byte_runs(&self) -> impl Iterator<Item = &[u8]> {
+        self.generalized.iter().filter_map(|item| match item {
+            GeneralizedItem::Bytes(bytes) => Some(&bytes[..]),
+            GeneralizedItem::Gap | GeneralizedItem::GapRun(_) => None,
+        })
+    }
+
+    /// Convert generalized to bytes, filling each insertion point with a random token from `dict`.
+    /// Insertion points stay empty if `dict` is empty.
+    /// Given the same `rand` seed, the output is always the same.
+    pub fn generalized_to_bytes_filled<R>(&self, rand: &mut R, dict: &[Vec<u8>]) -> Vec<u8>
+    where
+        R: Rand,
+    {
+        let mut bytes = vec![];
+        for item in &self.generalized {
+            match item {
+                GeneralizedItem::Bytes(b) => bytes.extend_from_slice(b),
+                GeneralizedItem::Gap | GeneralizedItem::GapRun(_) => {
+                    if dict.is_empty() {
+                        continue;
+                    }
+                    for _ in 0..item.gap_len().unwrap() {
+                        bytes.extend_from_slice(rand.choose(dict));
+                    }
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Splices `self` and `other`: keeps `self` up to its `at_gap`-th gap, followed by `other` from its `at_gap`-th gap on.
+    /// If either input has fewer gaps, its last gap is used instead.
+    /// The result gets normalized, so that it starts and ends with a gap
+    /// and adjacent byte runs, as well as adjacent gaps, are merged.
+    #[must_use]
+    pub fn splice(&self, other: &Self, at_gap: usize) -> Self {
+        /// The index of the `at_gap`-th gap in `items`, or of its last gap
+        fn nth_gap(items: &[GeneralizedItem], at_gap: usize) -> Option<usize> {
+            let mut gaps = items
+                .iter()
+                .enumerate()
+                .filter(|(_, item)| item.is_gap())
+                .map(|(i, _)| i);
+            let last = gaps.clone().next_back();
+            gaps.nth(at_gap).or(last)
+        }
+
+        let head = match nth_gap(&self.generalized, at_gap) {
+            Some(idx) => &self.generalized[..idx],
+            None => &self.generalized[..],
+        };
+        let tail = match nth_gap(&other.generalized, at_gap) {
+            Some(idx) => &other.generalized[idx..],
+            None => &[],
+        };
+
+        Self::normalized(head.iter().chain(tail))
+    }
+
+    /// Re-aligns this generalization to the `bytes` actually stored for a testcase,
+    /// for example after a mutation changed them.
+    /// Each retained byte run is searched for in `bytes`, in order, and the bytes in between
+    /// end up next to the gaps they were inserted at.
+    /// If `bytes` is shorter than the bytes of this generalization, or a byte run is missing,
+    /// the result degrades to all of `bytes` as a single byte run.
+    #[must_use]
+    pub fn realign(&self, bytes: &[u8]) -> Self {
+        let degraded = || Self::normalized([GeneralizedItem::Bytes(bytes.into())].iter());
+
+        let mut items = vec![];
+        let mut cursor = 0;
+        let mut retained = 0;
+        for item in &self.generalized {
+            match item {
+                GeneralizedItem::Bytes(run) if !run.is_empty() => {
+                    retained += run.len();
+                    if retained > bytes.len() {
+                        return degraded();
+                    }
+                    let Some(offset) = bytes[cursor..]
+                        .windows(run.len())
+                        .position(|window| window == &run[..])
+                    else {
+                        return degraded();
+                    };
+                    let start = cursor + offset;
+                    items.push(GeneralizedItem::Bytes(bytes[cursor..start].into()));
+                    items.push(item.clone());
+                    cursor = start + run.len();
+                }
+                GeneralizedItem::Bytes(_) => {}
+                GeneralizedItem::Gap | GeneralizedItem::GapRun(_) => items.push(item.clone()),
+            }
+        }
+        items.push(GeneralizedItem::Bytes(bytes[cursor..].into()));
+        Self::normalized(items.iter())
+    }
+
+    /// Builds a generalization from the given items, starting and ending with a gap,
+    /// merging adjacent byte runs as well as adjacent gaps, and dropping empty byte runs.
+    /// Byte runs without a neighbouring run get shared with `items` instead of copied.
+    fn normalized<'a, I>(items: I) -> Self
+    where
+        I: Iterator<Item = &'a GeneralizedItem>,
+    {
+        let mut generalized: Vec<GeneralizedItem> = vec![GeneralizedItem::Gap];
+        // The adjacent byte runs seen since the last gap, merged once the next gap comes
+        let mut runs: Vec<&Rc<[u8]>> = vec![];
+        for item in items.chain(&[GeneralizedItem::Gap]) {
+            match item {
+                GeneralizedItem::Bytes(bytes) => {
+                    if !bytes.is_empty() {
+                        runs.push(bytes);
+                    }
+                }
+                GeneralizedItem::Gap | GeneralizedItem::GapRun(_) => {
+                    match runs.as_slice() {
+                        [] => {}
+                        [run] => generalized.push(GeneralizedItem::Bytes(Rc::clone(run))),
+                        runs => generalized.push(GeneralizedItem::Bytes(
+                            runs.iter().flat_map(|run| run.iter()).copied().collect(),
+                        )),
+                    }
+                    runs.clear();
+                    let last = generalized.last_mut().unwrap();
+                    if last.is_gap() {
+                        // Adjacent gaps are the same insertion point, keep the longer run
+                        let len = last.gap_len().max(item.gap_len()).unwrap();
+                        *last = GeneralizedItem::gap(len);
+                    } else {
+                        generalized.push(item.clone());
+                    }
+                }
+            }
+        }
+        Self { generalized }
+    }
+
+    /// Normalizes the generalization in place, see [`GeneralizedInputMetadata::splice`].
+    /// Mutators editing [`GeneralizedInputMetadata::generalized_mut`] call this afterwards.
+    pub fn normalize(&mut self) {
+        let items = core::mem::take(&mut self.generalized);
+        *self = Self::normalized(items.iter());
+    }
+
+    /// Bounds the number of items to `max_items`, but at least `3`, by merging the trailing items
+    /// into a single byte run followed by a gap. Gaps among the merged items get dropped,
+    /// so the bytes stay the same and the result still ends with a gap.
+    pub fn truncate_to(&mut self, max_items: usize) {
+        if self.generalized.len() <= max_items {
+            return;
+        }
+        // Keep the leading gap, and leave room for the merged bytes and the trailing gap
+        let keep = max_items.max(3) - 2;
+        let tail: Vec<u8> = self
+            .generalized
+            .drain(keep..)
             .filter_map(|item| match item {
                 GeneralizedItem::Bytes(bytes) => Some(bytes),
-                GeneralizedItem::Gap => None,
+                GeneralizedItem::Gap | GeneralizedItem::GapRun(_) => None,
             })
-            .flatten()
-            .copied()
-            .collect()
+            .fold(vec![], |mut tail, bytes| {
+                tail.extend_from_slice(&bytes);
+                tail
+            });
+        match self.generalized.last_mut() {
+            Some(GeneralizedItem::Bytes(last)) => *last = [&last[..], &tail[..]].concat().into(),
+            _ if !tail.is_empty() => self.generalized.push(GeneralizedItem::Bytes(tail.into())),
+            _ => {}
+        }
+        if !self
+            .generalized
+            .last()
+            .map_or(false, GeneralizedItem::is_gap)
+        {
+            self.generalized.push(GeneralizedItem::Gap);
+        }
+    }
+
+    /// A human-readable summary of at most `width` characters, for logging.
+    /// Byte runs get rendered as hex, truncated with `…` after [`SUMMARY_MAX_RUN_BYTES`] bytes,
+    /// gaps as `[GAP]`, and runs of `n` gaps as `[GAP*n]`, separated by spaces.
+    /// If the summary is longer than `width`, it gets cut and ends with `…`.
+    #[must_use]
+    pub fn summary(&self, width: usize) -> String {
+        let mut summary = String::new();
+        for (i, item) in self.generalized.iter().enumerate() {
+            if i > 0 {
+                summary.push(' ');
+            }
+            match item {
+                GeneralizedItem::Bytes(bytes) => {
+                    for byte in bytes.iter().take(SUMMARY_MAX_RUN_BYTES) {
+                        write!(summary, "{byte:02x}").unwrap();
+                    }
+                    if bytes.len() > SUMMARY_MAX_RUN_BYTES {
+                        summary.push('…');
+                    }
+                }
+                GeneralizedItem::Gap => summary.push_str("[GAP]"),
+                GeneralizedItem::GapRun(len) => write!(summary, "[GAP*{len}]").unwrap(),
+            }
+            if summary.chars().count() > width {
+                break;
+            }
+        }
+
+        if summary.chars().count() > width {
+            summary = summary.chars().take(width.saturating_sub(1)).collect();
+            if width > 0 {
+                summary.push('…');
+            }
+        }
+        summary
     }
 
     /// Get the generalized input
@@ -95,15 +480,147 @@ impl GeneralizedInputMetadata {
         &self.generalized
     }
 
-    /// Get the generalized input (mutable)
+    /// Get the generalized input (mutable).
+    /// Callers that grow it should bound it with [`GeneralizedInputMetadata::truncate_to`] afterwards.
+    pub fn generalized_mut(&mut self) -> &mut Vec<GeneralizedItem> {
+        &mut self.generalized
+    }
+}
+
+/// An input holding both its raw bytes and their generalized form, so that the generalization
+/// travels with the input instead of living in the [`GeneralizedInputMetadata`] of its testcase.
+/// The target gets the raw bytes; both fields get serialized.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct GeneralizedInput {
+    /// The raw input bytes
+    bytes: Vec<u8>,
+    /// The generalized form of the bytes
+    generalized: Vec<GeneralizedItem>,
+}
+
+impl Input for GeneralizedInput {
+    /// Generate a name for this input, based on its raw bytes
+    fn generate_name(&self, _idx: usize) -> String {
+        let mut hasher = AHasher::new_with_keys(0, 0);
+        hasher.write(self.bytes());
+        format!("{:016x}", hasher.finish())
+    }
+
+    /// Skips or coerces this input, if it is empty
+    fn apply_empty_input_policy(&mut self, policy: EmptyInputPolicy) -> bool {
+        policy.apply_to_bytes(&mut self.bytes)
+    }
+
+    #[inline]
+    fn len_hint(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+/// Rc Ref-cell from Input
+impl From<GeneralizedInput> for Rc<RefCell<GeneralizedInput>> {
+    fn from(input: GeneralizedInput) -> Self {
+        Rc::new(RefCell::new(input))
+    }
+}
+
+impl HasBytesVec for GeneralizedInput {
+    #[inline]
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    #[inline]
+    fn bytes_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.bytes
+    }
+}
+
+impl HasTargetBytes for GeneralizedInput {
+    #[inline]
+    fn target_bytes(&self) -> OwnedSlice<u8> {
+        OwnedSlice::from(&self.bytes)
+    }
+}
+
+impl HasLen for GeneralizedInput {
+    #[inline]
+    fn len(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+/// Seeds the generalized form with a single [`GeneralizedItem::Bytes`] item holding all bytes
+impl From<BytesInput> for GeneralizedInput {
+    fn from(input: BytesInput) -> Self {
+        let generalized = vec![GeneralizedItem::Bytes(input.bytes.as_slice().into())];
+        Self::new(input.bytes, generalized)
+    }
+}
+
+impl From<GeneralizedInput> for BytesInput {
+    fn from(input: GeneralizedInput) -> Self {
+        BytesInput::new(input.bytes)
+    }
+}
+
+impl GeneralizedInput {
+    /// Creates a new generalized input from raw bytes and their generalized form
+    #[must_use]
+    pub fn new(bytes: Vec<u8>, generalized: Vec<GeneralizedItem>) -> Self {
+        Self { bytes, generalized }
+    }
+
+    /// Get the generalized form of this input
+    #[must_use]
+    pub fn generalized(&self) -> &[GeneralizedItem] {
+        &self.generalized
+    }
+
+    /// Get the generalized form of this input (mutable)
     pub fn generalized_mut(&mut self) -> &mut Vec<GeneralizedItem> {
         &mut self.generalized
     }
+
+    /// Replaces the raw bytes with the concatenation of the generalized [`GeneralizedItem::Bytes`] items
+    pub fn update_bytes_from_generalized(&mut self) {
+        self.bytes = self
+            .generalized
+            .iter()
+            .filter_map(|item| match item {
+                GeneralizedItem::Bytes(bytes) => Some(&bytes[..]),
+                GeneralizedItem::Gap | GeneralizedItem::GapRun(_) => None,
+            })
+            .flatten()
+            .copied()
+            .collect();
+    }
+}
+
+/// A state metadata configuring whether the [`GeneralizedInputMetadata`] of new testcases found by
+/// a Grimoire mutation gets re-aligned to their stored bytes (see [`GeneralizedInputMetadata::realign`]).
+/// Re-alignment is enabled if this metadata is missing. Disabling it saves the work if nothing
+/// rewrites the stored inputs, as the mutational stages store the bytes of the generalization as they are.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GeneralizedRealignMetadata {
+    /// Whether to re-align the generalization of new testcases
+    pub enabled: bool,
+}
+
+impl_serdeany!(GeneralizedRealignMetadata);
+
+impl GeneralizedRealignMetadata {
+    /// Creates a new [`struct@GeneralizedRealignMetadata`]
+    #[must_use]
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
 }
 
 impl<S> MutatedTransform<BytesInput, S> for GeneralizedInputMetadata
 where
-    S: HasCorpus,
+    S: HasCorpus + HasMetadata,
+    S::Input: HasBytesVec,
 {
     type Post = Self;
 
@@ -129,18 +646,433 @@ where
 
 impl<S> MutatedTransformPost<S> for GeneralizedInputMetadata
 where
-    S: HasCorpus,
+    S: HasCorpus + HasMetadata,
+    S::Input: HasBytesVec,
 {
+    /// Stores the metadata in the testcase, realigned to its bytes unless disabled by the
+    /// [`struct@GeneralizedRealignMetadata`], along with a [`struct@GeneralizedStageMetadata`] for the `stage_idx`
     fn post_exec(
         self,
         state: &mut S,
-        _stage_idx: i32,
+        stage_idx: i32,
         corpus_idx: Option<CorpusId>,
     ) -> Result<(), Error> {
         if let Some(corpus_idx) = corpus_idx {
+            let realign = state
+                .metadata()
+                .get::<GeneralizedRealignMetadata>()
+                .map_or(true, |meta| meta.enabled);
             let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
-            testcase.metadata_mut().insert(self);
+            let meta = if realign {
+                self.realign(testcase.load_input()?.bytes())
+            } else {
+                self
+            };
+            testcase.metadata_mut().insert(meta);
+            testcase
+                .metadata_mut()
+                .insert(GeneralizedStageMetadata::new(stage_idx));
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, vec::Vec};
+
+    use crate::{
+        bolts::{rands::StdRand, AsSlice},
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::{
+            BytesInput, GeneralizedInput, GeneralizedInputMetadata, GeneralizedItem,
+            GeneralizedRealignMetadata, GeneralizedStageMetadata, HasBytesVec, HasTargetBytes,
+            Input, SUMMARY_MAX_RUN_BYTES,
+        },
+        stages::mutational::MutatedTransformPost,
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_gap_runs() {
+        let meta = GeneralizedInputMetadata::generalized_from_options(&[
+            Some(b'a'),
+            None,
+            None,
+            None,
+            Some(b'b'),
+            None,
+            Some(b'c'),
+            None,
+            None,
+        ]);
+        assert_eq!(
+            meta.generalized(),
+            &[
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"a".as_slice().into()),
+                GeneralizedItem::GapRun(3),
+                GeneralizedItem::Bytes(b"b".as_slice().into()),
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"c".as_slice().into()),
+                GeneralizedItem::GapRun(2),
+            ]
+        );
+        assert_eq!(meta.generalized_len(), 10);
+        assert_eq!(meta.generalized_to_bytes(), b"abc");
+
+        // Metadata serialized before gap runs existed still deserializes:
+        // `[Gap, Bytes(b"a"), Gap]`, as postcard-serialized by the original format
+        let serialized = [3, 1, 0, 1, b'a', 1];
+        let deserialized: GeneralizedInputMetadata = postcard::from_bytes(&serialized).unwrap();
+        assert_eq!(
+            deserialized.generalized(),
+            &[
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"a".as_slice().into()),
+                GeneralizedItem::Gap,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_structurally_eq() {
+        let a = GeneralizedInputMetadata::generalized_from_options(&[
+            Some(b'a'),
+            None,
+            Some(b'b'),
+            Some(b'c'),
+        ]);
+        let b = GeneralizedInputMetadata::generalized_from_options(&[
+            None,
+            Some(b'a'),
+            Some(b'b'),
+            None,
+            None,
+            Some(b'c'),
+        ]);
+        let c = GeneralizedInputMetadata::generalized_from_options(&[Some(b'a'), Some(b'b')]);
+
+        assert_ne!(a, b);
+        assert!(a.structurally_eq(&b));
+        assert_eq!(a.byte_signature_hash(), b.byte_signature_hash());
+        assert!(!a.structurally_eq(&c));
+        assert_ne!(a.byte_signature_hash(), c.byte_signature_hash());
+    }
+
+    #[test]
+    fn test_summary() {
+        let meta = GeneralizedInputMetadata::generalized_from_options(&[
+            Some(b'a'),
+            Some(b'b'),
+            None,
+            None,
+            Some(b'c'),
+        ]);
+        assert_eq!(meta.summary(usize::MAX), "[GAP] 6162 [GAP*2] 63 [GAP]");
+        assert_eq!(format!("{meta}"), "[GAP] 6162 [GAP*2] 63 [GAP]");
+        assert_eq!(meta.summary(12), "[GAP] 6162 …");
+        assert_eq!(format!("{meta:.12}"), "[GAP] 6162 …");
+        // The width is a minimum, it does not cut the summary
+        assert_eq!(format!("{meta:12}"), "[GAP] 6162 [GAP*2] 63 [GAP]");
+
+        let meta = GeneralizedInputMetadata::generalized_from_options(&[Some(0xff); 20]);
+        assert_eq!(
+            meta.summary(usize::MAX),
+            format!("[GAP] {}… [GAP]", "ff".repeat(SUMMARY_MAX_RUN_BYTES))
+        );
+    }
+
+    #[test]
+    fn test_to_options() {
+        // Normalized option slices start and end with an insertion point
+        let normalized: &[&[Option<u8>]] = &[
+            &[None],
+            &[None, Some(b'a'), None],
+            &[
+                None,
+                None,
+                Some(b'a'),
+                Some(b'b'),
+                None,
+                None,
+                None,
+                Some(b'c'),
+                None,
+            ],
+        ];
+        for options in normalized {
+            let meta = GeneralizedInputMetadata::from(*options);
+            assert_eq!(&meta.to_options(), options);
+            assert_eq!(GeneralizedInputMetadata::from(&meta.to_options()[..]), meta);
+        }
+
+        // Other slices get normalized, after which the round-trip is idempotent
+        let meta = GeneralizedInputMetadata::from(&[Some(b'a'), None, Some(b'b')][..]);
+        let options = meta.to_options();
+        assert_eq!(options, [None, Some(b'a'), None, Some(b'b'), None]);
+        assert_eq!(
+            GeneralizedInputMetadata::from(&options[..]).to_options(),
+            options
+        );
+    }
+
+    #[test]
+    fn test_generalized_input() {
+        let mut input = GeneralizedInput::from(BytesInput::new(b"abcd".to_vec()));
+        assert_eq!(input.bytes(), b"abcd");
+        assert_eq!(
+            input.generalized(),
+            &[GeneralizedItem::Bytes(b"abcd".as_slice().into())]
+        );
+
+        input.generalized_mut().insert(0, GeneralizedItem::Gap);
+        input
+            .generalized_mut()
+            .push(GeneralizedItem::Bytes(b"ef".as_slice().into()));
+        input.update_bytes_from_generalized();
+        assert_eq!(input.target_bytes().as_slice(), b"abcdef");
+
+        // Both the bytes and the generalized form survive serialization
+        let serialized = postcard::to_allocvec(&input).unwrap();
+        let deserialized: GeneralizedInput = postcard::from_bytes(&serialized).unwrap();
+        assert_eq!(deserialized, input);
+        assert_eq!(deserialized.generate_name(0), input.generate_name(0));
+    }
+
+    #[test]
+    fn test_generalized_to_bytes_filled() {
+        let meta = GeneralizedInputMetadata::generalized_from_options(&[
+            Some(b'a'),
+            None,
+            None,
+            Some(b'b'),
+        ]);
+        let dict = vec![b"X".to_vec(), b"YY".to_vec()];
+
+        let filled = meta.generalized_to_bytes_filled(&mut StdRand::with_seed(1337), &dict);
+        assert_eq!(
+            filled,
+            meta.generalized_to_bytes_filled(&mut StdRand::with_seed(1337), &dict)
+        );
+        assert!(filled.iter().all(|b| b"abXY".contains(b)));
+        // 4 insertion points, each filled with a single token
+        let known_len = meta.generalized_to_bytes().len();
+        assert!((known_len + 4..=known_len + 8).contains(&filled.len()));
+        assert_eq!(
+            meta.generalized_to_bytes_filled(&mut StdRand::with_seed(0), &[]),
+            b"ab"
+        );
+    }
+
+    #[test]
+    fn test_splice() {
+        let a = GeneralizedInputMetadata::generalized_from_options(&[
+            Some(b'a'),
+            None,
+            Some(b'b'),
+            None,
+            Some(b'c'),
+        ]);
+        let b = GeneralizedInputMetadata::generalized_from_options(&[
+            Some(b'x'),
+            None,
+            None,
+            Some(b'y'),
+        ]);
+
+        // a: [Gap, a, Gap, b, Gap, c, Gap], b: [Gap, x, GapRun(2), y, Gap]
+        assert_eq!(
+            a.splice(&b, 1).generalized(),
+            &[
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"a".as_slice().into()),
+                GeneralizedItem::GapRun(2),
+                GeneralizedItem::Bytes(b"y".as_slice().into()),
+                GeneralizedItem::Gap,
+            ]
+        );
+        assert_eq!(a.splice(&b, 0), b);
+
+        // b only has 3 gaps, its last one gets used
+        assert_eq!(a.splice(&b, 3), a);
+        assert_eq!(
+            a.splice(&b, 100).generalized(),
+            a.splice(&b, 3).generalized()
+        );
+
+        let empty = GeneralizedInputMetadata::default();
+        assert_eq!(a.splice(&empty, 2).generalized_to_bytes(), b"ab");
+        assert_eq!(empty.splice(&a, 2).generalized_to_bytes(), b"c");
+    }
+
+    #[test]
+    fn test_realign() {
+        // [Gap, ab, GapRun(2), cd, Gap]
+        let meta = GeneralizedInputMetadata::generalized_from_options(&[
+            Some(b'a'),
+            Some(b'b'),
+            None,
+            None,
+            Some(b'c'),
+            Some(b'd'),
+        ]);
+        assert_eq!(meta.realign(b"abcd"), meta);
+
+        // Inserted bytes stay next to the gap they were inserted at
+        assert_eq!(
+            meta.realign(b"abXYcdZ").generalized(),
+            &[
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"ab".as_slice().into()),
+                GeneralizedItem::GapRun(2),
+                GeneralizedItem::Bytes(b"XYcd".as_slice().into()),
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"Z".as_slice().into()),
+                GeneralizedItem::Gap,
+            ]
+        );
+
+        // Too short, or missing a byte run: a single byte run
+        let degraded = [
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(b"abc".as_slice().into()),
+            GeneralizedItem::Gap,
+        ];
+        assert_eq!(meta.realign(b"abc").generalized(), &degraded);
+        assert_eq!(
+            meta.realign(b"cdab").generalized()[1..2],
+            [GeneralizedItem::Bytes(b"cdab".as_slice().into())]
+        );
+        assert_eq!(meta.realign(b"").generalized(), &[GeneralizedItem::Gap]);
+    }
+
+    #[test]
+    fn test_truncate_to() {
+        let options = (0..16_u8)
+            .map(|i| if i % 2 == 0 { Some(i) } else { None })
+            .collect::<Vec<_>>();
+        let meta = GeneralizedInputMetadata::generalized_from_options(&options);
+        assert_eq!(meta.generalized().len(), 17);
+
+        let mut truncated = meta.clone();
+        truncated.truncate_to(100);
+        assert_eq!(truncated, meta);
+
+        for max_items in [0, 3, 4, 7, 15] {
+            let mut truncated = meta.clone();
+            truncated.truncate_to(max_items);
+            let items = truncated.generalized();
+            assert!(items.len() <= max_items.max(3));
+            assert!(items.first().unwrap().is_gap());
+            assert!(items.last().unwrap().is_gap());
+            assert_eq!(
+                truncated.generalized_to_bytes(),
+                meta.generalized_to_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_clone_shares_bytes() {
+        let seed = BytesInput::new(vec![0x41; 1 << 20]);
+        let meta = GeneralizedInputMetadata::generalized_from_options(
+            &seed.bytes().iter().copied().map(Some).collect::<Vec<_>>(),
+        );
+        let cloned = meta.clone();
+        let (GeneralizedItem::Bytes(a), GeneralizedItem::Bytes(b)) =
+            (&meta.generalized()[1], &cloned.generalized()[1])
+        else {
+            panic!("expected a byte run after the leading gap");
+        };
+        assert!(Rc::ptr_eq(a, b));
+
+        // Normalizing shares the runs it does not need to merge
+        let gapped = GeneralizedInputMetadata::normalized(meta.generalized().iter());
+        let GeneralizedItem::Bytes(c) = &gapped.generalized()[1] else {
+            panic!("expected a byte run after the leading gap");
+        };
+        assert!(Rc::ptr_eq(a, c));
+        assert_eq!(gapped.generalized_to_bytes(), seed.bytes());
+    }
+
+    #[test]
+    fn test_post_exec_stage_idx() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let idx = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"ab".to_vec())))
+            .unwrap();
+
+        let meta = GeneralizedInputMetadata::generalized_from_options(&[Some(b'a'), Some(b'b')]);
+        MutatedTransformPost::post_exec(meta, &mut state, 3, Some(idx)).unwrap();
+
+        let testcase = state.corpus().get(idx).unwrap().borrow();
+        let stored = testcase
+            .metadata()
+            .get::<GeneralizedInputMetadata>()
+            .unwrap();
+        assert_eq!(stored.generalized_to_bytes(), b"ab");
+        assert_eq!(
+            testcase.metadata().get::<GeneralizedStageMetadata>(),
+            Some(&GeneralizedStageMetadata::new(3))
+        );
+
+        // The generalization keeps its old serialized form, holding the items only
+        assert_eq!(
+            postcard::to_allocvec(stored).unwrap(),
+            postcard::to_allocvec(&(stored.generalized().to_vec(),)).unwrap()
+        );
+        // So the metadata serialized along with it in a testcase still deserializes
+        let serialized = postcard::to_allocvec(&*testcase).unwrap();
+        let deserialized: Testcase<BytesInput> = postcard::from_bytes(&serialized).unwrap();
+        assert_eq!(
+            deserialized.metadata().get::<GeneralizedInputMetadata>(),
+            Some(stored)
+        );
+        assert_eq!(
+            deserialized.metadata().get::<GeneralizedStageMetadata>(),
+            Some(&GeneralizedStageMetadata::new(3))
+        );
+        drop(testcase);
+
+        // The stored input no longer matches, it gets realigned unless disabled
+        let idx = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"aXb".to_vec())))
+            .unwrap();
+        let meta = GeneralizedInputMetadata::generalized_from_options(&[
+            None,
+            Some(b'a'),
+            None,
+            Some(b'b'),
+        ]);
+        MutatedTransformPost::post_exec(meta.clone(), &mut state, 3, Some(idx)).unwrap();
+        let stored = |state: &StdState<_, InMemoryCorpus<BytesInput>, _, _>| {
+            state
+                .corpus()
+                .get(idx)
+                .unwrap()
+                .borrow()
+                .metadata()
+                .get::<GeneralizedInputMetadata>()
+                .unwrap()
+                .generalized_to_bytes()
+        };
+        assert_eq!(stored(&state), b"aXb");
+
+        state.add_metadata(GeneralizedRealignMetadata::new(false));
+        MutatedTransformPost::post_exec(meta, &mut state, 3, Some(idx)).unwrap();
+        assert_eq!(stored(&state), b"ab");
+    }
+}
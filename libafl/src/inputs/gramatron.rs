@@ -47,6 +47,11 @@ impl Input for GramatronInput {
         }
         format!("{:016x}", hasher.finish())
     }
+
+    #[inline]
+    fn len_hint(&self) -> Option<usize> {
+        Some(self.len())
+    }
 }
 
 /// Rc Ref-cell from Input
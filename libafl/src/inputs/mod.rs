@@ -3,6 +3,9 @@
 pub mod bytes;
 pub use bytes::BytesInput;
 
+pub mod fixed_len;
+pub use fixed_len::FixedLenBytesInput;
+
 pub mod encoded;
 pub use encoded::*;
 
@@ -49,6 +52,19 @@ pub trait Input: Clone + Serialize + serde::de::DeserializeOwned + Debug {
 
     /// An hook executed if the input is stored as `Testcase`
     fn wrapped_as_testcase(&mut self) {}
+
+    /// An hook applying the [`EmptyInputPolicy`] before this input gets executed.
+    /// Returns `false` if the input should not be executed at all.
+    /// Inputs that are never delivered as zero bytes can keep the default, which always executes.
+    fn apply_empty_input_policy(&mut self, _policy: EmptyInputPolicy) -> bool {
+        true
+    }
+
+    /// The length of this input, if it has one, for the [`crate::corpus::CorpusSizeStats`].
+    /// Inputs implementing [`crate::bolts::HasLen`] should return it here.
+    fn len_hint(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// An input for the target
@@ -59,7 +75,7 @@ pub trait Input: Clone + Serialize + serde::de::DeserializeOwned + Debug {
     where
         P: AsRef<Path>,
     {
-        write_file_atomic(path, &postcard::to_allocvec(self)?)
+        write_file_atomic(path, &self.to_file_bytes()?)
     }
 
     /// Load the content of this input from a file
@@ -70,7 +86,17 @@ pub trait Input: Clone + Serialize + serde::de::DeserializeOwned + Debug {
         let mut file = File::open(path)?;
         let mut bytes: Vec<u8> = vec![];
         file.read_to_end(&mut bytes)?;
-        Ok(postcard::from_bytes(&bytes)?)
+        Self::from_file_bytes(&bytes)
+    }
+
+    /// The bytes [`Input::to_file`] writes, a postcard by default
+    fn to_file_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(postcard::to_allocvec(self)?)
+    }
+
+    /// Parses the bytes [`Input::to_file_bytes`] returned, for example after decompressing them in memory
+    fn from_file_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(postcard::from_bytes(bytes)?)
     }
 
     /// Generate a name for this input
@@ -78,6 +104,49 @@ pub trait Input: Clone + Serialize + serde::de::DeserializeOwned + Debug {
 
     /// An hook executed if the input is stored as `Testcase`
     fn wrapped_as_testcase(&mut self) {}
+
+    /// An hook applying the [`EmptyInputPolicy`] before this input gets executed.
+    /// Returns `false` if the input should not be executed at all.
+    /// Inputs that are never delivered as zero bytes can keep the default, which always executes.
+    fn apply_empty_input_policy(&mut self, _policy: EmptyInputPolicy) -> bool {
+        true
+    }
+
+    /// The length of this input, if it has one, for the [`crate::corpus::CorpusSizeStats`].
+    /// Inputs implementing [`crate::bolts::HasLen`] should return it here.
+    fn len_hint(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// How inputs without any bytes get handled before they are delivered to the target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmptyInputPolicy {
+    /// Execute empty inputs as they are
+    #[default]
+    Execute,
+    /// Do not execute empty inputs at all
+    Skip,
+    /// Execute empty inputs as the single given byte instead
+    Coerce(u8),
+}
+
+impl EmptyInputPolicy {
+    /// Applies this policy to the given bytes.
+    /// Returns `false` if they should not be executed at all.
+    pub fn apply_to_bytes(self, bytes: &mut Vec<u8>) -> bool {
+        if !bytes.is_empty() {
+            return true;
+        }
+        match self {
+            EmptyInputPolicy::Execute => true,
+            EmptyInputPolicy::Skip => false,
+            EmptyInputPolicy::Coerce(byte) => {
+                bytes.push(byte);
+                true
+            }
+        }
+    }
 }
 
 /// Convert between two input types with a state
@@ -40,6 +40,11 @@ impl Input for NautilusInput {
         format!("{:016x}", hasher.finish())*/
         format!("id:{idx}")
     }
+
+    #[inline]
+    fn len_hint(&self) -> Option<usize> {
+        Some(self.len())
+    }
 }
 
 /// Rc Ref-cell from Input
@@ -172,6 +172,8 @@ pub enum Error {
     IllegalArgument(String, ErrorBacktrace),
     /// The performed action is not supported on the current platform
     Unsupported(String, ErrorBacktrace),
+    /// A wall-clock time budget was exceeded
+    Timeout(String, ErrorBacktrace),
     /// Shutting down, not really an error.
     ShuttingDown,
     /// Something else happened
@@ -255,6 +257,14 @@ impl Error {
     {
         Error::IllegalArgument(arg.into(), ErrorBacktrace::new())
     }
+    /// A wall-clock time budget was exceeded
+    #[must_use]
+    pub fn timeout<S>(arg: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Error::Timeout(arg.into(), ErrorBacktrace::new())
+    }
     /// Shutting down, not really an error.
     #[must_use]
     pub fn shutting_down() -> Self {
@@ -331,6 +341,10 @@ impl fmt::Display for Error {
                 )?;
                 display_error_backtrace(f, b)
             }
+            Self::Timeout(s, b) => {
+                write!(f, "Timeout: {0}", &s)?;
+                display_error_backtrace(f, b)
+            }
             Self::ShuttingDown => write!(f, "Shutting down!"),
             Self::Unknown(s, b) => {
                 write!(f, "Unknown error: {0}", &s)?;
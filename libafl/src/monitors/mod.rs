@@ -556,6 +556,9 @@ pub struct ClientPerfMonitor {
 
     /// Current time set by `start_timer`
     timer_start: Option<u64>,
+
+    /// Events dropped instead of fired, as the event manager could not keep up
+    dropped_events: u64,
 }
 
 /// Various features that are measured for performance
@@ -592,6 +595,18 @@ pub enum PerfFeature {
     /// Time spent getting the feedback from `is_interesting` from all objectives
     GetObjectivesInterestingAll = 9,
 
+    /// Time spent in the `init` of a push stage
+    PushStageInit = 10,
+
+    /// Time spent in the `pre_exec` of a push stage
+    PushStagePreExec = 11,
+
+    /// Time spent in the `post_exec` of a push stage
+    PushStagePostExec = 12,
+
+    /// Time spent in the `deinit` of a push stage
+    PushStageDeinit = 13,
+
     /// Used as a counter to know how many elements are in [`PerfFeature`]. Must be the
     /// last value in the enum.
     Count, // !! No more values here since Count is last! !!
@@ -616,6 +631,10 @@ impl From<PerfFeature> for usize {
             PerfFeature::GetObjectivesInterestingAll => {
                 PerfFeature::GetObjectivesInterestingAll as usize
             }
+            PerfFeature::PushStageInit => PerfFeature::PushStageInit as usize,
+            PerfFeature::PushStagePreExec => PerfFeature::PushStagePreExec as usize,
+            PerfFeature::PushStagePostExec => PerfFeature::PushStagePostExec as usize,
+            PerfFeature::PushStageDeinit => PerfFeature::PushStageDeinit as usize,
             PerfFeature::Count => PerfFeature::Count as usize,
         }
     }
@@ -635,6 +654,10 @@ impl From<usize> for PerfFeature {
             7 => PerfFeature::PostExecObservers,
             8 => PerfFeature::GetFeedbackInterestingAll,
             9 => PerfFeature::GetObjectivesInterestingAll,
+            10 => PerfFeature::PushStageInit,
+            11 => PerfFeature::PushStagePreExec,
+            12 => PerfFeature::PushStagePostExec,
+            13 => PerfFeature::PushStageDeinit,
             _ => panic!("Unknown PerfFeature: {val}"),
         }
     }
@@ -662,6 +685,7 @@ impl ClientPerfMonitor {
             stages_used: vec![],
             feedbacks: HashMap::new(),
             timer_start: None,
+            dropped_events: 0,
         }
     }
 
@@ -684,6 +708,7 @@ impl ClientPerfMonitor {
         self.update_manager(monitor.manager);
         self.update_stages(&monitor.stages);
         self.update_feedbacks(&monitor.feedbacks);
+        self.update_dropped_events(monitor.dropped_events);
     }
 
     /// Gets the elapsed time since the internal timer started. Resets the timer when
@@ -760,6 +785,15 @@ impl ClientPerfMonitor {
             .expect("update_manager overflow");
     }
 
+    /// Add the given `count` to the events dropped instead of fired
+    #[inline]
+    pub fn update_dropped_events(&mut self, count: u64) {
+        self.dropped_events = self
+            .dropped_events
+            .checked_add(count)
+            .expect("update_dropped_events overflow");
+    }
+
     /// Update the total stage counter and increment the stage counter for the next stage
     #[inline]
     pub fn finish_stage(&mut self) {
@@ -866,6 +900,12 @@ impl ClientPerfMonitor {
     pub fn feedbacks(&self) -> &HashMap<String, u64> {
         &self.feedbacks
     }
+
+    /// The number of events dropped instead of fired, as the event manager could not keep up
+    #[must_use]
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events
+    }
 }
 
 #[cfg(feature = "introspection")]
@@ -934,6 +974,10 @@ impl core::fmt::Display for ClientPerfMonitor {
             writeln!(f, "    {feedback_percent:6.4}: {feedback_name}")?;
         }
 
+        if self.dropped_events > 0 {
+            writeln!(f, "  Dropped events: {}", self.dropped_events)?;
+        }
+
         write!(f, "  {other_percent:6.4}: Not Measured")?;
 
         Ok(())
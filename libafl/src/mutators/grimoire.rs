@@ -1,13 +1,13 @@
 //! Grimoire is the rewritten grimoire mutator in rust.
 //! See the original repo [`Grimoire`](https://github.com/RUB-SysSec/grimoire) for more details.
 
-use alloc::vec::Vec;
+use alloc::{rc::Rc, vec::Vec};
 use core::cmp::{max, min};
 
 use crate::{
     bolts::{rands::Rand, tuples::Named},
     corpus::Corpus,
-    inputs::{GeneralizedInputMetadata, GeneralizedItem},
+    inputs::{GeneralizedInputMetadata, GeneralizedItem, DEFAULT_MAX_GENERALIZED_ITEMS},
     mutators::{token_mutations::Tokens, MutationResult, Mutator},
     stages::generalization::GeneralizedIndexesMetadata,
     state::{HasCorpus, HasMetadata, HasRand},
@@ -53,11 +53,7 @@ where
                 if other.generalized_len() > 0 {
                     let gen = other.generalized();
 
-                    for (i, _) in gen
-                        .iter()
-                        .enumerate()
-                        .filter(|&(_, x)| *x == GeneralizedItem::Gap)
-                    {
+                    for (i, _) in gen.iter().enumerate().filter(|&(_, x)| x.is_gap()) {
                         gap_indices.push(i);
                     }
                     let min_idx = gap_indices[rand1 % gap_indices.len()];
@@ -66,13 +62,13 @@ where
 
                     gap_indices.clear();
 
-                    if items.last() == Some(&GeneralizedItem::Gap) {
+                    if items.last().map_or(false, GeneralizedItem::is_gap) {
                         min_idx += 1;
                     }
                     items.extend_from_slice(&gen[min_idx..=max_idx]);
 
-                    debug_assert!(items.first() == Some(&GeneralizedItem::Gap));
-                    debug_assert!(items.last() == Some(&GeneralizedItem::Gap));
+                    debug_assert!(items.first().map_or(false, GeneralizedItem::is_gap));
+                    debug_assert!(items.last().map_or(false, GeneralizedItem::is_gap));
 
                     return Ok(());
                 }
@@ -84,14 +80,14 @@ where
         if let Some(meta) = state.metadata().get::<Tokens>() {
             if !meta.tokens().is_empty() {
                 let tok = &meta.tokens()[rand1 % meta.tokens().len()];
-                if items.last() != Some(&GeneralizedItem::Gap) {
+                if !items.last().map_or(false, GeneralizedItem::is_gap) {
                     items.push(GeneralizedItem::Gap);
                 }
-                items.push(GeneralizedItem::Bytes(tok.clone()));
+                items.push(GeneralizedItem::Bytes(tok.as_slice().into()));
                 items.push(GeneralizedItem::Gap);
 
-                debug_assert!(items.first() == Some(&GeneralizedItem::Gap));
-                debug_assert!(items.last() == Some(&GeneralizedItem::Gap));
+                debug_assert!(items.first().map_or(false, GeneralizedItem::is_gap));
+                debug_assert!(items.last().map_or(false, GeneralizedItem::is_gap));
 
                 return Ok(());
             }
@@ -105,22 +101,25 @@ where
         .unwrap();
     let gen = other.generalized();
 
-    if items.last() == Some(&GeneralizedItem::Gap) && gen.first() == Some(&GeneralizedItem::Gap) {
+    if items.last().map_or(false, GeneralizedItem::is_gap)
+        && gen.first().map_or(false, GeneralizedItem::is_gap)
+    {
         items.extend_from_slice(&gen[1..]);
     } else {
         items.extend_from_slice(gen);
     }
 
-    debug_assert!(items.first() == Some(&GeneralizedItem::Gap));
-    debug_assert!(items.last() == Some(&GeneralizedItem::Gap));
+    debug_assert!(items.first().map_or(false, GeneralizedItem::is_gap));
+    debug_assert!(items.last().map_or(false, GeneralizedItem::is_gap));
 
     Ok(())
 }
 
 /// Extend the generalized input with another random one from the corpus
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct GrimoireExtensionMutator {
     gap_indices: Vec<usize>,
+    max_items: usize,
 }
 
 impl<S> Mutator<GeneralizedInputMetadata, S> for GrimoireExtensionMutator
@@ -138,6 +137,7 @@ where
             generalised_meta.generalized_mut(),
             &mut self.gap_indices,
         )?;
+        generalised_meta.truncate_to(self.max_items);
 
         Ok(MutationResult::Mutated)
     }
@@ -155,15 +155,31 @@ impl GrimoireExtensionMutator {
     pub fn new() -> Self {
         Self {
             gap_indices: vec![],
+            max_items: DEFAULT_MAX_GENERALIZED_ITEMS,
         }
     }
+
+    /// Sets the maximum number of items of the extended generalized input,
+    /// see [`GeneralizedInputMetadata::truncate_to`]. Defaults to [`DEFAULT_MAX_GENERALIZED_ITEMS`].
+    #[must_use]
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+}
+
+impl Default for GrimoireExtensionMutator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// Extend the generalized input with another random one from the corpus
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct GrimoireRecursiveReplacementMutator {
     scratch: Vec<GeneralizedItem>,
     gap_indices: Vec<usize>,
+    max_items: usize,
 }
 
 impl<S> Mutator<GeneralizedInputMetadata, S> for GrimoireRecursiveReplacementMutator
@@ -186,11 +202,7 @@ where
 
             let gen = generalised_meta.generalized_mut();
 
-            for (i, _) in gen
-                .iter()
-                .enumerate()
-                .filter(|&(_, x)| *x == GeneralizedItem::Gap)
-            {
+            for (i, _) in gen.iter().enumerate().filter(|&(_, x)| x.is_gap()) {
                 self.gap_indices.push(i);
             }
             if self.gap_indices.is_empty() {
@@ -209,6 +221,7 @@ where
 
             mutated = MutationResult::Mutated;
         }
+        generalised_meta.truncate_to(self.max_items);
 
         Ok(mutated)
     }
@@ -227,8 +240,63 @@ impl GrimoireRecursiveReplacementMutator {
         Self {
             scratch: vec![],
             gap_indices: vec![],
+            max_items: DEFAULT_MAX_GENERALIZED_ITEMS,
         }
     }
+
+    /// Sets the maximum number of items of the replaced generalized input,
+    /// see [`GeneralizedInputMetadata::truncate_to`]. Defaults to [`DEFAULT_MAX_GENERALIZED_ITEMS`].
+    #[must_use]
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+}
+
+impl Default for GrimoireRecursiveReplacementMutator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Replaces the occurrences of `token_1` in `bytes` with `token_2`, or only the first one if `stop_at_first`.
+/// The shared bytes only get copied if there is a match. Returns `true` if anything got replaced.
+fn replace_tokens(
+    bytes: &mut Rc<[u8]>,
+    token_1: &[u8],
+    token_2: &[u8],
+    stop_at_first: bool,
+) -> bool {
+    let mut replaced: Option<Vec<u8>> = None;
+    let mut i = 0;
+    loop {
+        let current = replaced.as_deref().unwrap_or(bytes);
+        if !current
+            .len()
+            .checked_sub(token_1.len())
+            .map_or(false, |len| i < len)
+        {
+            break;
+        }
+        if current[i..].starts_with(token_1) {
+            replaced
+                .get_or_insert_with(|| bytes.to_vec())
+                .splice(i..(i + token_1.len()), token_2.iter().copied());
+            if stop_at_first {
+                break;
+            }
+            i += token_2.len();
+        } else {
+            i += 1;
+        }
+    }
+    match replaced {
+        Some(replaced) => {
+            *bytes = replaced.into();
+            true
+        }
+        None => false,
+    }
 }
 
 /// Replace matching tokens with others from the tokens metadata
@@ -275,48 +343,22 @@ where
         let gen = generalised_meta.generalized_mut();
         rand_idx %= gen.len();
 
-        'first: for item in &mut gen[..rand_idx] {
+        for item in &mut gen[..rand_idx] {
             if let GeneralizedItem::Bytes(bytes) = item {
-                let mut i = 0;
-                while bytes
-                    .len()
-                    .checked_sub(token_1.len())
-                    .map_or(false, |len| i < len)
-                {
-                    if bytes[i..].starts_with(token_1) {
-                        bytes.splice(i..(i + token_1.len()), token_2.iter().copied());
-
-                        mutated = MutationResult::Mutated;
-                        if stop_at_first {
-                            break 'first;
-                        }
-                        i += token_2.len();
-                    } else {
-                        i += 1;
+                if replace_tokens(bytes, token_1, token_2, stop_at_first) {
+                    mutated = MutationResult::Mutated;
+                    if stop_at_first {
+                        return Ok(mutated);
                     }
                 }
             }
         }
-        if mutated == MutationResult::Skipped || !stop_at_first {
-            'second: for item in &mut gen[rand_idx..] {
-                if let GeneralizedItem::Bytes(bytes) = item {
-                    let mut i = 0;
-                    while bytes
-                        .len()
-                        .checked_sub(token_1.len())
-                        .map_or(false, |len| i < len)
-                    {
-                        if bytes[i..].starts_with(token_1) {
-                            bytes.splice(i..(i + token_1.len()), token_2.iter().copied());
-
-                            mutated = MutationResult::Mutated;
-                            if stop_at_first {
-                                break 'second;
-                            }
-                            i += token_2.len();
-                        } else {
-                            i += 1;
-                        }
+        for item in &mut gen[rand_idx..] {
+            if let GeneralizedItem::Bytes(bytes) = item {
+                if replace_tokens(bytes, token_1, token_2, stop_at_first) {
+                    mutated = MutationResult::Mutated;
+                    if stop_at_first {
+                        break;
                     }
                 }
             }
@@ -358,11 +400,7 @@ where
     ) -> Result<MutationResult, Error> {
         let gen = generalised_meta.generalized_mut();
 
-        for (i, _) in gen
-            .iter()
-            .enumerate()
-            .filter(|&(_, x)| *x == GeneralizedItem::Gap)
-        {
+        for (i, _) in gen.iter().enumerate().filter(|&(_, x)| x.is_gap()) {
             self.gap_indices.push(i);
         }
         let min_idx =
@@ -399,3 +437,287 @@ impl GrimoireRandomDeleteMutator {
         }
     }
 }
+
+/// Inserts, removes or duplicates gaps of the generalized input, keeping its bytes.
+/// A gap gets inserted by splitting a byte run, removed by merging the byte runs around it,
+/// and duplicated by growing it into a longer [`GeneralizedItem::GapRun`].
+/// The leading and trailing gaps are never removed, and the result gets normalized.
+#[derive(Debug)]
+pub struct GeneralizedGapMutator {
+    indices: Vec<usize>,
+    max_items: usize,
+}
+
+impl<S> Mutator<GeneralizedInputMetadata, S> for GeneralizedGapMutator
+where
+    S: HasRand,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        generalised_meta: &mut GeneralizedInputMetadata,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let gen = generalised_meta.generalized_mut();
+        let last = gen.len().saturating_sub(1);
+
+        self.indices.clear();
+        match state.rand_mut().below(3) {
+            0 => {
+                // Insert a gap into a byte run
+                for (i, item) in gen.iter().enumerate() {
+                    if matches!(item, GeneralizedItem::Bytes(bytes) if bytes.len() > 1) {
+                        self.indices.push(i);
+                    }
+                }
+                if self.indices.is_empty() {
+                    return Ok(MutationResult::Skipped);
+                }
+                let idx = *state.rand_mut().choose(&self.indices);
+                let GeneralizedItem::Bytes(bytes) = &mut gen[idx] else {
+                    unreachable!()
+                };
+                let at = 1 + state.rand_mut().below(bytes.len() as u64 - 1) as usize;
+                let tail: Rc<[u8]> = bytes[at..].into();
+                *bytes = bytes[..at].into();
+                gen.splice(
+                    idx + 1..idx + 1,
+                    [GeneralizedItem::Gap, GeneralizedItem::Bytes(tail)],
+                );
+            }
+            1 => {
+                // Remove an inner gap, or shorten a run of gaps
+                for (i, item) in gen.iter().enumerate() {
+                    match item.gap_len() {
+                        Some(len) if len > 1 || (i != 0 && i != last) => self.indices.push(i),
+                        _ => {}
+                    }
+                }
+                if self.indices.is_empty() {
+                    return Ok(MutationResult::Skipped);
+                }
+                let idx = *state.rand_mut().choose(&self.indices);
+                match gen[idx].gap_len() {
+                    Some(len) if len > 1 => gen[idx] = GeneralizedItem::gap(len - 1),
+                    _ => {
+                        gen.remove(idx);
+                    }
+                }
+            }
+            _ => {
+                // Duplicate a gap
+                for (i, item) in gen.iter().enumerate() {
+                    if item.is_gap() {
+                        self.indices.push(i);
+                    }
+                }
+                if self.indices.is_empty() {
+                    return Ok(MutationResult::Skipped);
+                }
+                let idx = *state.rand_mut().choose(&self.indices);
+                gen[idx] = GeneralizedItem::gap(gen[idx].gap_len().unwrap() + 1);
+            }
+        }
+
+        generalised_meta.normalize();
+        generalised_meta.truncate_to(self.max_items);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for GeneralizedGapMutator {
+    fn name(&self) -> &str {
+        "GeneralizedGapMutator"
+    }
+}
+
+impl GeneralizedGapMutator {
+    /// Creates a new [`GeneralizedGapMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            indices: vec![],
+            max_items: DEFAULT_MAX_GENERALIZED_ITEMS,
+        }
+    }
+
+    /// Sets the maximum number of items of the mutated generalized input,
+    /// see [`GeneralizedInputMetadata::truncate_to`]. Defaults to [`DEFAULT_MAX_GENERALIZED_ITEMS`].
+    #[must_use]
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+}
+
+impl Default for GeneralizedGapMutator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Recombines the byte runs of the generalized input with those of another generalized corpus entry,
+/// splicing them at a random gap, see [`GeneralizedInputMetadata::splice`].
+/// The result is normalized, so that it starts and ends with a gap.
+#[derive(Debug)]
+pub struct GeneralizedSpliceMutator {
+    max_items: usize,
+}
+
+impl<S> Mutator<GeneralizedInputMetadata, S> for GeneralizedSpliceMutator
+where
+    S: HasMetadata + HasRand + HasCorpus,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        generalised_meta: &mut GeneralizedInputMetadata,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let rand_idx = state.rand_mut().next() as usize;
+        let idx = {
+            let meta = state.metadata().get::<GeneralizedIndexesMetadata>().ok_or_else(|| {
+                Error::key_not_found("GeneralizedIndexesMetadata needed by GeneralizedSpliceMutator not found, make sure that you have GeneralizationStage in")
+            })?;
+            if meta.indexes.is_empty() {
+                return Ok(MutationResult::Skipped);
+            }
+            *meta
+                .indexes
+                .iter()
+                .nth(rand_idx % meta.indexes.len())
+                .unwrap()
+        };
+
+        let gaps = generalised_meta
+            .generalized()
+            .iter()
+            .filter(|item| item.is_gap())
+            .count();
+        let at_gap = state.rand_mut().below(max(gaps, 1) as u64) as usize;
+
+        let spliced = {
+            let other_testcase = state.corpus().get(idx)?.borrow();
+            let Some(other) = other_testcase.metadata().get::<GeneralizedInputMetadata>() else {
+                return Ok(MutationResult::Skipped);
+            };
+            generalised_meta.splice(other, at_gap)
+        };
+        if spliced.generalized() == generalised_meta.generalized() {
+            return Ok(MutationResult::Skipped);
+        }
+
+        *generalised_meta = spliced;
+        generalised_meta.truncate_to(self.max_items);
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for GeneralizedSpliceMutator {
+    fn name(&self) -> &str {
+        "GeneralizedSpliceMutator"
+    }
+}
+
+impl GeneralizedSpliceMutator {
+    /// Creates a new [`GeneralizedSpliceMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            max_items: DEFAULT_MAX_GENERALIZED_ITEMS,
+        }
+    }
+
+    /// Sets the maximum number of items of the spliced generalized input,
+    /// see [`GeneralizedInputMetadata::truncate_to`]. Defaults to [`DEFAULT_MAX_GENERALIZED_ITEMS`].
+    #[must_use]
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+}
+
+impl Default for GeneralizedSpliceMutator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::{BytesInput, GeneralizedInputMetadata, GeneralizedItem},
+        mutators::{GeneralizedGapMutator, GeneralizedSpliceMutator, MutationResult, Mutator},
+        stages::generalization::GeneralizedIndexesMetadata,
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    fn is_normalized(meta: &GeneralizedInputMetadata) -> bool {
+        let gen = meta.generalized();
+        gen.first().map_or(false, GeneralizedItem::is_gap)
+            && gen.last().map_or(false, GeneralizedItem::is_gap)
+            && gen
+                .windows(2)
+                .all(|pair| pair[0].is_gap() != pair[1].is_gap())
+            && gen
+                .iter()
+                .all(|item| !matches!(item, GeneralizedItem::Bytes(bytes) if bytes.is_empty()))
+    }
+
+    #[test]
+    fn test_generalized_mutators_normalize() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let other = GeneralizedInputMetadata::generalized_from_options(&[
+            Some(b'x'),
+            None,
+            Some(b'y'),
+            Some(b'z'),
+            None,
+        ]);
+        let mut testcase = Testcase::new(BytesInput::new(b"xyz".to_vec()));
+        testcase.add_metadata(other);
+        let id = state.corpus_mut().add(testcase).unwrap();
+        let mut indexes = GeneralizedIndexesMetadata::new();
+        indexes.indexes.insert(id);
+        state.add_metadata(indexes);
+
+        let mut meta = GeneralizedInputMetadata::generalized_from_options(&[
+            Some(b'a'),
+            Some(b'b'),
+            None,
+            Some(b'c'),
+            Some(b'd'),
+        ]);
+        let bytes = meta.generalized_to_bytes();
+
+        let mut gap_mutator = GeneralizedGapMutator::new();
+        let mut mutated = false;
+        for _ in 0..100 {
+            let res = gap_mutator.mutate(&mut state, &mut meta, 0).unwrap();
+            mutated |= res == MutationResult::Mutated;
+            assert!(is_normalized(&meta));
+            // Gap mutations keep the bytes
+            assert_eq!(meta.generalized_to_bytes(), bytes);
+        }
+        assert!(mutated);
+
+        let mut splice_mutator = GeneralizedSpliceMutator::new();
+        for _ in 0..100 {
+            splice_mutator.mutate(&mut state, &mut meta, 0).unwrap();
+            assert!(is_normalized(&meta));
+        }
+    }
+}
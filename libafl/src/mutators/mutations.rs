@@ -1,18 +1,23 @@
 //! A wide variety of mutations used during fuzzing.
+//!
+//! All mutations handle empty inputs gracefully: the ones working on existing bytes return
+//! [`MutationResult::Skipped`] for them, while the ones inserting new bytes may grow them.
+//! To keep empty inputs from reaching the target, see [`crate::inputs::EmptyInputPolicy`].
 
 use alloc::{borrow::ToOwned, vec::Vec};
 use core::{
-    cmp::{max, min},
+    cmp::{max, min, Ordering},
     mem::size_of,
 };
 
 use crate::{
     bolts::{rands::Rand, tuples::Named},
     corpus::Corpus,
-    inputs::HasBytesVec,
+    feedbacks::HotOffsetsMetadata,
+    inputs::{BytesInput, HasBytesVec},
     mutators::{MutationResult, Mutator},
     random_corpus_id,
-    state::{HasCorpus, HasMaxSize, HasRand},
+    state::{HasCorpus, HasMaxSize, HasMetadata, HasRand},
     Error,
 };
 
@@ -895,6 +900,62 @@ impl BytesSwapMutator {
     }
 }
 
+/// Repeats the whole input 1 to `max_repeats` times, appending the copies to the input.
+/// The number of copies is reduced so that the result does not exceed the max size.
+#[derive(Debug)]
+pub struct RepeatWholeInputMutator {
+    max_repeats: usize,
+}
+
+impl<I, S> Mutator<I, S> for RepeatWholeInputMutator
+where
+    S: HasRand + HasMaxSize,
+    I: HasBytesVec,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let max_size = state.max_size();
+        let size = input.bytes().len();
+        if size == 0 || size + size > max_size {
+            return Ok(MutationResult::Skipped);
+        }
+
+        let repeats = 1 + state.rand_mut().below(self.max_repeats as u64) as usize;
+        let repeats = min(repeats, (max_size - size) / size);
+
+        for _ in 0..repeats {
+            input.bytes_mut().extend_from_within(..size);
+        }
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for RepeatWholeInputMutator {
+    fn name(&self) -> &str {
+        "RepeatWholeInputMutator"
+    }
+}
+
+impl RepeatWholeInputMutator {
+    /// Creates a new [`RepeatWholeInputMutator`], appending up to `max_repeats` copies of the input.
+    #[must_use]
+    pub fn new(max_repeats: usize) -> Self {
+        assert!(max_repeats > 0, "max_repeats must be at least 1");
+        Self { max_repeats }
+    }
+}
+
+impl Default for RepeatWholeInputMutator {
+    fn default() -> Self {
+        Self::new(4)
+    }
+}
+
 /// Crossover insert mutation for inputs with a bytes vector
 #[derive(Debug, Default)]
 pub struct CrossoverInsertMutator;
@@ -1120,6 +1181,213 @@ impl SpliceMutator {
     }
 }
 
+/// Moves the input one edit closer to a target input on each mutation.
+/// Differing bytes are replaced first, then the length is adjusted one byte at a time.
+/// Skips once the input equals the target.
+#[derive(Debug, Clone)]
+pub struct TargetDeltaMutator {
+    target: Vec<u8>,
+}
+
+impl<I, S> Mutator<I, S> for TargetDeltaMutator
+where
+    I: HasBytesVec,
+{
+    #[allow(clippy::cast_sign_loss)]
+    fn mutate(
+        &mut self,
+        _state: &mut S,
+        input: &mut I,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let (first_diff, _) = locate_diffs(input.bytes(), &self.target);
+        if first_diff >= 0 {
+            let idx = first_diff as usize;
+            input.bytes_mut()[idx] = self.target[idx];
+            return Ok(MutationResult::Mutated);
+        }
+
+        let size = input.bytes().len();
+        match size.cmp(&self.target.len()) {
+            Ordering::Less => input.bytes_mut().push(self.target[size]),
+            Ordering::Greater => input.bytes_mut().truncate(size - 1),
+            Ordering::Equal => return Ok(MutationResult::Skipped),
+        }
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for TargetDeltaMutator {
+    fn name(&self) -> &str {
+        "TargetDeltaMutator"
+    }
+}
+
+impl TargetDeltaMutator {
+    /// Creates a new [`TargetDeltaMutator`], moving inputs towards the given `target`.
+    #[must_use]
+    pub fn new(target: &BytesInput) -> Self {
+        Self {
+            target: target.bytes().to_vec(),
+        }
+    }
+
+    /// The target this mutator moves inputs towards
+    #[must_use]
+    pub fn target(&self) -> &[u8] {
+        &self.target
+    }
+}
+
+/// Splice mutation cutting both the input and a donor from the corpus right after a delimiter,
+/// such as a newline, so that all delimited records (lines) stay whole.
+/// Falls back to splicing at a byte offset if either of them contains no delimiter.
+#[derive(Debug, Clone)]
+pub struct DelimiterSpliceMutator {
+    delimiter: u8,
+}
+
+impl<S> Mutator<S::Input, S> for DelimiterSpliceMutator
+where
+    S: HasCorpus + HasRand,
+    S::Input: HasBytesVec,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut S::Input,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        // We don't want to use the testcase we're already using for splicing
+        let idx = random_corpus_id!(state.corpus(), state.rand_mut());
+        if let Some(cur) = state.corpus().current() {
+            if idx == *cur {
+                return Ok(MutationResult::Skipped);
+            }
+        }
+
+        let (other_len, cuts, other_cuts) = {
+            let mut other_testcase = state.corpus().get(idx)?.borrow_mut();
+            let other = other_testcase.load_input()?.bytes();
+            if input.bytes().is_empty() || other.is_empty() {
+                return Ok(MutationResult::Skipped);
+            }
+            // Cut points in the input may keep all of its records, the donor has to contribute at least one
+            (
+                other.len(),
+                self.cut_points(input.bytes(), input.bytes().len()),
+                self.cut_points(other, other.len() - 1),
+            )
+        };
+
+        let (cut, other_cut) = if cuts.is_empty() || other_cuts.is_empty() {
+            let split_at = state
+                .rand_mut()
+                .below(min(input.bytes().len(), other_len) as u64)
+                as usize;
+            (split_at, split_at)
+        } else {
+            (
+                *state.rand_mut().choose(&cuts),
+                *state.rand_mut().choose(&other_cuts),
+            )
+        };
+
+        let mut other_testcase = state.corpus().get(idx)?.borrow_mut();
+        let other = other_testcase.load_input()?;
+        input.bytes_mut().truncate(cut);
+        input
+            .bytes_mut()
+            .extend_from_slice(&other.bytes()[other_cut..]);
+
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for DelimiterSpliceMutator {
+    fn name(&self) -> &str {
+        "DelimiterSpliceMutator"
+    }
+}
+
+impl DelimiterSpliceMutator {
+    /// Creates a new [`DelimiterSpliceMutator`], cutting inputs after the given `delimiter`.
+    #[must_use]
+    pub fn new(delimiter: u8) -> Self {
+        Self { delimiter }
+    }
+
+    /// Creates a new [`DelimiterSpliceMutator`], cutting inputs into lines.
+    #[must_use]
+    pub fn lines() -> Self {
+        Self::new(b'\n')
+    }
+
+    /// The positions right after each delimiter in `bytes`, up to `max`
+    fn cut_points(&self, bytes: &[u8], max: usize) -> Vec<usize> {
+        bytes
+            .iter()
+            .enumerate()
+            .filter(|(i, b)| **b == self.delimiter && *i < max)
+            .map(|(i, _)| i + 1)
+            .collect()
+    }
+}
+
+/// Randomizes a byte at one of the offsets in which the current testcase differs from its parent,
+/// as recorded by the [`crate::feedbacks::HotOffsetsFeedback`].
+/// Skips if the current testcase has no [`struct@HotOffsetsMetadata`].
+#[derive(Debug, Default)]
+pub struct HotOffsetsMutator;
+
+impl<S> Mutator<S::Input, S> for HotOffsetsMutator
+where
+    S: HasCorpus + HasRand,
+    S::Input: HasBytesVec,
+{
+    fn mutate(
+        &mut self,
+        state: &mut S,
+        input: &mut S::Input,
+        _stage_idx: i32,
+    ) -> Result<MutationResult, Error> {
+        let Some(idx) = *state.corpus().current() else {
+            return Ok(MutationResult::Skipped);
+        };
+        let offsets = {
+            let testcase = state.corpus().get(idx)?.borrow();
+            let Some(meta) = testcase.metadata().get::<HotOffsetsMetadata>() else {
+                return Ok(MutationResult::Skipped);
+            };
+            // The offsets are sorted, so all offsets still inside the input form a prefix
+            let len = input.bytes().len();
+            let count = meta.offsets.partition_point(|offset| *offset < len);
+            if count == 0 {
+                return Ok(MutationResult::Skipped);
+            }
+            meta.offsets[..count].to_vec()
+        };
+
+        let offset = *state.rand_mut().choose(&offsets);
+        input.bytes_mut()[offset] ^= 1 + state.rand_mut().below(254) as u8;
+        Ok(MutationResult::Mutated)
+    }
+}
+
+impl Named for HotOffsetsMutator {
+    fn name(&self) -> &str {
+        "HotOffsetsMutator"
+    }
+}
+
+impl HotOffsetsMutator {
+    /// Creates a new [`HotOffsetsMutator`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self
+    }
+}
+
 // Converts a hex u8 to its u8 value: 'A' -> 10 etc.
 fn from_hex(hex: u8) -> Result<u8, Error> {
     match hex {
@@ -1169,7 +1437,7 @@ mod tests {
     use crate::{
         bolts::{
             rands::StdRand,
-            tuples::{tuple_list, HasConstLen},
+            tuples::{tuple_list, HasConstLen, NamedTuple},
         },
         corpus::{Corpus, InMemoryCorpus},
         feedbacks::ConstFeedback,
@@ -1178,7 +1446,7 @@ mod tests {
         state::{HasMetadata, StdState},
     };
 
-    fn test_mutations<I, S>() -> impl MutatorsTuple<I, S>
+    fn test_mutations<I, S>() -> impl MutatorsTuple<I, S> + NamedTuple
     where
         S: HasRand + HasMetadata + HasMaxSize,
         I: HasBytesVec,
@@ -1260,4 +1528,174 @@ mod tests {
             inputs.append(&mut new_testcases);
         }
     }
+
+    #[test]
+    fn test_empty_inputs() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut corpus = InMemoryCorpus::new();
+        corpus.add(BytesInput::new(vec![0x42; 16]).into()).unwrap();
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut mutations = test_mutations();
+        for idx in 0..mutations.len() {
+            for _ in 0..16 {
+                let mut input = BytesInput::new(vec![]);
+                let res = mutations
+                    .get_and_mutate(idx.into(), &mut state, &mut input, 0)
+                    .unwrap();
+                // Only the expand and random insert mutations add bytes to an empty input
+                if matches!(
+                    mutations.name(idx),
+                    Some("BytesExpandMutator" | "BytesRandInsertMutator")
+                ) {
+                    assert_eq!(res, MutationResult::Mutated);
+                    assert!(!input.bytes().is_empty());
+                } else {
+                    assert_eq!(res, MutationResult::Skipped);
+                    assert!(input.bytes().is_empty());
+                }
+            }
+        }
+
+        // The mutations taking bytes from other testcases do not panic either
+        let mut input = BytesInput::new(vec![]);
+        SpliceMutator::new()
+            .mutate(&mut state, &mut input, 0)
+            .unwrap();
+        CrossoverReplaceMutator::new()
+            .mutate(&mut state, &mut input, 0)
+            .unwrap();
+        HotOffsetsMutator::new()
+            .mutate(&mut state, &mut input, 0)
+            .unwrap();
+        CrossoverInsertMutator::new()
+            .mutate(&mut state, &mut input, 0)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_repeat_whole_input() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        // With a single repeat, the input is always doubled
+        let mut mutator = RepeatWholeInputMutator::new(1);
+        let mut input = BytesInput::new(vec![1, 2, 3]);
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Mutated
+        );
+        assert_eq!(input.bytes(), &[1, 2, 3, 1, 2, 3]);
+
+        // Tripling would exceed the max size, so the input may only be doubled
+        state.set_max_size(10);
+        let mut mutator = RepeatWholeInputMutator::new(2);
+        for _ in 0..16 {
+            let mut input = BytesInput::new(vec![1, 2, 3, 4]);
+            assert_eq!(
+                mutator.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Mutated
+            );
+            assert_eq!(input.bytes(), &[1, 2, 3, 4, 1, 2, 3, 4]);
+        }
+
+        // Not even doubling fits
+        let mut input = BytesInput::new(vec![0; 6]);
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Skipped
+        );
+    }
+
+    #[test]
+    fn test_target_delta() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        for (start, target) in [
+            (&b"hello world"[..], &b"help"[..]),
+            (b"abc", b"xbcdef"),
+            (b"", b"ab"),
+        ] {
+            let mut mutator = TargetDeltaMutator::new(&BytesInput::new(target.to_vec()));
+            let mut input = BytesInput::new(start.to_vec());
+            let mut steps = 0;
+            while mutator.mutate(&mut state, &mut input, 0).unwrap() == MutationResult::Mutated {
+                steps += 1;
+                assert!(steps <= start.len() + target.len());
+            }
+            assert_eq!(input.bytes(), target);
+        }
+    }
+
+    #[test]
+    fn test_delimiter_splice() {
+        let lines = [&b"aaa\n"[..], b"bb\n", b"c\n", b"xx\n", b"yyyy\n", b"z\n"];
+
+        let mut corpus = InMemoryCorpus::new();
+        corpus
+            .add(BytesInput::new(b"xx\nyyyy\nz\n".to_vec()).into())
+            .unwrap();
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut mutator = DelimiterSpliceMutator::lines();
+        for _ in 0..32 {
+            let mut input = BytesInput::new(b"aaa\nbb\nc\n".to_vec());
+            assert_eq!(
+                mutator.mutate(&mut state, &mut input, 0).unwrap(),
+                MutationResult::Mutated
+            );
+            // Only whole lines, and at least one of them from the donor
+            assert!(input.bytes().ends_with(b"\n"));
+            let mut donated = 0;
+            for line in input.bytes().split_inclusive(|b| *b == b'\n') {
+                let idx = lines.iter().position(|l| *l == line).unwrap();
+                if idx >= 3 {
+                    donated += 1;
+                }
+            }
+            assert!(donated > 0);
+        }
+
+        // Without delimiters, the mutator splices at a byte offset
+        let mut input = BytesInput::new(b"abc".to_vec());
+        assert_eq!(
+            mutator.mutate(&mut state, &mut input, 0).unwrap(),
+            MutationResult::Mutated
+        );
+        assert!(!input.bytes().is_empty());
+    }
 }
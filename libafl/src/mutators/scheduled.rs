@@ -201,6 +201,135 @@ where
     }
 }
 
+/// Applies the stacked mutations of a [`ScheduledMutator`] one by one.
+/// The input can be checkpointed after some of them and rolled back to that checkpoint
+/// later on, for example if a filter rejects the fully mutated input, to only retry the tail of the stack.
+#[derive(Debug, Clone)]
+pub struct ScheduledMutationPipeline<I> {
+    /// The number of stacked mutations
+    stack_len: u64,
+    /// The number of mutations applied so far
+    applied: u64,
+    /// The input, the number of mutations applied, and their result at the last checkpoint
+    checkpoint: Option<(I, u64, MutationResult)>,
+    /// If any of the applied mutations mutated the input
+    result: MutationResult,
+}
+
+impl<I> ScheduledMutationPipeline<I>
+where
+    I: Clone,
+{
+    /// Creates a new pipeline for the given input, using the [`ScheduledMutator::iterations`] of the `mutator`
+    pub fn new<MT, S, SM>(mutator: &SM, state: &mut S, input: &I) -> Self
+    where
+        MT: MutatorsTuple<I, S>,
+        SM: ScheduledMutator<I, MT, S>,
+    {
+        Self::with_stack_len(mutator.iterations(state, input))
+    }
+
+    /// Creates a new pipeline of `stack_len` stacked mutations
+    #[must_use]
+    pub fn with_stack_len(stack_len: u64) -> Self {
+        Self {
+            stack_len,
+            applied: 0,
+            checkpoint: None,
+            result: MutationResult::Skipped,
+        }
+    }
+
+    /// The number of stacked mutations
+    #[must_use]
+    pub fn stack_len(&self) -> u64 {
+        self.stack_len
+    }
+
+    /// The number of mutations applied so far
+    #[must_use]
+    pub fn applied(&self) -> u64 {
+        self.applied
+    }
+
+    /// If all stacked mutations have been applied
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.applied >= self.stack_len
+    }
+
+    /// [`MutationResult::Mutated`] if any of the applied mutations mutated the input
+    #[must_use]
+    pub fn result(&self) -> MutationResult {
+        self.result
+    }
+
+    /// Applies the next scheduled mutation to the input, returning its result.
+    /// Returns [`MutationResult::Skipped`] if all mutations have been applied already.
+    pub fn apply_next<MT, S, SM>(
+        &mut self,
+        mutator: &mut SM,
+        state: &mut S,
+        input: &mut I,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error>
+    where
+        MT: MutatorsTuple<I, S>,
+        SM: ScheduledMutator<I, MT, S>,
+    {
+        if self.is_done() {
+            return Ok(MutationResult::Skipped);
+        }
+        let idx = mutator.schedule(state, input);
+        let outcome = mutator
+            .mutations_mut()
+            .get_and_mutate(idx, state, input, stage_idx)?;
+        if outcome == MutationResult::Mutated {
+            self.result = MutationResult::Mutated;
+        }
+        self.applied += 1;
+        Ok(outcome)
+    }
+
+    /// Applies all remaining scheduled mutations to the input
+    pub fn apply_remaining<MT, S, SM>(
+        &mut self,
+        mutator: &mut SM,
+        state: &mut S,
+        input: &mut I,
+        stage_idx: i32,
+    ) -> Result<MutationResult, Error>
+    where
+        MT: MutatorsTuple<I, S>,
+        SM: ScheduledMutator<I, MT, S>,
+    {
+        while !self.is_done() {
+            self.apply_next(mutator, state, input, stage_idx)?;
+        }
+        Ok(self.result)
+    }
+
+    /// Remembers the input, as mutated so far, to roll back to later
+    pub fn checkpoint(&mut self, input: &I) {
+        self.checkpoint = Some((input.clone(), self.applied, self.result));
+    }
+
+    /// Restores the input and the [`ScheduledMutationPipeline::result`] to the last checkpoint,
+    /// so that the mutations after it can be retried.
+    /// The checkpoint is kept, to roll back to it again if needed.
+    pub fn rollback(&mut self, input: &mut I) -> Result<(), Error> {
+        let Some((checkpointed, applied, result)) = &self.checkpoint else {
+            return Err(Error::illegal_state(
+                "Cannot roll back a mutation pipeline without checkpoint",
+            ));
+        };
+        input.clone_from(checkpointed);
+        self.applied = *applied;
+        self.result = *result;
+        Ok(())
+    }
+}
+
 /// Tuple type of the mutations that compose the Havoc mutator
 pub type HavocMutationsType = tuple_list_type!(
     BitFlipMutator,
@@ -422,8 +551,8 @@ mod tests {
         inputs::{BytesInput, HasBytesVec},
         mutators::{
             mutations::SpliceMutator,
-            scheduled::{havoc_mutations, StdScheduledMutator},
-            Mutator,
+            scheduled::{havoc_mutations, ScheduledMutationPipeline, StdScheduledMutator},
+            MutationResult, Mutator,
         },
         state::StdState,
     };
@@ -518,6 +647,75 @@ mod tests {
             assert_ne!(equal_in_a_row, 5);
         }
     }
+
+    #[test]
+    fn test_pipeline_rollback() {
+        let mut corpus: InMemoryCorpus<BytesInput> = InMemoryCorpus::new();
+        corpus
+            .add(Testcase::new(b"jumps over the lazy dog".to_vec().into()))
+            .unwrap();
+
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0x1337),
+            corpus,
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut havoc = StdScheduledMutator::new(havoc_mutations());
+        let mut input = BytesInput::new(b"the quick brown fox".to_vec());
+        let mut pipeline = ScheduledMutationPipeline::with_stack_len(16);
+
+        assert!(pipeline.rollback(&mut input).is_err());
+
+        // Rolling back also forgets the result of the rolled back mutations
+        let mut scratch = input.clone();
+        let mut from_start = ScheduledMutationPipeline::with_stack_len(16);
+        from_start.checkpoint(&scratch);
+        assert_eq!(
+            from_start
+                .apply_remaining(&mut havoc, &mut state, &mut scratch, 0)
+                .unwrap(),
+            MutationResult::Mutated
+        );
+        from_start.rollback(&mut scratch).unwrap();
+        assert_eq!(from_start.result(), MutationResult::Skipped);
+        assert_eq!(scratch, input);
+
+        for _ in 0..8 {
+            pipeline
+                .apply_next(&mut havoc, &mut state, &mut input, 0)
+                .unwrap();
+        }
+        pipeline.checkpoint(&input);
+        let intermediate = input.clone();
+
+        pipeline
+            .apply_remaining(&mut havoc, &mut state, &mut input, 0)
+            .unwrap();
+        assert!(pipeline.is_done());
+        assert_eq!(pipeline.applied(), 16);
+
+        // Rolling back restores the input after 8 of 16 mutations exactly
+        pipeline.rollback(&mut input).unwrap();
+        assert_eq!(input, intermediate);
+        assert_eq!(pipeline.applied(), 8);
+        assert!(!pipeline.is_done());
+
+        // The retry only applies the tail of the stack
+        pipeline
+            .apply_next(&mut havoc, &mut state, &mut input, 0)
+            .unwrap();
+        assert_eq!(pipeline.applied(), 9);
+        pipeline
+            .apply_remaining(&mut havoc, &mut state, &mut input, 0)
+            .unwrap();
+        assert_eq!(pipeline.applied(), 16);
+    }
 }
 
 /// `SchedulerMutator` Python bindings
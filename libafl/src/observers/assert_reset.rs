@@ -0,0 +1,197 @@
+//! The [`AssertResetObserver`] checks that the observer it wraps resets its state before each run.
+//! Observers that keep state from the previous run leak it into the next one (for example, coverage),
+//! which is hard to spot otherwise. Only available in debug builds, with the `observer_reset_checks` feature.
+
+use alloc::vec::Vec;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::Named, executors::ExitKind, inputs::UsesInput, observers::Observer, Error,
+};
+
+/// Wraps an [`Observer`], panicking if it did not reset to its initial (serialized) state in `pre_exec`.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "O: Serialize + DeserializeOwned")]
+pub struct AssertResetObserver<O> {
+    base: O,
+    /// The serialized state of the observer before its first run
+    initial: Vec<u8>,
+    /// The serialized state of the observer after its last run, if any
+    last_run: Option<Vec<u8>>,
+}
+
+impl<O> AssertResetObserver<O>
+where
+    O: Serialize,
+{
+    /// Wraps the given observer. Its current state is the state it has to reset to before each run.
+    pub fn new(base: O) -> Result<Self, Error> {
+        let initial = postcard::to_allocvec(&base)?;
+        Ok(Self {
+            base,
+            initial,
+            last_run: None,
+        })
+    }
+
+    /// The wrapped observer
+    pub fn base(&self) -> &O {
+        &self.base
+    }
+
+    /// The wrapped observer (mutable)
+    pub fn base_mut(&mut self) -> &mut O {
+        &mut self.base
+    }
+}
+
+impl<O> Named for AssertResetObserver<O>
+where
+    O: Named,
+{
+    fn name(&self) -> &str {
+        self.base.name()
+    }
+}
+
+impl<O, S> Observer<S> for AssertResetObserver<O>
+where
+    O: Observer<S> + Serialize,
+    S: UsesInput,
+{
+    fn flush(&mut self) -> Result<(), Error> {
+        self.base.flush()
+    }
+
+    fn pre_exec(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.base.pre_exec(state, input)?;
+        if let Some(last_run) = self.last_run.take() {
+            let current = postcard::to_allocvec(&self.base)?;
+            assert!(
+                current == self.initial,
+                "Observer {} did not reset its state in pre_exec{}",
+                self.base.name(),
+                if current == last_run {
+                    " (it still holds the state of the last run)"
+                } else {
+                    ""
+                }
+            );
+        }
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.base.post_exec(state, input, exit_kind)?;
+        self.last_run = Some(postcard::to_allocvec(&self.base)?);
+        Ok(())
+    }
+
+    fn pre_exec_child(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.base.pre_exec_child(state, input)
+    }
+
+    fn post_exec_child(
+        &mut self,
+        state: &mut S,
+        input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.base.post_exec_child(state, input, exit_kind)
+    }
+
+    fn observes_stdout(&self) -> bool {
+        self.base.observes_stdout()
+    }
+
+    fn observes_stderr(&self) -> bool {
+        self.base.observes_stderr()
+    }
+
+    fn observe_stdout(&mut self, stdout: &[u8]) {
+        self.base.observe_stdout(stdout);
+    }
+
+    fn observe_stderr(&mut self, stderr: &[u8]) {
+        self.base.observe_stderr(stderr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::AssertResetObserver;
+    use crate::{
+        bolts::tuples::Named,
+        executors::ExitKind,
+        inputs::{NopInput, UsesInput},
+        observers::Observer,
+        state::NopState,
+        Error,
+    };
+
+    /// Counts the runs since the last reset
+    #[derive(Debug, Serialize, Deserialize)]
+    struct CountingObserver {
+        resets: bool,
+        count: usize,
+    }
+
+    impl Named for CountingObserver {
+        fn name(&self) -> &str {
+            "counting"
+        }
+    }
+
+    impl<S> Observer<S> for CountingObserver
+    where
+        S: UsesInput,
+    {
+        fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+            if self.resets {
+                self.count = 0;
+            }
+            Ok(())
+        }
+
+        fn post_exec(
+            &mut self,
+            _state: &mut S,
+            _input: &S::Input,
+            _exit_kind: &ExitKind,
+        ) -> Result<(), Error> {
+            self.count += 1;
+            Ok(())
+        }
+    }
+
+    fn run_twice(resets: bool) {
+        let mut state = NopState::<NopInput>::new();
+        let input = NopInput {};
+        let mut observer = AssertResetObserver::new(CountingObserver { resets, count: 0 }).unwrap();
+        for _ in 0..2 {
+            observer.pre_exec(&mut state, &input).unwrap();
+            observer
+                .post_exec(&mut state, &input, &ExitKind::Ok)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_assert_reset() {
+        run_twice(true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Observer counting did not reset its state in pre_exec")]
+    fn test_assert_reset_broken() {
+        run_twice(false);
+    }
+}
@@ -0,0 +1,95 @@
+//! The [`CrashPcObserver`] keeps the faulting program counter of a crashing run,
+//! so that crashes can be deduplicated by the instruction they fault at.
+
+use alloc::string::{String, ToString};
+use core::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::tuples::Named,
+    executors::ExitKind,
+    inputs::UsesInput,
+    observers::{Observer, ObserverWithHashField},
+    Error,
+};
+
+/// The default name of the [`CrashPcObserver`].
+/// The in-process executors fill the observer with this name on crashes.
+pub const CRASH_PC_OBSERVER_NAME: &str = "CrashPcObserver";
+
+/// An observer holding the program counter the last run crashed at, if it crashed.
+///
+/// The in-process executors read the faulting instruction pointer from the signal context
+/// of the crash handler (see [`crate::bolts::minibsod::crash_pc`]).
+/// When fuzzing with QEMU, set it using the emulator's `Regs::Pc` instead, before the run returns.
+/// A pc set during the run takes precedence over the one from the signal context.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CrashPcObserver {
+    name: String,
+    pc: Option<u64>,
+}
+
+impl CrashPcObserver {
+    /// Creates a new [`CrashPcObserver`] with the given name.
+    /// Only the observer named [`CRASH_PC_OBSERVER_NAME`] gets filled by the in-process executors.
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            pc: None,
+        }
+    }
+
+    /// The program counter the last run crashed at
+    #[must_use]
+    pub fn pc(&self) -> Option<u64> {
+        self.pc
+    }
+
+    /// Sets the program counter the current run crashed at
+    pub fn set_pc(&mut self, pc: u64) {
+        self.pc = Some(pc);
+    }
+}
+
+impl Default for CrashPcObserver {
+    fn default() -> Self {
+        Self::new(CRASH_PC_OBSERVER_NAME)
+    }
+}
+
+impl ObserverWithHashField for CrashPcObserver {
+    /// The faulting program counter, used as hash
+    fn hash(&self) -> Option<u64> {
+        self.pc
+    }
+}
+
+impl<S> Observer<S> for CrashPcObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.pc = None;
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        if exit_kind != &ExitKind::Crash {
+            self.pc = None;
+        }
+        Ok(())
+    }
+}
+
+impl Named for CrashPcObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -9,6 +9,7 @@ use core::{
     hash::Hasher,
     iter::Flatten,
     marker::PhantomData,
+    ops::Range,
     slice::{from_raw_parts, Iter, IterMut},
 };
 
@@ -117,6 +118,14 @@ pub trait MapObserver: HasLen + Named + Serialize + serde::de::DeserializeOwned
     /// Get the number of set entries with the specified indexes
     fn how_many_set(&self, indexes: &[usize]) -> usize;
 
+    /// Collect the indexes of the set entries, the ones that differ from the initial value, into `indexes`.
+    /// `indexes` gets cleared first, so that callers can keep reusing its allocation over executions.
+    fn set_indexes(&self, indexes: &mut Vec<usize>) {
+        let initial = self.initial();
+        indexes.clear();
+        indexes.extend((0..self.usable_count()).filter(|idx| *self.get(*idx) != initial));
+    }
+
     /// Resize the inner map to be smaller (and thus faster to process)
     /// It returns Some(old size) on success, None on failure
     fn downsize_map(&mut self, _new_len: usize) -> Option<usize> {
@@ -182,6 +191,36 @@ where
     }
 }
 
+/// How a [`StdMapObserver`] resets its map before each run
+#[derive(Default)]
+pub enum ResetStrategy<T> {
+    /// Reset every entry to the initial value, the default
+    #[default]
+    Zero,
+    /// Keep the map as it is, so that entries accumulate over runs
+    Keep,
+    /// Reset the map with a custom function, for example to let some counters persist
+    Custom(fn(&mut [T])),
+}
+
+impl<T> Clone for ResetStrategy<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ResetStrategy<T> {}
+
+impl<T> Debug for ResetStrategy<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Zero => f.write_str("Zero"),
+            Self::Keep => f.write_str("Keep"),
+            Self::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
 /// The Map Observer retrieves the state of a map,
 /// that will get updated by the target.
 /// A well-known example is the AFL-Style coverage map.
@@ -195,6 +234,9 @@ where
     map: OwnedMutSlice<'a, T>,
     initial: T,
     name: String,
+    /// Functions can not be serialized, a deserialized observer resets with [`ResetStrategy::Zero`]
+    #[serde(skip)]
+    reset_strategy: ResetStrategy<T>,
 }
 
 impl<'a, S, T> Observer<S> for StdMapObserver<'a, T, false>
@@ -209,9 +251,17 @@ where
         + serde::de::DeserializeOwned
         + Debug,
 {
+    /// Resets the map according to its [`ResetStrategy`]
     #[inline]
     fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
-        self.reset_map()
+        match self.reset_strategy {
+            ResetStrategy::Zero => self.reset_map(),
+            ResetStrategy::Keep => Ok(()),
+            ResetStrategy::Custom(reset) => {
+                reset(self.map.as_mut_slice());
+                Ok(())
+            }
+        }
     }
 }
 
@@ -468,6 +518,7 @@ where
             name: name.into(),
             map,
             initial: T::default(),
+            reset_strategy: ResetStrategy::Zero,
         }
     }
 
@@ -481,6 +532,7 @@ where
             map: OwnedMutSlice::from(map),
             name: name.into(),
             initial: T::default(),
+            reset_strategy: ResetStrategy::Zero,
         }
     }
 
@@ -497,6 +549,7 @@ where
             map,
             name: name.into(),
             initial: T::default(),
+            reset_strategy: ResetStrategy::Zero,
         }
     }
 
@@ -523,6 +576,24 @@ where
     pub fn map_mut(&mut self) -> &mut OwnedMutSlice<'a, T> {
         &mut self.map
     }
+
+    /// Sets how the map gets reset before each run, defaults to [`ResetStrategy::Zero`]
+    #[must_use]
+    pub fn with_reset_strategy(mut self, reset_strategy: ResetStrategy<T>) -> Self {
+        self.reset_strategy = reset_strategy;
+        self
+    }
+
+    /// Sets how the map gets reset before each run
+    pub fn set_reset_strategy(&mut self, reset_strategy: ResetStrategy<T>) {
+        self.reset_strategy = reset_strategy;
+    }
+
+    /// How the map gets reset before each run
+    #[must_use]
+    pub fn reset_strategy(&self) -> ResetStrategy<T> {
+        self.reset_strategy
+    }
 }
 
 impl<'a, T> StdMapObserver<'a, T, false>
@@ -1652,6 +1723,220 @@ where
     }
 }
 
+/// Map observer that only keeps the coverage of edges originating in a whitelist of address ranges,
+/// for example, the ranges of the target module.
+/// The entries of all other edges are reset to the initial value after each run,
+/// so that they never count as novel coverage in a map feedback.
+/// Mapping the edges back to their originating addresses requires a table of the pc of each edge id.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(bound = "M: serde::de::DeserializeOwned")]
+pub struct AddressFilterMapObserver<M>
+where
+    M: Serialize,
+{
+    base: M,
+    /// The map indexes of the edges outside of the whitelist
+    ignored: Vec<usize>,
+}
+
+impl<S, M> Observer<S> for AddressFilterMapObserver<M>
+where
+    M: MapObserver + Observer<S>,
+    S: UsesInput,
+{
+    #[inline]
+    fn pre_exec(&mut self, state: &mut S, input: &S::Input) -> Result<(), Error> {
+        self.base.pre_exec(state, input)
+    }
+
+    #[inline]
+    fn post_exec(
+        &mut self,
+        state: &mut S,
+        input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.base.post_exec(state, input, exit_kind)?;
+        let initial = self.base.initial();
+        for idx in &self.ignored {
+            *self.base.get_mut(*idx) = initial;
+        }
+        Ok(())
+    }
+}
+
+impl<M> Named for AddressFilterMapObserver<M>
+where
+    M: Named + Serialize + serde::de::DeserializeOwned,
+{
+    #[inline]
+    fn name(&self) -> &str {
+        self.base.name()
+    }
+}
+
+impl<M> HasLen for AddressFilterMapObserver<M>
+where
+    M: MapObserver,
+{
+    #[inline]
+    fn len(&self) -> usize {
+        self.base.len()
+    }
+}
+
+impl<M> MapObserver for AddressFilterMapObserver<M>
+where
+    M: MapObserver,
+{
+    type Entry = M::Entry;
+
+    #[inline]
+    fn initial(&self) -> M::Entry {
+        self.base.initial()
+    }
+
+    #[inline]
+    fn usable_count(&self) -> usize {
+        self.base.usable_count()
+    }
+
+    #[inline]
+    fn get(&self, idx: usize) -> &M::Entry {
+        self.base.get(idx)
+    }
+
+    #[inline]
+    fn get_mut(&mut self, idx: usize) -> &mut M::Entry {
+        self.base.get_mut(idx)
+    }
+
+    /// Count the set bytes in the map
+    fn count_bytes(&self) -> u64 {
+        self.base.count_bytes()
+    }
+
+    /// Reset the map
+    #[inline]
+    fn reset_map(&mut self) -> Result<(), Error> {
+        self.base.reset_map()
+    }
+
+    fn hash(&self) -> u64 {
+        self.base.hash()
+    }
+
+    fn to_vec(&self) -> Vec<M::Entry> {
+        self.base.to_vec()
+    }
+
+    fn how_many_set(&self, indexes: &[usize]) -> usize {
+        self.base.how_many_set(indexes)
+    }
+
+    fn downsize_map(&mut self, new_len: usize) -> Option<usize> {
+        self.base.downsize_map(new_len)
+    }
+}
+
+impl<M> AsSlice for AddressFilterMapObserver<M>
+where
+    M: MapObserver + AsSlice,
+{
+    type Entry = <M as AsSlice>::Entry;
+    #[inline]
+    fn as_slice(&self) -> &[Self::Entry] {
+        self.base.as_slice()
+    }
+}
+
+impl<M> AsMutSlice for AddressFilterMapObserver<M>
+where
+    M: MapObserver + AsMutSlice,
+{
+    type Entry = <M as AsMutSlice>::Entry;
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [Self::Entry] {
+        self.base.as_mut_slice()
+    }
+}
+
+impl<M> AddressFilterMapObserver<M>
+where
+    M: MapObserver + Serialize + serde::de::DeserializeOwned,
+{
+    /// Creates a new [`AddressFilterMapObserver`].
+    /// `edge_pcs` holds the originating pc of each edge id of the `base` map,
+    /// only edges with a pc inside of one of the `whitelist` ranges are kept.
+    /// Edges without an entry in `edge_pcs` are ignored.
+    pub fn new(base: M, edge_pcs: &[u64], whitelist: &[Range<u64>]) -> Self {
+        let ignored = (0..base.len())
+            .filter(|idx| {
+                edge_pcs
+                    .get(*idx)
+                    .map_or(true, |pc| !whitelist.iter().any(|range| range.contains(pc)))
+            })
+            .collect();
+        Self { base, ignored }
+    }
+
+    /// The map indexes of the edges outside of the whitelist
+    #[must_use]
+    pub fn ignored(&self) -> &[usize] {
+        &self.ignored
+    }
+}
+
+impl<'it, M> AsIter<'it> for AddressFilterMapObserver<M>
+where
+    M: MapObserver + AsIter<'it, Item = <M as MapObserver>::Entry>,
+{
+    type Item = <M as MapObserver>::Entry;
+    type IntoIter = <M as AsIter<'it>>::IntoIter;
+
+    fn as_iter(&'it self) -> Self::IntoIter {
+        self.base.as_iter()
+    }
+}
+
+impl<'it, M> AsIterMut<'it> for AddressFilterMapObserver<M>
+where
+    M: MapObserver + AsIterMut<'it, Item = <M as MapObserver>::Entry>,
+{
+    type Item = <M as MapObserver>::Entry;
+    type IntoIter = <M as AsIterMut<'it>>::IntoIter;
+
+    fn as_iter_mut(&'it mut self) -> Self::IntoIter {
+        self.base.as_iter_mut()
+    }
+}
+
+impl<'it, M> IntoIterator for &'it AddressFilterMapObserver<M>
+where
+    M: MapObserver + Serialize,
+    &'it M: IntoIterator<Item = &'it <M as MapObserver>::Entry>,
+{
+    type Item = &'it <M as MapObserver>::Entry;
+    type IntoIter = <&'it M as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.base.into_iter()
+    }
+}
+
+impl<'it, M> IntoIterator for &'it mut AddressFilterMapObserver<M>
+where
+    M: MapObserver + Serialize,
+    &'it mut M: IntoIterator<Item = &'it mut <M as MapObserver>::Entry>,
+{
+    type Item = &'it mut <M as MapObserver>::Entry;
+    type IntoIter = <&'it mut M as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.base.into_iter()
+    }
+}
+
 /// The Multi Map Observer merge different maps into one observer
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(bound = "T: serde::de::DeserializeOwned")]
@@ -2164,6 +2449,94 @@ where
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::InMemoryCorpus,
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::{ConstFeedback, Feedback, MaxMapFeedback},
+        inputs::BytesInput,
+        observers::{
+            AddressFilterMapObserver, MapObserver, Observer, OwnedMapObserver, ResetStrategy,
+            StdMapObserver,
+        },
+        state::{NopState, StdState},
+    };
+
+    #[test]
+    fn test_address_filter() {
+        let edge_pcs = [0x1000, 0x2000, 0x1010, 0x3000];
+        let observer = AddressFilterMapObserver::new(
+            OwnedMapObserver::new("map", vec![0_u8; 4]),
+            &edge_pcs,
+            &[0x1000..0x1100, 0x4000..0x5000],
+        );
+        assert_eq!(observer.ignored(), &[1, 3]);
+
+        let mut feedback = MaxMapFeedback::new(&observer);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![]);
+        let mut observers = tuple_list!(observer);
+
+        // Run once hitting only edges outside of the whitelist, then once hitting an edge inside
+        for (hit, novel) in [(&[1, 3][..], false), (&[2], true)] {
+            observers.0.pre_exec(&mut state, &input).unwrap();
+            for idx in hit {
+                *observers.0.get_mut(*idx) = 1;
+            }
+            observers
+                .0
+                .post_exec(&mut state, &input, &ExitKind::Ok)
+                .unwrap();
+
+            let interesting = feedback
+                .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+                .unwrap();
+            assert_eq!(interesting, novel);
+        }
+    }
+
+    #[test]
+    fn test_reset_strategy() {
+        /// Keeps the counters of the first half of the map
+        fn reset_second_half(map: &mut [u8]) {
+            let half = map.len() / 2;
+            map[half..].fill(0);
+        }
+
+        let mut state = NopState::new();
+        let input = BytesInput::new(vec![]);
+        for (strategy, expected) in [
+            (ResetStrategy::Zero, [0, 1, 0, 1]),
+            (ResetStrategy::Keep, [1, 1, 1, 1]),
+            (ResetStrategy::Custom(reset_second_half), [1, 1, 0, 1]),
+        ] {
+            let mut observer =
+                StdMapObserver::new_owned("map", vec![0_u8; 4]).with_reset_strategy(strategy);
+
+            // Run two inputs, hitting different entries
+            for hit in [[0, 2], [1, 3]] {
+                observer.pre_exec(&mut state, &input).unwrap();
+                for idx in hit {
+                    *observer.get_mut(idx) = 1;
+                }
+            }
+            assert_eq!(observer.to_vec(), expected);
+        }
+    }
+}
+
 /// `MapObserver` Python bindings
 #[cfg(feature = "python")]
 #[allow(missing_docs)]
@@ -19,6 +19,15 @@ pub use stacktrace::*;
 pub mod concolic;
 
 pub mod value;
+
+pub mod crash_pc;
+pub use crash_pc::{CrashPcObserver, CRASH_PC_OBSERVER_NAME};
+
+pub mod phase_timing;
+pub use phase_timing::{PhaseClock, PhaseTimingObserver};
+
+#[cfg(all(debug_assertions, feature = "observer_reset_checks"))]
+pub mod assert_reset;
 // Rust is breaking this with 'error: intrinsic safety mismatch between list of intrinsics within the compiler and core library intrinsics for intrinsic `type_id`' and so we disable this component for the moment
 //#[cfg(unstable_feature)]
 //pub mod owned;
@@ -30,6 +39,8 @@ use alloc::{
 };
 use core::{fmt::Debug, time::Duration};
 
+#[cfg(all(debug_assertions, feature = "observer_reset_checks"))]
+pub use assert_reset::AssertResetObserver;
 use serde::{Deserialize, Serialize};
 pub use value::*;
 
@@ -0,0 +1,225 @@
+//! The [`PhaseTimingObserver`] splits the runtime of an execution into the time spent in the target,
+//! and the overhead of the harness around it, such as the hooks of the instrumentation.
+
+use alloc::{
+    string::{String, ToString},
+    sync::Arc,
+};
+use core::{
+    fmt::Debug,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::{current_time, tuples::Named},
+    executors::ExitKind,
+    inputs::UsesInput,
+    observers::Observer,
+    Error,
+};
+
+/// No mark set yet
+const UNSET: u64 = u64::MAX;
+
+/// The start and the end of the target execution, in nanoseconds of [`current_time`]
+#[derive(Debug)]
+struct PhaseMarks {
+    start: AtomicU64,
+    end: AtomicU64,
+}
+
+impl Default for PhaseMarks {
+    fn default() -> Self {
+        Self {
+            start: AtomicU64::new(UNSET),
+            end: AtomicU64::new(UNSET),
+        }
+    }
+}
+
+/// A handle to mark the start and the end of the target execution for a [`PhaseTimingObserver`],
+/// to be moved into the harness, or a hook of the executor.
+/// All clones of a clock share the same marks.
+#[derive(Clone, Debug, Default)]
+pub struct PhaseClock {
+    marks: Arc<PhaseMarks>,
+}
+
+impl PhaseClock {
+    /// Marks the start of the target execution, right before the harness calls into the target
+    pub fn mark_target_start(&self) {
+        self.marks
+            .start
+            .store(current_time().as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Marks the end of the target execution, right after the target returned
+    pub fn mark_target_end(&self) {
+        self.marks
+            .end
+            .store(current_time().as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Clears both marks, for the next execution
+    fn reset(&self) {
+        self.marks.start.store(UNSET, Ordering::Relaxed);
+        self.marks.end.store(UNSET, Ordering::Relaxed);
+    }
+
+    /// The marks of the execution, if the start was marked.
+    /// An execution that did not mark its end, for example as it hung, ends at `now`.
+    fn target_span(&self, now: Duration) -> Option<(Duration, Duration)> {
+        let start = self.marks.start.load(Ordering::Relaxed);
+        if start == UNSET {
+            return None;
+        }
+        let end = match self.marks.end.load(Ordering::Relaxed) {
+            UNSET => now,
+            end => Duration::from_nanos(end),
+        };
+        Some((Duration::from_nanos(start), end))
+    }
+}
+
+/// An observer splitting the runtime of the last execution into the time spent in the target,
+/// and the overhead of the harness, such as the `pre_exec` and `post_exec` hooks of other observers,
+/// or the reporting of `ASan`. Lets a feedback ignore timeouts dominated by the overhead.
+///
+/// The runtime is measured from the `pre_exec` to the `post_exec` of this observer,
+/// so put it first in the [`crate::observers::ObserversTuple`] to include the hooks of the other observers.
+/// The target execution has to be marked through the [`PhaseClock`] of this observer, see [`Self::clock`].
+/// If the target did not mark its end, as it hung, it counts as running until the `post_exec`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhaseTimingObserver {
+    name: String,
+    start_time: Duration,
+    /// The runtime of the last execution, and the part of it spent in the target, if marked
+    last_runtime: Option<Duration>,
+    last_target_time: Option<Duration>,
+    /// Not serialized, a deserialized observer gets a new clock
+    #[serde(skip)]
+    clock: PhaseClock,
+}
+
+impl PhaseTimingObserver {
+    /// Creates a new [`PhaseTimingObserver`] with the given name
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            start_time: Duration::ZERO,
+            last_runtime: None,
+            last_target_time: None,
+            clock: PhaseClock::default(),
+        }
+    }
+
+    /// The [`PhaseClock`] to mark the target execution with
+    #[must_use]
+    pub fn clock(&self) -> PhaseClock {
+        self.clock.clone()
+    }
+
+    /// The whole runtime of the last execution, from the `pre_exec` to the `post_exec` of this observer
+    #[must_use]
+    pub fn total_time(&self) -> Option<Duration> {
+        self.last_runtime
+    }
+
+    /// The time the last execution spent in the target, `None` if the target execution was not marked
+    #[must_use]
+    pub fn target_time(&self) -> Option<Duration> {
+        self.last_target_time
+    }
+
+    /// The time the last execution spent outside of the target, `None` if the target execution was not marked
+    #[must_use]
+    pub fn overhead_time(&self) -> Option<Duration> {
+        Some(self.last_runtime?.saturating_sub(self.last_target_time?))
+    }
+}
+
+impl<S> Observer<S> for PhaseTimingObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.last_runtime = None;
+        self.last_target_time = None;
+        self.clock.reset();
+        self.start_time = current_time();
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &S::Input,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        let now = current_time();
+        self.last_runtime = now.checked_sub(self.start_time);
+        self.last_target_time = self
+            .clock
+            .target_span(now)
+            .map(|(start, end)| end.saturating_sub(start));
+        Ok(())
+    }
+}
+
+impl Named for PhaseTimingObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread::sleep, time::Duration};
+
+    use super::PhaseTimingObserver;
+    use crate::{executors::ExitKind, inputs::BytesInput, observers::Observer, state::NopState};
+
+    #[test]
+    fn test_phase_timing_observer() {
+        let mut observer = PhaseTimingObserver::new("phases");
+        let mut state = NopState::new();
+        let input = BytesInput::new(vec![]);
+        let clock = observer.clock();
+
+        // Without marks, the phases are unknown
+        Observer::<NopState<BytesInput>>::pre_exec(&mut observer, &mut state, &input).unwrap();
+        observer
+            .post_exec(&mut state, &input, &ExitKind::Ok)
+            .unwrap();
+        assert!(observer.total_time().is_some());
+        assert_eq!(observer.target_time(), None);
+        assert_eq!(observer.overhead_time(), None);
+
+        Observer::<NopState<BytesInput>>::pre_exec(&mut observer, &mut state, &input).unwrap();
+        sleep(Duration::from_millis(20));
+        clock.mark_target_start();
+        sleep(Duration::from_millis(5));
+        clock.mark_target_end();
+        observer
+            .post_exec(&mut state, &input, &ExitKind::Ok)
+            .unwrap();
+        let target = observer.target_time().unwrap();
+        let overhead = observer.overhead_time().unwrap();
+        assert!(target >= Duration::from_millis(5));
+        assert!(overhead >= Duration::from_millis(20));
+        assert_eq!(target + overhead, observer.total_time().unwrap());
+
+        // A hanging target runs until the post_exec
+        Observer::<NopState<BytesInput>>::pre_exec(&mut observer, &mut state, &input).unwrap();
+        clock.mark_target_start();
+        sleep(Duration::from_millis(5));
+        observer
+            .post_exec(&mut state, &input, &ExitKind::Timeout)
+            .unwrap();
+        assert!(observer.target_time().unwrap() >= Duration::from_millis(5));
+    }
+}
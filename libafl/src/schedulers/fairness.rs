@@ -0,0 +1,326 @@
+//! The [`FairnessScheduler`] distributes the scheduling evenly across families of [`Testcase`]s,
+//! so that a prolific lineage does not dominate the fuzzing campaign.
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    inputs::UsesInput,
+    schedulers::Scheduler,
+    state::{HasCorpus, HasMetadata, UsesState},
+    Error,
+};
+
+/// A testcase metadata tagging the family (for example, the initial seed) a [`Testcase`] descends from.
+/// Testcases without this metadata belong to the family `0`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FamilyMetadata {
+    /// The family of this testcase
+    pub family: u64,
+}
+
+crate::impl_serdeany!(FamilyMetadata);
+
+impl FamilyMetadata {
+    /// Creates a new [`struct@FamilyMetadata`]
+    #[must_use]
+    pub fn new(family: u64) -> Self {
+        Self { family }
+    }
+}
+
+/// A state metadata holding the number of times each family has been scheduled
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FamilySchedulingMetadata {
+    /// family -> number of times a testcase of this family has been scheduled
+    pub counts: HashMap<u64, usize>,
+    /// family -> number of testcases of this family in the corpus
+    pub members: HashMap<u64, usize>,
+}
+
+crate::impl_serdeany!(FamilySchedulingMetadata);
+
+impl FamilySchedulingMetadata {
+    /// The family that has been scheduled the least so far, if any
+    #[must_use]
+    pub fn least_scheduled(&self) -> Option<u64> {
+        self.counts
+            .iter()
+            .min_by_key(|(family, count)| (**count, **family))
+            .map(|(family, _)| *family)
+    }
+
+    /// Registers a new member of the given family
+    fn add_member(&mut self, family: u64) {
+        *self.members.entry(family).or_insert(0) += 1;
+        if !self.counts.contains_key(&family) {
+            // A new family starts level with the least scheduled one, instead of starving all others
+            let min = self.counts.values().min().copied().unwrap_or(0);
+            self.counts.insert(family, min);
+        }
+    }
+
+    /// Unregisters a member of the given family, forgetting the family if it was the last member
+    fn remove_member(&mut self, family: u64) {
+        if let Some(members) = self.members.get_mut(&family) {
+            *members = members.saturating_sub(1);
+            if *members == 0 {
+                self.members.remove(&family);
+                self.counts.remove(&family);
+            }
+        }
+    }
+}
+
+/// The [`FairnessScheduler`] wraps a `base` [`Scheduler`] and biases its choice towards the family
+/// of [`Testcase`]s (see [`struct@FamilyMetadata`]) that has been scheduled the least so far.
+/// The `base` scheduler always decides which testcase gets picked: it is asked again, up to
+/// `max_redraws` times, until its pick belongs to that family. If it never does, its last pick is
+/// used as is.
+#[derive(Debug, Clone)]
+pub struct FairnessScheduler<CS> {
+    base: CS,
+    max_redraws: usize,
+}
+
+/// The default number of times the `base` scheduler of a [`FairnessScheduler`] gets asked again
+pub const DEFAULT_MAX_REDRAWS: usize = 32;
+
+impl<CS> UsesState for FairnessScheduler<CS>
+where
+    CS: UsesState,
+{
+    type State = CS::State;
+}
+
+impl<CS> Scheduler for FairnessScheduler<CS>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata,
+{
+    /// Add an entry to the corpus, registering its family
+    fn on_add(&self, state: &mut CS::State, idx: CorpusId) -> Result<(), Error> {
+        let family = Self::family(state, idx)?;
+        Self::scheduling_metadata_mut(state).add_member(family);
+        self.base.on_add(state, idx)
+    }
+
+    /// Replaces the testcase at the given idx, moving it to its new family
+    fn on_replace(
+        &self,
+        state: &mut CS::State,
+        idx: CorpusId,
+        testcase: &Testcase<<CS::State as UsesInput>::Input>,
+    ) -> Result<(), Error> {
+        let prev_family = Self::testcase_family(testcase);
+        let family = Self::family(state, idx)?;
+        if prev_family != family {
+            let meta = Self::scheduling_metadata_mut(state);
+            meta.remove_member(prev_family);
+            meta.add_member(family);
+        }
+        self.base.on_replace(state, idx, testcase)
+    }
+
+    /// Removes an entry from the corpus, forgetting its family if it was the last member
+    fn on_remove(
+        &self,
+        state: &mut CS::State,
+        idx: CorpusId,
+        testcase: &Option<Testcase<<CS::State as UsesInput>::Input>>,
+    ) -> Result<(), Error> {
+        self.base.on_remove(state, idx, testcase)?;
+        let Some(testcase) = testcase else {
+            return Ok(());
+        };
+        let family = Self::testcase_family(testcase);
+        if let Some(meta) = state.metadata_mut().get_mut::<FamilySchedulingMetadata>() {
+            meta.remove_member(family);
+        }
+        Ok(())
+    }
+
+    /// Gets the next entry, preferring the least scheduled family.
+    /// The `base` scheduler is asked again, up to `max_redraws` times, until its entry belongs
+    /// to the least scheduled family. Otherwise, its last entry gets scheduled.
+    fn next(&self, state: &mut CS::State) -> Result<CorpusId, Error> {
+        let target = state
+            .metadata()
+            .get::<FamilySchedulingMetadata>()
+            .and_then(FamilySchedulingMetadata::least_scheduled);
+
+        let mut idx = self.base.next(state)?;
+        let mut family = Self::family(state, idx)?;
+        if let Some(target) = target {
+            for _ in 0..self.max_redraws {
+                if family == target {
+                    break;
+                }
+                idx = self.base.next(state)?;
+                family = Self::family(state, idx)?;
+            }
+        }
+
+        if let Some(meta) = state.metadata_mut().get_mut::<FamilySchedulingMetadata>() {
+            *meta.counts.entry(family).or_insert(0) += 1;
+        }
+        Ok(idx)
+    }
+}
+
+impl<CS> FairnessScheduler<CS>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata,
+{
+    /// Creates a new [`FairnessScheduler`] that wraps a `base` [`Scheduler`]
+    #[must_use]
+    pub fn new(base: CS) -> Self {
+        Self {
+            base,
+            max_redraws: DEFAULT_MAX_REDRAWS,
+        }
+    }
+
+    /// Sets how many times the `base` scheduler gets asked again for an entry of the least
+    /// scheduled family
+    #[must_use]
+    pub fn with_max_redraws(mut self, max_redraws: usize) -> Self {
+        self.max_redraws = max_redraws;
+        self
+    }
+
+    /// Get a reference to the base scheduler
+    pub fn base(&self) -> &CS {
+        &self.base
+    }
+
+    /// The family of the testcase at the given idx
+    fn family(state: &CS::State, idx: CorpusId) -> Result<u64, Error> {
+        Ok(Self::testcase_family(&state.corpus().get(idx)?.borrow()))
+    }
+
+    /// The family of the given testcase
+    fn testcase_family(testcase: &Testcase<<CS::State as UsesInput>::Input>) -> u64 {
+        testcase
+            .metadata()
+            .get::<FamilyMetadata>()
+            .map_or(0, |meta| meta.family)
+    }
+
+    /// The [`FamilySchedulingMetadata`] of the state, added if missing
+    fn scheduling_metadata_mut(state: &mut CS::State) -> &mut FamilySchedulingMetadata {
+        if state.metadata().get::<FamilySchedulingMetadata>().is_none() {
+            state.add_metadata(FamilySchedulingMetadata::default());
+        }
+        state
+            .metadata_mut()
+            .get_mut::<FamilySchedulingMetadata>()
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::BytesInput,
+        schedulers::{
+            fairness::{FamilyMetadata, FamilySchedulingMetadata},
+            FairnessScheduler, QueueScheduler, Scheduler,
+        },
+        state::{HasCorpus, HasMetadata, StdState, UsesState},
+        Error,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// A [`QueueScheduler`] counting how often it got asked for the next entry
+    #[derive(Debug)]
+    struct CountingScheduler {
+        base: QueueScheduler<TestState>,
+        draws: Cell<usize>,
+        last: Cell<Option<CorpusId>>,
+    }
+
+    impl UsesState for CountingScheduler {
+        type State = TestState;
+    }
+
+    impl Scheduler for CountingScheduler {
+        fn on_add(&self, state: &mut TestState, idx: CorpusId) -> Result<(), Error> {
+            self.base.on_add(state, idx)
+        }
+
+        fn next(&self, state: &mut TestState) -> Result<CorpusId, Error> {
+            self.draws.set(self.draws.get() + 1);
+            let idx = self.base.next(state)?;
+            self.last.set(Some(idx));
+            Ok(idx)
+        }
+    }
+
+    #[test]
+    fn test_fairness_scheduler() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let scheduler = FairnessScheduler::new(CountingScheduler {
+            base: QueueScheduler::new(),
+            draws: Cell::new(0),
+            last: Cell::new(None),
+        });
+
+        // Family 1 is four times as prolific as family 2
+        for i in 0..10_u8 {
+            let mut testcase = Testcase::new(BytesInput::new(vec![i]));
+            testcase.add_metadata(FamilyMetadata::new(if i < 8 { 1 } else { 2 }));
+            let idx = state.corpus_mut().add(testcase).unwrap();
+            scheduler.on_add(&mut state, idx).unwrap();
+        }
+
+        for _ in 0..100 {
+            let idx = scheduler.next(&mut state).unwrap();
+            assert_eq!(*state.corpus().current(), Some(idx));
+            // The pick always comes from the base scheduler
+            assert_eq!(scheduler.base().last.get(), Some(idx));
+        }
+        // The base scheduler got asked again only to reach the other family
+        assert!(scheduler.base().draws.get() > 100);
+        assert!(scheduler.base().draws.get() <= 100 * 9);
+
+        let counts = &state
+            .metadata()
+            .get::<FamilySchedulingMetadata>()
+            .unwrap()
+            .counts;
+        assert_eq!(counts[&1] + counts[&2], 100);
+        assert!(counts[&1].abs_diff(counts[&2]) <= 1);
+
+        // Moving the last member of family 2 forgets it
+        for i in 8..10_usize {
+            let idx = CorpusId::from(i);
+            let mut testcase = Testcase::new(BytesInput::new(vec![0]));
+            testcase.add_metadata(FamilyMetadata::new(1));
+            let prev = state.corpus_mut().replace(idx, testcase).unwrap();
+            scheduler.on_replace(&mut state, idx, &prev).unwrap();
+        }
+        let meta = state.metadata().get::<FamilySchedulingMetadata>().unwrap();
+        assert_eq!(meta.members.get(&1), Some(&10));
+        assert!(!meta.counts.contains_key(&2));
+    }
+}
@@ -0,0 +1,201 @@
+//! The [`MetadataWeightedScheduler`] samples [`crate::corpus::Testcase`]s proportionally to a weight
+//! kept in their [`struct@WeightMetadata`], for example set from their execution time or novelty.
+
+use alloc::borrow::ToOwned;
+use core::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::{Corpus, CorpusId},
+    inputs::UsesInput,
+    random_corpus_id,
+    schedulers::{next_enabled, Scheduler},
+    state::{HasCorpus, HasMetadata, HasRand, UsesState},
+    Error,
+};
+
+/// The weight of `Testcase`s without a [`struct@WeightMetadata`]
+pub const DEFAULT_TESTCASE_WEIGHT: f64 = 1.0;
+
+/// A testcase metadata holding the weight a [`MetadataWeightedScheduler`] samples the `Testcase` with
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WeightMetadata {
+    /// The weight, relative to the weights of the other testcases
+    pub weight: f64,
+}
+
+crate::impl_serdeany!(WeightMetadata);
+
+impl WeightMetadata {
+    /// Creates a new [`struct@WeightMetadata`]
+    #[must_use]
+    pub fn new(weight: f64) -> Self {
+        Self { weight }
+    }
+}
+
+/// Samples the next `Testcase` with a probability proportional to its [`struct@WeightMetadata`].
+/// Testcases without weight count as [`DEFAULT_TESTCASE_WEIGHT`], so that sampling is uniform if no weights were set.
+/// Negative weights, as well as disabled testcases, count as `0`.
+///
+/// Unlike the [`crate::schedulers::WeightedScheduler`], the weights are not computed by the scheduler,
+/// but set by the user with [`MetadataWeightedScheduler::set_weight`] or by a feedback.
+#[derive(Debug, Clone)]
+pub struct MetadataWeightedScheduler<S> {
+    phantom: PhantomData<S>,
+}
+
+impl<S> UsesState for MetadataWeightedScheduler<S>
+where
+    S: UsesInput,
+{
+    type State = S;
+}
+
+impl<S> Scheduler for MetadataWeightedScheduler<S>
+where
+    S: HasCorpus + HasRand,
+{
+    /// Gets the next entry, sampled by weight
+    #[allow(clippy::cast_precision_loss)]
+    fn next(&self, state: &mut Self::State) -> Result<CorpusId, Error> {
+        if state.corpus().count() == 0 {
+            return Err(Error::empty("No entries in corpus".to_owned()));
+        }
+
+        let mut total = 0.0;
+        for id in state.corpus().ids() {
+            total += Self::weight(state, id)?;
+        }
+
+        let id = if total > 0.0 {
+            let threshold = total * (state.rand_mut().next() as f64 / u64::MAX as f64);
+            let mut sum = 0.0;
+            let mut ret = state.corpus().last().unwrap();
+            for id in state.corpus().ids() {
+                let weight = Self::weight(state, id)?;
+                sum += weight;
+                if weight > 0.0 && sum >= threshold {
+                    ret = id;
+                    break;
+                }
+            }
+            ret
+        } else {
+            next_enabled(state, |state| {
+                Ok(random_corpus_id!(state.corpus(), state.rand_mut()))
+            })?
+        };
+        *state.corpus_mut().current_mut() = Some(id);
+        Ok(id)
+    }
+}
+
+impl<S> MetadataWeightedScheduler<S>
+where
+    S: HasCorpus,
+{
+    /// Creates a new [`MetadataWeightedScheduler`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the weight of the `Testcase` with the given id
+    #[allow(clippy::unused_self)]
+    pub fn set_weight(&self, state: &mut S, id: CorpusId, weight: f64) -> Result<(), Error> {
+        let mut testcase = state.corpus().get(id)?.borrow_mut();
+        if let Some(meta) = testcase.metadata_mut().get_mut::<WeightMetadata>() {
+            meta.weight = weight;
+        } else {
+            testcase.add_metadata(WeightMetadata::new(weight));
+        }
+        Ok(())
+    }
+
+    /// The weight of the `Testcase` with the given id, as used for sampling
+    fn weight(state: &S, id: CorpusId) -> Result<f64, Error> {
+        let testcase = state.corpus().get(id)?.borrow();
+        if testcase.disabled() {
+            return Ok(0.0);
+        }
+        let weight = testcase
+            .metadata()
+            .get::<WeightMetadata>()
+            .map_or(DEFAULT_TESTCASE_WEIGHT, |meta| meta.weight);
+        Ok(weight.max(0.0))
+    }
+}
+
+impl<S> Default for MetadataWeightedScheduler<S>
+where
+    S: HasCorpus,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::BytesInput,
+        schedulers::{MetadataWeightedScheduler, Scheduler},
+        state::{HasCorpus, StdState},
+    };
+
+    #[test]
+    fn test_metadata_weighted_scheduler() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let scheduler = MetadataWeightedScheduler::new();
+
+        let ids = (0..4_u8)
+            .map(|i| {
+                let id = state
+                    .corpus_mut()
+                    .add(Testcase::new(BytesInput::new(vec![i])))
+                    .unwrap();
+                scheduler.on_add(&mut state, id).unwrap();
+                id
+            })
+            .collect::<Vec<_>>();
+
+        let count_picks = |state: &mut StdState<_, _, _, _>| {
+            let mut picks = [0_usize; 4];
+            for _ in 0..4000 {
+                let id = scheduler.next(state).unwrap();
+                picks[ids.iter().position(|x| *x == id).unwrap()] += 1;
+            }
+            picks
+        };
+
+        // Without weights, every entry gets picked
+        let picks = count_picks(&mut state);
+        assert!(picks.iter().all(|p| *p > 500));
+
+        scheduler.set_weight(&mut state, ids[2], 10.0).unwrap();
+        scheduler.set_weight(&mut state, ids[3], 0.0).unwrap();
+        let picks = count_picks(&mut state);
+        assert_eq!(picks[3], 0);
+        assert!(picks[2] > 5 * picks[0]);
+        assert!(picks[2] > 5 * picks[1]);
+    }
+}
@@ -268,9 +268,13 @@ where
     pub fn cull(&self, state: &mut CS::State) -> Result<(), Error> {
         let Some(top_rated) = state.metadata().get::<TopRatedsMetadata>() else { return Ok(()) };
 
+        // Walk the map indexes in order, so that the favored entries do not depend on the hash map iteration order
+        let mut top_rated = top_rated.map.iter().collect::<Vec<_>>();
+        top_rated.sort_unstable_by_key(|(key, _)| **key);
+
         let mut acc = HashSet::new();
 
-        for (key, idx) in &top_rated.map {
+        for (key, idx) in top_rated {
             if !acc.contains(key) {
                 let mut entry = state.corpus().get(*idx)?.borrow_mut();
                 let meta = entry.metadata().get::<M>().ok_or_else(|| {
@@ -323,3 +327,66 @@ pub type LenTimeMinimizerScheduler<CS, M> =
 /// that exercise all the entries registered in the [`MapIndexesMetadata`].
 pub type IndexesLenTimeMinimizerScheduler<CS> =
     MinimizerScheduler<CS, LenTimeMulTestcaseScore<<CS as UsesState>::State>, MapIndexesMetadata>;
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::{ConstFeedback, MapIndexesMetadata},
+        inputs::BytesInput,
+        schedulers::{
+            minimizer::{IsFavoredMetadata, TopRatedsMetadata},
+            IndexesLenTimeMinimizerScheduler, QueueScheduler,
+        },
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_cull_order() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let scheduler = IndexesLenTimeMinimizerScheduler::new(QueueScheduler::new());
+
+        // The top rated entry of index 0 covers all indexes, every other one only its own
+        let mut top_rated = TopRatedsMetadata::new();
+        let mut add = |indexes: Vec<usize>| {
+            let mut testcase = Testcase::new(BytesInput::new(vec![0]));
+            testcase.add_metadata(MapIndexesMetadata::new(indexes));
+            state.corpus_mut().add(testcase).unwrap()
+        };
+        let all = add((0..64).collect());
+        top_rated.map.insert(0, all);
+        let singles = (1..64)
+            .map(|idx| {
+                let id = add(vec![idx]);
+                top_rated.map.insert(idx, id);
+                id
+            })
+            .collect::<Vec<_>>();
+        state.add_metadata(top_rated);
+
+        // Walking the indexes in order, the first entry already covers all others
+        scheduler.cull(&mut state).unwrap();
+        let favored = |id| {
+            state
+                .corpus()
+                .get(id)
+                .unwrap()
+                .borrow()
+                .has_metadata::<IsFavoredMetadata>()
+        };
+        assert!(favored(all));
+        assert!(!singles.into_iter().any(favored));
+    }
+}
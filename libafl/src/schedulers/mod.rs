@@ -29,6 +29,18 @@ pub use weighted::{StdWeightedScheduler, WeightedScheduler};
 pub mod tuneable;
 pub use tuneable::*;
 
+pub mod fairness;
+pub use fairness::FairnessScheduler;
+
+pub mod near_miss_sched;
+pub use near_miss_sched::NearMissScheduler;
+
+pub mod metadata_weighted;
+pub use metadata_weighted::MetadataWeightedScheduler;
+
+pub mod rareness;
+pub use rareness::RarenessScheduler;
+
 use crate::{
     bolts::rands::Rand,
     corpus::{Corpus, CorpusId, Testcase},
@@ -70,6 +82,62 @@ pub trait Scheduler: UsesState {
     fn next(&self, state: &mut Self::State) -> Result<CorpusId, Error>;
 }
 
+/// Draws entries with `draw` until it returns one that is not disabled (see [`Testcase::disabled`]).
+/// `draw` is called at most twice per corpus entry. If none of its entries is enabled, the first
+/// enabled entry after its last one becomes the current entry instead.
+/// Errors with [`Error::Empty`] if all entries in the corpus are disabled.
+pub fn next_enabled<S, F>(state: &mut S, mut draw: F) -> Result<CorpusId, Error>
+where
+    S: HasCorpus,
+    F: FnMut(&mut S) -> Result<CorpusId, Error>,
+{
+    let max_draws = state.corpus().count().saturating_mul(2).max(1);
+    let mut id = draw(state)?;
+    for _ in 1..max_draws {
+        if !state.corpus().get(id)?.borrow().disabled() {
+            return Ok(id);
+        }
+        id = draw(state)?;
+    }
+    if !state.corpus().get(id)?.borrow().disabled() {
+        return Ok(id);
+    }
+
+    // Walk the corpus, wrapping around, starting after the last drawn entry
+    let corpus = state.corpus();
+    let after = corpus.ids().skip_while(|other| *other != id).skip(1);
+    let mut found = None;
+    for other in after.chain(corpus.ids()) {
+        if !corpus.get(other)?.borrow().disabled() {
+            found = Some(other);
+            break;
+        }
+    }
+    let Some(id) = found else {
+        return Err(Error::empty(
+            "All entries in the corpus are disabled".to_owned(),
+        ));
+    };
+    *state.corpus_mut().current_mut() = Some(id);
+    Ok(id)
+}
+
+/// Removes every corpus entry `f` returns `false` for, see [`Corpus::retain`],
+/// and tells the `scheduler` about each removed entry through [`Scheduler::on_remove`].
+/// Returns the number of removed entries.
+pub fn retain_corpus<CS, F>(scheduler: &CS, state: &mut CS::State, f: F) -> Result<usize, Error>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus,
+    F: FnMut(&Testcase<<CS::State as UsesInput>::Input>) -> bool,
+{
+    let removed = state.corpus_mut().retain_collect(f)?;
+    for (id, testcase) in &removed {
+        scheduler.on_remove(state, *id, &Some(testcase.clone()))?;
+    }
+    Ok(removed.len())
+}
+
 /// Feed the fuzzer simply with a random testcase on request
 #[derive(Debug, Clone)]
 pub struct RandScheduler<S> {
@@ -87,12 +155,14 @@ impl<S> Scheduler for RandScheduler<S>
 where
     S: HasCorpus + HasRand,
 {
-    /// Gets the next entry at random
+    /// Gets the next entry at random, skipping disabled entries
     fn next(&self, state: &mut Self::State) -> Result<CorpusId, Error> {
         if state.corpus().count() == 0 {
             Err(Error::empty("No entries in corpus".to_owned()))
         } else {
-            let id = random_corpus_id!(state.corpus(), state.rand_mut());
+            let id = next_enabled(state, |state| {
+                Ok(random_corpus_id!(state.corpus(), state.rand_mut()))
+            })?;
             *state.corpus_mut().current_mut() = Some(id);
             Ok(id)
         }
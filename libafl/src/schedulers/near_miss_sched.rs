@@ -0,0 +1,210 @@
+//! The [`NearMissScheduler`] prefers [`Testcase`]s that came close to new coverage,
+//! for example by taking a branch towards code that was not covered yet.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Corpus, CorpusId, Testcase},
+    inputs::UsesInput,
+    schedulers::Scheduler,
+    state::{HasCorpus, HasMetadata, UsesState},
+    Error,
+};
+
+/// The default factor a near-miss score gets multiplied with each time its [`Testcase`] is scheduled
+pub const DEFAULT_NEAR_MISS_DECAY: f64 = 0.5;
+
+/// The default minimum near-miss score a [`Testcase`] needs to be preferred
+pub const DEFAULT_MIN_NEAR_MISS_SCORE: f64 = 1.0;
+
+/// A testcase metadata holding how close the last execution of a [`Testcase`] got to new coverage.
+/// Usually populated by a [`crate::feedbacks::NearMissFeedback`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct NearMissMetadata {
+    /// The near-miss score, higher means closer to new coverage
+    pub score: f64,
+}
+
+crate::impl_serdeany!(NearMissMetadata);
+
+impl NearMissMetadata {
+    /// Creates a new [`struct@NearMissMetadata`]
+    #[must_use]
+    pub fn new(score: f64) -> Self {
+        Self { score }
+    }
+}
+
+/// The [`NearMissScheduler`] wraps a `base` [`Scheduler`] and schedules the [`Testcase`] with the highest
+/// near-miss score (see [`struct@NearMissMetadata`]) instead, as long as that score is at least `min_score`.
+/// Each time a testcase is scheduled this way, its score decays, so that it does not starve the others forever.
+#[derive(Debug, Clone)]
+pub struct NearMissScheduler<CS> {
+    base: CS,
+    decay: f64,
+    min_score: f64,
+}
+
+impl<CS> UsesState for NearMissScheduler<CS>
+where
+    CS: UsesState,
+{
+    type State = CS::State;
+}
+
+impl<CS> Scheduler for NearMissScheduler<CS>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata,
+{
+    /// Add an entry to the corpus
+    fn on_add(&self, state: &mut CS::State, idx: CorpusId) -> Result<(), Error> {
+        self.base.on_add(state, idx)
+    }
+
+    /// Replaces the testcase at the given idx
+    fn on_replace(
+        &self,
+        state: &mut CS::State,
+        idx: CorpusId,
+        testcase: &Testcase<<CS::State as UsesInput>::Input>,
+    ) -> Result<(), Error> {
+        self.base.on_replace(state, idx, testcase)
+    }
+
+    /// Removes an entry from the corpus
+    fn on_remove(
+        &self,
+        state: &mut CS::State,
+        idx: CorpusId,
+        testcase: &Option<Testcase<<CS::State as UsesInput>::Input>>,
+    ) -> Result<(), Error> {
+        self.base.on_remove(state, idx, testcase)
+    }
+
+    /// Gets the next entry, preferring the highest near-miss score
+    fn next(&self, state: &mut CS::State) -> Result<CorpusId, Error> {
+        let mut best: Option<(CorpusId, f64)> = None;
+        for idx in state.corpus().ids() {
+            let testcase = state.corpus().get(idx)?.borrow();
+            if testcase.disabled() {
+                continue;
+            }
+            let score = testcase
+                .metadata()
+                .get::<NearMissMetadata>()
+                .map_or(0.0, |meta| meta.score);
+            if score >= self.min_score && best.map_or(true, |(_, best_score)| score > best_score) {
+                best = Some((idx, score));
+            }
+        }
+
+        let Some((idx, _)) = best else {
+            return self.base.next(state);
+        };
+        if let Some(meta) = state
+            .corpus()
+            .get(idx)?
+            .borrow_mut()
+            .metadata_mut()
+            .get_mut::<NearMissMetadata>()
+        {
+            meta.score *= self.decay;
+        }
+        *state.corpus_mut().current_mut() = Some(idx);
+        Ok(idx)
+    }
+}
+
+impl<CS> NearMissScheduler<CS>
+where
+    CS: Scheduler,
+    CS::State: HasCorpus + HasMetadata,
+{
+    /// Creates a new [`NearMissScheduler`] that wraps a `base` [`Scheduler`],
+    /// using the [`DEFAULT_NEAR_MISS_DECAY`] and [`DEFAULT_MIN_NEAR_MISS_SCORE`]
+    #[must_use]
+    pub fn new(base: CS) -> Self {
+        Self::with_decay(base, DEFAULT_NEAR_MISS_DECAY, DEFAULT_MIN_NEAR_MISS_SCORE)
+    }
+
+    /// Creates a new [`NearMissScheduler`] that wraps a `base` [`Scheduler`].
+    /// Scores get multiplied with `decay` each time their testcase is scheduled,
+    /// and testcases with a score below `min_score` are left to the `base` scheduler.
+    #[must_use]
+    pub fn with_decay(base: CS, decay: f64, min_score: f64) -> Self {
+        Self {
+            base,
+            decay,
+            min_score,
+        }
+    }
+
+    /// Get a reference to the base scheduler
+    pub fn base(&self) -> &CS {
+        &self.base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::BytesInput,
+        schedulers::{near_miss_sched::NearMissMetadata, NearMissScheduler, QueueScheduler, Scheduler},
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_near_miss_scheduler() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let scheduler = NearMissScheduler::new(QueueScheduler::new());
+
+        let mut near_miss = None;
+        for i in 0..4_u8 {
+            let mut testcase = Testcase::new(BytesInput::new(vec![i]));
+            if i == 2 {
+                testcase.add_metadata(NearMissMetadata::new(8.0));
+            }
+            let idx = state.corpus_mut().add(testcase).unwrap();
+            scheduler.on_add(&mut state, idx).unwrap();
+            if i == 2 {
+                near_miss = Some(idx);
+            }
+        }
+        let near_miss = near_miss.unwrap();
+
+        // 8 -> 4 -> 2 -> 1 -> 0.5: favored for the first four schedulings
+        for _ in 0..4 {
+            assert_eq!(scheduler.next(&mut state).unwrap(), near_miss);
+        }
+        let score = state
+            .corpus()
+            .get(near_miss)
+            .unwrap()
+            .borrow()
+            .metadata()
+            .get::<NearMissMetadata>()
+            .unwrap()
+            .score;
+        assert!(score < 1.0);
+
+        // Afterwards, the base scheduler gets to pick every entry again
+        let picked = (0..4)
+            .filter(|_| scheduler.next(&mut state).unwrap() == near_miss)
+            .count();
+        assert_eq!(picked, 1);
+    }
+}
@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     corpus::{Corpus, CorpusId, SchedulerTestcaseMetaData},
     inputs::UsesInput,
-    schedulers::Scheduler,
+    schedulers::{next_enabled, Scheduler},
     state::{HasCorpus, HasMetadata, UsesState},
     Error,
 };
@@ -215,24 +215,27 @@ where
         if state.corpus().count() == 0 {
             Err(Error::empty(String::from("No entries in corpus")))
         } else {
-            let id = match state.corpus().current() {
-                Some(cur) => {
-                    if let Some(next) = state.corpus().next(*cur) {
-                        next
-                    } else {
-                        let psmeta = state
-                            .metadata_mut()
-                            .get_mut::<SchedulerMetadata>()
-                            .ok_or_else(|| {
-                                Error::key_not_found("SchedulerMetadata not found".to_string())
-                            })?;
-                        psmeta.set_queue_cycles(psmeta.queue_cycles() + 1);
-                        state.corpus().first().unwrap()
+            let id = next_enabled(state, |state| {
+                let id = match state.corpus().current() {
+                    Some(cur) => {
+                        if let Some(next) = state.corpus().next(*cur) {
+                            next
+                        } else {
+                            let psmeta = state
+                                .metadata_mut()
+                                .get_mut::<SchedulerMetadata>()
+                                .ok_or_else(|| {
+                                    Error::key_not_found("SchedulerMetadata not found".to_string())
+                                })?;
+                            psmeta.set_queue_cycles(psmeta.queue_cycles() + 1);
+                            state.corpus().first().unwrap()
+                        }
                     }
-                }
-                None => state.corpus().first().unwrap(),
-            };
-            *state.corpus_mut().current_mut() = Some(id);
+                    None => state.corpus().first().unwrap(),
+                };
+                *state.corpus_mut().current_mut() = Some(id);
+                Ok(id)
+            })?;
 
             // Update the handicap
             let mut testcase = state.corpus().get(id)?.borrow_mut();
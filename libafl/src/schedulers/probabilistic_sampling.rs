@@ -1,17 +1,16 @@
 //! Probabilistic sampling scheduler is a corpus scheduler that feeds the fuzzer
 //! with sampled item from the corpus.
 
-use alloc::string::String;
+use alloc::{collections::BTreeMap, string::String};
 use core::marker::PhantomData;
 
-use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     bolts::rands::Rand,
     corpus::{Corpus, CorpusId},
     inputs::UsesInput,
-    schedulers::{Scheduler, TestcaseScore},
+    schedulers::{next_enabled, Scheduler, TestcaseScore},
     state::{HasCorpus, HasMetadata, HasRand, UsesState},
     Error,
 };
@@ -26,10 +25,11 @@ where
 }
 
 /// A state metadata holding a map of probability of corpus elements.
+/// The map is ordered, so that sampling is reproducible for a given [`Rand`] seed.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProbabilityMetadata {
     /// corpus index -> probability
-    pub map: HashMap<CorpusId, f64>,
+    pub map: BTreeMap<CorpusId, f64>,
     /// total probability of all items in the map
     pub total_probability: f64,
 }
@@ -41,7 +41,7 @@ impl ProbabilityMetadata {
     #[must_use]
     pub fn new() -> Self {
         Self {
-            map: HashMap::default(),
+            map: BTreeMap::new(),
             total_probability: 0.0,
         }
     }
@@ -106,24 +106,27 @@ where
         self.store_probability(state, idx)
     }
 
-    /// Gets the next entry
+    /// Gets the next entry, skipping disabled entries
     #[allow(clippy::cast_precision_loss)]
     fn next(&self, state: &mut Self::State) -> Result<CorpusId, Error> {
         if state.corpus().count() == 0 {
             Err(Error::empty(String::from("No entries in corpus")))
         } else {
-            let rand_prob: f64 = (state.rand_mut().below(100) as f64) / 100.0;
-            let meta = state.metadata().get::<ProbabilityMetadata>().unwrap();
-            let threshold = meta.total_probability * rand_prob;
-            let mut k: f64 = 0.0;
-            let mut ret = *meta.map.keys().last().unwrap();
-            for (idx, prob) in meta.map.iter() {
-                k += prob;
-                if k >= threshold {
-                    ret = *idx;
-                    break;
+            let ret = next_enabled(state, |state| {
+                let rand_prob: f64 = (state.rand_mut().below(100) as f64) / 100.0;
+                let meta = state.metadata().get::<ProbabilityMetadata>().unwrap();
+                let threshold = meta.total_probability * rand_prob;
+                let mut k: f64 = 0.0;
+                let mut ret = *meta.map.keys().last().unwrap();
+                for (idx, prob) in meta.map.iter() {
+                    k += prob;
+                    if k >= threshold {
+                        ret = *idx;
+                        break;
+                    }
                 }
-            }
+                Ok(ret)
+            })?;
             *state.corpus_mut().current_mut() = Some(ret);
             Ok(ret)
         }
@@ -143,6 +146,7 @@ where
 #[cfg(test)]
 #[cfg(feature = "std")]
 mod tests {
+    use alloc::vec::Vec;
     use core::{borrow::BorrowMut, marker::PhantomData};
 
     use crate::{
@@ -210,4 +214,34 @@ mod tests {
         assert_eq!(next_idx1, next_idx2);
         assert_ne!(next_idx1, next_idx3);
     }
+
+    #[test]
+    fn test_prob_sampling_reproducible() {
+        let select_sequence = || {
+            let scheduler = UniformProbabilitySamplingScheduler::new();
+            let mut feedback = ConstFeedback::new(false);
+            let mut objective = ConstFeedback::new(false);
+            let mut state = StdState::new(
+                StdRand::with_seed(12),
+                InMemoryCorpus::new(),
+                InMemoryCorpus::new(),
+                &mut feedback,
+                &mut objective,
+            )
+            .unwrap();
+            for i in 0..16_u8 {
+                let idx = state
+                    .corpus_mut()
+                    .add(Testcase::new(BytesInput::new(vec![i])))
+                    .unwrap();
+                scheduler.on_add(&mut state, idx).unwrap();
+            }
+            (0..256)
+                .map(|_| scheduler.next(&mut state).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // All entries tie, so any hash map iteration order would leak into the selections
+        assert_eq!(select_sequence(), select_sequence());
+    }
 }
@@ -6,7 +6,7 @@ use core::marker::PhantomData;
 use crate::{
     corpus::{Corpus, CorpusId},
     inputs::UsesInput,
-    schedulers::Scheduler,
+    schedulers::{next_enabled, Scheduler},
     state::{HasCorpus, UsesState},
     Error,
 };
@@ -28,18 +28,21 @@ impl<S> Scheduler for QueueScheduler<S>
 where
     S: HasCorpus,
 {
-    /// Gets the next entry in the queue
+    /// Gets the next entry in the queue, skipping disabled entries
     fn next(&self, state: &mut Self::State) -> Result<CorpusId, Error> {
         if state.corpus().count() == 0 {
             Err(Error::empty("No entries in corpus".to_owned()))
         } else {
-            let id = state
-                .corpus()
-                .current()
-                .map(|id| state.corpus().next(id))
-                .flatten()
-                .unwrap_or_else(|| state.corpus().first().unwrap());
-            *state.corpus_mut().current_mut() = Some(id);
+            let id = next_enabled(state, |state| {
+                let id = state
+                    .corpus()
+                    .current()
+                    .map(|id| state.corpus().next(id))
+                    .flatten()
+                    .unwrap_or_else(|| state.corpus().first().unwrap());
+                *state.corpus_mut().current_mut() = Some(id);
+                Ok(id)
+            })?;
             Ok(id)
         }
     }
@@ -65,15 +68,16 @@ impl<S> Default for QueueScheduler<S> {
 #[cfg(feature = "std")]
 mod tests {
 
-    use std::{fs, path::PathBuf};
+    use std::{fs, path::PathBuf, vec::Vec};
 
     use crate::{
         bolts::rands::StdRand,
-        corpus::{Corpus, OnDiskCorpus, Testcase},
+        corpus::{Corpus, InMemoryCorpus, OnDiskCorpus, Testcase},
         feedbacks::ConstFeedback,
-        inputs::bytes::BytesInput,
-        schedulers::{QueueScheduler, Scheduler},
+        inputs::{bytes::BytesInput, HasBytesVec},
+        schedulers::{next_enabled, retain_corpus, QueueScheduler, RandScheduler, Scheduler},
         state::{HasCorpus, StdState},
+        Error,
     };
 
     #[test]
@@ -113,4 +117,154 @@ mod tests {
 
         fs::remove_dir_all("target/.test/fancy").unwrap();
     }
+
+    #[test]
+    fn test_skip_disabled() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let ids = (0..3_u8)
+            .map(|i| {
+                state
+                    .corpus_mut()
+                    .add(Testcase::new(BytesInput::new(vec![i])))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        state
+            .corpus()
+            .get(ids[1])
+            .unwrap()
+            .borrow_mut()
+            .set_disabled(true);
+
+        let queue = QueueScheduler::new();
+        let rand = RandScheduler::new();
+        for _ in 0..10 {
+            assert_ne!(queue.next(&mut state).unwrap(), ids[1]);
+            assert_ne!(rand.next(&mut state).unwrap(), ids[1]);
+        }
+
+        // Disabled entries are kept, but there is nothing left to schedule
+        for idx in &ids {
+            state
+                .corpus()
+                .get(*idx)
+                .unwrap()
+                .borrow_mut()
+                .set_disabled(true);
+        }
+        assert_eq!(state.corpus().count(), 3);
+        assert!(matches!(queue.next(&mut state), Err(Error::Empty(..))));
+        assert!(matches!(rand.next(&mut state), Err(Error::Empty(..))));
+    }
+
+    #[test]
+    fn test_next_enabled_bounded() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let ids = (0..3_u8)
+            .map(|i| {
+                state
+                    .corpus_mut()
+                    .add(Testcase::new(BytesInput::new(vec![i])))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        state
+            .corpus()
+            .get(ids[1])
+            .unwrap()
+            .borrow_mut()
+            .set_disabled(true);
+
+        // A draw stuck on a disabled entry falls back to the next enabled one
+        let mut draws = 0;
+        let id = next_enabled(&mut state, |_| {
+            draws += 1;
+            Ok(ids[1])
+        })
+        .unwrap();
+        assert_eq!(id, ids[2]);
+        assert_eq!(*state.corpus().current(), Some(ids[2]));
+        assert_eq!(draws, 6);
+
+        // Wrapping around the end of the corpus
+        state
+            .corpus()
+            .get(ids[2])
+            .unwrap()
+            .borrow_mut()
+            .set_disabled(true);
+        let id = next_enabled(&mut state, |_| Ok(ids[2])).unwrap();
+        assert_eq!(id, ids[0]);
+
+        state
+            .corpus()
+            .get(ids[0])
+            .unwrap()
+            .borrow_mut()
+            .set_disabled(true);
+        assert!(matches!(
+            next_enabled(&mut state, |_| Ok(ids[0])),
+            Err(Error::Empty(..))
+        ));
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let ids = (0..5_u8)
+            .map(|i| {
+                state
+                    .corpus_mut()
+                    .add(Testcase::new(BytesInput::new(vec![i])))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+
+        let scheduler = QueueScheduler::new();
+        assert_eq!(scheduler.next(&mut state).unwrap(), ids[0]);
+        assert_eq!(scheduler.next(&mut state).unwrap(), ids[1]);
+
+        // Removing the current entry keeps the queue going after it
+        let removed = retain_corpus(&scheduler, &mut state, |testcase| {
+            testcase.input().as_ref().unwrap().bytes()[0] % 2 == 0
+        })
+        .unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(state.corpus().count(), 3);
+        assert_eq!(*state.corpus().current(), Some(ids[0]));
+        assert_eq!(scheduler.next(&mut state).unwrap(), ids[2]);
+        assert_eq!(scheduler.next(&mut state).unwrap(), ids[4]);
+
+        // Without a kept entry before the current one, there is no current entry left
+        assert_eq!(state.corpus_mut().retain(|_| false).unwrap(), 3);
+        assert_eq!(*state.corpus().current(), None);
+        assert!(state.corpus().is_empty());
+    }
 }
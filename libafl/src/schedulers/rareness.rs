@@ -0,0 +1,258 @@
+//! The [`RarenessScheduler`] favors the [`crate::corpus::Testcase`]s covering globally rare edges,
+//! so that rarely hit parts of the target get fuzzed more.
+
+use alloc::{borrow::ToOwned, vec::Vec};
+use core::marker::PhantomData;
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bolts::rands::Rand,
+    corpus::{Corpus, CorpusId, Testcase},
+    feedbacks::MapIndexesMetadata,
+    inputs::UsesInput,
+    random_corpus_id,
+    schedulers::{next_enabled, Scheduler},
+    state::{HasCorpus, HasMetadata, HasRand, UsesState},
+    Error,
+};
+
+/// A state metadata holding how often each edge got hit, over all executions.
+/// Usually populated by an [`crate::feedbacks::EdgeHitsFeedback`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EdgeHitsMetadata {
+    /// The number of executions that hit each edge, indexed by map index
+    pub hits: Vec<u64>,
+}
+
+crate::impl_serdeany!(EdgeHitsMetadata);
+
+impl EdgeHitsMetadata {
+    /// Creates a new [`struct@EdgeHitsMetadata`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one execution hitting the edges at the given map indexes
+    pub fn record(&mut self, indexes: &[usize]) {
+        for idx in indexes {
+            if *idx >= self.hits.len() {
+                self.hits.resize(idx + 1, 0);
+            }
+            self.hits[*idx] = self.hits[*idx].saturating_add(1);
+        }
+    }
+
+    /// The number of executions that hit the edge at the given map index
+    #[must_use]
+    pub fn hits(&self, idx: usize) -> u64 {
+        self.hits.get(idx).copied().unwrap_or(0)
+    }
+}
+
+/// A state metadata holding how often the [`RarenessScheduler`] picked each entry
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RarenessPicksMetadata {
+    /// The number of times each entry got picked
+    pub picks: HashMap<CorpusId, u64>,
+}
+
+crate::impl_serdeany!(RarenessPicksMetadata);
+
+impl RarenessPicksMetadata {
+    /// The number of times the entry got picked
+    #[must_use]
+    pub fn picks(&self, id: CorpusId) -> u64 {
+        self.picks.get(&id).copied().unwrap_or(0)
+    }
+}
+
+/// Schedules an enabled [`crate::corpus::Testcase`] at random, weighted by the rarity of its rarest edge,
+/// as counted in the [`struct@EdgeHitsMetadata`] of the state.
+/// The weight of an entry is `1 / ((hits + 1) * (picks + 1))`, with `hits` the hits of its rarest edge and `picks`
+/// the number of times it got picked before, kept in the [`struct@RarenessPicksMetadata`]. The decay keeps an entry
+/// whose mutants never hit its rare edge from starving all others.
+/// The edges of a testcase are the ones in its [`struct@MapIndexesMetadata`], so the
+/// [`crate::feedbacks::MapFeedback`] needs to track indexes.
+/// Testcases without indexes only get scheduled, uniformly at random, if no testcase has any.
+#[derive(Debug, Clone)]
+pub struct RarenessScheduler<S> {
+    phantom: PhantomData<S>,
+}
+
+impl<S> UsesState for RarenessScheduler<S>
+where
+    S: UsesInput,
+{
+    type State = S;
+}
+
+impl<S> Scheduler for RarenessScheduler<S>
+where
+    S: HasCorpus + HasMetadata + HasRand,
+{
+    /// Forgets how often the removed entry got picked
+    fn on_remove(
+        &self,
+        state: &mut Self::State,
+        idx: CorpusId,
+        _testcase: &Option<Testcase<<Self::State as UsesInput>::Input>>,
+    ) -> Result<(), Error> {
+        if let Some(meta) = state.metadata_mut().get_mut::<RarenessPicksMetadata>() {
+            meta.picks.remove(&idx);
+        }
+        Ok(())
+    }
+
+    /// Gets an entry at random, favoring the ones covering rare edges
+    #[allow(clippy::cast_precision_loss)]
+    fn next(&self, state: &mut Self::State) -> Result<CorpusId, Error> {
+        if state.corpus().count() == 0 {
+            return Err(Error::empty("No entries in corpus".to_owned()));
+        }
+
+        let mut weights: Vec<(CorpusId, f64)> = Vec::new();
+        if let Some(edge_hits) = state.metadata().get::<EdgeHitsMetadata>() {
+            let picks = state.metadata().get::<RarenessPicksMetadata>();
+            for id in state.corpus().ids() {
+                let testcase = state.corpus().get(id)?.borrow();
+                if testcase.disabled() {
+                    continue;
+                }
+                let rareness = testcase
+                    .metadata()
+                    .get::<MapIndexesMetadata>()
+                    .and_then(|meta| meta.list.iter().map(|idx| edge_hits.hits(*idx)).min());
+                if let Some(rareness) = rareness {
+                    let picks = picks.map_or(0, |picks| picks.picks(id));
+                    weights.push((id, 1.0 / ((rareness as f64 + 1.0) * (picks as f64 + 1.0))));
+                }
+            }
+        }
+
+        let id = match weights.last() {
+            Some((last, _)) => {
+                let total: f64 = weights.iter().map(|(_, weight)| weight).sum();
+                let mut threshold =
+                    state.rand_mut().between(0, 1_000_000_000) as f64 / 1_000_000_000_f64 * total;
+                // Falls back to the last entry, if rounding leaves a rest
+                let mut picked = *last;
+                for (id, weight) in &weights {
+                    if threshold < *weight {
+                        picked = *id;
+                        break;
+                    }
+                    threshold -= weight;
+                }
+                picked
+            }
+            None => next_enabled(state, |state| {
+                Ok(random_corpus_id!(state.corpus(), state.rand_mut()))
+            })?,
+        };
+
+        if !state.has_metadata::<RarenessPicksMetadata>() {
+            state.add_metadata(RarenessPicksMetadata::default());
+        }
+        *state
+            .metadata_mut()
+            .get_mut::<RarenessPicksMetadata>()
+            .unwrap()
+            .picks
+            .entry(id)
+            .or_insert(0) += 1;
+        *state.corpus_mut().current_mut() = Some(id);
+        Ok(id)
+    }
+}
+
+impl<S> RarenessScheduler<S> {
+    /// Creates a new [`RarenessScheduler`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> Default for RarenessScheduler<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        feedbacks::{ConstFeedback, MapIndexesMetadata},
+        inputs::BytesInput,
+        schedulers::{
+            rareness::{EdgeHitsMetadata, RarenessPicksMetadata},
+            RarenessScheduler, Scheduler,
+        },
+        state::{HasCorpus, HasMetadata, StdState},
+    };
+
+    #[test]
+    fn test_rareness_scheduler() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let scheduler = RarenessScheduler::new();
+
+        // All entries cover edge 0, each one covers another edge on top
+        let ids = [vec![0, 1], vec![0, 2], vec![0, 3]].map(|edges| {
+            let mut testcase = Testcase::new(BytesInput::new(vec![0]));
+            testcase.add_metadata(MapIndexesMetadata::new(edges));
+            let id = state.corpus_mut().add(testcase).unwrap();
+            scheduler.on_add(&mut state, id).unwrap();
+            id
+        });
+
+        let mut edge_hits = EdgeHitsMetadata::new();
+        for _ in 0..3 {
+            edge_hits.record(&[0, 1]);
+        }
+        edge_hits.record(&[0, 2]);
+        for _ in 0..2 {
+            edge_hits.record(&[0, 3]);
+        }
+        state.add_metadata(edge_hits);
+
+        // The entries covering rarer edges get picked more often, but none of them starves
+        let mut counts = [0; 3];
+        for _ in 0..300 {
+            let id = scheduler.next(&mut state).unwrap();
+            assert_eq!(*state.corpus().current(), Some(id));
+            counts[ids.iter().position(|other| *other == id).unwrap()] += 1;
+        }
+        assert!(counts[1] > counts[2]);
+        assert!(counts[2] > counts[0]);
+        assert!(counts[0] > 0);
+
+        let picks = state.metadata().get::<RarenessPicksMetadata>().unwrap();
+        for (id, count) in ids.iter().zip(counts) {
+            assert_eq!(picks.picks(*id), count);
+        }
+
+        // Removed entries are forgotten
+        let removed = state.corpus_mut().remove(ids[1]).unwrap();
+        scheduler
+            .on_remove(&mut state, ids[1], &Some(removed))
+            .unwrap();
+        let picks = state.metadata().get::<RarenessPicksMetadata>().unwrap();
+        assert_eq!(picks.picks(ids[1]), 0);
+    }
+}
@@ -11,7 +11,7 @@ use crate::{
     corpus::{Corpus, CorpusId},
     impl_serdeany,
     inputs::UsesInput,
-    schedulers::Scheduler,
+    schedulers::{next_enabled, Scheduler},
     state::{HasCorpus, HasMetadata, UsesState},
     Error,
 };
@@ -92,20 +92,25 @@ impl<S> Scheduler for TuneableScheduler<S>
 where
     S: HasCorpus + HasMetadata,
 {
-    /// Gets the next entry in the queue
+    /// Gets the next entry in the queue, skipping disabled entries.
+    /// If the entry set with [`TuneableScheduler::set_next`] is disabled, the queue is walked instead.
     fn next(&self, state: &mut Self::State) -> Result<CorpusId, Error> {
         if state.corpus().count() == 0 {
             return Err(Error::empty("No entries in corpus".to_owned()));
         }
-        let id = if let Some(next) = Self::get_next(state) {
-            // next was set
-            next
-        } else if let Some(next) = state.corpus().next(Self::get_current(state)) {
-            next
-        } else {
-            state.corpus().first().unwrap()
-        };
-        *state.corpus_mut().current_mut() = Some(id);
+        let mut next_set = Self::get_next(state);
+        let id = next_enabled(state, |state| {
+            let id = if let Some(next) = next_set.take() {
+                // next was set
+                next
+            } else if let Some(next) = state.corpus().next(Self::get_current(state)) {
+                next
+            } else {
+                state.corpus().first().unwrap()
+            };
+            *state.corpus_mut().current_mut() = Some(id);
+            Ok(id)
+        })?;
         Ok(id)
     }
 }
@@ -13,6 +13,7 @@ use crate::{
     inputs::UsesInput,
     random_corpus_id,
     schedulers::{
+        next_enabled,
         powersched::{PowerSchedule, SchedulerMetadata},
         testcase_score::{CorpusWeightTestcaseScore, TestcaseScore},
         Scheduler,
@@ -294,10 +295,28 @@ where
         if corpus_counts == 0 {
             Err(Error::empty(String::from("No entries in corpus")))
         } else {
-            let s = random_corpus_id!(state.corpus(), state.rand_mut());
+            // Sample from the alias table, skipping disabled entries
+            let idx = next_enabled(state, |state| {
+                let s = random_corpus_id!(state.corpus(), state.rand_mut());
 
-            // Choose a random value between 0.000000000 and 1.000000000
-            let probability = state.rand_mut().between(0, 1000000000) as f64 / 1000000000_f64;
+                // Choose a random value between 0.000000000 and 1.000000000
+                let probability = state.rand_mut().between(0, 1000000000) as f64 / 1000000000_f64;
+
+                let wsmeta = state
+                    .metadata()
+                    .get::<WeightedScheduleMetadata>()
+                    .ok_or_else(|| {
+                        Error::key_not_found("WeigthedScheduleMetadata not found".to_string())
+                    })?;
+
+                Ok(
+                    if probability < *wsmeta.alias_probability().get(&s).unwrap() {
+                        s
+                    } else {
+                        *wsmeta.alias_table().get(&s).unwrap()
+                    },
+                )
+            })?;
 
             let wsmeta = state
                 .metadata_mut()
@@ -315,12 +334,6 @@ where
                 wsmeta.set_runs_current_cycle(current_cycles + 1);
             }
 
-            let idx = if probability < *wsmeta.alias_probability().get(&s).unwrap() {
-                s
-            } else {
-                *wsmeta.alias_table().get(&s).unwrap()
-            };
-
             // Update depth
             if current_cycles > corpus_counts {
                 let psmeta = state
@@ -354,3 +367,66 @@ where
 
 /// The standard corpus weight, same as aflpp
 pub type StdWeightedScheduler<S> = WeightedScheduler<CorpusWeightTestcaseScore<S>, S>;
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::marker::PhantomData;
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase},
+        feedbacks::ConstFeedback,
+        inputs::{BytesInput, HasBytesVec},
+        schedulers::{Scheduler, TestcaseScore, WeightedScheduler},
+        state::{HasCorpus, HasMetadata, StdState},
+        Error,
+    };
+
+    /// Weighs testcases by their first byte, so that many of them tie
+    #[derive(Debug, Clone)]
+    pub struct FirstByteScore<S> {
+        phantom: PhantomData<S>,
+    }
+
+    impl<S> TestcaseScore<S> for FirstByteScore<S>
+    where
+        S: HasMetadata + HasCorpus,
+        S::Input: HasBytesVec,
+    {
+        fn compute(entry: &mut Testcase<S::Input>, _state: &S) -> Result<f64, Error> {
+            Ok(f64::from(entry.input().as_ref().unwrap().bytes()[0]))
+        }
+    }
+
+    fn select_sequence() -> Vec<CorpusId> {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let scheduler = WeightedScheduler::<FirstByteScore<_>, _>::new();
+        for i in 0..16_u8 {
+            let idx = state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(vec![1 + i % 3, i])))
+                .unwrap();
+            scheduler.on_add(&mut state, idx).unwrap();
+        }
+
+        (0..256)
+            .map(|_| scheduler.next(&mut state).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_weighted_reproducible() {
+        assert_eq!(select_sequence(), select_sequence());
+    }
+}
@@ -15,8 +15,11 @@ use crate::{
     bolts::AsSlice,
     corpus::{Corpus, CorpusId},
     executors::{Executor, HasObservers},
-    feedbacks::map::MapNoveltiesMetadata,
-    inputs::{BytesInput, GeneralizedInputMetadata, GeneralizedItem, HasBytesVec, UsesInput},
+    feedbacks::{map::MapNoveltiesMetadata, record_max_gaps},
+    inputs::{
+        BytesInput, GeneralizedInputMetadata, GeneralizedItem, HasBytesVec, UsesInput,
+        DEFAULT_MAX_GENERALIZED_ITEMS,
+    },
     mark_feature_time,
     observers::{MapObserver, ObserversTuple},
     stages::Stage,
@@ -44,9 +47,8 @@ impl GeneralizedIndexesMetadata {
     }
 }
 
-fn increment_by_offset(_list: &[Option<u8>], idx: usize, off: u8) -> usize {
-    idx + 1 + off as usize
-}
+/// The default sizes of the chunks the [`GeneralizationStage`] tries to replace with gaps, in order
+pub const DEFAULT_GENERALIZATION_CHUNK_SIZES: [usize; 5] = [256, 128, 64, 32, 1];
 
 fn find_next_char(list: &[Option<u8>], mut idx: usize, ch: u8) -> usize {
     while idx < list.len() {
@@ -62,6 +64,8 @@ fn find_next_char(list: &[Option<u8>], mut idx: usize, ch: u8) -> usize {
 #[derive(Clone, Debug)]
 pub struct GeneralizationStage<EM, O, OT, Z> {
     map_observer_name: String,
+    chunk_sizes: Vec<usize>,
+    max_items: usize,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(EM, O, OT, Z)>,
 }
@@ -119,6 +123,7 @@ where
                     .unwrap()
                     .indexes
                     .insert(corpus_idx);
+                record_max_gaps(state, corpus_idx)?;
                 return Ok(());
             }
 
@@ -139,127 +144,29 @@ where
             return Ok(());
         }
 
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            increment_by_offset,
-            255,
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            increment_by_offset,
-            127,
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            increment_by_offset,
-            63,
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            increment_by_offset,
-            31,
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            increment_by_offset,
-            0,
-        )?;
+        for chunk_size in self.chunk_sizes.clone() {
+            self.find_gaps(
+                fuzzer,
+                executor,
+                state,
+                manager,
+                &mut payload,
+                &novelties,
+                |_, idx| idx + chunk_size.max(1),
+            )?;
+        }
 
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b'.',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b';',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b',',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b'\n',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b'\r',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b'#',
-        )?;
-        self.find_gaps(
-            fuzzer,
-            executor,
-            state,
-            manager,
-            &mut payload,
-            &novelties,
-            find_next_char,
-            b' ',
-        )?;
+        for split_char in [b'.', b';', b',', b'\n', b'\r', b'#', b' '] {
+            self.find_gaps(
+                fuzzer,
+                executor,
+                state,
+                manager,
+                &mut payload,
+                &novelties,
+                |list, idx| find_next_char(list, idx, split_char),
+            )?;
+        }
 
         self.find_gaps_in_closures(
             fuzzer,
@@ -325,10 +232,17 @@ where
         if payload.len() <= MAX_GENERALIZED_LEN {
             // Save the modified input in the corpus
             {
-                let meta = GeneralizedInputMetadata::generalized_from_options(&payload);
-
-                debug_assert!(meta.generalized().first() == Some(&GeneralizedItem::Gap));
-                debug_assert!(meta.generalized().last() == Some(&GeneralizedItem::Gap));
+                let mut meta = GeneralizedInputMetadata::generalized_from_options(&payload);
+                meta.truncate_to(self.max_items);
+
+                debug_assert!(meta
+                    .generalized()
+                    .first()
+                    .map_or(false, GeneralizedItem::is_gap));
+                debug_assert!(meta
+                    .generalized()
+                    .last()
+                    .map_or(false, GeneralizedItem::is_gap));
 
                 let mut entry = state.corpus().get(corpus_idx)?.borrow_mut();
                 entry.metadata_mut().insert(meta);
@@ -340,6 +254,7 @@ where
                 .unwrap()
                 .indexes
                 .insert(corpus_idx);
+            record_max_gaps(state, corpus_idx)?;
         }
 
         Ok(())
@@ -362,6 +277,8 @@ where
     pub fn new(map_observer: &O) -> Self {
         Self {
             map_observer_name: map_observer.name().to_string(),
+            chunk_sizes: DEFAULT_GENERALIZATION_CHUNK_SIZES.to_vec(),
+            max_items: DEFAULT_MAX_GENERALIZED_ITEMS,
             phantom: PhantomData,
         }
     }
@@ -371,10 +288,41 @@ where
     pub fn from_name(map_observer_name: &str) -> Self {
         Self {
             map_observer_name: map_observer_name.to_string(),
+            chunk_sizes: DEFAULT_GENERALIZATION_CHUNK_SIZES.to_vec(),
+            max_items: DEFAULT_MAX_GENERALIZED_ITEMS,
             phantom: PhantomData,
         }
     }
 
+    /// Sets the sizes of the chunks that get replaced with gaps, if the coverage stays the same without them.
+    /// The chunks are scanned in the given order, a size of `0` counts as `1`.
+    /// Defaults to [`DEFAULT_GENERALIZATION_CHUNK_SIZES`].
+    #[must_use]
+    pub fn with_chunk_sizes(mut self, chunk_sizes: Vec<usize>) -> Self {
+        self.chunk_sizes = chunk_sizes;
+        self
+    }
+
+    /// The sizes of the chunks that get replaced with gaps, in scan order
+    #[must_use]
+    pub fn chunk_sizes(&self) -> &[usize] {
+        &self.chunk_sizes
+    }
+
+    /// Sets the maximum number of items of the stored generalizations,
+    /// see [`GeneralizedInputMetadata::truncate_to`]. Defaults to [`DEFAULT_MAX_GENERALIZED_ITEMS`].
+    #[must_use]
+    pub fn with_max_items(mut self, max_items: usize) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    /// The maximum number of items of the stored generalizations
+    #[must_use]
+    pub fn max_items(&self) -> usize {
+        self.max_items
+    }
+
     fn verify_input<E>(
         &self,
         fuzzer: &mut Z,
@@ -419,7 +367,7 @@ where
     }
 
     #[allow(clippy::too_many_arguments)]
-    fn find_gaps<E>(
+    fn find_gaps<E, F>(
         &self,
         fuzzer: &mut Z,
         executor: &mut E,
@@ -427,16 +375,16 @@ where
         manager: &mut EM,
         payload: &mut Vec<Option<u8>>,
         novelties: &[usize],
-        find_next_index: fn(&[Option<u8>], usize, u8) -> usize,
-        split_char: u8,
+        find_next_index: F,
     ) -> Result<(), Error>
     where
         E: Executor<EM, Z> + HasObservers<Observers = OT, State = EM::State>,
+        F: Fn(&[Option<u8>], usize) -> usize,
         Z: UsesState<State = EM::State>,
     {
         let mut start = 0;
         while start < payload.len() {
-            let mut end = find_next_index(payload, start, split_char);
+            let mut end = find_next_index(payload, start);
             if end > payload.len() {
                 end = payload.len();
             }
@@ -521,3 +469,130 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::{Executor, ExitKind, HasObservers},
+        feedbacks::{map::MapNoveltiesMetadata, ConstFeedback},
+        inputs::{BytesInput, GeneralizedInputMetadata, GeneralizedItem, HasBytesVec},
+        observers::{MapObserver, StdMapObserver, UsesObservers},
+        schedulers::QueueScheduler,
+        stages::{tests::TestState, GeneralizationStage, Stage},
+        state::{HasCorpus, HasMetadata, StdState, UsesState},
+        Error, StdFuzzer,
+    };
+
+    type TestObservers = (StdMapObserver<'static, u8, false>, ());
+
+    /// An executor covering map entry `1` for every input containing an `X`
+    #[derive(Debug)]
+    struct CoverageExecutor {
+        observers: TestObservers,
+    }
+
+    impl UsesState for CoverageExecutor {
+        type State = TestState;
+    }
+
+    impl UsesObservers for CoverageExecutor {
+        type Observers = TestObservers;
+    }
+
+    impl HasObservers for CoverageExecutor {
+        fn observers(&self) -> &TestObservers {
+            &self.observers
+        }
+
+        fn observers_mut(&mut self) -> &mut TestObservers {
+            &mut self.observers
+        }
+    }
+
+    impl<EM, Z> Executor<EM, Z> for CoverageExecutor
+    where
+        EM: UsesState<State = TestState>,
+        Z: UsesState<State = TestState>,
+    {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut TestState,
+            _mgr: &mut EM,
+            input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            if input.bytes().contains(&b'X') {
+                *self.observers.0.get_mut(1) = 1;
+            }
+            Ok(ExitKind::Ok)
+        }
+    }
+
+    /// Generalizes `aaaaXXXXbbbb` with the given chunk sizes
+    fn generalize(chunk_sizes: Vec<usize>) -> GeneralizedInputMetadata {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut testcase = Testcase::new(BytesInput::new(b"aaaaXXXXbbbb".to_vec()));
+        testcase.add_metadata(MapNoveltiesMetadata::new(vec![1]));
+        let idx = state.corpus_mut().add(testcase).unwrap();
+
+        let mut fuzzer =
+            StdFuzzer::<_, _, _, TestObservers>::new(QueueScheduler::new(), feedback, objective);
+        let mut executor = CoverageExecutor {
+            observers: tuple_list!(StdMapObserver::new_owned("map", vec![0_u8; 4])),
+        };
+        let mut mgr = NopEventManager::new();
+        let mut generalization_stage =
+            GeneralizationStage::new(&executor.observers.0).with_chunk_sizes(chunk_sizes);
+        generalization_stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, idx)
+            .unwrap();
+
+        let testcase = state.corpus().get(idx).unwrap().borrow();
+        testcase
+            .metadata()
+            .get::<GeneralizedInputMetadata>()
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn test_generalization_chunk_sizes() {
+        // Chunks of 4 bytes only cut off the runs around the `X`s
+        let meta = generalize(vec![4]);
+        assert_eq!(
+            meta.generalized(),
+            &[
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"XXXX".as_slice().into()),
+                GeneralizedItem::Gap,
+            ]
+        );
+        // The generalized bytes still trigger the same coverage
+        assert!(meta.generalized_to_bytes().contains(&b'X'));
+
+        // Single bytes narrow it down to a single `X`
+        let meta = generalize(vec![1]);
+        assert_eq!(
+            meta.generalized(),
+            &[
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"X".as_slice().into()),
+                GeneralizedItem::Gap,
+            ]
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! The [`GeneralizedMinimizerStage`] shrinks the [`GeneralizedInputMetadata`] of corpus entries,
+//! removing whole items of the generalization instead of arbitrary byte ranges.
+
+use core::marker::PhantomData;
+
+use crate::{
+    corpus::{Corpus, CorpusId},
+    events::EventFirer,
+    executors::{Executor, HasObservers},
+    feedbacks::{Feedback, FeedbackFactory},
+    inputs::{BytesInput, GeneralizedInputMetadata, GeneralizedItem, HasBytesVec, UsesInput},
+    observers::ObserversTuple,
+    schedulers::Scheduler,
+    stages::Stage,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, UsesState},
+    Error, ExecutesInput, HasScheduler,
+};
+
+/// A stage minimizing the [`GeneralizedInputMetadata`] of a corpus entry, as found by the
+/// [`crate::stages::GeneralizationStage`].
+///
+/// It first tries to remove each [`GeneralizedItem::Bytes`] run, until no more run can be removed.
+/// A removal is only accepted if the bytes of the resulting generalization result in the same
+/// [`crate::executors::ExitKind`] as the original entry, and if the feedback created by the factory
+/// deems them interesting (e.g., for the same crash signature).
+/// The gaps between the remaining runs are kept, so the result is still an instance of the same structure.
+/// The entry is replaced by the minimized bytes and generalization, if the bytes got shorter.
+/// Entries without a [`GeneralizedInputMetadata`] are left alone.
+#[derive(Clone, Debug)]
+pub struct GeneralizedMinimizerStage<E, EM, F, FF, OT, Z> {
+    factory: FF,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, F, OT, Z)>,
+}
+
+impl<E, EM, F, FF, OT, Z> UsesState for GeneralizedMinimizerStage<E, EM, F, FF, OT, Z>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E, EM, F, FF, OT, Z> Stage<E, EM, Z> for GeneralizedMinimizerStage<E, EM, F, FF, OT, Z>
+where
+    E: Executor<EM, Z> + HasObservers<Observers = OT>,
+    E::State: UsesInput<Input = BytesInput>
+        + HasCorpus
+        + HasExecutions
+        + HasMetadata
+        + HasClientPerfMonitor,
+    EM: EventFirer<State = E::State>,
+    F: Feedback<E::State>,
+    FF: FeedbackFactory<F, E::State, OT>,
+    OT: ObserversTuple<E::State>,
+    Z: ExecutesInput<E, EM, State = E::State> + HasScheduler,
+{
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut E::State,
+        manager: &mut EM,
+        corpus_idx: CorpusId,
+    ) -> Result<(), Error> {
+        let orig = {
+            let entry = state.corpus().get(corpus_idx)?.borrow();
+            match entry.metadata().get::<GeneralizedInputMetadata>() {
+                Some(meta) => meta.clone(),
+                None => return Ok(()),
+            }
+        };
+
+        let base_input = BytesInput::new(orig.generalized_to_bytes());
+        let orig_exit_kind = fuzzer.execute_input(state, executor, manager, &base_input)?;
+        let mut feedback = self.factory.create_feedback(executor.observers());
+
+        // Removing the byte runs, one at a time
+        let mut base = orig.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            let mut idx = 1;
+            while idx + 1 < base.generalized().len() {
+                if !matches!(base.generalized()[idx], GeneralizedItem::Bytes(_)) {
+                    idx += 1;
+                    continue;
+                }
+                let candidate = Self::without_item(&base, idx);
+                let input = BytesInput::new(candidate.generalized_to_bytes());
+                let exit_kind = fuzzer.execute_input(state, executor, manager, &input)?;
+                if exit_kind == orig_exit_kind
+                    && feedback.is_interesting(
+                        state,
+                        manager,
+                        &input,
+                        executor.observers(),
+                        &exit_kind,
+                    )?
+                {
+                    base = candidate;
+                    changed = true;
+                } else {
+                    idx += 1;
+                }
+            }
+        }
+
+        let bytes = base.generalized_to_bytes();
+        if bytes.len() < base_input.bytes().len() {
+            let mut testcase = state.corpus().get(corpus_idx)?.borrow().clone();
+            testcase.set_input(BytesInput::new(bytes));
+            testcase.metadata_mut().insert(base);
+            let prev = state.corpus_mut().replace(corpus_idx, testcase)?;
+            fuzzer
+                .scheduler_mut()
+                .on_replace(state, corpus_idx, &prev)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<E, EM, F, FF, OT, Z> GeneralizedMinimizerStage<E, EM, F, FF, OT, Z> {
+    /// Creates a new [`GeneralizedMinimizerStage`], accepting removals the feedback created by `factory` deems interesting
+    #[must_use]
+    pub fn new(factory: FF) -> Self {
+        Self {
+            factory,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The generalization without the item at `idx`, normalized
+    fn without_item(meta: &GeneralizedInputMetadata, idx: usize) -> GeneralizedInputMetadata {
+        let mut candidate = meta.clone();
+        candidate.generalized_mut().remove(idx);
+        candidate.normalize();
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::ConstFeedback,
+        inputs::{BytesInput, GeneralizedInputMetadata, GeneralizedItem, HasBytesVec},
+        schedulers::QueueScheduler,
+        stages::{tests::TestExecutor, GeneralizedMinimizerStage, Stage},
+        state::{HasCorpus, HasMetadata, StdState},
+        StdFuzzer,
+    };
+
+    #[test]
+    fn test_generalized_minimizer() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut meta = GeneralizedInputMetadata::default();
+        meta.generalized_mut().extend([
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(b"aa".as_slice().into()),
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(b"bXb".as_slice().into()),
+            GeneralizedItem::GapRun(2),
+            GeneralizedItem::Bytes(b"cc".as_slice().into()),
+            GeneralizedItem::Gap,
+        ]);
+        let mut testcase = Testcase::new(BytesInput::new(meta.generalized_to_bytes()));
+        testcase.add_metadata(meta);
+        let corpus_idx = state.corpus_mut().add(testcase).unwrap();
+
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut executor = TestExecutor::crash_on_x();
+        let mut mgr = NopEventManager::new();
+        let mut min_stage = GeneralizedMinimizerStage::new(|_: &()| ConstFeedback::True);
+        min_stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, corpus_idx)
+            .unwrap();
+
+        let testcase = state.corpus().get(corpus_idx).unwrap().borrow();
+        assert_eq!(testcase.input().as_ref().unwrap().bytes(), b"bXb");
+        assert_eq!(
+            testcase
+                .metadata()
+                .get::<GeneralizedInputMetadata>()
+                .unwrap()
+                .generalized(),
+            &[
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"bXb".as_slice().into()),
+                GeneralizedItem::GapRun(2),
+            ]
+        );
+        drop(testcase);
+
+        // Entries without a generalization are skipped
+        let id = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"aXa".to_vec())))
+            .unwrap();
+        min_stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, id)
+            .unwrap();
+        assert_eq!(
+            state
+                .corpus()
+                .get(id)
+                .unwrap()
+                .borrow()
+                .input()
+                .as_ref()
+                .unwrap()
+                .bytes(),
+            b"aXa"
+        );
+    }
+
+    #[test]
+    fn test_generalized_minimizer_keeps_gaps() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut meta = GeneralizedInputMetadata::default();
+        meta.generalized_mut().extend([
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(b"aX".as_slice().into()),
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(b"bb".as_slice().into()),
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(b"Yc".as_slice().into()),
+            GeneralizedItem::Gap,
+        ]);
+        let mut testcase = Testcase::new(BytesInput::new(meta.generalized_to_bytes()));
+        testcase.add_metadata(meta);
+        let corpus_idx = state.corpus_mut().add(testcase).unwrap();
+
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        // Needs both runs to crash
+        let mut executor = TestExecutor::new(|bytes| {
+            if bytes.contains(&b'X') && bytes.contains(&b'Y') {
+                ExitKind::Crash
+            } else {
+                ExitKind::Ok
+            }
+        });
+        let mut mgr = NopEventManager::new();
+        let mut min_stage = GeneralizedMinimizerStage::new(|_: &()| ConstFeedback::True);
+        min_stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, corpus_idx)
+            .unwrap();
+
+        let testcase = state.corpus().get(corpus_idx).unwrap().borrow();
+        assert_eq!(testcase.input().as_ref().unwrap().bytes(), b"aXYc");
+        assert_eq!(
+            testcase
+                .metadata()
+                .get::<GeneralizedInputMetadata>()
+                .unwrap()
+                .generalized(),
+            &[
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"aX".as_slice().into()),
+                GeneralizedItem::Gap,
+                GeneralizedItem::Bytes(b"Yc".as_slice().into()),
+                GeneralizedItem::Gap,
+            ]
+        );
+    }
+}
@@ -6,11 +6,15 @@ Other stages may enrich [`crate::corpus::Testcase`]s with metadata.
 
 /// Mutational stage is the normal fuzzing stage.
 pub mod mutational;
-pub use mutational::{MutationalStage, StdMutationalStage};
+pub use mutational::{
+    grimoire_mutational_stage, GrimoireMutationalStage, MutationalStage, StdMutationalStage,
+    GRIMOIRE_MAX_STACK_POW,
+};
 
 pub mod tmin;
 pub use tmin::{
-    MapEqualityFactory, MapEqualityFeedback, StdTMinMutationalStage, TMinMutationalStage,
+    MapEqualityFactory, MapEqualityFeedback, SolutionMinimizationStage, SolutionMinimizedMetadata,
+    StdTMinMutationalStage, TMinMutationalStage,
 };
 
 pub mod push;
@@ -27,6 +31,9 @@ pub use power::{PowerMutationalStage, StdPowerMutationalStage};
 pub mod generalization;
 pub use generalization::GeneralizationStage;
 
+pub mod generalized_minimizer;
+pub use generalized_minimizer::GeneralizedMinimizerStage;
+
 pub mod owned;
 pub use owned::StagesOwnedList;
 
@@ -270,23 +277,29 @@ where
         push_stage.init(fuzzer, state, event_mgr, executor.observers_mut())?;
 
         loop {
-            let input =
-                match push_stage.pre_exec(fuzzer, state, event_mgr, executor.observers_mut()) {
-                    Some(Ok(next_input)) => next_input,
-                    Some(Err(err)) => return Err(err),
-                    None => break,
-                };
-
-            let exit_kind = fuzzer.execute_input(state, executor, event_mgr, &input)?;
-
-            push_stage.post_exec(
+            let burst = match push_stage.pre_exec_many(
                 fuzzer,
                 state,
                 event_mgr,
                 executor.observers_mut(),
-                input,
-                exit_kind,
-            )?;
+            ) {
+                Some(Ok(burst)) if !burst.is_empty() => burst,
+                Some(Err(err)) => return Err(err),
+                Some(Ok(_)) | None => break,
+            };
+
+            for input in burst {
+                let exit_kind = fuzzer.execute_input(state, executor, event_mgr, &input)?;
+
+                push_stage.post_exec(
+                    fuzzer,
+                    state,
+                    event_mgr,
+                    executor.observers_mut(),
+                    input,
+                    exit_kind,
+                )?;
+            }
         }
 
         self.push_stage
@@ -378,6 +391,90 @@ where
     }
 }
 
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        executors::{Executor, ExitKind, HasObservers},
+        inputs::{BytesInput, HasBytesVec},
+        observers::UsesObservers,
+        state::{StdState, UsesState},
+        Error,
+    };
+
+    pub(crate) type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    /// An executor without observers, running the inputs through `classify` and recording them
+    #[derive(Debug)]
+    pub(crate) struct TestExecutor {
+        observers: (),
+        classify: fn(&[u8]) -> ExitKind,
+        /// The bytes of each input run so far
+        pub(crate) inputs: Vec<Vec<u8>>,
+    }
+
+    impl TestExecutor {
+        /// Creates a new [`TestExecutor`], returning the [`ExitKind`] `classify` gives each input
+        pub(crate) fn new(classify: fn(&[u8]) -> ExitKind) -> Self {
+            Self {
+                observers: (),
+                classify,
+                inputs: Vec::new(),
+            }
+        }
+
+        /// Crashes on every input containing an `X`
+        pub(crate) fn crash_on_x() -> Self {
+            Self::new(|bytes| {
+                if bytes.contains(&b'X') {
+                    ExitKind::Crash
+                } else {
+                    ExitKind::Ok
+                }
+            })
+        }
+    }
+
+    impl UsesState for TestExecutor {
+        type State = TestState;
+    }
+
+    impl UsesObservers for TestExecutor {
+        type Observers = ();
+    }
+
+    impl HasObservers for TestExecutor {
+        fn observers(&self) -> &() {
+            &self.observers
+        }
+
+        fn observers_mut(&mut self) -> &mut () {
+            &mut self.observers
+        }
+    }
+
+    impl<EM, Z> Executor<EM, Z> for TestExecutor
+    where
+        EM: UsesState<State = TestState>,
+        Z: UsesState<State = TestState>,
+    {
+        fn run_target(
+            &mut self,
+            _fuzzer: &mut Z,
+            _state: &mut TestState,
+            _mgr: &mut EM,
+            input: &BytesInput,
+        ) -> Result<ExitKind, Error> {
+            self.inputs.push(input.bytes().to_vec());
+            Ok((self.classify)(input.bytes()))
+        }
+    }
+}
+
 /// `Stage` Python bindings
 #[cfg(feature = "python")]
 #[allow(missing_docs)]
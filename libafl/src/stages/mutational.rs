@@ -9,12 +9,15 @@ use crate::{
     bolts::rands::Rand,
     corpus::{Corpus, CorpusId, Testcase},
     fuzzer::Evaluator,
-    inputs::Input,
+    inputs::{BytesInput, GeneralizedInputMetadata, Input, UsesInput},
     mark_feature_time,
-    mutators::Mutator,
+    mutators::{
+        MutationResult, Mutator, MutatorsTuple, ScheduledMutationPipeline, ScheduledMutator,
+        StdScheduledMutator,
+    },
     stages::Stage,
     start_timer,
-    state::{HasClientPerfMonitor, HasCorpus, HasRand, UsesState},
+    state::{HasClientPerfMonitor, HasCorpus, HasMetadata, HasRand, UsesState},
     Error,
 };
 
@@ -117,7 +120,9 @@ where
 
         start_timer!(state);
         let testcase = state.corpus().get(corpus_idx)?.borrow();
-        let Ok(input) = I::try_transform_from(&testcase, state, corpus_idx) else { return Ok(()); };
+        let Ok(input) = I::try_transform_from(&testcase, state, corpus_idx) else {
+            return Ok(());
+        };
         drop(testcase);
         mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
 
@@ -149,6 +154,8 @@ pub static DEFAULT_MUTATIONAL_MAX_ITERATIONS: u64 = 128;
 #[derive(Clone, Debug)]
 pub struct StdMutationalStage<E, EM, I, M, Z> {
     mutator: M,
+    /// The stacked mutations of the last [`StdMutationalStage::mutate_filtered`]
+    pipeline: Option<ScheduledMutationPipeline<I>>,
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(E, EM, I, Z)>,
 }
@@ -245,9 +252,262 @@ where
     pub fn transforming(mutator: M) -> Self {
         Self {
             mutator,
+            pipeline: None,
             phantom: PhantomData,
         }
     }
+
+    /// The stacked mutations of the last [`StdMutationalStage::mutate_filtered`], if any
+    #[must_use]
+    pub fn pipeline(&self) -> Option<&ScheduledMutationPipeline<I>> {
+        self.pipeline.as_ref()
+    }
+
+    /// Remembers the `input`, as mutated so far by the last [`StdMutationalStage::mutate_filtered`],
+    /// to roll back to later
+    pub fn checkpoint(&mut self, input: &I) -> Result<(), Error>
+    where
+        I: Clone,
+    {
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return Err(Error::illegal_state(
+                "Cannot checkpoint a mutational stage without stacked mutations",
+            ));
+        };
+        pipeline.checkpoint(input);
+        Ok(())
+    }
+
+    /// Restores the `input` to the last checkpoint of the stacked mutations,
+    /// so that only the mutations after it get retried
+    pub fn rollback(&mut self, input: &mut I) -> Result<(), Error>
+    where
+        I: Clone,
+    {
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return Err(Error::illegal_state(
+                "Cannot roll back a mutational stage without stacked mutations",
+            ));
+        };
+        pipeline.rollback(input)
+    }
+
+    /// Applies the stacked mutations of the scheduled mutator to the `input`, checkpointing it after
+    /// the first `checkpoint_at` of them. If the `filter` rejects the result, the input gets rolled back
+    /// to the checkpoint and only the tail of the stack is retried, up to `max_retries` times.
+    /// Returns [`MutationResult::Skipped`] if the `filter` rejected every attempt, leaving the input
+    /// rolled back to the checkpoint.
+    pub fn mutate_filtered<MT, F>(
+        &mut self,
+        state: &mut Z::State,
+        input: &mut I,
+        stage_idx: i32,
+        checkpoint_at: u64,
+        max_retries: usize,
+        mut filter: F,
+    ) -> Result<MutationResult, Error>
+    where
+        M: ScheduledMutator<I, MT, Z::State>,
+        MT: MutatorsTuple<I, Z::State>,
+        I: Clone,
+        F: FnMut(&Z::State, &I) -> bool,
+    {
+        let mut pipeline = ScheduledMutationPipeline::new(&self.mutator, state, input);
+        while pipeline.applied() < checkpoint_at && !pipeline.is_done() {
+            pipeline.apply_next(&mut self.mutator, state, input, stage_idx)?;
+        }
+        self.pipeline = Some(pipeline);
+        self.checkpoint(input)?;
+
+        for retry in 0..=max_retries {
+            if retry > 0 {
+                self.rollback(input)?;
+            }
+            let pipeline = self.pipeline.as_mut().unwrap();
+            let result = pipeline.apply_remaining(&mut self.mutator, state, input, stage_idx)?;
+            if filter(state, input) {
+                return Ok(result);
+            }
+        }
+        self.rollback(input)?;
+        Ok(MutationResult::Skipped)
+    }
+}
+
+/// The maximum stack pow of the mutator of a [`grimoire_mutational_stage`]
+pub const GRIMOIRE_MAX_STACK_POW: u64 = 3;
+
+/// A [`StdMutationalStage`] mutating the [`GeneralizedInputMetadata`] of the corpus entries with the mutations `MT`,
+/// as built by [`grimoire_mutational_stage`]
+pub type GrimoireMutationalStage<E, EM, MT, Z> = StdMutationalStage<
+    E,
+    EM,
+    GeneralizedInputMetadata,
+    StdScheduledMutator<GeneralizedInputMetadata, MT, <Z as UsesState>::State>,
+    Z,
+>;
+
+/// Creates the mutational stage of Grimoire, stacking the given `mutations`, usually the Grimoire mutators,
+/// on the [`GeneralizedInputMetadata`] of each corpus entry.
+/// Entries without a [`GeneralizedInputMetadata`], as they were not generalized yet
+/// by a [`crate::stages::GeneralizationStage`], get skipped.
+pub fn grimoire_mutational_stage<E, EM, MT, Z>(
+    mutations: MT,
+) -> GrimoireMutationalStage<E, EM, MT, Z>
+where
+    E: UsesState<State = Z::State>,
+    EM: UsesState<State = Z::State>,
+    MT: MutatorsTuple<GeneralizedInputMetadata, Z::State>,
+    Z: Evaluator<E, EM>,
+    Z::State:
+        HasClientPerfMonitor + HasCorpus + HasRand + HasMetadata + UsesInput<Input = BytesInput>,
+{
+    StdMutationalStage::transforming(StdScheduledMutator::with_max_stack_pow(
+        mutations,
+        GRIMOIRE_MAX_STACK_POW,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::ConstFeedback,
+        inputs::{BytesInput, GeneralizedInputMetadata, GeneralizedItem},
+        mutators::{
+            havoc_mutations, GrimoireExtensionMutator, GrimoireRandomDeleteMutator, MutationResult,
+            StdScheduledMutator,
+        },
+        schedulers::QueueScheduler,
+        stages::{
+            generalization::GeneralizedIndexesMetadata,
+            grimoire_mutational_stage,
+            tests::{TestExecutor, TestState},
+            Stage, StdMutationalStage,
+        },
+        state::{HasCorpus, HasMetadata, StdState},
+        StdFuzzer,
+    };
+
+    type TestFuzzer = StdFuzzer<QueueScheduler<TestState>, ConstFeedback, ConstFeedback, ()>;
+
+    #[test]
+    fn test_mutate_filtered_rollback() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"jumps over".to_vec())))
+            .unwrap();
+
+        let mut havoc_stage: StdMutationalStage<
+            TestExecutor,
+            NopEventManager<TestState>,
+            BytesInput,
+            _,
+            TestFuzzer,
+        > = StdMutationalStage::new(StdScheduledMutator::new(havoc_mutations()));
+        let mut input = BytesInput::new(b"the quick brown fox".to_vec());
+        assert!(havoc_stage.rollback(&mut input).is_err());
+
+        // Reject the first attempt
+        let mut attempts = 0;
+        let result = havoc_stage
+            .mutate_filtered(&mut state, &mut input, 0, 1, 2, |_, _| {
+                attempts += 1;
+                attempts > 1
+            })
+            .unwrap();
+        assert_eq!(attempts, 2);
+        assert_eq!(result, MutationResult::Mutated);
+        let pipeline = havoc_stage.pipeline().unwrap();
+        assert!(pipeline.is_done());
+
+        // Rolling back restores the input after the first mutation exactly, for another retry
+        let mut intermediate = input.clone();
+        havoc_stage.rollback(&mut intermediate).unwrap();
+        assert_eq!(havoc_stage.pipeline().unwrap().applied(), 1);
+        let mut again = BytesInput::new(b"x".to_vec());
+        havoc_stage.rollback(&mut again).unwrap();
+        assert_eq!(again, intermediate);
+
+        // A filter rejecting everything gives up after the retries
+        let mut attempts = 0;
+        let result = havoc_stage
+            .mutate_filtered(&mut state, &mut input, 0, 1, 2, |_, _| {
+                attempts += 1;
+                false
+            })
+            .unwrap();
+        assert_eq!(attempts, 3);
+        assert_eq!(result, MutationResult::Skipped);
+        // The rejected input got rolled back to the checkpoint
+        let mut checkpoint = BytesInput::new(b"x".to_vec());
+        havoc_stage.rollback(&mut checkpoint).unwrap();
+        assert_eq!(input, checkpoint);
+    }
+
+    #[test]
+    fn test_grimoire_mutational_stage() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+
+        let mut meta = GeneralizedInputMetadata::default();
+        meta.generalized_mut().extend([
+            GeneralizedItem::Gap,
+            GeneralizedItem::Bytes(b"abc".as_slice().into()),
+            GeneralizedItem::Gap,
+        ]);
+        let mut testcase = Testcase::new(BytesInput::new(b"abc".to_vec()));
+        testcase.add_metadata(meta);
+        let corpus_idx = state.corpus_mut().add(testcase).unwrap();
+        // As the GeneralizationStage would have recorded it
+        let mut indexes = GeneralizedIndexesMetadata::new();
+        indexes.indexes.insert(corpus_idx);
+        state.add_metadata(indexes);
+
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut executor = TestExecutor::new(|_| ExitKind::Ok);
+        let mut mgr = NopEventManager::new();
+        let mut grimoire_stage = grimoire_mutational_stage(tuple_list!(
+            GrimoireExtensionMutator::new(),
+            GrimoireRandomDeleteMutator::new()
+        ));
+        grimoire_stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, corpus_idx)
+            .unwrap();
+        assert!(!executor.inputs.is_empty());
+
+        // Entries that were not generalized get skipped
+        executor.inputs.clear();
+        let id = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"abc".to_vec())))
+            .unwrap();
+        grimoire_stage
+            .perform(&mut fuzzer, &mut executor, &mut state, &mut mgr, id)
+            .unwrap();
+        assert!(executor.inputs.is_empty());
+    }
 }
 
 #[cfg(feature = "python")]
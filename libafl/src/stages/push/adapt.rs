@@ -0,0 +1,236 @@
+//! The [`AdaptStage`] allows to run a normal [`Stage`] inside of a push-driven fuzzing loop.
+//! Normal stages execute their inputs themselves, so the wrapped [`Stage`] needs its own executor.
+
+use alloc::rc::Rc;
+use core::{
+    cell::{Cell, RefCell},
+    fmt::Debug,
+};
+
+use super::{PushStage, PushStageHelper, PushStageSharedState};
+use crate::{
+    corpus::CorpusId,
+    events::{EventFirer, EventRestarter, HasEventManagerId, ProgressReporter},
+    executors::ExitKind,
+    inputs::UsesInput,
+    observers::ObserversTuple,
+    schedulers::Scheduler,
+    stages::Stage,
+    state::{HasClientPerfMonitor, HasExecutions, HasMetadata, HasRand, UsesState},
+    Error, EvaluatorObservers, ExecutionProcessor, HasScheduler,
+};
+
+/// Runs a normal [`Stage`] as a [`PushStage`].
+/// In `pre_exec`, the wrapped stage is performed to completion against the shared state,
+/// using its own executor, after which the push stage returns `None`.
+/// This way, the wrapped stage runs exactly once per push stage cycle.
+#[derive(Debug)]
+pub struct AdaptStage<CS, E, EM, OT, ST, Z>
+where
+    CS: Scheduler,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
+    OT: ObserversTuple<CS::State>,
+    CS::State: HasClientPerfMonitor + HasRand + Debug,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+{
+    stage: ST,
+    executor: E,
+    current_corpus_idx: Option<CorpusId>,
+    psh: PushStageHelper<CS, EM, OT, Z>,
+}
+
+impl<CS, E, EM, OT, ST, Z> AdaptStage<CS, E, EM, OT, ST, Z>
+where
+    CS: Scheduler,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
+    OT: ObserversTuple<CS::State>,
+    CS::State: HasClientPerfMonitor + HasRand + Debug,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+{
+    /// Creates a new [`AdaptStage`], wrapping the given [`Stage`] and the executor it runs with
+    #[must_use]
+    #[allow(clippy::type_complexity)]
+    pub fn new(
+        stage: ST,
+        executor: E,
+        shared_state: Rc<RefCell<Option<PushStageSharedState<CS, EM, OT, Z>>>>,
+        exit_kind: Rc<Cell<Option<ExitKind>>>,
+    ) -> Self {
+        Self {
+            stage,
+            executor,
+            current_corpus_idx: None,
+            psh: PushStageHelper::new(shared_state, exit_kind),
+        }
+    }
+
+    /// The wrapped [`Stage`]
+    #[must_use]
+    pub fn stage(&self) -> &ST {
+        &self.stage
+    }
+
+    /// The wrapped [`Stage`] (mutable)
+    pub fn stage_mut(&mut self) -> &mut ST {
+        &mut self.stage
+    }
+
+    /// The executor used by the wrapped [`Stage`]
+    #[must_use]
+    pub fn executor(&self) -> &E {
+        &self.executor
+    }
+
+    /// The executor used by the wrapped [`Stage`] (mutable)
+    pub fn executor_mut(&mut self) -> &mut E {
+        &mut self.executor
+    }
+}
+
+impl<CS, E, EM, OT, ST, Z> PushStage<CS, EM, OT, Z> for AdaptStage<CS, E, EM, OT, ST, Z>
+where
+    CS: Scheduler,
+    CS::State: HasClientPerfMonitor + HasRand + HasExecutions + HasMetadata + Debug,
+    E: UsesState<State = CS::State>,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId + ProgressReporter,
+    OT: ObserversTuple<CS::State>,
+    ST: Stage<E, EM, Z, State = CS::State>,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+{
+    #[inline]
+    fn push_stage_helper(&self) -> &PushStageHelper<CS, EM, OT, Z> {
+        &self.psh
+    }
+
+    #[inline]
+    fn push_stage_helper_mut(&mut self) -> &mut PushStageHelper<CS, EM, OT, Z> {
+        &mut self.psh
+    }
+
+    fn set_current_corpus_idx(&mut self, corpus_idx: CorpusId) {
+        self.current_corpus_idx = Some(corpus_idx);
+    }
+
+    fn init(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        _event_mgr: &mut EM,
+        _observers: &mut OT,
+    ) -> Result<(), Error> {
+        // Find a testcase to work on, unless someone already set it
+        if self.current_corpus_idx.is_none() {
+            self.current_corpus_idx = Some(fuzzer.scheduler().next(state)?);
+        }
+        Ok(())
+    }
+
+    fn pre_exec(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        _observers: &mut OT,
+    ) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        // The wrapped stage runs its inputs itself, we never return one.
+        let corpus_idx = self.current_corpus_idx.take()?;
+        match self
+            .stage
+            .perform(fuzzer, &mut self.executor, state, event_mgr, corpus_idx)
+        {
+            Ok(()) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    #[inline]
+    fn on_error_reset(&mut self) {
+        self.current_corpus_idx = None;
+    }
+}
+
+impl<CS, E, EM, OT, ST, Z> Iterator for AdaptStage<CS, E, EM, OT, ST, Z>
+where
+    CS: Scheduler,
+    CS::State: HasClientPerfMonitor + HasRand + HasExecutions + HasMetadata + Debug,
+    E: UsesState<State = CS::State>,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId + ProgressReporter,
+    OT: ObserversTuple<CS::State>,
+    ST: Stage<E, EM, Z, State = CS::State>,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+{
+    type Item = Result<<CS::State as UsesInput>::Input, Error>;
+
+    fn next(&mut self) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        self.next_std()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::{
+        cell::{Cell, RefCell},
+        marker::PhantomData,
+    };
+
+    use super::AdaptStage;
+    use crate::{
+        corpus::CorpusId,
+        stages::{
+            push::tests::{test_shared_state, TestEventManager, TestFuzzer, TestState},
+            ClosureStage,
+        },
+        state::UsesState,
+        Error,
+    };
+
+    #[derive(Debug)]
+    struct NopExecutor {
+        phantom: PhantomData<TestState>,
+    }
+
+    impl UsesState for NopExecutor {
+        type State = TestState;
+    }
+
+    #[test]
+    fn test_adapt_stage() {
+        let runs = Rc::new(RefCell::new(0_usize));
+        let runs_clone = runs.clone();
+        let stage = ClosureStage::new(
+            move |_fuzzer: &mut TestFuzzer,
+                  _executor: &mut NopExecutor,
+                  _state: &mut TestState,
+                  _mgr: &mut TestEventManager,
+                  _corpus_idx: CorpusId|
+                  -> Result<(), Error> {
+                *runs_clone.borrow_mut() += 1;
+                Ok(())
+            },
+        );
+        let executor = NopExecutor {
+            phantom: PhantomData,
+        };
+        let mut push_stage = AdaptStage::new(
+            stage,
+            executor,
+            test_shared_state(),
+            Rc::new(Cell::new(None)),
+        );
+
+        // Each push cycle runs the wrapped stage once, never yielding an input.
+        assert!(push_stage.next().is_none());
+        assert_eq!(*runs.borrow(), 1);
+        assert!(push_stage.next().is_none());
+        assert_eq!(*runs.borrow(), 2);
+    }
+}
@@ -0,0 +1,234 @@
+//! The [`BudgetedPushStage`] stops a wrapped [`PushStage`] once a budget of inputs, shared between stages, is spent.
+
+use alloc::rc::Rc;
+use core::{cell::Cell, fmt::Debug, marker::PhantomData};
+
+use super::{
+    PushStage, PushStageHelper, PushStageProgress, PushStageSharedState, RcSharedState,
+    SharedStateCell,
+};
+use crate::{
+    corpus::CorpusId,
+    events::{EventFirer, EventRestarter, HasEventManagerId, ProgressReporter},
+    executors::ExitKind,
+    inputs::UsesInput,
+    observers::ObserversTuple,
+    schedulers::Scheduler,
+    state::{HasClientPerfMonitor, HasExecutions, HasMetadata, HasRand},
+    Error, EvaluatorObservers, ExecutionProcessor, HasScheduler,
+};
+
+/// Wraps a [`PushStage`], spending one unit of a budget for each input it returns.
+/// Once the budget is spent, `pre_exec` returns `None`, ending the cycle of the wrapped stage.
+/// Several stages wrapped with the same budget stop after the given number of inputs, combined.
+#[derive(Debug)]
+pub struct BudgetedPushStage<CS, EM, OT, PS, Z, SH = RcSharedState<CS, EM, OT, Z>> {
+    stage: PS,
+    budget: Rc<Cell<usize>>,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(CS, EM, OT, Z, SH)>,
+}
+
+impl<CS, EM, OT, PS, Z, SH> BudgetedPushStage<CS, EM, OT, PS, Z, SH> {
+    /// Wraps the given `stage`, spending the shared `budget`
+    #[must_use]
+    pub fn new(stage: PS, budget: Rc<Cell<usize>>) -> Self {
+        Self {
+            stage,
+            budget,
+            phantom: PhantomData,
+        }
+    }
+
+    /// The inputs left in the shared budget
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.budget.get()
+    }
+
+    /// The wrapped [`PushStage`]
+    #[must_use]
+    pub fn stage(&self) -> &PS {
+        &self.stage
+    }
+
+    /// The wrapped [`PushStage`] (mutable)
+    pub fn stage_mut(&mut self) -> &mut PS {
+        &mut self.stage
+    }
+}
+
+impl<CS, EM, OT, PS, Z, SH> PushStage<CS, EM, OT, Z, SH>
+    for BudgetedPushStage<CS, EM, OT, PS, Z, SH>
+where
+    CS: Scheduler,
+    CS::State: HasClientPerfMonitor + HasRand + HasExecutions + HasMetadata,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId + ProgressReporter,
+    OT: ObserversTuple<CS::State>,
+    PS: PushStage<CS, EM, OT, Z, SH>,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
+{
+    #[inline]
+    fn push_stage_helper(&self) -> &PushStageHelper<CS, EM, OT, Z, SH> {
+        self.stage.push_stage_helper()
+    }
+
+    #[inline]
+    fn push_stage_helper_mut(&mut self) -> &mut PushStageHelper<CS, EM, OT, Z, SH> {
+        self.stage.push_stage_helper_mut()
+    }
+
+    #[inline]
+    fn set_current_corpus_idx(&mut self, corpus_idx: CorpusId) {
+        self.stage.set_current_corpus_idx(corpus_idx);
+    }
+
+    #[inline]
+    fn init(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+    ) -> Result<(), Error> {
+        self.stage.init(fuzzer, state, event_mgr, observers)
+    }
+
+    /// Returns the next input of the wrapped stage, or `None` if the budget is spent
+    fn pre_exec(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+    ) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        let remaining = self.budget.get();
+        if remaining == 0 {
+            return None;
+        }
+        let ret = self.stage.pre_exec(fuzzer, state, event_mgr, observers);
+        if let Some(Ok(_)) = ret {
+            self.budget.set(remaining - 1);
+        }
+        ret
+    }
+
+    /// The batch limit of the wrapped stage, capped to the budget left
+    #[inline]
+    fn batch_limit(&self) -> usize {
+        self.stage.batch_limit().min(self.budget.get())
+    }
+
+    #[inline]
+    fn post_exec(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+        input: <CS::State as UsesInput>::Input,
+        exit_kind: ExitKind,
+    ) -> Result<(), Error> {
+        self.stage
+            .post_exec(fuzzer, state, event_mgr, observers, input, exit_kind)
+    }
+
+    #[inline]
+    fn deinit(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+    ) -> Result<(), Error> {
+        self.stage.deinit(fuzzer, state, event_mgr, observers)
+    }
+
+    #[inline]
+    fn on_error_reset(&mut self) {
+        self.stage.on_error_reset();
+    }
+
+    #[inline]
+    fn snapshot(&self) -> PushStageProgress {
+        self.stage.snapshot()
+    }
+
+    #[inline]
+    fn restore(&mut self, progress: &PushStageProgress) -> Result<(), Error> {
+        self.stage.restore(progress)
+    }
+}
+
+impl<CS, EM, OT, PS, Z, SH> Iterator for BudgetedPushStage<CS, EM, OT, PS, Z, SH>
+where
+    CS: Scheduler,
+    CS::State: HasClientPerfMonitor + HasRand + HasExecutions + HasMetadata,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId + ProgressReporter,
+    OT: ObserversTuple<CS::State>,
+    PS: PushStage<CS, EM, OT, Z, SH>,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
+{
+    type Item = Result<<CS::State as UsesInput>::Input, Error>;
+
+    fn next(&mut self) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        self.next_std()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    use super::BudgetedPushStage;
+    use crate::{
+        executors::ExitKind,
+        mutators::BitFlipMutator,
+        stages::push::{tests::test_shared_state, StdMutationalPushStage},
+    };
+
+    #[test]
+    fn test_shared_budget() {
+        let shared_state = test_shared_state();
+        let exit_kind = Rc::new(Cell::new(None));
+        let budget = Rc::new(Cell::new(100));
+        let mut stages = [7, 11].map(|iterations| {
+            BudgetedPushStage::new(
+                StdMutationalPushStage::with_iterations(
+                    BitFlipMutator::new(),
+                    shared_state.clone(),
+                    exit_kind.clone(),
+                    0,
+                    iterations,
+                ),
+                budget.clone(),
+            )
+        });
+
+        let mut yielded = 0;
+        loop {
+            let mut cycle_yielded = 0;
+            for stage in &mut stages {
+                for input in stage.by_ref() {
+                    input.unwrap();
+                    cycle_yielded += 1;
+                    exit_kind.set(Some(ExitKind::Ok));
+                }
+            }
+            if cycle_yielded == 0 {
+                break;
+            }
+            yielded += cycle_yielded;
+        }
+
+        assert_eq!(yielded, 100);
+        assert_eq!(stages[0].remaining(), 0);
+    }
+}
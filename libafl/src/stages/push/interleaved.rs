@@ -0,0 +1,482 @@
+//! The [`InterleavedPushStage`] runs a deterministic bit-flip pass and a havoc pass on each corpus entry,
+//! like the stages of AFL, but returning each input instead of executing it.
+
+use alloc::rc::Rc;
+use core::{cell::Cell, fmt::Debug};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    mutational::DEFAULT_MUTATIONAL_MAX_ITERATIONS, PushStage, PushStageHelper, PushStageProgress,
+    PushStageSharedState, RcSharedState, SharedStateCell,
+};
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+use crate::{
+    bolts::rands::Rand,
+    corpus::{Corpus, CorpusId},
+    events::{EventFirer, EventRestarter, HasEventManagerId, ProgressReporter},
+    executors::ExitKind,
+    fuzzer::ExecuteInputResult,
+    inputs::{HasBytesVec, UsesInput},
+    mark_feature_time,
+    mutators::Mutator,
+    observers::ObserversTuple,
+    schedulers::Scheduler,
+    start_timer,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, HasRand},
+    Error, EvaluatorObservers, ExecutionProcessor, HasScheduler,
+};
+
+/// The phase an [`InterleavedPushStage`] is in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InterleavedPhase {
+    /// Flipping each bit of the corpus entry, one at a time
+    #[default]
+    Deterministic,
+    /// Applying the havoc mutator to the corpus entry
+    Havoc,
+}
+
+/// The number of inputs an [`InterleavedPushStage`] returned in each phase of the ongoing cycle
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InterleavedPhaseStats {
+    /// The inputs of the deterministic phase
+    pub deterministic: usize,
+    /// The inputs of the havoc phase
+    pub havoc: usize,
+    /// The number of phase changes since the stage got created
+    pub transitions: usize,
+}
+
+/// A testcase metadata marking a corpus entry whose deterministic phase an [`InterleavedPushStage`]
+/// completed, so that later cycles on it skip straight to the havoc phase
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct DeterministicDoneMetadata;
+
+crate::impl_serdeany!(DeterministicDoneMetadata);
+
+/// A push stage working on one corpus entry per cycle, in two phases.
+/// First, the deterministic phase returns the entry with each of its bits flipped, one bit per input.
+/// It only runs in the first cycle on an entry, see [`DeterministicDoneMetadata`].
+/// Then, the havoc phase returns the entry mutated by the havoc mutator, a random number of times up to
+/// [`DEFAULT_MUTATIONAL_MAX_ITERATIONS`], or a fixed number of times.
+/// `pre_exec` returns `None` once both phases are done.
+#[derive(Clone, Debug)]
+pub struct InterleavedPushStage<CS, EM, M, OT, Z, SH = RcSharedState<CS, EM, OT, Z>>
+where
+    CS: Scheduler,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
+    M: Mutator<CS::Input, CS::State>,
+    OT: ObserversTuple<CS::State>,
+    CS::State: HasClientPerfMonitor + HasRand + Clone + Debug,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
+{
+    current_corpus_idx: Option<CorpusId>,
+    phase: InterleavedPhase,
+    stats: InterleavedPhaseStats,
+    /// The number of bits to flip, one per input
+    bits_to_flip: usize,
+    bits_flipped: usize,
+    havoc_to_do: usize,
+    havoc_done: usize,
+    /// If set, the exact number of havoc inputs per corpus entry
+    fixed_havoc_iterations: Option<usize>,
+
+    stage_idx: i32,
+
+    mutator: M,
+
+    psh: PushStageHelper<CS, EM, OT, Z, SH>,
+}
+
+impl<CS, EM, M, OT, Z, SH> InterleavedPushStage<CS, EM, M, OT, Z, SH>
+where
+    CS: Scheduler,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
+    M: Mutator<CS::Input, CS::State>,
+    OT: ObserversTuple<CS::State>,
+    CS::State: HasClientPerfMonitor + HasCorpus + HasRand + Clone + Debug,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
+{
+    /// Creates a new [`InterleavedPushStage`], using `mutator` for the havoc phase
+    #[must_use]
+    pub fn new(
+        mutator: M,
+        shared_state: SH,
+        exit_kind: Rc<Cell<Option<ExitKind>>>,
+        stage_idx: i32,
+    ) -> Self {
+        Self {
+            mutator,
+            psh: PushStageHelper::new(shared_state, exit_kind),
+            current_corpus_idx: None,
+            phase: InterleavedPhase::Deterministic,
+            stats: InterleavedPhaseStats::default(),
+            bits_to_flip: 0,
+            bits_flipped: 0,
+            havoc_to_do: 0,
+            havoc_done: 0,
+            fixed_havoc_iterations: None,
+            stage_idx,
+        }
+    }
+
+    /// Emits exactly `iterations` havoc inputs per corpus entry, instead of a random number of them
+    #[must_use]
+    pub fn with_havoc_iterations(mut self, iterations: usize) -> Self {
+        self.fixed_havoc_iterations = Some(iterations);
+        self
+    }
+
+    /// The phase the stage is in. The next input `pre_exec` returns belongs to it,
+    /// unless the phase is exhausted.
+    #[must_use]
+    pub fn phase(&self) -> InterleavedPhase {
+        self.phase
+    }
+
+    /// The number of inputs returned in each phase of the ongoing cycle, and the phase changes so far
+    #[must_use]
+    pub fn phase_stats(&self) -> InterleavedPhaseStats {
+        self.stats
+    }
+
+    /// Sets the current corpus index
+    pub fn set_current_corpus_idx(&mut self, current_corpus_idx: CorpusId) {
+        self.current_corpus_idx = Some(current_corpus_idx);
+    }
+
+    /// Switches to the given phase, counting the transition
+    fn enter_phase(&mut self, phase: InterleavedPhase) {
+        if self.phase != phase {
+            self.phase = phase;
+            self.stats.transitions += 1;
+        }
+    }
+
+    /// Loads a copy of the input of the current corpus entry
+    fn load_input(&self, state: &mut CS::State) -> Result<<CS::State as UsesInput>::Input, Error> {
+        let corpus_idx = self
+            .current_corpus_idx
+            .ok_or_else(|| Error::illegal_state("InterleavedPushStage has no corpus entry"))?;
+        let mut testcase = state.corpus().get(corpus_idx)?.borrow_mut();
+        Ok(testcase.load_input()?.clone())
+    }
+}
+
+impl<CS, EM, M, OT, Z, SH> PushStage<CS, EM, OT, Z, SH>
+    for InterleavedPushStage<CS, EM, M, OT, Z, SH>
+where
+    CS: Scheduler,
+    CS::Input: HasBytesVec,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId + ProgressReporter,
+    M: Mutator<CS::Input, CS::State>,
+    OT: ObserversTuple<CS::State>,
+    CS::State:
+        HasClientPerfMonitor + HasCorpus + HasRand + HasExecutions + HasMetadata + Clone + Debug,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
+{
+    #[inline]
+    fn push_stage_helper(&self) -> &PushStageHelper<CS, EM, OT, Z, SH> {
+        &self.psh
+    }
+
+    #[inline]
+    fn push_stage_helper_mut(&mut self) -> &mut PushStageHelper<CS, EM, OT, Z, SH> {
+        &mut self.psh
+    }
+
+    /// Picks the corpus entry, unless someone already set it, and starts with the deterministic phase,
+    /// unless it got completed on this entry before
+    fn init(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        _event_mgr: &mut EM,
+        _observers: &mut OT,
+    ) -> Result<(), Error> {
+        let corpus_idx = match self.current_corpus_idx {
+            Some(corpus_idx) => corpus_idx,
+            None => fuzzer.scheduler().next(state)?,
+        };
+        self.current_corpus_idx = Some(corpus_idx);
+
+        let deterministic_done = state
+            .corpus()
+            .get(corpus_idx)?
+            .borrow()
+            .metadata()
+            .contains::<DeterministicDoneMetadata>();
+        self.bits_to_flip = if deterministic_done {
+            0
+        } else {
+            self.load_input(state)?.bytes().len() * 8
+        };
+        self.bits_flipped = 0;
+        self.havoc_to_do = match self.fixed_havoc_iterations {
+            Some(iterations) => iterations,
+            None => 1 + state.rand_mut().below(DEFAULT_MUTATIONAL_MAX_ITERATIONS) as usize,
+        };
+        self.havoc_done = 0;
+        self.stats.deterministic = 0;
+        self.stats.havoc = 0;
+        self.enter_phase(if deterministic_done {
+            InterleavedPhase::Havoc
+        } else {
+            InterleavedPhase::Deterministic
+        });
+        Ok(())
+    }
+
+    fn pre_exec(
+        &mut self,
+        _fuzzer: &mut Z,
+        state: &mut CS::State,
+        _event_mgr: &mut EM,
+        _observers: &mut OT,
+    ) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        if self.phase == InterleavedPhase::Deterministic && self.bits_flipped >= self.bits_to_flip {
+            if let Some(corpus_idx) = self.current_corpus_idx {
+                match state.corpus().get(corpus_idx) {
+                    Ok(testcase) => testcase
+                        .borrow_mut()
+                        .add_metadata(DeterministicDoneMetadata),
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+            self.enter_phase(InterleavedPhase::Havoc);
+        }
+        if self.phase == InterleavedPhase::Havoc && self.havoc_done >= self.havoc_to_do {
+            // Both phases are done
+            return None;
+        }
+
+        start_timer!(state);
+        let mut input = match self.load_input(state) {
+            Ok(input) => input,
+            Err(err) => return Some(Err(err)),
+        };
+        mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+
+        match self.phase {
+            InterleavedPhase::Deterministic => {
+                let bit = self.bits_flipped;
+                input.bytes_mut()[bit / 8] ^= 1 << (bit % 8);
+                self.stats.deterministic += 1;
+            }
+            InterleavedPhase::Havoc => {
+                start_timer!(state);
+                if let Err(err) = self.mutator.mutate(state, &mut input, self.stage_idx) {
+                    return Some(Err(err));
+                }
+                mark_feature_time!(state, PerfFeature::Mutate);
+                self.stats.havoc += 1;
+            }
+        }
+
+        self.push_stage_helper_mut()
+            .current_input
+            .replace(input.clone());
+
+        Some(Ok(input))
+    }
+
+    fn post_exec(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+        last_input: <CS::State as UsesInput>::Input,
+        exit_kind: ExitKind,
+    ) -> Result<(), Error> {
+        let (res, _) =
+            fuzzer.process_execution(state, event_mgr, last_input, observers, &exit_kind, true)?;
+        if res != ExecuteInputResult::None {
+            self.push_stage_helper_mut().record_interesting();
+        }
+
+        match self.phase {
+            InterleavedPhase::Deterministic => self.bits_flipped += 1,
+            InterleavedPhase::Havoc => {
+                start_timer!(state);
+                self.mutator
+                    .post_exec(state, self.stage_idx, self.current_corpus_idx)?;
+                mark_feature_time!(state, PerfFeature::MutatePostExec);
+                self.havoc_done += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// A single input while flipping bits, as the next bit is only flipped after `post_exec`,
+    /// then the havoc inputs left in this cycle
+    #[inline]
+    fn batch_limit(&self) -> usize {
+        if self.phase == InterleavedPhase::Deterministic && self.bits_flipped < self.bits_to_flip {
+            1
+        } else {
+            self.havoc_to_do.saturating_sub(self.havoc_done)
+        }
+    }
+
+    #[inline]
+    fn deinit(
+        &mut self,
+        _fuzzer: &mut Z,
+        _state: &mut CS::State,
+        _event_mgr: &mut EM,
+        _observers: &mut OT,
+    ) -> Result<(), Error> {
+        self.current_corpus_idx = None;
+        Ok(())
+    }
+
+    #[inline]
+    fn on_error_reset(&mut self) {
+        self.current_corpus_idx = None;
+        self.bits_to_flip = 0;
+        self.bits_flipped = 0;
+        self.havoc_to_do = 0;
+        self.havoc_done = 0;
+        self.enter_phase(InterleavedPhase::Deterministic);
+    }
+
+    /// The progress, with the phase, and the bits and havoc inputs to do and done as counters
+    fn snapshot(&self) -> PushStageProgress {
+        PushStageProgress {
+            corpus_idx: self.current_corpus_idx,
+            counters: vec![
+                usize::from(self.phase == InterleavedPhase::Havoc),
+                self.bits_to_flip,
+                self.bits_flipped,
+                self.havoc_to_do,
+                self.havoc_done,
+            ],
+            ..self.psh.progress()
+        }
+    }
+
+    fn restore(&mut self, progress: &PushStageProgress) -> Result<(), Error> {
+        let [phase, bits_to_flip, bits_flipped, havoc_to_do, havoc_done] = progress.counters[..]
+        else {
+            return Err(Error::illegal_argument(format!(
+                "Expected 5 counters to restore an InterleavedPushStage, got {}",
+                progress.counters.len()
+            )));
+        };
+        let phase = match phase {
+            0 => InterleavedPhase::Deterministic,
+            1 => InterleavedPhase::Havoc,
+            _ => {
+                return Err(Error::illegal_argument(format!(
+                    "Unknown InterleavedPushStage phase {phase}"
+                )))
+            }
+        };
+        if progress.initialized && progress.corpus_idx.is_none() {
+            return Err(Error::illegal_argument(
+                "Can not restore an initialized InterleavedPushStage without a corpus index",
+            ));
+        }
+        self.psh.restore_progress(progress);
+        self.current_corpus_idx = progress.corpus_idx;
+        self.phase = phase;
+        self.bits_to_flip = bits_to_flip;
+        self.bits_flipped = bits_flipped;
+        self.havoc_to_do = havoc_to_do;
+        self.havoc_done = havoc_done;
+        Ok(())
+    }
+}
+
+impl<CS, EM, M, OT, Z, SH> Iterator for InterleavedPushStage<CS, EM, M, OT, Z, SH>
+where
+    CS: Scheduler,
+    CS::Input: HasBytesVec,
+    EM: EventFirer + EventRestarter + HasEventManagerId + ProgressReporter<State = CS::State>,
+    M: Mutator<CS::Input, CS::State>,
+    OT: ObserversTuple<CS::State>,
+    CS::State:
+        HasClientPerfMonitor + HasCorpus + HasRand + HasExecutions + HasMetadata + Clone + Debug,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
+{
+    type Item = Result<<CS::State as UsesInput>::Input, Error>;
+
+    fn next(&mut self) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        self.next_std()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    use super::{InterleavedPhase, InterleavedPushStage};
+    use crate::{
+        executors::ExitKind,
+        inputs::HasBytesVec,
+        mutators::BitFlipMutator,
+        stages::push::{tests::test_shared_state, PushStage},
+    };
+
+    #[test]
+    fn test_interleaved_push_stage() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = InterleavedPushStage::new(
+            BitFlipMutator::new(),
+            test_shared_state(),
+            exit_kind.clone(),
+            0,
+        )
+        .with_havoc_iterations(3);
+        assert_eq!(stage.phase(), InterleavedPhase::Deterministic);
+
+        // The deterministic phase only runs in the first cycle on the entry
+        for bits in [32, 0] {
+            let mut phases = vec![];
+            while let Some(input) = stage.next() {
+                let input = input.unwrap();
+                let i = phases.len();
+                if stage.phase() == InterleavedPhase::Deterministic {
+                    // Each deterministic input has exactly the i-th bit of the entry flipped
+                    let mut expected = b"aaaa".to_vec();
+                    expected[i / 8] ^= 1 << (i % 8);
+                    assert_eq!(input.bytes(), expected);
+                }
+                phases.push(stage.phase());
+                exit_kind.set(Some(ExitKind::Ok));
+            }
+            assert_eq!(phases.len(), bits + 3);
+            assert!(phases[..bits]
+                .iter()
+                .all(|phase| *phase == InterleavedPhase::Deterministic));
+            assert!(phases[bits..]
+                .iter()
+                .all(|phase| *phase == InterleavedPhase::Havoc));
+
+            let stats = stage.phase_stats();
+            assert_eq!(stats.deterministic, bits);
+            assert_eq!(stats.havoc, 3);
+            assert_eq!(stats.transitions, 1);
+        }
+
+        let snapshot = stage.snapshot();
+        assert!(!snapshot.initialized);
+        assert_eq!(snapshot.counters, [1, 0, 0, 3, 3]);
+    }
+}
@@ -4,34 +4,137 @@
 //! The push stage relies on internal mutability of the supplied `Observers`.
 //!
 
+/// Runs a normal stage inside of a push stage loop.
+pub mod adapt;
+/// Stops push stages once a shared budget of inputs is spent.
+pub mod budget;
+/// Interleaves deterministic and havoc phases on each corpus entry.
+pub mod interleaved;
 /// Mutational stage is the normal fuzzing stage.
 pub mod mutational;
-use alloc::rc::Rc;
+/// Replays the corpus without mutations.
+pub mod replay;
+/// Logs the executions of push stages to disk.
+#[cfg(feature = "std")]
+pub mod tracing;
+use alloc::{collections::VecDeque, rc::Rc, vec::Vec};
 use core::{
     cell::{Cell, RefCell},
     marker::PhantomData,
     time::Duration,
 };
+#[cfg(feature = "std")]
+use std::sync::{mpsc::Sender, Arc, Condvar, Mutex};
 
+pub use adapt::AdaptStage;
+pub use budget::BudgetedPushStage;
+pub use interleaved::{
+    DeterministicDoneMetadata, InterleavedPhase, InterleavedPhaseStats, InterleavedPushStage,
+};
 pub use mutational::StdMutationalPushStage;
+pub use replay::ReplayPushStage;
+use serde::{Deserialize, Serialize};
+use smallvec::{smallvec, SmallVec};
+#[cfg(feature = "std")]
+pub use tracing::TracingPushStage;
 
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
 use crate::{
-    bolts::current_time,
-    corpus::CorpusId,
-    events::{EventFirer, EventRestarter, HasEventManagerId, ProgressReporter},
+    bolts::{current_time, rands::Rand},
+    corpus::{Corpus, CorpusId},
+    events::{
+        Event, EventConfig, EventFirer, EventManagerId, EventRestarter, HasEventManagerId,
+        ProgressReporter,
+    },
     executors::ExitKind,
     inputs::UsesInput,
     observers::ObserversTuple,
     schedulers::Scheduler,
-    state::{HasClientPerfMonitor, HasExecutions, HasMetadata, HasRand},
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, HasRand},
     Error, EvaluatorObservers, ExecutionProcessor, HasScheduler,
 };
 
 /// Send a monitor update all 15 (or more) seconds
 const STATS_TIMEOUT_DEFAULT: Duration = Duration::from_secs(15);
 
+/// The default number of discoveries a [`PushStageSharedState`] queues before dropping new ones
+pub const DEFAULT_DISCOVERY_QUEUE_CAPACITY: usize = 1024;
+
+/// Holds the shared state of several [`PushStage`]s, lending it to one stage at a time.
+/// `next_std` takes it for each step of a stage and puts it back afterwards.
+pub trait SharedStateCell<T>: Clone {
+    /// Takes the shared state, leaving the cell empty
+    fn take_shared(&self) -> Option<T>;
+
+    /// Takes the shared state without waiting for it, or returns `None` if another stage holds it
+    fn try_take_shared(&self) -> Option<T>;
+
+    /// Puts the shared state (back) into the cell
+    fn set_shared(&self, shared_state: T);
+}
+
+impl<T> SharedStateCell<T> for Rc<RefCell<Option<T>>> {
+    /// Takes the shared state, or returns `None` if another stage holds it
+    #[inline]
+    fn take_shared(&self) -> Option<T> {
+        self.borrow_mut().take()
+    }
+
+    #[inline]
+    fn try_take_shared(&self) -> Option<T> {
+        self.take_shared()
+    }
+
+    #[inline]
+    fn set_shared(&self, shared_state: T) {
+        self.borrow_mut().replace(shared_state);
+    }
+}
+
+/// Shares the state between stages on several threads, for example working on the same corpus.
+/// Each thread creates its own stages from a clone of the [`Arc`]. To move the [`Arc`] to other threads,
+/// the state, the fuzzer, the event manager, and the observers need to be [`Send`].
+#[cfg(feature = "std")]
+impl<T> SharedStateCell<T> for Arc<(Mutex<Option<T>>, Condvar)> {
+    /// Takes the shared state, waiting for the stage of another thread to put it back first.
+    /// Never returns `None`, but blocks forever if the shared state never gets set.
+    fn take_shared(&self) -> Option<T> {
+        let (lock, cvar) = &**self;
+        let mut shared_state = cvar
+            .wait_while(lock.lock().unwrap(), |shared_state| shared_state.is_none())
+            .unwrap();
+        shared_state.take()
+    }
+
+    #[inline]
+    fn try_take_shared(&self) -> Option<T> {
+        self.0.lock().unwrap().take()
+    }
+
+    #[inline]
+    fn set_shared(&self, shared_state: T) {
+        let (lock, cvar) = &**self;
+        lock.lock().unwrap().replace(shared_state);
+        cvar.notify_one();
+    }
+}
+
+/// The default [`SharedStateCell`] of the [`PushStage`]s, for stages on a single thread
+pub type RcSharedState<CS, EM, OT, Z> = Rc<RefCell<Option<PushStageSharedState<CS, EM, OT, Z>>>>;
+
+/// A [`SharedStateCell`] to run [`PushStage`]s on multiple threads
+#[cfg(feature = "std")]
+pub type ArcMutexSharedState<CS, EM, OT, Z> =
+    Arc<(Mutex<Option<PushStageSharedState<CS, EM, OT, Z>>>, Condvar)>;
+
+/// A [`PushStageHelper`] sharing the state with the stages of other threads
+#[cfg(feature = "std")]
+pub type ArcMutexPushStageHelper<CS, EM, OT, Z> =
+    PushStageHelper<CS, EM, OT, Z, ArcMutexSharedState<CS, EM, OT, Z>>;
+
 // The shared state for all [`PushStage`]s
-/// Should be stored inside a `[Rc<RefCell<_>>`]
+/// Should be stored inside a [`SharedStateCell`], such as an [`RcSharedState`]
 #[derive(Clone, Debug)]
 pub struct PushStageSharedState<CS, EM, OT, Z>
 where
@@ -51,6 +154,12 @@ where
     pub event_mgr: EM,
     /// The [`crate::observers::ObserversTuple`]
     pub observers: OT,
+    /// The discoveries queued by `try_fire_discovery`, oldest first
+    pending_discoveries: VecDeque<Event<<CS::State as UsesInput>::Input>>,
+    /// The maximum number of queued discoveries
+    discovery_capacity: usize,
+    /// The number of discoveries dropped, as the queue was full
+    dropped_discoveries: usize,
     phantom: PhantomData<(CS, Z)>,
 }
 
@@ -72,14 +181,291 @@ where
             fuzzer,
             event_mgr,
             observers,
+            pending_discoveries: VecDeque::new(),
+            discovery_capacity: DEFAULT_DISCOVERY_QUEUE_CAPACITY,
+            dropped_discoveries: 0,
             phantom: PhantomData,
         }
     }
+
+    /// Sets the number of discoveries `try_fire_discovery` queues before dropping new ones,
+    /// [`DEFAULT_DISCOVERY_QUEUE_CAPACITY`] by default
+    #[must_use]
+    pub fn with_discovery_capacity(mut self, capacity: usize) -> Self {
+        self.discovery_capacity = capacity;
+        self
+    }
+
+    /// The number of discoveries queued by `try_fire_discovery`, not fired yet
+    #[inline]
+    #[must_use]
+    pub fn pending_discoveries(&self) -> usize {
+        self.pending_discoveries.len()
+    }
+
+    /// The number of discoveries `try_fire_discovery` dropped, as the queue was full
+    #[inline]
+    #[must_use]
+    pub fn dropped_discoveries(&self) -> usize {
+        self.dropped_discoveries
+    }
+
+    /// Fires up to `max` of the discoveries queued by [`Self::try_fire_discovery`], oldest first.
+    /// Returns the number of discoveries fired.
+    /// If firing fails, the failed discovery stays queued.
+    pub fn flush_discoveries(&mut self, max: usize) -> Result<usize, Error> {
+        let mut fired = 0;
+        while fired < max {
+            let Some(event) = self.pending_discoveries.pop_front() else {
+                break;
+            };
+            if let Err(err) = self.event_mgr.fire(&mut self.state, event.clone()) {
+                self.pending_discoveries.push_front(event);
+                return Err(err);
+            }
+            fired += 1;
+        }
+        Ok(fired)
+    }
+
+    /// The random number generator of the shared state
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use libafl::{
+    /// #     bolts::rands::{Rand, StdRand},
+    /// #     corpus::{Corpus, InMemoryCorpus, Testcase},
+    /// #     events::NopEventManager,
+    /// #     feedbacks::ConstFeedback,
+    /// #     inputs::BytesInput,
+    /// #     schedulers::QueueScheduler,
+    /// #     stages::push::PushStageSharedState,
+    /// #     state::{HasCorpus, StdState},
+    /// #     StdFuzzer,
+    /// # };
+    /// # let mut feedback = ConstFeedback::new(false);
+    /// # let mut objective = ConstFeedback::new(false);
+    /// # let mut state = StdState::new(
+    /// #     StdRand::with_seed(0),
+    /// #     InMemoryCorpus::<BytesInput>::new(),
+    /// #     InMemoryCorpus::new(),
+    /// #     &mut feedback,
+    /// #     &mut objective,
+    /// # )
+    /// # .unwrap();
+    /// # state
+    /// #     .corpus_mut()
+    /// #     .add(Testcase::new(BytesInput::new(b"aaaa".to_vec())))
+    /// #     .unwrap();
+    /// # let fuzzer = StdFuzzer::<_, _, _, ()>::new(QueueScheduler::new(), feedback, objective);
+    /// let mut shared_state = PushStageSharedState::new(fuzzer, state, (), NopEventManager::new());
+    /// assert!(shared_state.rand_mut().below(16) < 16);
+    /// ```
+    #[inline]
+    pub fn rand_mut(&mut self) -> &mut <CS::State as HasRand>::Rand {
+        self.state.rand_mut()
+    }
+
+    /// Reseeds the [`Rand`] of the state.
+    /// Call it before the first `init` of the push stages to get the same sequence of inputs for the same seed,
+    /// for example in tests.
+    #[inline]
+    pub fn reseed(&mut self, seed: u64) {
+        self.state.rand_mut().set_seed(seed);
+    }
+}
+
+impl<CS, EM, OT, Z> PushStageSharedState<CS, EM, OT, Z>
+where
+    CS: Scheduler,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
+    OT: ObserversTuple<CS::State>,
+    CS::State: HasClientPerfMonitor + HasCorpus + HasRand,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+{
+    /// The number of entries in the corpus of the shared state
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use libafl::{
+    /// #     bolts::rands::StdRand,
+    /// #     corpus::{Corpus, InMemoryCorpus, Testcase},
+    /// #     events::NopEventManager,
+    /// #     feedbacks::ConstFeedback,
+    /// #     inputs::BytesInput,
+    /// #     schedulers::QueueScheduler,
+    /// #     stages::push::PushStageSharedState,
+    /// #     state::{HasCorpus, StdState},
+    /// #     StdFuzzer,
+    /// # };
+    /// # let mut feedback = ConstFeedback::new(false);
+    /// # let mut objective = ConstFeedback::new(false);
+    /// # let mut state = StdState::new(
+    /// #     StdRand::with_seed(0),
+    /// #     InMemoryCorpus::<BytesInput>::new(),
+    /// #     InMemoryCorpus::new(),
+    /// #     &mut feedback,
+    /// #     &mut objective,
+    /// # )
+    /// # .unwrap();
+    /// # state
+    /// #     .corpus_mut()
+    /// #     .add(Testcase::new(BytesInput::new(b"aaaa".to_vec())))
+    /// #     .unwrap();
+    /// # let fuzzer = StdFuzzer::<_, _, _, ()>::new(QueueScheduler::new(), feedback, objective);
+    /// let shared_state = PushStageSharedState::new(fuzzer, state, (), NopEventManager::new());
+    /// assert_eq!(shared_state.corpus_count(), 1);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn corpus_count(&self) -> usize {
+        self.state.corpus().count()
+    }
+
+    /// Fires an [`Event::NewTestcase`] for the given `input` through the event manager,
+    /// so that push stages can notify the other nodes of testcases they discovered themselves.
+    /// Like the fuzzer does, the observers get serialized into the event, unless the event manager
+    /// is configured as [`EventConfig::AlwaysUnique`]. The testcase is reported with an [`ExitKind::Ok`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use libafl::{
+    /// #     bolts::rands::StdRand,
+    /// #     corpus::InMemoryCorpus,
+    /// #     events::NopEventManager,
+    /// #     feedbacks::ConstFeedback,
+    /// #     inputs::BytesInput,
+    /// #     schedulers::QueueScheduler,
+    /// #     stages::push::PushStageSharedState,
+    /// #     state::StdState,
+    /// #     StdFuzzer,
+    /// # };
+    /// # let mut feedback = ConstFeedback::new(false);
+    /// # let mut objective = ConstFeedback::new(false);
+    /// # let state = StdState::new(
+    /// #     StdRand::with_seed(0),
+    /// #     InMemoryCorpus::<BytesInput>::new(),
+    /// #     InMemoryCorpus::new(),
+    /// #     &mut feedback,
+    /// #     &mut objective,
+    /// # )
+    /// # .unwrap();
+    /// # let fuzzer = StdFuzzer::<_, _, _, ()>::new(QueueScheduler::new(), feedback, objective);
+    /// let mut shared_state = PushStageSharedState::new(fuzzer, state, (), NopEventManager::new());
+    /// shared_state
+    ///     .fire_discovery(&BytesInput::new(b"new".to_vec()))
+    ///     .unwrap();
+    /// ```
+    pub fn fire_discovery(&mut self, input: &<CS::State as UsesInput>::Input) -> Result<(), Error>
+    where
+        CS::State: HasExecutions,
+        OT: Serialize,
+    {
+        let event = self.discovery_event(input)?;
+        self.event_mgr.fire(&mut self.state, event)
+    }
+
+    /// Queues an [`Event::NewTestcase`] for the given `input`, to be fired by [`Self::flush_discoveries`],
+    /// instead of blocking on a slow event manager like [`Self::fire_discovery`].
+    /// The observers and counters get serialized right away, so the event reports the run that found `input`.
+    /// `next_std` flushes the queue at the end of each cycle.
+    /// If the queue is full, the discovery gets dropped and counted, also in the
+    /// [`crate::monitors::ClientPerfMonitor`] with the `introspection` feature.
+    /// Returns `false` if the discovery got dropped.
+    pub fn try_fire_discovery(
+        &mut self,
+        input: &<CS::State as UsesInput>::Input,
+    ) -> Result<bool, Error>
+    where
+        CS::State: HasExecutions,
+        OT: Serialize,
+    {
+        if self.pending_discoveries.len() >= self.discovery_capacity {
+            self.dropped_discoveries += 1;
+            #[cfg(feature = "introspection")]
+            self.state
+                .introspection_monitor_mut()
+                .update_dropped_events(1);
+            return Ok(false);
+        }
+        let event = self.discovery_event(input)?;
+        self.pending_discoveries.push_back(event);
+        Ok(true)
+    }
+
+    /// The [`Event::NewTestcase`] reporting `input` with the current observers and counters
+    fn discovery_event(
+        &mut self,
+        input: &<CS::State as UsesInput>::Input,
+    ) -> Result<Event<<CS::State as UsesInput>::Input>, Error>
+    where
+        CS::State: HasExecutions,
+        OT: Serialize,
+    {
+        let observers_buf = if self.event_mgr.configuration() == EventConfig::AlwaysUnique {
+            None
+        } else {
+            Some(self.event_mgr.serialize_observers::<OT>(&self.observers)?)
+        };
+        Ok(Event::NewTestcase {
+            input: input.clone(),
+            observers_buf,
+            exit_kind: ExitKind::Ok,
+            corpus_size: self.state.corpus().count(),
+            client_config: self.event_mgr.configuration(),
+            time: current_time(),
+            executions: *self.state.executions(),
+        })
+    }
+}
+
+/// A summary of a full [`PushStage`] cycle, from `init` to `deinit`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CycleMetrics {
+    /// The number of inputs the stage returned to be executed
+    pub inputs_yielded: usize,
+    /// The number of executions the stage found interesting
+    pub interesting: usize,
+    /// The number of cycles aborted by an error since the previous full cycle.
+    /// The inputs and finds of aborted cycles are not counted.
+    pub errors: usize,
+    /// The wall time since the start of the cycle
+    pub wall_time: Duration,
+}
+
+/// A monitor update sent to the monitor sink of a [`PushStageHelper`],
+/// instead of reporting the progress through the event manager
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MonitorUpdate {
+    /// The id of the event manager of the client reporting
+    pub client_id: EventManagerId,
+    /// The number of executions so far
+    pub executions: usize,
+    /// The time of the report
+    pub time: Duration,
+}
+
+/// A snapshot of the progress of a [`PushStage`], taken by [`PushStage::snapshot`].
+/// It can be stored alongside the fuzzer state, to [`PushStage::restore`] the stage mid-cycle after a restart.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushStageProgress {
+    /// If the stage was in the middle of a cycle
+    pub initialized: bool,
+    /// The corpus index the stage was working on
+    pub corpus_idx: Option<CorpusId>,
+    /// The internal counters of the stage, specific to each stage
+    pub counters: Vec<usize>,
 }
 
 /// Helper class for the [`PushStage`] trait, taking care of borrowing the shared state
-#[derive(Clone, Debug)]
-pub struct PushStageHelper<CS, EM, OT, Z>
+/// from its [`SharedStateCell`] `SH`
+#[derive(Debug)]
+pub struct PushStageHelper<CS, EM, OT, Z, SH = RcSharedState<CS, EM, OT, Z>>
 where
     CS: Scheduler,
     EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
@@ -88,6 +474,7 @@ where
     Z: ExecutionProcessor<OT, State = CS::State>
         + EvaluatorObservers<OT>
         + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
 {
     /// If this stage has already been initalized.
     /// This gets reset to `false` after one iteration of the stage is done.
@@ -95,23 +482,71 @@ where
     /// The last time the monitor was updated
     pub last_monitor_time: Duration,
     /// The shared state, keeping track of the corpus and the fuzzer
-    #[allow(clippy::type_complexity)]
-    pub shared_state: Rc<RefCell<Option<PushStageSharedState<CS, EM, OT, Z>>>>,
+    pub shared_state: SH,
     /// If the last iteration failed
     pub errored: bool,
+    /// If `next_std` times the `init`, `pre_exec`, `post_exec`, and `deinit` of the stage,
+    /// reporting them as the `PushStage*` [`crate::monitors::PerfFeature`]s to the client perf monitor.
+    /// Only has an effect with the `introspection` feature.
+    pub perf_enabled: bool,
 
     /// The corpus index we're currently working on
     pub current_corpus_idx: Option<CorpusId>,
 
     /// The input we just ran
     pub current_input: Option<<CS::State as UsesInput>::Input>, // Todo: Get rid of copy
+    /// The last input `next_std` returned, kept after its `post_exec`
+    last_input: Option<<CS::State as UsesInput>::Input>,
 
     #[allow(clippy::type_complexity)]
     phantom: PhantomData<(CS, EM, OT, Z)>,
     exit_kind: Rc<Cell<Option<ExitKind>>>,
+    /// Set to stop the stage before its next `pre_exec`, shared like the `exit_kind`
+    cancel: Rc<Cell<bool>>,
+
+    /// The metrics of the ongoing cycle
+    current_cycle: CycleMetrics,
+    /// The time at which the ongoing cycle started
+    cycle_start: Option<Duration>,
+    /// The metrics of the last full cycle
+    last_cycle: Option<CycleMetrics>,
+    /// The wall-clock budget of a single iteration of the stage, from `init` to `deinit`
+    iteration_deadline: Option<Duration>,
+    /// The minimum time between two monitor updates
+    monitor_timeout: Duration,
+    /// The result of the last `pre_exec`, if it was only peeked at so far. The last step was finished by the peek.
+    #[allow(clippy::option_option)]
+    peeked: Option<Option<Result<<CS::State as UsesInput>::Input, Error>>>,
+    /// The inputs of the last burst returned by `pre_exec_many` that `next_std` did not return yet, last first
+    pending_inputs: SmallVec<[<CS::State as UsesInput>::Input; 4]>,
+    /// Where monitor updates get sent to, instead of reporting them through the event manager
+    #[cfg(feature = "std")]
+    monitor_sink: Option<Sender<MonitorUpdate>>,
 }
 
-impl<CS, EM, OT, Z> PushStageHelper<CS, EM, OT, Z>
+/// Clones an [`Error`], keeping its kind and message, but not its backtrace
+fn clone_error(err: &Error) -> Error {
+    match err {
+        Error::Serialize(s, _) => Error::serialize(s.clone()),
+        #[cfg(feature = "std")]
+        Error::File(err, _) => Error::file(std::io::Error::new(err.kind(), format!("{err}"))),
+        Error::EmptyOptional(s, _) => Error::empty_optional(s.clone()),
+        Error::KeyNotFound(s, _) => Error::key_not_found(s.clone()),
+        Error::Empty(s, _) => Error::empty(s.clone()),
+        Error::IteratorEnd(s, _) => Error::iterator_end(s.clone()),
+        Error::NotImplemented(s, _) => Error::not_implemented(s.clone()),
+        Error::IllegalState(s, _) => Error::illegal_state(s.clone()),
+        Error::IllegalArgument(s, _) => Error::illegal_argument(s.clone()),
+        Error::Unsupported(s, _) => Error::unsupported(s.clone()),
+        Error::Timeout(s, _) => Error::timeout(s.clone()),
+        Error::ShuttingDown => Error::shutting_down(),
+        Error::Unknown(s, _) => Error::unknown(s.clone()),
+        #[allow(unreachable_patterns)]
+        _ => Error::unknown(format!("{err}")),
+    }
+}
+
+impl<CS, EM, OT, Z, SH> Clone for PushStageHelper<CS, EM, OT, Z, SH>
 where
     CS: Scheduler,
     EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
@@ -120,38 +555,95 @@ where
     Z: ExecutionProcessor<OT, State = CS::State>
         + EvaluatorObservers<OT>
         + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
+{
+    /// Clones this helper. A peeked error is carried over with the same kind and message.
+    fn clone(&self) -> Self {
+        Self {
+            initialized: self.initialized,
+            last_monitor_time: self.last_monitor_time,
+            shared_state: self.shared_state.clone(),
+            errored: self.errored,
+            perf_enabled: self.perf_enabled,
+            current_corpus_idx: self.current_corpus_idx,
+            current_input: self.current_input.clone(),
+            last_input: self.last_input.clone(),
+            phantom: PhantomData,
+            exit_kind: self.exit_kind.clone(),
+            cancel: self.cancel.clone(),
+            current_cycle: self.current_cycle,
+            cycle_start: self.cycle_start,
+            last_cycle: self.last_cycle,
+            iteration_deadline: self.iteration_deadline,
+            monitor_timeout: self.monitor_timeout,
+            peeked: match &self.peeked {
+                Some(Some(Ok(input))) => Some(Some(Ok(input.clone()))),
+                Some(Some(Err(err))) => Some(Some(Err(clone_error(err)))),
+                Some(None) => Some(None),
+                None => None,
+            },
+            pending_inputs: self.pending_inputs.clone(),
+            #[cfg(feature = "std")]
+            monitor_sink: self.monitor_sink.clone(),
+        }
+    }
+}
+
+impl<CS, EM, OT, Z, SH> PushStageHelper<CS, EM, OT, Z, SH>
+where
+    CS: Scheduler,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
+    OT: ObserversTuple<CS::State>,
+    CS::State: HasClientPerfMonitor + HasRand,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
 {
     /// Create a new [`PushStageHelper`]
     #[must_use]
-    #[allow(clippy::type_complexity)]
-    pub fn new(
-        shared_state: Rc<RefCell<Option<PushStageSharedState<CS, EM, OT, Z>>>>,
-        exit_kind_ref: Rc<Cell<Option<ExitKind>>>,
-    ) -> Self {
+    pub fn new(shared_state: SH, exit_kind_ref: Rc<Cell<Option<ExitKind>>>) -> Self {
         Self {
             shared_state,
             initialized: false,
             phantom: PhantomData,
             last_monitor_time: current_time(),
             exit_kind: exit_kind_ref,
+            cancel: Rc::new(Cell::new(false)),
             errored: false,
+            perf_enabled: false,
             current_input: None,
+            last_input: None,
             current_corpus_idx: None,
+            current_cycle: CycleMetrics::default(),
+            cycle_start: None,
+            last_cycle: None,
+            iteration_deadline: None,
+            monitor_timeout: STATS_TIMEOUT_DEFAULT,
+            peeked: None,
+            pending_inputs: SmallVec::new(),
+            #[cfg(feature = "std")]
+            monitor_sink: None,
         }
     }
 
-    /// Sets the shared state for this helper (and all other helpers owning the same [`RefCell`])
+    /// Sets the shared state for this helper (and all other helpers owning the same [`SharedStateCell`])
     #[inline]
     pub fn set_shared_state(&mut self, shared_state: PushStageSharedState<CS, EM, OT, Z>) {
-        (*self.shared_state.borrow_mut()).replace(shared_state);
+        self.shared_state.set_shared(shared_state);
     }
 
     /// Takes the shared state from this helper, replacing it with `None`
     #[inline]
-    #[allow(clippy::type_complexity)]
     pub fn take_shared_state(&mut self) -> Option<PushStageSharedState<CS, EM, OT, Z>> {
-        let shared_state_ref = &mut (*self.shared_state).borrow_mut();
-        shared_state_ref.take()
+        self.shared_state.take_shared()
+    }
+
+    /// Takes the shared state from this helper without waiting for it.
+    /// Returns `None` if the stage of another thread holds it.
+    #[inline]
+    pub fn try_take_shared_state(&mut self) -> Option<PushStageSharedState<CS, EM, OT, Z>> {
+        self.shared_state.try_take_shared()
     }
 
     /// Returns the exit kind of the last run
@@ -167,6 +659,180 @@ where
         self.exit_kind.set(None);
     }
 
+    /// The last input returned by `next_std`, to be correlated with its [`ExitKind`] once it ran,
+    /// e.g. to reproduce a crash. It stays available after the `post_exec` and the end of the cycle.
+    #[inline]
+    #[must_use]
+    pub fn last_input(&self) -> Option<&<CS::State as UsesInput>::Input> {
+        self.last_input.as_ref()
+    }
+
+    /// The cancellation token of this helper.
+    /// Setting it to `true` makes `next_std` end the cycle, running `deinit`, instead of calling `pre_exec` again.
+    /// The stage keeps returning `None` until the token gets reset to `false`.
+    #[inline]
+    #[must_use]
+    pub fn cancel_token(&self) -> Rc<Cell<bool>> {
+        self.cancel.clone()
+    }
+
+    /// Sets the cancellation token, to share one token between several stages
+    #[inline]
+    pub fn set_cancel_token(&mut self, cancel: Rc<Cell<bool>>) {
+        self.cancel = cancel;
+    }
+
+    /// Requests the stage (and all stages sharing the cancellation token) to stop before the next `pre_exec`
+    #[inline]
+    pub fn request_cancel(&self) {
+        self.cancel.set(true);
+    }
+
+    /// If a cancellation got requested
+    #[inline]
+    #[must_use]
+    pub fn cancel_requested(&self) -> bool {
+        self.cancel.get()
+    }
+
+    /// The metrics of the last full cycle of the stage, if it finished a cycle yet
+    #[inline]
+    #[must_use]
+    pub fn last_cycle_metrics(&self) -> Option<CycleMetrics> {
+        self.last_cycle
+    }
+
+    /// Records that an execution of the ongoing cycle was interesting.
+    /// Called by the stages, as only they process the executions.
+    #[inline]
+    pub fn record_interesting(&mut self) {
+        self.current_cycle.interesting += 1;
+    }
+
+    /// Sets the wall-clock budget of a single iteration of the stage, from `init` to `deinit`.
+    /// Once exceeded, `next_std` aborts the iteration with an [`Error::Timeout`].
+    #[inline]
+    pub fn set_iteration_deadline(&mut self, deadline: Option<Duration>) {
+        self.iteration_deadline = deadline;
+    }
+
+    /// The wall-clock budget of a single iteration of the stage, if any
+    #[inline]
+    #[must_use]
+    pub fn iteration_deadline(&self) -> Option<Duration> {
+        self.iteration_deadline
+    }
+
+    /// Sets the minimum time between two monitor updates, 15 seconds by default
+    #[inline]
+    pub fn set_monitor_timeout(&mut self, monitor_timeout: Duration) {
+        self.monitor_timeout = monitor_timeout;
+    }
+
+    /// The minimum time between two monitor updates
+    #[inline]
+    #[must_use]
+    pub fn monitor_timeout(&self) -> Duration {
+        self.monitor_timeout
+    }
+
+    /// If the ongoing iteration ran for longer than its deadline
+    #[inline]
+    #[must_use]
+    pub fn deadline_exceeded(&self) -> bool {
+        match (self.iteration_deadline, self.cycle_start) {
+            (Some(deadline), Some(cycle_start)) => {
+                current_time().saturating_sub(cycle_start) > deadline
+            }
+            _ => false,
+        }
+    }
+
+    /// Sends the monitor updates to `sink` instead of reporting them through the event manager,
+    /// so that `next_std` does not block on a slow monitor. The receiving end forwards them to the monitor.
+    /// If the receiver hangs up, the updates get reported through the event manager again.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn set_monitor_sink(&mut self, sink: Sender<MonitorUpdate>) {
+        self.monitor_sink = Some(sink);
+    }
+
+    /// Sends a [`MonitorUpdate`] to the monitor sink, if the monitor timeout passed.
+    /// Returns the new last monitor time, or `None` if there is no sink to report to.
+    #[cfg(feature = "std")]
+    fn report_to_sink(&mut self, executions: usize, client_id: EventManagerId) -> Option<Duration> {
+        let sink = self.monitor_sink.as_ref()?;
+        let cur = current_time();
+        if cur.checked_sub(self.last_monitor_time).unwrap_or_default() <= self.monitor_timeout {
+            return Some(self.last_monitor_time);
+        }
+        let update = MonitorUpdate {
+            client_id,
+            executions,
+            time: cur,
+        };
+        if sink.send(update).is_err() {
+            self.monitor_sink = None;
+            return None;
+        }
+        Some(cur)
+    }
+
+    /// The progress of the stage, without any stage-specific counters
+    #[must_use]
+    pub fn progress(&self) -> PushStageProgress {
+        PushStageProgress {
+            initialized: self.initialized,
+            corpus_idx: self.current_corpus_idx,
+            counters: Vec::new(),
+        }
+    }
+
+    /// Restores the progress of the stage, ignoring any stage-specific counters.
+    /// The input that was in flight when the progress was taken does not get `post_exec`ed.
+    pub fn restore_progress(&mut self, progress: &PushStageProgress) {
+        self.initialized = progress.initialized;
+        self.current_corpus_idx = progress.corpus_idx;
+        self.current_input = None;
+        self.peeked = None;
+        self.pending_inputs.clear();
+        self.reset_exit_kind();
+    }
+
+    /// Starts a timing span for the client perf monitor, if `perf_enabled`
+    #[cfg(feature = "introspection")]
+    #[inline]
+    fn start_perf_span(&self) -> Option<u64> {
+        self.perf_enabled.then(crate::bolts::cpu::read_time_counter)
+    }
+
+    /// Ends a timing span started by `start_perf_span`, adding its time to the given [`PerfFeature`].
+    /// Does not use the timer of the [`crate::monitors::ClientPerfMonitor`], as the stages may use it themselves.
+    #[cfg(feature = "introspection")]
+    #[inline]
+    fn end_perf_span(state: &mut CS::State, span_start: Option<u64>, feature: PerfFeature) {
+        if let Some(span_start) = span_start {
+            let elapsed = crate::bolts::cpu::read_time_counter().saturating_sub(span_start);
+            state
+                .introspection_monitor_mut()
+                .update_feature(feature, elapsed);
+        }
+    }
+
+    /// Starts timing a new cycle
+    fn start_cycle(&mut self) {
+        self.cycle_start = Some(current_time());
+    }
+
+    /// Stores the metrics of the cycle that just finished, and resets them for the next one
+    fn end_cycle(&mut self) {
+        let mut metrics = core::mem::take(&mut self.current_cycle);
+        if let Some(cycle_start) = self.cycle_start.take() {
+            metrics.wall_time = current_time().saturating_sub(cycle_start);
+        }
+        self.last_cycle = Some(metrics);
+    }
+
     /// Resets this state after a full stage iter.
     fn end_of_iter(&mut self, shared_state: PushStageSharedState<CS, EM, OT, Z>, errored: bool) {
         self.set_shared_state(shared_state);
@@ -174,6 +840,13 @@ where
         self.current_corpus_idx = None;
         if errored {
             self.initialized = false;
+            self.current_cycle = CycleMetrics {
+                errors: self.current_cycle.errors + 1,
+                ..CycleMetrics::default()
+            };
+            drop(self.current_input.take());
+            self.pending_inputs.clear();
+            self.peeked = None;
         }
     }
 }
@@ -181,7 +854,8 @@ where
 /// A push stage is a generator that returns a single testcase for each call.
 /// It's an iterator so we can chain it.
 /// After it has finished once, we will call it agan for the next fuzzer round.
-pub trait PushStage<CS, EM, OT, Z>: Iterator
+/// The [`SharedStateCell`] `SH` the stage borrows the shared state from defaults to an [`RcSharedState`].
+pub trait PushStage<CS, EM, OT, Z, SH = RcSharedState<CS, EM, OT, Z>>: Iterator
 where
     CS: Scheduler,
     CS::State: HasClientPerfMonitor + HasRand + HasExecutions + HasMetadata,
@@ -190,11 +864,12 @@ where
     Z: ExecutionProcessor<OT, State = CS::State>
         + EvaluatorObservers<OT>
         + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
 {
     /// Gets the [`PushStageHelper`]
-    fn push_stage_helper(&self) -> &PushStageHelper<CS, EM, OT, Z>;
+    fn push_stage_helper(&self) -> &PushStageHelper<CS, EM, OT, Z, SH>;
     /// Gets the [`PushStageHelper`] (mutable)
-    fn push_stage_helper_mut(&mut self) -> &mut PushStageHelper<CS, EM, OT, Z>;
+    fn push_stage_helper_mut(&mut self) -> &mut PushStageHelper<CS, EM, OT, Z, SH>;
 
     /// Set the current corpus index this stage works on
     fn set_current_corpus_idx(&mut self, corpus_idx: CorpusId) {
@@ -226,6 +901,24 @@ where
         _observers: &mut OT,
     ) -> Option<Result<<CS::State as UsesInput>::Input, Error>>;
 
+    /// Called by `next_std`, [`PushStage::pre_exec_batch`] and the [`crate::stages::PushStageAdapter`] instead of
+    /// `pre_exec`, returning a burst of test cases to be executed one after another.
+    /// `next_std` returns them from successive calls, each followed by its `post_exec`, before calling this again.
+    /// Stages generating several related inputs at once can override this instead of keeping track of them.
+    /// An empty burst ends the stage, like `None`. Defaults to a burst of the single input of `pre_exec`.
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    fn pre_exec_many(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+    ) -> Option<Result<SmallVec<[<CS::State as UsesInput>::Input; 4]>, Error>> {
+        self.pre_exec(fuzzer, state, event_mgr, observers)
+            .map(|res| res.map(|input| smallvec![input]))
+    }
+
     /// Called after the execution of a testcase finished.
     #[inline]
     fn post_exec(
@@ -240,6 +933,71 @@ where
         Ok(())
     }
 
+    /// How many inputs `pre_exec` may return in a row, without `post_exec` in between, before this cycle is done.
+    /// Defaults to `1`, as a stage advancing in `post_exec` would return inputs past the end of its cycle.
+    #[inline]
+    fn batch_limit(&self) -> usize {
+        1
+    }
+
+    /// Returns up to `n` test cases to be executed as a batch, for example pipelined through a forkserver.
+    /// Defaults to collecting bursts of `pre_exec_many` until the batch holds [`PushStage::batch_limit`] inputs,
+    /// stopping early once it returns `None`, an empty burst, or an error.
+    /// A burst is never split, so the last one may take the batch past `n`.
+    ///
+    /// Unlike `next_std`, this does no bookkeeping: the driver owns the shared state, calls `init` and `deinit`,
+    /// and hands the results to [`PushStage::post_exec_batch`].
+    fn pre_exec_batch(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+        n: usize,
+    ) -> Vec<Result<<CS::State as UsesInput>::Input, Error>> {
+        let n = n.min(self.batch_limit());
+        let mut batch = Vec::with_capacity(n);
+        while batch.len() < n {
+            match self.pre_exec_many(fuzzer, state, event_mgr, observers) {
+                Some(Ok(burst)) if burst.is_empty() => break,
+                Some(Ok(burst)) => batch.extend(burst.into_iter().map(Ok)),
+                Some(Err(err)) => {
+                    batch.push(Err(err));
+                    break;
+                }
+                None => break,
+            }
+        }
+        batch
+    }
+
+    /// Called after the execution of a batch returned by [`PushStage::pre_exec_batch`] finished,
+    /// with the observers and the [`ExitKind`] of each input, in order.
+    /// As each run overwrites the observers of the previous one, the driver has to snapshot them per input.
+    /// Defaults to calling `post_exec` for each input, stopping at the first error.
+    fn post_exec_batch(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut [OT],
+        inputs: Vec<<CS::State as UsesInput>::Input>,
+        exit_kinds: Vec<ExitKind>,
+    ) -> Result<(), Error> {
+        if inputs.len() != exit_kinds.len() || inputs.len() != observers.len() {
+            return Err(Error::illegal_argument(format!(
+                "Got {} exit kinds and {} observers for a batch of {} inputs",
+                exit_kinds.len(),
+                observers.len(),
+                inputs.len()
+            )));
+        }
+        for ((input, exit_kind), observers) in inputs.into_iter().zip(exit_kinds).zip(observers) {
+            self.post_exec(fuzzer, state, event_mgr, observers, input, exit_kind)?;
+        }
+        Ok(())
+    }
+
     /// Called after the stage finished (`pre_exec` returned `None`)
     #[inline]
     fn deinit(
@@ -252,71 +1010,292 @@ where
         Ok(())
     }
 
-    /// This is the default implementation for `next` for this stage
-    fn next_std(&mut self) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
-        let mut shared_state = {
-            let shared_state_ref = &mut (*self.push_stage_helper_mut().shared_state).borrow_mut();
-            shared_state_ref.take().unwrap()
-        };
+    /// Called by `next_std` after any step of this stage returned an error.
+    /// The shared state has already been stashed back at this point.
+    /// Stages should clear their internal iteration state here, so the next cycle starts fresh.
+    #[inline]
+    fn on_error_reset(&mut self) {}
 
-        let step_success = if self.push_stage_helper().initialized {
-            // We already ran once
+    /// A snapshot of the progress of this stage, to [`PushStage::restore`] it after a restart.
+    /// Take it between calls to `next`; stages with internal counters override it to include them.
+    #[inline]
+    fn snapshot(&self) -> PushStageProgress {
+        self.push_stage_helper().progress()
+    }
 
-            let last_input = self.push_stage_helper_mut().current_input.take().unwrap();
+    /// Restores the progress of this stage from a [`PushStage::snapshot`].
+    /// The input that was returned last before the snapshot does not get `post_exec`ed.
+    #[inline]
+    fn restore(&mut self, progress: &PushStageProgress) -> Result<(), Error> {
+        self.push_stage_helper_mut().restore_progress(progress);
+        Ok(())
+    }
 
-            self.post_exec(
-                &mut shared_state.fuzzer,
-                &mut shared_state.state,
-                &mut shared_state.event_mgr,
-                &mut shared_state.observers,
-                last_input,
-                self.push_stage_helper().exit_kind().unwrap(),
-            )
+    /// The metrics of the last full cycle of this stage, populated at `deinit`
+    #[inline]
+    fn last_cycle_metrics(&self) -> Option<CycleMetrics> {
+        self.push_stage_helper().last_cycle_metrics()
+    }
+
+    /// Finishes the last step of this stage: runs the `post_exec` of the last input with its exit kind,
+    /// or `init` at the start of a cycle. Called by `next_std` and `peek_next`, stages should not override it.
+    fn finish_step(
+        &mut self,
+        shared_state: &mut PushStageSharedState<CS, EM, OT, Z>,
+    ) -> Result<(), Error> {
+        let res = if self.push_stage_helper().initialized {
+            // We already ran once
+
+            if let Some(last_input) = self.push_stage_helper_mut().current_input.take() {
+                #[cfg(feature = "introspection")]
+                let span_start = self.push_stage_helper().start_perf_span();
+                let res = self.post_exec(
+                    &mut shared_state.fuzzer,
+                    &mut shared_state.state,
+                    &mut shared_state.event_mgr,
+                    &mut shared_state.observers,
+                    last_input,
+                    self.push_stage_helper().exit_kind().unwrap(),
+                );
+                #[cfg(feature = "introspection")]
+                PushStageHelper::<CS, EM, OT, Z, SH>::end_perf_span(
+                    &mut shared_state.state,
+                    span_start,
+                    PerfFeature::PushStagePostExec,
+                );
+                res
+            } else {
+                // Restored mid-cycle, the last input never ran in this process
+                Ok(())
+            }
         } else {
-            self.init(
+            self.push_stage_helper_mut().start_cycle();
+            #[cfg(feature = "introspection")]
+            let span_start = self.push_stage_helper().start_perf_span();
+            let res = self.init(
                 &mut shared_state.fuzzer,
                 &mut shared_state.state,
                 &mut shared_state.event_mgr,
                 &mut shared_state.observers,
-            )
+            );
+            #[cfg(feature = "introspection")]
+            PushStageHelper::<CS, EM, OT, Z, SH>::end_perf_span(
+                &mut shared_state.state,
+                span_start,
+                PerfFeature::PushStageInit,
+            );
+            res
+        };
+        if res.is_ok() {
+            self.push_stage_helper_mut().initialized = true;
+        }
+        res
+    }
+
+    /// Returns what the next call to `next` will return, without consuming it.
+    /// Like `next`, it first runs the `post_exec` of the last input (or `init`, at the start of a cycle),
+    /// so the exit kind of the last input has to be set already. Then it runs `pre_exec` once:
+    /// the following `next` returns the peeked result instead, so peeking does not change the inputs of the stage.
+    fn peek_next<'a>(&'a mut self) -> Option<&'a Result<<CS::State as UsesInput>::Input, Error>>
+    where
+        CS: 'a,
+        EM: 'a,
+        OT: 'a,
+        Z: 'a,
+        SH: 'a,
+    {
+        if self.push_stage_helper().peeked.is_none() {
+            let mut shared_state = self.push_stage_helper_mut().take_shared_state().unwrap();
+            // An error gets cleaned up by the following `next`, which returns it
+            let peeked = match self.finish_step(&mut shared_state) {
+                Ok(()) => {
+                    if let Some(input) = self.push_stage_helper_mut().pending_inputs.pop() {
+                        Some(Ok(input))
+                    } else {
+                        match self.pre_exec_many(
+                            &mut shared_state.fuzzer,
+                            &mut shared_state.state,
+                            &mut shared_state.event_mgr,
+                            &mut shared_state.observers,
+                        ) {
+                            Some(Ok(mut inputs)) => {
+                                inputs.reverse();
+                                let first = inputs.pop();
+                                self.push_stage_helper_mut().pending_inputs = inputs;
+                                first.map(Ok)
+                            }
+                            Some(Err(err)) => Some(Err(err)),
+                            None => None,
+                        }
+                    }
+                }
+                Err(err) => Some(Err(err)),
+            };
+            self.push_stage_helper_mut().set_shared_state(shared_state);
+            self.push_stage_helper_mut().peeked = Some(peeked);
+        }
+        self.push_stage_helper()
+            .peeked
+            .as_ref()
+            .and_then(Option::as_ref)
+    }
+
+    /// This is the default implementation for `next` for this stage
+    #[allow(clippy::too_many_lines)]
+    fn next_std(&mut self) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        if !self.push_stage_helper().initialized && self.push_stage_helper().cancel_requested() {
+            // Nothing to clean up before the cycle started
+            return None;
+        }
+
+        let mut shared_state = self.push_stage_helper_mut().take_shared_state().unwrap();
+
+        // A peek already finished the last step
+        let step_success = if self.push_stage_helper().peeked.is_some() {
+            Ok(())
+        } else {
+            self.finish_step(&mut shared_state)
         };
         if let Err(err) = step_success {
             self.push_stage_helper_mut().end_of_iter(shared_state, true);
+            self.on_error_reset();
             return Some(Err(err));
         }
 
         //for i in 0..num {
-        let ret = self.pre_exec(
-            &mut shared_state.fuzzer,
-            &mut shared_state.state,
-            &mut shared_state.event_mgr,
-            &mut shared_state.observers,
-        );
+        let peeked = self.push_stage_helper_mut().peeked.take();
+        let ret = if self.push_stage_helper().cancel_requested() {
+            // End the cycle as if the stage was done
+            None
+        } else if let Some(peeked) = peeked {
+            peeked
+        } else if let Some(input) = self.push_stage_helper_mut().pending_inputs.pop() {
+            Some(Ok(input))
+        } else {
+            #[cfg(feature = "introspection")]
+            let span_start = self.push_stage_helper().start_perf_span();
+            let burst = self.pre_exec_many(
+                &mut shared_state.fuzzer,
+                &mut shared_state.state,
+                &mut shared_state.event_mgr,
+                &mut shared_state.observers,
+            );
+            #[cfg(feature = "introspection")]
+            PushStageHelper::<CS, EM, OT, Z, SH>::end_perf_span(
+                &mut shared_state.state,
+                span_start,
+                PerfFeature::PushStagePreExec,
+            );
+            match burst {
+                Some(Ok(mut inputs)) => {
+                    inputs.reverse();
+                    let first = inputs.pop();
+                    self.push_stage_helper_mut().pending_inputs = inputs;
+                    first.map(Ok)
+                }
+                Some(Err(err)) => Some(Err(err)),
+                None => None,
+            }
+        };
+        if let Some(Ok(input)) = &ret {
+            // Stages only set the input to `post_exec` for the inputs they return from `pre_exec` themselves
+            if self.push_stage_helper().current_input.is_none() {
+                self.push_stage_helper_mut().current_input = Some(input.clone());
+            }
+            self.push_stage_helper_mut().last_input = Some(input.clone());
+        }
+        match ret {
+            Some(Err(_)) => {
+                self.push_stage_helper_mut().end_of_iter(shared_state, true);
+                self.on_error_reset();
+                return ret;
+            }
+            Some(Ok(_)) if self.push_stage_helper().deadline_exceeded() => {
+                // Deinit anyways, the stage may hold on to resources of this iteration
+                #[cfg(feature = "introspection")]
+                let span_start = self.push_stage_helper().start_perf_span();
+                let deinit = self.deinit(
+                    &mut shared_state.fuzzer,
+                    &mut shared_state.state,
+                    &mut shared_state.event_mgr,
+                    &mut shared_state.observers,
+                );
+                #[cfg(feature = "introspection")]
+                PushStageHelper::<CS, EM, OT, Z, SH>::end_perf_span(
+                    &mut shared_state.state,
+                    span_start,
+                    PerfFeature::PushStageDeinit,
+                );
+                self.push_stage_helper_mut().end_of_iter(shared_state, true);
+                self.on_error_reset();
+                if let Err(err) = deinit {
+                    return Some(Err(err));
+                }
+                return Some(Err(Error::timeout(format!(
+                    "Push stage iteration exceeded its deadline of {:?}",
+                    self.push_stage_helper().iteration_deadline().unwrap()
+                ))));
+            }
+            Some(Ok(_)) => self.push_stage_helper_mut().current_cycle.inputs_yielded += 1,
+            None => (),
+        }
         if ret.is_none() {
-            // We're done.
+            // We're done. A cancel may end the cycle in the middle of a burst
             drop(self.push_stage_helper_mut().current_input.take());
+            self.push_stage_helper_mut().pending_inputs.clear();
             self.push_stage_helper_mut().initialized = false;
 
-            if let Err(err) = self.deinit(
+            #[cfg(feature = "introspection")]
+            let span_start = self.push_stage_helper().start_perf_span();
+            let deinit = self.deinit(
                 &mut shared_state.fuzzer,
                 &mut shared_state.state,
                 &mut shared_state.event_mgr,
                 &mut shared_state.observers,
-            ) {
+            );
+            #[cfg(feature = "introspection")]
+            PushStageHelper::<CS, EM, OT, Z, SH>::end_perf_span(
+                &mut shared_state.state,
+                span_start,
+                PerfFeature::PushStageDeinit,
+            );
+            if let Err(err) = deinit {
                 self.push_stage_helper_mut().end_of_iter(shared_state, true);
+                self.on_error_reset();
                 return Some(Err(err));
             };
+            self.push_stage_helper_mut().end_cycle();
+
+            if let Err(err) = shared_state.flush_discoveries(usize::MAX) {
+                self.push_stage_helper_mut().end_of_iter(shared_state, true);
+                self.on_error_reset();
+                return Some(Err(err));
+            }
 
             let last_monitor_time = self.push_stage_helper().last_monitor_time;
+            let monitor_timeout = self.push_stage_helper().monitor_timeout();
 
-            let new_monitor_time = match shared_state.event_mgr.maybe_report_progress(
-                &mut shared_state.state,
-                last_monitor_time,
-                STATS_TIMEOUT_DEFAULT,
-            ) {
+            #[cfg(feature = "std")]
+            let sent = self.push_stage_helper_mut().report_to_sink(
+                *shared_state.state.executions(),
+                shared_state.event_mgr.mgr_id(),
+            );
+            #[cfg(not(feature = "std"))]
+            let sent = None;
+
+            let reported = match sent {
+                Some(new_time) => Ok(new_time),
+                None => shared_state.event_mgr.maybe_report_progress(
+                    &mut shared_state.state,
+                    last_monitor_time,
+                    monitor_timeout,
+                ),
+            };
+            let new_monitor_time = match reported {
                 Ok(new_time) => new_time,
                 Err(err) => {
                     self.push_stage_helper_mut().end_of_iter(shared_state, true);
+                    self.on_error_reset();
                     return Some(Err(err));
                 }
             };
@@ -331,3 +1310,877 @@ where
         ret
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloc::{rc::Rc, vec::Vec};
+    use core::{
+        cell::{Cell, RefCell},
+        time::Duration,
+    };
+    use std::sync::{Arc, Condvar, Mutex};
+
+    use super::{
+        smallvec, PushStage, PushStageHelper, PushStageSharedState, SharedStateCell, SmallVec,
+        StdMutationalPushStage,
+    };
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, InMemoryCorpus, Testcase},
+        events::{Event, NopEventManager},
+        executors::ExitKind,
+        feedbacks::ConstFeedback,
+        inputs::{BytesInput, HasBytesVec},
+        mutators::{havoc_mutations, BitFlipMutator, StdScheduledMutator},
+        schedulers::{QueueScheduler, Scheduler},
+        state::{HasCorpus, HasExecutions, StdState},
+        Error, StdFuzzer,
+    };
+
+    pub(crate) type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+    pub(crate) type TestScheduler = QueueScheduler<TestState>;
+    pub(crate) type TestEventManager = NopEventManager<TestState>;
+    pub(crate) type TestFuzzer = StdFuzzer<TestScheduler, ConstFeedback, ConstFeedback, ()>;
+    pub(crate) type TestHelper = PushStageHelper<TestScheduler, TestEventManager, (), TestFuzzer>;
+
+    /// Creates a shared state with a single `b"aaaa"` entry in the corpus
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn test_shared_state(
+    ) -> Rc<RefCell<Option<PushStageSharedState<TestScheduler, TestEventManager, (), TestFuzzer>>>>
+    {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let scheduler = QueueScheduler::new();
+        let idx = state
+            .corpus_mut()
+            .add(Testcase::new(BytesInput::new(b"aaaa".to_vec())))
+            .unwrap();
+        scheduler.on_add(&mut state, idx).unwrap();
+        let fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+        Rc::new(RefCell::new(Some(PushStageSharedState::new(
+            fuzzer,
+            state,
+            (),
+            NopEventManager::new(),
+        ))))
+    }
+
+    /// A push stage emitting `to_do` inputs per cycle, failing in `post_exec` at `fail_at`
+    struct CountingPushStage {
+        psh: TestHelper,
+        to_do: usize,
+        done: usize,
+        fail_at: Option<usize>,
+        deinits: usize,
+        pre_execs: usize,
+    }
+
+    impl CountingPushStage {
+        /// A stage on a fresh [`test_shared_state`], emitting nothing and never failing
+        fn new(exit_kind: Rc<Cell<Option<ExitKind>>>) -> Self {
+            Self {
+                psh: PushStageHelper::new(test_shared_state(), exit_kind),
+                to_do: 0,
+                done: 0,
+                fail_at: None,
+                deinits: 0,
+                pre_execs: 0,
+            }
+        }
+    }
+
+    impl PushStage<TestScheduler, TestEventManager, (), TestFuzzer> for CountingPushStage {
+        fn push_stage_helper(&self) -> &TestHelper {
+            &self.psh
+        }
+
+        fn push_stage_helper_mut(&mut self) -> &mut TestHelper {
+            &mut self.psh
+        }
+
+        fn init(
+            &mut self,
+            _fuzzer: &mut TestFuzzer,
+            _state: &mut TestState,
+            _event_mgr: &mut TestEventManager,
+            _observers: &mut (),
+        ) -> Result<(), Error> {
+            self.to_do = 4;
+            self.done = 0;
+            Ok(())
+        }
+
+        fn pre_exec(
+            &mut self,
+            _fuzzer: &mut TestFuzzer,
+            _state: &mut TestState,
+            _event_mgr: &mut TestEventManager,
+            _observers: &mut (),
+        ) -> Option<Result<BytesInput, Error>> {
+            self.pre_execs += 1;
+            if self.done >= self.to_do {
+                return None;
+            }
+            let input = BytesInput::new(vec![b'a'; self.done + 1]);
+            self.psh.current_input.replace(input.clone());
+            Some(Ok(input))
+        }
+
+        fn post_exec(
+            &mut self,
+            _fuzzer: &mut TestFuzzer,
+            _state: &mut TestState,
+            _event_mgr: &mut TestEventManager,
+            _observers: &mut (),
+            _input: BytesInput,
+            _exit_kind: ExitKind,
+        ) -> Result<(), Error> {
+            if self.fail_at == Some(self.done) {
+                return Err(Error::illegal_state("injected error"));
+            }
+            if self.done % 2 == 0 {
+                self.psh.record_interesting();
+            }
+            self.done += 1;
+            Ok(())
+        }
+
+        fn deinit(
+            &mut self,
+            _fuzzer: &mut TestFuzzer,
+            _state: &mut TestState,
+            _event_mgr: &mut TestEventManager,
+            _observers: &mut (),
+        ) -> Result<(), Error> {
+            self.deinits += 1;
+            Ok(())
+        }
+
+        fn on_error_reset(&mut self) {
+            self.to_do = 0;
+            self.done = 0;
+        }
+    }
+
+    impl Iterator for CountingPushStage {
+        type Item = Result<BytesInput, Error>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.next_std()
+        }
+    }
+
+    #[test]
+    fn test_push_stage_init_once() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = CountingPushStage::new(exit_kind.clone());
+
+        // `init` runs at the start of the cycle only, and does not reset the progress of each step
+        for len in 1..=4 {
+            assert_eq!(stage.next().unwrap().unwrap().bytes().len(), len);
+            assert!(stage.psh.initialized);
+            exit_kind.set(Some(ExitKind::Ok));
+        }
+        assert!(stage.next().is_none());
+        assert_eq!(stage.done, 4);
+        assert_eq!(stage.deinits, 1);
+        assert!(!stage.psh.initialized);
+    }
+
+    #[test]
+    fn test_push_stage_error_reset() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = CountingPushStage {
+            fail_at: Some(1),
+            ..CountingPushStage::new(exit_kind.clone())
+        };
+
+        assert!(stage.next().unwrap().is_ok());
+        exit_kind.set(Some(ExitKind::Ok));
+        assert!(stage.next().unwrap().is_ok());
+        assert_eq!(stage.done, 1);
+        exit_kind.set(Some(ExitKind::Ok));
+        assert!(stage.next().unwrap().is_err());
+
+        // The in-flight iteration state got drained
+        assert_eq!(stage.done, 0);
+        assert_eq!(stage.to_do, 0);
+        assert!(!stage.psh.initialized);
+        assert!(stage.psh.current_input.is_none());
+        assert!(stage.psh.shared_state.borrow().is_some());
+
+        // The next cycle starts fresh
+        stage.fail_at = None;
+        assert_eq!(stage.next().unwrap().unwrap().bytes(), b"a");
+        assert_eq!(stage.to_do, 4);
+    }
+
+    /// A push stage emitting two bursts of three inputs per cycle
+    struct BurstPushStage {
+        psh: TestHelper,
+        bursts: usize,
+        executed: Vec<Vec<u8>>,
+    }
+
+    impl PushStage<TestScheduler, TestEventManager, (), TestFuzzer> for BurstPushStage {
+        fn push_stage_helper(&self) -> &TestHelper {
+            &self.psh
+        }
+
+        fn push_stage_helper_mut(&mut self) -> &mut TestHelper {
+            &mut self.psh
+        }
+
+        fn pre_exec(
+            &mut self,
+            _fuzzer: &mut TestFuzzer,
+            _state: &mut TestState,
+            _event_mgr: &mut TestEventManager,
+            _observers: &mut (),
+        ) -> Option<Result<BytesInput, Error>> {
+            unreachable!("next_std only calls pre_exec_many")
+        }
+
+        fn pre_exec_many(
+            &mut self,
+            _fuzzer: &mut TestFuzzer,
+            _state: &mut TestState,
+            _event_mgr: &mut TestEventManager,
+            _observers: &mut (),
+        ) -> Option<Result<SmallVec<[BytesInput; 4]>, Error>> {
+            if self.bursts == 2 {
+                self.bursts = 0;
+                return None;
+            }
+            self.bursts += 1;
+            let burst = self.bursts as u8;
+            Some(Ok(smallvec![
+                BytesInput::new(vec![burst, 0]),
+                BytesInput::new(vec![burst, 1]),
+                BytesInput::new(vec![burst, 2]),
+            ]))
+        }
+
+        fn post_exec(
+            &mut self,
+            _fuzzer: &mut TestFuzzer,
+            _state: &mut TestState,
+            _event_mgr: &mut TestEventManager,
+            _observers: &mut (),
+            input: BytesInput,
+            _exit_kind: ExitKind,
+        ) -> Result<(), Error> {
+            self.executed.push(input.bytes().to_vec());
+            Ok(())
+        }
+    }
+
+    impl Iterator for BurstPushStage {
+        type Item = Result<BytesInput, Error>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.next_std()
+        }
+    }
+
+    #[test]
+    fn test_push_stage_bursts() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = BurstPushStage {
+            psh: PushStageHelper::new(test_shared_state(), exit_kind.clone()),
+            bursts: 0,
+            executed: Vec::new(),
+        };
+
+        let mut yielded = Vec::new();
+        for input in stage.by_ref() {
+            yielded.push(input.unwrap().bytes().to_vec());
+            exit_kind.set(Some(ExitKind::Ok));
+        }
+
+        let expected: Vec<Vec<u8>> = [[1, 0], [1, 1], [1, 2], [2, 0], [2, 1], [2, 2]]
+            .iter()
+            .map(|input| input.to_vec())
+            .collect();
+        assert_eq!(yielded, expected);
+        // Each input of the bursts got its own `post_exec`
+        assert_eq!(stage.executed, expected);
+        assert_eq!(stage.last_cycle_metrics().unwrap().inputs_yielded, 6);
+
+        // Batches are made of whole bursts, even past the batch limit of a single input
+        let mut shared_state = stage.psh.take_shared_state().unwrap();
+        let PushStageSharedState {
+            fuzzer,
+            state,
+            event_mgr,
+            observers,
+            ..
+        } = &mut shared_state;
+        let batch = stage.pre_exec_batch(fuzzer, state, event_mgr, observers, 3);
+        let batch = batch
+            .into_iter()
+            .map(|input| input.unwrap().bytes().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(batch, expected[..3]);
+    }
+
+    #[test]
+    fn test_push_stage_cancel_burst() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = BurstPushStage {
+            psh: PushStageHelper::new(test_shared_state(), exit_kind.clone()),
+            bursts: 0,
+            executed: Vec::new(),
+        };
+        let cancel = stage.psh.cancel_token();
+
+        assert_eq!(stage.next().unwrap().unwrap().bytes(), [1, 0]);
+        exit_kind.set(Some(ExitKind::Ok));
+        cancel.set(true);
+        assert!(stage.next().is_none());
+        assert_eq!(stage.executed, [[1, 0]]);
+
+        // The rest of the cancelled burst does not leak into the next cycle
+        cancel.set(false);
+        assert_eq!(stage.next().unwrap().unwrap().bytes(), [2, 0]);
+    }
+
+    #[test]
+    fn test_arc_mutex_shared_state() {
+        let shared_state = Arc::new((
+            Mutex::new(test_shared_state().borrow_mut().take()),
+            Condvar::new(),
+        ));
+        let exit_kinds = [Rc::new(Cell::new(None)), Rc::new(Cell::new(None))];
+        let mut stages = [0, 1].map(|i| {
+            StdMutationalPushStage::with_iterations(
+                BitFlipMutator::new(),
+                shared_state.clone(),
+                exit_kinds[i].clone(),
+                0,
+                3,
+            )
+        });
+
+        // Both stages borrow the state in turns, as stages on different threads would
+        let mut yielded = [0, 0];
+        loop {
+            let mut done = true;
+            for (i, stage) in stages.iter_mut().enumerate() {
+                if let Some(input) = stage.next() {
+                    input.unwrap();
+                    yielded[i] += 1;
+                    exit_kinds[i].set(Some(ExitKind::Ok));
+                    done = false;
+                }
+            }
+            if done {
+                break;
+            }
+        }
+
+        assert_eq!(yielded, [3, 3]);
+        assert!(shared_state.0.lock().unwrap().is_some());
+    }
+
+    #[test]
+    fn test_arc_mutex_take_waits() {
+        let cell = Arc::new((Mutex::new(Some(1_u32)), Condvar::new()));
+        assert_eq!(cell.take_shared(), Some(1));
+        assert_eq!(cell.try_take_shared(), None);
+
+        // Another thread waits for the shared state to come back
+        let waiter = {
+            let cell = cell.clone();
+            std::thread::spawn(move || cell.take_shared())
+        };
+        cell.set_shared(2);
+        assert_eq!(waiter.join().unwrap(), Some(2));
+        assert_eq!(cell.try_take_shared(), None);
+    }
+
+    #[test]
+    fn test_push_stage_cancel() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = CountingPushStage::new(exit_kind.clone());
+        let cancel = stage.psh.cancel_token();
+
+        assert!(stage.next().unwrap().is_ok());
+        exit_kind.set(Some(ExitKind::Ok));
+        assert!(stage.next().unwrap().is_ok());
+        exit_kind.set(Some(ExitKind::Ok));
+        cancel.set(true);
+
+        // The last input still gets processed, then the cycle ends without another `pre_exec`
+        assert!(stage.next().is_none());
+        assert_eq!(stage.done, 2);
+        assert_eq!(stage.pre_execs, 2);
+        assert_eq!(stage.deinits, 1);
+        assert!(!stage.psh.initialized);
+        assert!(stage.psh.shared_state.borrow().is_some());
+        assert_eq!(stage.last_cycle_metrics().unwrap().inputs_yielded, 2);
+
+        // No new cycle starts until the token is reset
+        assert!(stage.next().is_none());
+        assert_eq!(stage.deinits, 1);
+        cancel.set(false);
+        assert!(stage.next().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_push_stage_last_input() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = StdMutationalPushStage::with_iterations(
+            BitFlipMutator::new(),
+            test_shared_state(),
+            exit_kind.clone(),
+            0,
+            3,
+        );
+        assert!(stage.push_stage_helper().last_input().is_none());
+
+        let mut last = None;
+        while let Some(input) = stage.next() {
+            let input = input.unwrap();
+            assert_eq!(stage.push_stage_helper().last_input(), Some(&input));
+            last = Some(input);
+            exit_kind.set(Some(ExitKind::Crash));
+        }
+        // The input of the last execution outlives its `post_exec` and the cycle
+        assert_eq!(stage.push_stage_helper().last_input(), last.as_ref());
+    }
+
+    #[test]
+    fn test_push_stage_cycle_metrics() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = CountingPushStage {
+            fail_at: Some(0),
+            ..CountingPushStage::new(exit_kind.clone())
+        };
+
+        // A cycle aborted by an error is not a full cycle
+        assert!(stage.next().unwrap().is_ok());
+        exit_kind.set(Some(ExitKind::Ok));
+        assert!(stage.next().unwrap().is_err());
+        assert!(stage.last_cycle_metrics().is_none());
+
+        stage.fail_at = None;
+        let mut yielded = 0;
+        for input in stage.by_ref() {
+            input.unwrap();
+            yielded += 1;
+            exit_kind.set(Some(ExitKind::Ok));
+        }
+
+        let metrics = stage.last_cycle_metrics().unwrap();
+        assert_eq!(yielded, 4);
+        assert_eq!(metrics.inputs_yielded, 4);
+        // Every other execution was interesting
+        assert_eq!(metrics.interesting, 2);
+        assert_eq!(metrics.errors, 1);
+    }
+
+    #[test]
+    fn test_push_stage_iteration_deadline() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = CountingPushStage::new(exit_kind.clone());
+        stage
+            .psh
+            .set_iteration_deadline(Some(Duration::from_millis(50)));
+
+        assert!(stage.next().unwrap().is_ok());
+        assert!(!stage.psh.deadline_exceeded());
+
+        // The execution of the input hangs: move the start of the iteration into the past
+        let cycle_start = stage.psh.cycle_start.unwrap();
+        stage.psh.cycle_start = Some(cycle_start.saturating_sub(Duration::from_millis(100)));
+        exit_kind.set(Some(ExitKind::Ok));
+        assert!(matches!(stage.next().unwrap(), Err(Error::Timeout(..))));
+
+        // The stage got deinitialized and the shared state restored
+        assert_eq!(stage.deinits, 1);
+        assert!(!stage.psh.initialized);
+        assert!(stage.psh.current_input.is_none());
+        assert!(stage.psh.shared_state.borrow().is_some());
+
+        // The next iteration gets a fresh budget
+        assert_eq!(stage.next().unwrap().unwrap().bytes(), b"a");
+        assert!(!stage.psh.deadline_exceeded());
+    }
+
+    #[test]
+    fn test_push_stage_peek() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = CountingPushStage::new(exit_kind.clone());
+
+        // Peeking repeatedly only generates a single input
+        assert_eq!(stage.peek_next().unwrap().as_ref().unwrap().bytes(), b"a");
+        assert_eq!(stage.peek_next().unwrap().as_ref().unwrap().bytes(), b"a");
+        assert_eq!(stage.pre_execs, 1);
+
+        // The following next returns the peeked input
+        assert_eq!(stage.next().unwrap().unwrap().bytes(), b"a");
+        assert_eq!(stage.pre_execs, 1);
+
+        // Peeking runs the post_exec of the last input first, so the stage moves on
+        exit_kind.set(Some(ExitKind::Ok));
+        assert_eq!(stage.peek_next().unwrap().as_ref().unwrap().bytes(), b"aa");
+        assert_eq!(stage.pre_execs, 2);
+        assert_eq!(stage.done, 1);
+        assert!(stage.psh.shared_state.borrow().is_some());
+
+        // The following next does not run it again
+        assert_eq!(stage.next().unwrap().unwrap().bytes(), b"aa");
+        assert_eq!(stage.done, 1);
+        assert_eq!(stage.pre_execs, 2);
+        exit_kind.set(Some(ExitKind::Ok));
+        assert_eq!(stage.next().unwrap().unwrap().bytes(), b"aaa");
+        exit_kind.set(Some(ExitKind::Ok));
+        assert_eq!(
+            stage.peek_next().unwrap().as_ref().unwrap().bytes(),
+            b"aaaa"
+        );
+        assert_eq!(stage.next().unwrap().unwrap().bytes(), b"aaaa");
+        exit_kind.set(Some(ExitKind::Ok));
+        assert!(stage.peek_next().is_none());
+        assert!(stage.next().is_none());
+        assert_eq!(stage.done, 4);
+        assert_eq!(stage.deinits, 1);
+
+        // Peeking does not change the input stream
+        let streams = [true, false].map(|peek| {
+            let exit_kind = Rc::new(Cell::new(None));
+            let mut stage = CountingPushStage::new(exit_kind.clone());
+            let mut stream = Vec::new();
+            loop {
+                if peek {
+                    stage.peek_next();
+                }
+                let Some(input) = stage.next() else {
+                    break;
+                };
+                stream.push(input.unwrap().bytes().to_vec());
+                exit_kind.set(Some(ExitKind::Ok));
+            }
+            stream
+        });
+        assert_eq!(streams[0], streams[1]);
+        assert_eq!(
+            streams[0],
+            [
+                b"a".to_vec(),
+                b"aa".to_vec(),
+                b"aaa".to_vec(),
+                b"aaaa".to_vec()
+            ]
+        );
+
+        // Clones keep a peeked error
+        stage.psh.peeked = Some(Some(Err(Error::illegal_state("peeked"))));
+        assert!(matches!(
+            stage.psh.clone().peeked,
+            Some(Some(Err(Error::IllegalState(..))))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_push_stage_monitor_sink() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = CountingPushStage::new(exit_kind.clone());
+        let (sender, receiver) = std::sync::mpsc::channel();
+        stage.psh.set_monitor_sink(sender);
+        // Make the end of the first cycle report
+        stage.psh.last_monitor_time = Duration::ZERO;
+
+        for input in stage.by_ref() {
+            input.unwrap();
+            exit_kind.set(Some(ExitKind::Ok));
+        }
+        let update = receiver.try_recv().unwrap();
+        assert_eq!(stage.psh.last_monitor_time, update.time);
+        assert!(receiver.try_recv().is_err());
+
+        // Once the receiver hangs up, the progress is reported through the event manager
+        drop(receiver);
+        stage.psh.last_monitor_time = Duration::ZERO;
+        for input in stage.by_ref() {
+            input.unwrap();
+            exit_kind.set(Some(ExitKind::Ok));
+        }
+        assert!(stage.psh.monitor_sink.is_none());
+        assert!(stage.psh.last_monitor_time > Duration::ZERO);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_push_stage_monitor_timeout() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = CountingPushStage::new(exit_kind.clone());
+        assert_eq!(stage.psh.monitor_timeout(), Duration::from_secs(15));
+        let (sender, receiver) = std::sync::mpsc::channel();
+        stage.psh.set_monitor_sink(sender);
+
+        // Without a timeout, every cycle reports
+        stage.psh.set_monitor_timeout(Duration::ZERO);
+        for _ in 0..3 {
+            for input in stage.by_ref() {
+                input.unwrap();
+                exit_kind.set(Some(ExitKind::Ok));
+            }
+        }
+        assert_eq!(receiver.try_iter().count(), 3);
+
+        stage.psh.last_monitor_time = Duration::ZERO;
+        stage.psh.set_monitor_timeout(Duration::MAX);
+        for input in stage.by_ref() {
+            input.unwrap();
+            exit_kind.set(Some(ExitKind::Ok));
+        }
+        assert_eq!(receiver.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_push_stage_batch() {
+        let mut stage = CountingPushStage::new(Rc::new(Cell::new(None)));
+        let mut shared_state = stage.psh.take_shared_state().unwrap();
+        let PushStageSharedState {
+            fuzzer,
+            state,
+            event_mgr,
+            observers,
+            ..
+        } = &mut shared_state;
+
+        // This stage only advances in `post_exec`, so it never batches more than a single input
+        stage.init(fuzzer, state, event_mgr, observers).unwrap();
+        let batch = stage.pre_exec_batch(fuzzer, state, event_mgr, observers, 3);
+        assert_eq!(batch.len(), 1);
+        let inputs = batch.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+
+        // Each input needs its observers and an exit kind
+        assert!(stage
+            .post_exec_batch(
+                fuzzer,
+                state,
+                event_mgr,
+                &mut [(), ()],
+                inputs.clone(),
+                vec![ExitKind::Ok]
+            )
+            .is_err());
+        assert!(stage
+            .post_exec_batch(
+                fuzzer,
+                state,
+                event_mgr,
+                &mut [()],
+                inputs.clone(),
+                vec![ExitKind::Ok; 2]
+            )
+            .is_err());
+        assert_eq!(stage.done, 0);
+        stage
+            .post_exec_batch(
+                fuzzer,
+                state,
+                event_mgr,
+                &mut [()],
+                inputs,
+                vec![ExitKind::Ok],
+            )
+            .unwrap();
+        assert_eq!(stage.done, 1);
+        stage.psh.set_shared_state(shared_state);
+
+        // A mutational stage batches the inputs left in its cycle, but not more
+        let mut stage = StdMutationalPushStage::with_iterations(
+            StdScheduledMutator::new(havoc_mutations()),
+            test_shared_state(),
+            Rc::new(Cell::new(None)),
+            0,
+            4,
+        );
+        let mut shared_state = stage.push_stage_helper_mut().take_shared_state().unwrap();
+        let PushStageSharedState {
+            fuzzer,
+            state,
+            event_mgr,
+            observers,
+            ..
+        } = &mut shared_state;
+        stage.init(fuzzer, state, event_mgr, observers).unwrap();
+        let mut batch_lens = Vec::new();
+        loop {
+            let batch = stage.pre_exec_batch(fuzzer, state, event_mgr, observers, 3);
+            if batch.is_empty() {
+                break;
+            }
+            batch_lens.push(batch.len());
+            let inputs = batch.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+            stage
+                .post_exec_batch(
+                    fuzzer,
+                    state,
+                    event_mgr,
+                    &mut vec![(); inputs.len()],
+                    inputs.clone(),
+                    vec![ExitKind::Ok; inputs.len()],
+                )
+                .unwrap();
+        }
+        assert_eq!(batch_lens, [3, 1]);
+        stage.push_stage_helper_mut().set_shared_state(shared_state);
+    }
+
+    #[test]
+    fn test_reseed() {
+        let run = |seed| {
+            let shared_state = test_shared_state();
+            shared_state.borrow_mut().as_mut().unwrap().reseed(seed);
+            let exit_kind = Rc::new(Cell::new(None));
+            let mut stage = StdMutationalPushStage::with_iterations(
+                StdScheduledMutator::new(havoc_mutations()),
+                shared_state,
+                exit_kind.clone(),
+                0,
+                32,
+            );
+            let mut inputs = Vec::new();
+            for input in stage.by_ref() {
+                inputs.push(input.unwrap().bytes().to_vec());
+                exit_kind.set(Some(ExitKind::Ok));
+            }
+            inputs
+        };
+
+        let inputs = run(1337);
+        assert_eq!(inputs.len(), 32);
+        assert_eq!(inputs, run(1337));
+        assert_ne!(inputs, run(42));
+    }
+
+    #[test]
+    fn test_discovery_queue() {
+        let shared_state = test_shared_state().borrow_mut().take().unwrap();
+        let mut shared_state = shared_state.with_discovery_capacity(2);
+        for bytes in [&b"a"[..], b"b", b"c"] {
+            shared_state
+                .try_fire_discovery(&BytesInput::new(bytes.to_vec()))
+                .unwrap();
+            *shared_state.state.executions_mut() += 1;
+        }
+        assert_eq!(shared_state.pending_discoveries(), 2);
+        assert_eq!(shared_state.dropped_discoveries(), 1);
+        #[cfg(feature = "introspection")]
+        {
+            use crate::state::HasClientPerfMonitor;
+            assert_eq!(
+                shared_state.state.introspection_monitor().dropped_events(),
+                1
+            );
+        }
+
+        // The counters are the ones of the run that found the input, not the ones at flush time
+        let executions: Vec<_> = shared_state
+            .pending_discoveries
+            .iter()
+            .map(|event| match event {
+                Event::NewTestcase { executions, .. } => *executions,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(executions, [0, 1]);
+
+        assert_eq!(shared_state.flush_discoveries(1).unwrap(), 1);
+        assert_eq!(shared_state.pending_discoveries(), 1);
+        assert!(shared_state
+            .try_fire_discovery(&BytesInput::new(b"d".to_vec()))
+            .unwrap());
+        assert_eq!(shared_state.flush_discoveries(usize::MAX).unwrap(), 2);
+        assert_eq!(shared_state.pending_discoveries(), 0);
+        assert_eq!(shared_state.dropped_discoveries(), 1);
+    }
+
+    #[test]
+    fn test_discovery_queue_flushed_at_cycle_end() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let shared_state = test_shared_state();
+        let mut stage = CountingPushStage {
+            psh: PushStageHelper::new(shared_state.clone(), exit_kind.clone()),
+            ..CountingPushStage::new(exit_kind.clone())
+        };
+
+        assert!(stage.next().unwrap().is_ok());
+        assert!(shared_state
+            .borrow_mut()
+            .as_mut()
+            .unwrap()
+            .try_fire_discovery(&BytesInput::new(b"new".to_vec()))
+            .unwrap());
+        exit_kind.set(Some(ExitKind::Ok));
+        let pending = || {
+            shared_state
+                .borrow()
+                .as_ref()
+                .unwrap()
+                .pending_discoveries()
+        };
+        for input in &mut stage {
+            input.unwrap();
+            assert_eq!(pending(), 1);
+            exit_kind.set(Some(ExitKind::Ok));
+        }
+        assert_eq!(pending(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "introspection")]
+    fn test_push_stage_perf() {
+        use crate::{monitors::PerfFeature, state::HasClientPerfMonitor};
+
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = CountingPushStage::new(exit_kind.clone());
+        let used_features = |stage: &CountingPushStage| {
+            let shared_state = stage.psh.shared_state.borrow();
+            let monitor = shared_state.as_ref().unwrap().state.introspection_monitor();
+            monitor
+                .used_stages()
+                .flat_map(|(_, features)| features.iter().enumerate())
+                .filter(|(_, time)| **time > 0)
+                .map(|(feature, _)| feature)
+                .collect::<alloc::vec::Vec<_>>()
+        };
+
+        // Disabled by default
+        for input in stage.by_ref() {
+            input.unwrap();
+            exit_kind.set(Some(ExitKind::Ok));
+        }
+        assert!(used_features(&stage).is_empty());
+
+        stage.psh.perf_enabled = true;
+        for input in stage.by_ref() {
+            input.unwrap();
+            exit_kind.set(Some(ExitKind::Ok));
+        }
+        let used = used_features(&stage);
+        for feature in [
+            PerfFeature::PushStageInit,
+            PerfFeature::PushStagePreExec,
+            PerfFeature::PushStagePostExec,
+            PerfFeature::PushStageDeinit,
+        ] {
+            assert!(used.contains(&usize::from(feature)));
+        }
+    }
+}
@@ -2,12 +2,12 @@
 //! For the current input, it will perform a range of random mutations, and then run them in the executor.
 
 use alloc::rc::Rc;
-use core::{
-    cell::{Cell, RefCell},
-    fmt::Debug,
-};
+use core::{cell::Cell, fmt::Debug};
 
-use super::{PushStage, PushStageHelper, PushStageSharedState};
+use super::{
+    PushStage, PushStageHelper, PushStageProgress, PushStageSharedState, RcSharedState,
+    SharedStateCell,
+};
 #[cfg(feature = "introspection")]
 use crate::monitors::PerfFeature;
 use crate::{
@@ -15,6 +15,7 @@ use crate::{
     corpus::{Corpus, CorpusId},
     events::{EventFirer, EventRestarter, HasEventManagerId, ProgressReporter},
     executors::ExitKind,
+    fuzzer::ExecuteInputResult,
     inputs::UsesInput,
     mark_feature_time,
     mutators::Mutator,
@@ -37,7 +38,7 @@ pub static DEFAULT_MUTATIONAL_MAX_ITERATIONS: u64 = 128;
 ///
 /// The default mutational push stage
 #[derive(Clone, Debug)]
-pub struct StdMutationalPushStage<CS, EM, M, OT, Z>
+pub struct StdMutationalPushStage<CS, EM, M, OT, Z, SH = RcSharedState<CS, EM, OT, Z>>
 where
     CS: Scheduler,
     EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
@@ -47,19 +48,24 @@ where
     Z: ExecutionProcessor<OT, State = CS::State>
         + EvaluatorObservers<OT>
         + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
 {
     current_corpus_idx: Option<CorpusId>,
     testcases_to_do: usize,
     testcases_done: usize,
+    /// If set, the exact number of inputs to emit per corpus entry
+    fixed_iterations: Option<usize>,
+    /// If set, the corpus entry is kept across cycles until [`StdMutationalPushStage::advance`] gets called
+    manual_advance: bool,
 
     stage_idx: i32,
 
     mutator: M,
 
-    psh: PushStageHelper<CS, EM, OT, Z>,
+    psh: PushStageHelper<CS, EM, OT, Z, SH>,
 }
 
-impl<CS, EM, M, OT, Z> StdMutationalPushStage<CS, EM, M, OT, Z>
+impl<CS, EM, M, OT, Z, SH> StdMutationalPushStage<CS, EM, M, OT, Z, SH>
 where
     CS: Scheduler,
     EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
@@ -69,20 +75,53 @@ where
     Z: ExecutionProcessor<OT, State = CS::State>
         + EvaluatorObservers<OT>
         + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
 {
-    /// Gets the number of iterations as a random number
-    #[allow(clippy::unused_self, clippy::unnecessary_wraps)] // TODO: we should put this function into a trait later
+    /// Gets the number of iterations, either the fixed count or a random number
+    #[allow(clippy::unnecessary_wraps)] // TODO: we should put this function into a trait later
     fn iterations(&self, state: &mut CS::State, _corpus_idx: CorpusId) -> Result<usize, Error> {
-        Ok(1 + state.rand_mut().below(DEFAULT_MUTATIONAL_MAX_ITERATIONS) as usize)
+        Ok(match self.fixed_iterations {
+            Some(iterations) => iterations,
+            None => 1 + state.rand_mut().below(DEFAULT_MUTATIONAL_MAX_ITERATIONS) as usize,
+        })
     }
 
     /// Sets the current corpus index
     pub fn set_current_corpus_idx(&mut self, current_corpus_idx: CorpusId) {
         self.current_corpus_idx = Some(current_corpus_idx);
     }
+
+    /// The corpus entry the stage works on, or will work on in its next cycle.
+    /// `None` if the next cycle asks the scheduler for one.
+    #[must_use]
+    pub fn current_corpus_id(&self) -> Option<CorpusId> {
+        self.current_corpus_idx
+    }
+
+    /// Moves on to the next corpus entry, as picked by the scheduler, for the next cycle.
+    /// Returns the new entry. Errors if a cycle is ongoing, or if the shared state is held elsewhere.
+    pub fn advance(&mut self) -> Result<CorpusId, Error> {
+        if self.psh.initialized {
+            return Err(Error::illegal_state(
+                "Can not advance a StdMutationalPushStage in the middle of a cycle",
+            ));
+        }
+        let mut shared_state = self.psh.try_take_shared_state().ok_or_else(|| {
+            Error::illegal_state("The shared state of the StdMutationalPushStage is not available")
+        })?;
+        let res = shared_state
+            .fuzzer
+            .scheduler()
+            .next(&mut shared_state.state);
+        self.psh.set_shared_state(shared_state);
+        let corpus_idx = res?;
+        self.current_corpus_idx = Some(corpus_idx);
+        Ok(corpus_idx)
+    }
 }
 
-impl<CS, EM, M, OT, Z> PushStage<CS, EM, OT, Z> for StdMutationalPushStage<CS, EM, M, OT, Z>
+impl<CS, EM, M, OT, Z, SH> PushStage<CS, EM, OT, Z, SH>
+    for StdMutationalPushStage<CS, EM, M, OT, Z, SH>
 where
     CS: Scheduler,
     EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId + ProgressReporter,
@@ -93,14 +132,15 @@ where
     Z: ExecutionProcessor<OT, State = CS::State>
         + EvaluatorObservers<OT>
         + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
 {
     #[inline]
-    fn push_stage_helper(&self) -> &PushStageHelper<CS, EM, OT, Z> {
+    fn push_stage_helper(&self) -> &PushStageHelper<CS, EM, OT, Z, SH> {
         &self.psh
     }
 
     #[inline]
-    fn push_stage_helper_mut(&mut self) -> &mut PushStageHelper<CS, EM, OT, Z> {
+    fn push_stage_helper_mut(&mut self) -> &mut PushStageHelper<CS, EM, OT, Z, SH> {
         &mut self.psh
     }
 
@@ -171,7 +211,11 @@ where
     ) -> Result<(), Error> {
         // todo: isintersting, etc.
 
-        fuzzer.process_execution(state, event_mgr, last_input, observers, &exit_kind, true)?;
+        let (res, _) =
+            fuzzer.process_execution(state, event_mgr, last_input, observers, &exit_kind, true)?;
+        if res != ExecuteInputResult::None {
+            self.push_stage_helper_mut().record_interesting();
+        }
 
         start_timer!(state);
         self.mutator
@@ -182,6 +226,12 @@ where
         Ok(())
     }
 
+    /// The testcases left in this cycle, as each `pre_exec` returns a fresh mutation
+    #[inline]
+    fn batch_limit(&self) -> usize {
+        self.testcases_to_do.saturating_sub(self.testcases_done)
+    }
+
     #[inline]
     fn deinit(
         &mut self,
@@ -190,12 +240,49 @@ where
         _event_mgr: &mut EM,
         _observers: &mut OT,
     ) -> Result<(), Error> {
+        if !self.manual_advance {
+            self.current_corpus_idx = None;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn on_error_reset(&mut self) {
         self.current_corpus_idx = None;
+        self.testcases_to_do = 0;
+        self.testcases_done = 0;
+    }
+
+    /// The progress, with the number of testcases to do and done as counters
+    fn snapshot(&self) -> PushStageProgress {
+        PushStageProgress {
+            corpus_idx: self.current_corpus_idx,
+            counters: vec![self.testcases_to_do, self.testcases_done],
+            ..self.psh.progress()
+        }
+    }
+
+    fn restore(&mut self, progress: &PushStageProgress) -> Result<(), Error> {
+        let [testcases_to_do, testcases_done] = progress.counters[..] else {
+            return Err(Error::illegal_argument(format!(
+                "Expected 2 counters to restore a StdMutationalPushStage, got {}",
+                progress.counters.len()
+            )));
+        };
+        if progress.initialized && progress.corpus_idx.is_none() {
+            return Err(Error::illegal_argument(
+                "Can not restore an initialized StdMutationalPushStage without a corpus index",
+            ));
+        }
+        self.psh.restore_progress(progress);
+        self.current_corpus_idx = progress.corpus_idx;
+        self.testcases_to_do = testcases_to_do;
+        self.testcases_done = testcases_done;
         Ok(())
     }
 }
 
-impl<CS, EM, M, OT, Z> Iterator for StdMutationalPushStage<CS, EM, M, OT, Z>
+impl<CS, EM, M, OT, Z, SH> Iterator for StdMutationalPushStage<CS, EM, M, OT, Z, SH>
 where
     CS: Scheduler,
     EM: EventFirer + EventRestarter + HasEventManagerId + ProgressReporter<State = CS::State>,
@@ -206,6 +293,7 @@ where
     Z: ExecutionProcessor<OT, State = CS::State>
         + EvaluatorObservers<OT>
         + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
 {
     type Item = Result<<CS::State as UsesInput>::Input, Error>;
 
@@ -214,7 +302,7 @@ where
     }
 }
 
-impl<CS, EM, M, OT, Z> StdMutationalPushStage<CS, EM, M, OT, Z>
+impl<CS, EM, M, OT, Z, SH> StdMutationalPushStage<CS, EM, M, OT, Z, SH>
 where
     CS: Scheduler,
     EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
@@ -224,13 +312,13 @@ where
     Z: ExecutionProcessor<OT, State = CS::State>
         + EvaluatorObservers<OT>
         + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
 {
     /// Creates a new default mutational stage
     #[must_use]
-    #[allow(clippy::type_complexity)]
     pub fn new(
         mutator: M,
-        shared_state: Rc<RefCell<Option<PushStageSharedState<CS, EM, OT, Z>>>>,
+        shared_state: SH,
         exit_kind: Rc<Cell<Option<ExitKind>>>,
         stage_idx: i32,
     ) -> Self {
@@ -240,7 +328,186 @@ where
             current_corpus_idx: None, // todo
             testcases_to_do: 0,
             testcases_done: 0,
+            fixed_iterations: None,
+            manual_advance: false,
             stage_idx,
         }
     }
+
+    /// Creates a new mutational stage emitting exactly `iterations` inputs per corpus entry,
+    /// instead of a random number of them. Useful to reproduce a run.
+    #[must_use]
+    pub fn with_iterations(
+        mutator: M,
+        shared_state: SH,
+        exit_kind: Rc<Cell<Option<ExitKind>>>,
+        stage_idx: i32,
+        iterations: usize,
+    ) -> Self {
+        Self {
+            fixed_iterations: Some(iterations),
+            ..Self::new(mutator, shared_state, exit_kind, stage_idx)
+        }
+    }
+
+    /// The fixed number of inputs emitted per corpus entry, if any
+    #[must_use]
+    pub fn fixed_iterations(&self) -> Option<usize> {
+        self.fixed_iterations
+    }
+
+    /// If `true`, the stage keeps working on the same corpus entry cycle after cycle,
+    /// until the driver calls [`Self::advance`], or sets another entry. Only the very first cycle,
+    /// or a cycle after an error, asks the scheduler for an entry by itself.
+    #[must_use]
+    pub fn with_manual_advance(mut self, manual_advance: bool) -> Self {
+        self.manual_advance = manual_advance;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    use super::StdMutationalPushStage;
+    use crate::{
+        corpus::{Corpus, Testcase},
+        executors::ExitKind,
+        fuzzer::HasScheduler,
+        inputs::BytesInput,
+        mutators::BitFlipMutator,
+        schedulers::Scheduler,
+        stages::push::{tests::test_shared_state, PushStage, PushStageProgress},
+        state::HasCorpus,
+    };
+
+    #[test]
+    fn test_with_iterations() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = StdMutationalPushStage::with_iterations(
+            BitFlipMutator::new(),
+            test_shared_state(),
+            exit_kind.clone(),
+            0,
+            3,
+        );
+        assert_eq!(stage.fixed_iterations(), Some(3));
+
+        // Every cycle emits exactly the fixed number of inputs
+        for _ in 0..2 {
+            let mut yielded = 0;
+            for input in stage.by_ref() {
+                input.unwrap();
+                yielded += 1;
+                exit_kind.set(Some(ExitKind::Ok));
+            }
+            assert_eq!(yielded, 3);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = StdMutationalPushStage::with_iterations(
+            BitFlipMutator::new(),
+            test_shared_state(),
+            exit_kind.clone(),
+            0,
+            5,
+        );
+        stage.next().unwrap().unwrap();
+        exit_kind.set(Some(ExitKind::Ok));
+        stage.next().unwrap().unwrap();
+
+        // The second input was returned, but never executed
+        let snapshot = stage.snapshot();
+        assert!(snapshot.initialized);
+        assert_eq!(snapshot.counters, [5, 1]);
+        let serialized = postcard::to_allocvec(&snapshot).unwrap();
+        let snapshot: PushStageProgress = postcard::from_bytes(&serialized).unwrap();
+
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut restored = StdMutationalPushStage::with_iterations(
+            BitFlipMutator::new(),
+            test_shared_state(),
+            exit_kind.clone(),
+            0,
+            5,
+        );
+        restored.restore(&snapshot).unwrap();
+        let mut yielded = 0;
+        for input in restored.by_ref() {
+            input.unwrap();
+            yielded += 1;
+            exit_kind.set(Some(ExitKind::Ok));
+        }
+        assert_eq!(yielded, 4);
+        let snapshot = restored.snapshot();
+        assert!(!snapshot.initialized);
+        assert_eq!(snapshot.counters, [5, 5]);
+
+        assert!(restored
+            .restore(&PushStageProgress {
+                initialized: true,
+                corpus_idx: None,
+                counters: vec![5, 1],
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_manual_advance() {
+        let shared_state = test_shared_state();
+        {
+            let mut shared_state = shared_state.borrow_mut();
+            let shared_state = shared_state.as_mut().unwrap();
+            let idx = shared_state
+                .state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(b"bbbb".to_vec())))
+                .unwrap();
+            shared_state
+                .fuzzer
+                .scheduler()
+                .on_add(&mut shared_state.state, idx)
+                .unwrap();
+        }
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = StdMutationalPushStage::with_iterations(
+            BitFlipMutator::new(),
+            shared_state,
+            exit_kind.clone(),
+            0,
+            2,
+        )
+        .with_manual_advance(true);
+        assert_eq!(stage.current_corpus_id(), None);
+
+        let run_cycle = |stage: &mut StdMutationalPushStage<_, _, _, _, _>| {
+            for input in stage.by_ref() {
+                input.unwrap();
+                exit_kind.set(Some(ExitKind::Ok));
+            }
+        };
+        run_cycle(&mut stage);
+        let first = stage.current_corpus_id().unwrap();
+
+        // The entry sticks until the driver advances
+        for _ in 0..3 {
+            run_cycle(&mut stage);
+            assert_eq!(stage.current_corpus_id(), Some(first));
+        }
+        let second = stage.advance().unwrap();
+        assert_ne!(second, first);
+        assert_eq!(stage.current_corpus_id(), Some(second));
+        run_cycle(&mut stage);
+        assert_eq!(stage.current_corpus_id(), Some(second));
+
+        // No moving on in the middle of a cycle
+        stage.next().unwrap().unwrap();
+        assert!(stage.advance().is_err());
+        assert_eq!(stage.current_corpus_id(), Some(second));
+    }
 }
@@ -0,0 +1,238 @@
+//! The [`ReplayPushStage`] returns each input of the corpus once, verbatim.
+//! Useful for deterministic regression or differential runs over a saved corpus, or saved crashes.
+
+use alloc::rc::Rc;
+use core::{
+    cell::{Cell, RefCell},
+    fmt::Debug,
+};
+
+use super::{PushStage, PushStageHelper, PushStageSharedState};
+#[cfg(feature = "introspection")]
+use crate::monitors::PerfFeature;
+use crate::{
+    corpus::{Corpus, CorpusId},
+    events::{EventFirer, EventRestarter, HasEventManagerId, ProgressReporter},
+    executors::ExitKind,
+    fuzzer::ExecuteInputResult,
+    inputs::UsesInput,
+    mark_feature_time,
+    observers::ObserversTuple,
+    schedulers::Scheduler,
+    start_timer,
+    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMetadata, HasRand},
+    Error, EvaluatorObservers, ExecutionProcessor, HasScheduler,
+};
+
+/// A push stage replaying the corpus without mutations.
+/// Each cycle returns the inputs of all entries that were in the corpus at `init`, in corpus order,
+/// and then `None`. Entries added during the cycle get replayed in the next one.
+#[derive(Clone, Debug)]
+pub struct ReplayPushStage<CS, EM, OT, Z>
+where
+    CS: Scheduler,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
+    OT: ObserversTuple<CS::State>,
+    CS::State: HasClientPerfMonitor + HasRand + Clone + Debug,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+{
+    /// The next entry to replay
+    cursor: Option<CorpusId>,
+    /// The last entry to replay in this cycle
+    last: Option<CorpusId>,
+
+    psh: PushStageHelper<CS, EM, OT, Z>,
+}
+
+impl<CS, EM, OT, Z> ReplayPushStage<CS, EM, OT, Z>
+where
+    CS: Scheduler,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId,
+    OT: ObserversTuple<CS::State>,
+    CS::State: HasClientPerfMonitor + HasCorpus + HasRand + Clone + Debug,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+{
+    /// Creates a new [`ReplayPushStage`]
+    #[must_use]
+    #[allow(clippy::type_complexity)]
+    pub fn new(
+        shared_state: Rc<RefCell<Option<PushStageSharedState<CS, EM, OT, Z>>>>,
+        exit_kind: Rc<Cell<Option<ExitKind>>>,
+    ) -> Self {
+        Self {
+            cursor: None,
+            last: None,
+            psh: PushStageHelper::new(shared_state, exit_kind),
+        }
+    }
+
+    /// The next corpus entry to replay, if any
+    #[must_use]
+    pub fn cursor(&self) -> Option<CorpusId> {
+        self.cursor
+    }
+}
+
+impl<CS, EM, OT, Z> PushStage<CS, EM, OT, Z> for ReplayPushStage<CS, EM, OT, Z>
+where
+    CS: Scheduler,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId + ProgressReporter,
+    OT: ObserversTuple<CS::State>,
+    CS::State:
+        HasClientPerfMonitor + HasCorpus + HasRand + HasExecutions + HasMetadata + Clone + Debug,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+{
+    #[inline]
+    fn push_stage_helper(&self) -> &PushStageHelper<CS, EM, OT, Z> {
+        &self.psh
+    }
+
+    #[inline]
+    fn push_stage_helper_mut(&mut self) -> &mut PushStageHelper<CS, EM, OT, Z> {
+        &mut self.psh
+    }
+
+    /// Resets the cursor to the first entry of the corpus
+    fn init(
+        &mut self,
+        _fuzzer: &mut Z,
+        state: &mut CS::State,
+        _event_mgr: &mut EM,
+        _observers: &mut OT,
+    ) -> Result<(), Error> {
+        self.cursor = state.corpus().first();
+        self.last = state.corpus().last();
+        Ok(())
+    }
+
+    fn pre_exec(
+        &mut self,
+        _fuzzer: &mut Z,
+        state: &mut CS::State,
+        _event_mgr: &mut EM,
+        _observers: &mut OT,
+    ) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        let corpus_idx = self.cursor?;
+        self.cursor = if Some(corpus_idx) == self.last {
+            None
+        } else {
+            state.corpus().next(corpus_idx)
+        };
+
+        start_timer!(state);
+        let input = match state.corpus().get(corpus_idx) {
+            Ok(testcase) => match testcase.borrow_mut().load_input() {
+                Ok(input) => input.clone(),
+                Err(err) => return Some(Err(err)),
+            },
+            Err(err) => return Some(Err(err)),
+        };
+        mark_feature_time!(state, PerfFeature::GetInputFromCorpus);
+
+        self.push_stage_helper_mut()
+            .current_input
+            .replace(input.clone());
+
+        Some(Ok(input))
+    }
+
+    fn post_exec(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+        last_input: <CS::State as UsesInput>::Input,
+        exit_kind: ExitKind,
+    ) -> Result<(), Error> {
+        let (res, _) =
+            fuzzer.process_execution(state, event_mgr, last_input, observers, &exit_kind, true)?;
+        if res != ExecuteInputResult::None {
+            self.push_stage_helper_mut().record_interesting();
+        }
+        Ok(())
+    }
+
+    /// Any number of inputs, as `pre_exec` already moves the cursor
+    #[inline]
+    fn batch_limit(&self) -> usize {
+        usize::MAX
+    }
+
+    #[inline]
+    fn on_error_reset(&mut self) {
+        self.cursor = None;
+        self.last = None;
+    }
+}
+
+impl<CS, EM, OT, Z> Iterator for ReplayPushStage<CS, EM, OT, Z>
+where
+    CS: Scheduler,
+    EM: EventFirer + EventRestarter + HasEventManagerId + ProgressReporter<State = CS::State>,
+    OT: ObserversTuple<CS::State>,
+    CS::State:
+        HasClientPerfMonitor + HasCorpus + HasRand + HasExecutions + HasMetadata + Clone + Debug,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+{
+    type Item = Result<<CS::State as UsesInput>::Input, Error>;
+
+    fn next(&mut self) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        self.next_std()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, vec::Vec};
+    use core::cell::Cell;
+
+    use super::ReplayPushStage;
+    use crate::{
+        corpus::{Corpus, Testcase},
+        executors::ExitKind,
+        inputs::{BytesInput, HasBytesVec},
+        stages::push::tests::test_shared_state,
+        state::HasCorpus,
+    };
+
+    #[test]
+    fn test_replay_push_stage() {
+        let shared_state = test_shared_state();
+        for bytes in [&b"bbbb"[..], b"cccc"] {
+            shared_state
+                .borrow_mut()
+                .as_mut()
+                .unwrap()
+                .state
+                .corpus_mut()
+                .add(Testcase::new(BytesInput::new(bytes.to_vec())))
+                .unwrap();
+        }
+
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = ReplayPushStage::new(shared_state, exit_kind.clone());
+
+        // Every cycle replays the whole corpus, verbatim
+        for _ in 0..2 {
+            let mut replayed = Vec::new();
+            for input in stage.by_ref() {
+                replayed.push(input.unwrap().bytes().to_vec());
+                exit_kind.set(Some(ExitKind::Ok));
+            }
+            assert_eq!(
+                replayed,
+                [b"aaaa".to_vec(), b"bbbb".to_vec(), b"cccc".to_vec()]
+            );
+            assert_eq!(stage.cursor(), None);
+        }
+    }
+}
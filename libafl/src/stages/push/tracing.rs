@@ -0,0 +1,349 @@
+//! The [`TracingPushStage`] logs each input a wrapped [`PushStage`] executed, and its [`ExitKind`], to disk.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+
+use super::{
+    PushStage, PushStageHelper, PushStageProgress, PushStageSharedState, RcSharedState,
+    SharedStateCell, SmallVec,
+};
+use crate::{
+    corpus::CorpusId,
+    events::{EventFirer, EventRestarter, HasEventManagerId, ProgressReporter},
+    executors::ExitKind,
+    inputs::UsesInput,
+    observers::ObserversTuple,
+    schedulers::Scheduler,
+    state::{HasClientPerfMonitor, HasExecutions, HasMetadata, HasRand},
+    Error, EvaluatorObservers, ExecutionProcessor, HasScheduler,
+};
+
+/// Wraps a [`PushStage`], appending each executed input and its [`ExitKind`] to a log file in `post_exec`.
+/// Each record is the `postcard`-serialized `(input, exit_kind)` pair, prefixed by its length as a
+/// little-endian `u32`. Read the log back with [`read_trace`].
+/// Once `max_records` records got written, further executions are no longer logged.
+/// Apart from the logging, all calls get delegated to the wrapped stage.
+#[derive(Debug)]
+pub struct TracingPushStage<CS, EM, OT, PS, Z, SH = RcSharedState<CS, EM, OT, Z>> {
+    stage: PS,
+    path: PathBuf,
+    file: File,
+    records: usize,
+    max_records: usize,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(CS, EM, OT, Z, SH)>,
+}
+
+impl<CS, EM, OT, PS, Z, SH> TracingPushStage<CS, EM, OT, PS, Z, SH> {
+    /// Wraps the given `stage`, appending its executions to the log at `path`, created if needed
+    pub fn new<P>(stage: PS, path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            stage,
+            path,
+            file,
+            records: 0,
+            max_records: usize::MAX,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Stops logging after `max_records` records got written by this stage
+    #[must_use]
+    pub fn with_max_records(mut self, max_records: usize) -> Self {
+        self.max_records = max_records;
+        self
+    }
+
+    /// The path of the log
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of records written by this stage so far
+    #[must_use]
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// The wrapped [`PushStage`]
+    #[must_use]
+    pub fn stage(&self) -> &PS {
+        &self.stage
+    }
+
+    /// The wrapped [`PushStage`] (mutable)
+    pub fn stage_mut(&mut self) -> &mut PS {
+        &mut self.stage
+    }
+
+    /// Appends a record for `input` and `exit_kind`, unless the cap is reached
+    fn record<I>(&mut self, input: &I, exit_kind: ExitKind) -> Result<(), Error>
+    where
+        I: serde::Serialize,
+    {
+        if self.records >= self.max_records {
+            return Ok(());
+        }
+        let payload = postcard::to_allocvec(&(input, exit_kind))?;
+        let len = u32::try_from(payload.len())
+            .map_err(|_| Error::illegal_argument("Input too large to be traced"))?;
+        let mut record = Vec::with_capacity(4 + payload.len());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&payload);
+        // A single write per record, so that a crash only ever loses whole records
+        self.file.write_all(&record)?;
+        self.records += 1;
+        Ok(())
+    }
+}
+
+/// Reads back the `(input, exit_kind)` records a [`TracingPushStage`] wrote to the log at `path`, in order.
+/// A truncated last record is ignored.
+pub fn read_trace<I, P>(path: P) -> Result<Vec<(I, ExitKind)>, Error>
+where
+    I: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let data = fs::read(path)?;
+    let mut records = vec![];
+    let mut pos = 0;
+    while pos + 4 <= data.len() {
+        let len = u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        pos += 4;
+        if pos + len > data.len() {
+            break;
+        }
+        records.push(postcard::from_bytes(&data[pos..pos + len])?);
+        pos += len;
+    }
+    Ok(records)
+}
+
+impl<CS, EM, OT, PS, Z, SH> PushStage<CS, EM, OT, Z, SH> for TracingPushStage<CS, EM, OT, PS, Z, SH>
+where
+    CS: Scheduler,
+    CS::State: HasClientPerfMonitor + HasRand + HasExecutions + HasMetadata,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId + ProgressReporter,
+    OT: ObserversTuple<CS::State>,
+    PS: PushStage<CS, EM, OT, Z, SH>,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
+{
+    #[inline]
+    fn push_stage_helper(&self) -> &PushStageHelper<CS, EM, OT, Z, SH> {
+        self.stage.push_stage_helper()
+    }
+
+    #[inline]
+    fn push_stage_helper_mut(&mut self) -> &mut PushStageHelper<CS, EM, OT, Z, SH> {
+        self.stage.push_stage_helper_mut()
+    }
+
+    #[inline]
+    fn set_current_corpus_idx(&mut self, corpus_idx: CorpusId) {
+        self.stage.set_current_corpus_idx(corpus_idx);
+    }
+
+    #[inline]
+    fn init(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+    ) -> Result<(), Error> {
+        self.stage.init(fuzzer, state, event_mgr, observers)
+    }
+
+    #[inline]
+    fn pre_exec(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+    ) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        self.stage.pre_exec(fuzzer, state, event_mgr, observers)
+    }
+
+    #[inline]
+    #[allow(clippy::type_complexity)]
+    fn pre_exec_many(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+    ) -> Option<Result<SmallVec<[<CS::State as UsesInput>::Input; 4]>, Error>> {
+        self.stage
+            .pre_exec_many(fuzzer, state, event_mgr, observers)
+    }
+
+    /// Logs the executed `input` and its `exit_kind`, then hands them to the wrapped stage
+    fn post_exec(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+        input: <CS::State as UsesInput>::Input,
+        exit_kind: ExitKind,
+    ) -> Result<(), Error> {
+        self.record(&input, exit_kind)?;
+        self.stage
+            .post_exec(fuzzer, state, event_mgr, observers, input, exit_kind)
+    }
+
+    #[inline]
+    fn batch_limit(&self) -> usize {
+        self.stage.batch_limit()
+    }
+
+    #[inline]
+    fn pre_exec_batch(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+        n: usize,
+    ) -> Vec<Result<<CS::State as UsesInput>::Input, Error>> {
+        self.stage
+            .pre_exec_batch(fuzzer, state, event_mgr, observers, n)
+    }
+
+    /// Logs each executed input of the batch and its exit kind, then hands them to the wrapped stage
+    fn post_exec_batch(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut [OT],
+        inputs: Vec<<CS::State as UsesInput>::Input>,
+        exit_kinds: Vec<ExitKind>,
+    ) -> Result<(), Error> {
+        for (input, exit_kind) in inputs.iter().zip(&exit_kinds) {
+            self.record(input, *exit_kind)?;
+        }
+        self.stage
+            .post_exec_batch(fuzzer, state, event_mgr, observers, inputs, exit_kinds)
+    }
+
+    #[inline]
+    fn deinit(
+        &mut self,
+        fuzzer: &mut Z,
+        state: &mut CS::State,
+        event_mgr: &mut EM,
+        observers: &mut OT,
+    ) -> Result<(), Error> {
+        self.stage.deinit(fuzzer, state, event_mgr, observers)
+    }
+
+    #[inline]
+    fn on_error_reset(&mut self) {
+        self.stage.on_error_reset();
+    }
+
+    #[inline]
+    fn snapshot(&self) -> PushStageProgress {
+        self.stage.snapshot()
+    }
+
+    #[inline]
+    fn restore(&mut self, progress: &PushStageProgress) -> Result<(), Error> {
+        self.stage.restore(progress)
+    }
+}
+
+impl<CS, EM, OT, PS, Z, SH> Iterator for TracingPushStage<CS, EM, OT, PS, Z, SH>
+where
+    CS: Scheduler,
+    CS::State: HasClientPerfMonitor + HasRand + HasExecutions + HasMetadata,
+    EM: EventFirer<State = CS::State> + EventRestarter + HasEventManagerId + ProgressReporter,
+    OT: ObserversTuple<CS::State>,
+    PS: PushStage<CS, EM, OT, Z, SH>,
+    Z: ExecutionProcessor<OT, State = CS::State>
+        + EvaluatorObservers<OT>
+        + HasScheduler<Scheduler = CS>,
+    SH: SharedStateCell<PushStageSharedState<CS, EM, OT, Z>>,
+{
+    type Item = Result<<CS::State as UsesInput>::Input, Error>;
+
+    fn next(&mut self) -> Option<Result<<CS::State as UsesInput>::Input, Error>> {
+        self.next_std()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{rc::Rc, vec::Vec};
+    use core::cell::Cell;
+    use std::{env::temp_dir, fs};
+
+    use super::{read_trace, TracingPushStage};
+    use crate::{
+        executors::ExitKind,
+        inputs::BytesInput,
+        mutators::BitFlipMutator,
+        stages::push::{tests::test_shared_state, StdMutationalPushStage},
+    };
+
+    #[test]
+    fn test_tracing_push_stage() {
+        let path = temp_dir().join("libafl_test_tracing_push_stage.trace");
+        let _ = fs::remove_file(&path);
+
+        let shared_state = test_shared_state();
+        let exit_kind = Rc::new(Cell::new(None));
+        let mut stage = TracingPushStage::new(
+            StdMutationalPushStage::with_iterations(
+                BitFlipMutator::new(),
+                shared_state,
+                exit_kind.clone(),
+                0,
+                5,
+            ),
+            &path,
+        )
+        .unwrap()
+        .with_max_records(3);
+
+        let mut inputs = vec![];
+        let kinds = [
+            ExitKind::Ok,
+            ExitKind::Crash,
+            ExitKind::Timeout,
+            ExitKind::Ok,
+        ];
+        for (i, input) in stage.by_ref().enumerate() {
+            inputs.push(input.unwrap());
+            exit_kind.set(Some(kinds[i % kinds.len()]));
+        }
+        assert_eq!(inputs.len(), 5);
+        assert_eq!(stage.records(), 3);
+
+        let trace: Vec<(BytesInput, ExitKind)> = read_trace(&path).unwrap();
+        assert_eq!(trace.len(), 3);
+        for (i, (input, kind)) in trace.iter().enumerate() {
+            assert_eq!(input, &inputs[i]);
+            assert_eq!(*kind, kinds[i]);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -8,6 +8,7 @@ use core::{
 };
 
 use ahash::AHasher;
+use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "introspection")]
 use crate::monitors::PerfFeature;
@@ -24,7 +25,10 @@ use crate::{
     schedulers::Scheduler,
     stages::Stage,
     start_timer,
-    state::{HasClientPerfMonitor, HasCorpus, HasExecutions, HasMaxSize, UsesState},
+    state::{
+        HasClientPerfMonitor, HasCorpus, HasExecutions, HasMaxSize, HasMetadata, HasSolutions,
+        UsesState,
+    },
     Error, ExecutesInput, ExecutionProcessor, HasFeedback, HasScheduler,
 };
 
@@ -284,6 +288,150 @@ where
     }
 }
 
+/// Marks a solution as already minimized by the [`SolutionMinimizationStage`]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct SolutionMinimizedMetadata;
+
+crate::impl_serdeany!(SolutionMinimizedMetadata);
+
+/// A stage that keeps shrinking already-saved solutions in the background.
+///
+/// Every `interval` invocations, it picks a solution not yet marked with [`SolutionMinimizedMetadata`]
+/// and runs a minimization pass of at most `runs` executions over it.
+/// A reduction is only accepted if it results in the same [`ExitKind`] as the original solution,
+/// and if the feedback created by the factory deems it interesting (e.g., for the same crash signature).
+/// To preserve the [`ExitKind`] only, use a factory returning [`crate::feedbacks::ConstFeedback::True`].
+/// The stored solution is replaced if a smaller one was found.
+///
+/// You must provide at least one mutator that actually reduces size.
+#[derive(Clone, Debug)]
+pub struct SolutionMinimizationStage<E, EM, F, FF, M, OT, Z> {
+    mutator: M,
+    factory: FF,
+    runs: usize,
+    interval: usize,
+    invocations: usize,
+    #[allow(clippy::type_complexity)]
+    phantom: PhantomData<(E, EM, F, OT, Z)>,
+}
+
+impl<E, EM, F, FF, M, OT, Z> UsesState for SolutionMinimizationStage<E, EM, F, FF, M, OT, Z>
+where
+    E: UsesState,
+{
+    type State = E::State;
+}
+
+impl<E, EM, F, FF, M, OT, Z> Stage<E, EM, Z> for SolutionMinimizationStage<E, EM, F, FF, M, OT, Z>
+where
+    E: Executor<EM, Z> + HasObservers<Observers = OT>,
+    E::State: HasSolutions + HasExecutions + HasMaxSize + HasClientPerfMonitor,
+    <E::State as UsesInput>::Input: HasLen,
+    EM: EventFirer<State = E::State>,
+    F: Feedback<E::State>,
+    FF: FeedbackFactory<F, E::State, OT>,
+    M: Mutator<E::Input, E::State>,
+    OT: ObserversTuple<E::State>,
+    Z: ExecutesInput<E, EM, State = E::State>,
+{
+    #[allow(clippy::cast_possible_wrap)] // more than i32 stages on 32 bit system - highly unlikely...
+    fn perform(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        state: &mut E::State,
+        manager: &mut EM,
+        _corpus_idx: CorpusId,
+    ) -> Result<(), Error> {
+        self.invocations += 1;
+        if self.invocations < self.interval {
+            return Ok(());
+        }
+        self.invocations = 0;
+
+        let solutions = state.solutions();
+        let solution_idx = match solutions.ids().find(|idx| {
+            !solutions
+                .get(*idx)
+                .unwrap()
+                .borrow()
+                .has_metadata::<SolutionMinimizedMetadata>()
+        }) {
+            Some(idx) => idx,
+            None => return Ok(()),
+        };
+
+        let mut base = state
+            .solutions()
+            .get(solution_idx)?
+            .borrow_mut()
+            .load_input()?
+            .clone();
+        let orig_len = base.len();
+
+        let orig_exit_kind = fuzzer.execute_input(state, executor, manager, &base)?;
+        let mut feedback = self.factory.create_feedback(executor.observers());
+
+        let orig_max_size = state.max_size();
+        for i in 0..self.runs {
+            let mut input = base.clone();
+            let before_len = input.len();
+            state.set_max_size(before_len);
+
+            self.mutator.mutate(state, &mut input, i as i32)?;
+
+            // skip any mutations that don't reduce size, so we don't waste eval time
+            if input.len() < before_len {
+                let exit_kind = fuzzer.execute_input(state, executor, manager, &input)?;
+                if exit_kind == orig_exit_kind
+                    && feedback.is_interesting(
+                        state,
+                        manager,
+                        &input,
+                        executor.observers(),
+                        &exit_kind,
+                    )?
+                {
+                    base = input;
+                }
+            }
+
+            self.mutator.post_exec(state, i as i32, None)?;
+        }
+        state.set_max_size(orig_max_size);
+
+        if base.len() < orig_len {
+            let mut testcase = Testcase::with_executions(base, *state.executions());
+            testcase.add_metadata(SolutionMinimizedMetadata);
+            state.solutions_mut().replace(solution_idx, testcase)?;
+        } else {
+            state
+                .solutions()
+                .get(solution_idx)?
+                .borrow_mut()
+                .add_metadata(SolutionMinimizedMetadata);
+        }
+
+        Ok(())
+    }
+}
+
+impl<E, EM, F, FF, M, OT, Z> SolutionMinimizationStage<E, EM, F, FF, M, OT, Z> {
+    /// Creates a new [`SolutionMinimizationStage`], minimizing one solution every `interval` invocations,
+    /// using at most `runs` executions each.
+    #[must_use]
+    pub fn new(mutator: M, factory: FF, runs: usize, interval: usize) -> Self {
+        Self {
+            mutator,
+            factory,
+            runs,
+            interval,
+            invocations: 0,
+            phantom: PhantomData,
+        }
+    }
+}
+
 /// A feedback which checks if the hash of the currently observed map is equal to the original hash
 /// provided
 #[derive(Clone, Debug)]
@@ -387,3 +535,91 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        bolts::rands::StdRand,
+        corpus::{Corpus, CorpusId, InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        feedbacks::ConstFeedback,
+        inputs::{BytesInput, HasBytesVec},
+        mutators::BytesDeleteMutator,
+        schedulers::QueueScheduler,
+        stages::{
+            tests::{TestExecutor, TestState},
+            SolutionMinimizationStage, SolutionMinimizedMetadata, Stage,
+        },
+        state::{HasMetadata, HasSolutions, StdState},
+        StdFuzzer,
+    };
+
+    #[test]
+    fn test_solution_minimization() {
+        let mut feedback = ConstFeedback::new(false);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(1337),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let solution_idx = state
+            .solutions_mut()
+            .add(Testcase::new(BytesInput::new(b"aaaaaaaXaaaaaaaa".to_vec())))
+            .unwrap();
+
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut executor = TestExecutor::crash_on_x();
+        let mut mgr = NopEventManager::new();
+        let mut min_stage = SolutionMinimizationStage::new(
+            BytesDeleteMutator::new(),
+            |_: &()| ConstFeedback::True,
+            256,
+            2,
+        );
+
+        let solution_len = |state: &TestState| {
+            state
+                .solutions()
+                .get(solution_idx)
+                .unwrap()
+                .borrow()
+                .input()
+                .as_ref()
+                .unwrap()
+                .bytes()
+                .len()
+        };
+
+        // Nothing happens before the interval passed
+        min_stage
+            .perform(
+                &mut fuzzer,
+                &mut executor,
+                &mut state,
+                &mut mgr,
+                CorpusId(0),
+            )
+            .unwrap();
+        assert_eq!(solution_len(&state), 16);
+
+        min_stage
+            .perform(
+                &mut fuzzer,
+                &mut executor,
+                &mut state,
+                &mut mgr,
+                CorpusId(0),
+            )
+            .unwrap();
+        let testcase = state.solutions().get(solution_idx).unwrap().borrow();
+        assert!(testcase.has_metadata::<SolutionMinimizedMetadata>());
+        let minimized = testcase.input().as_ref().unwrap().bytes().to_vec();
+        drop(testcase);
+        assert!(minimized.len() < 16);
+        assert!(minimized.contains(&b'X'));
+    }
+}
@@ -3,6 +3,7 @@
 use core::{fmt::Debug, marker::PhantomData, time::Duration};
 #[cfg(feature = "std")]
 use std::{
+    ffi::OsStr,
     fs,
     path::{Path, PathBuf},
     vec::Vec,
@@ -12,6 +13,8 @@ use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 #[cfg(test)]
 use crate::bolts::rands::StdRand;
+#[cfg(feature = "std")]
+use crate::corpus::ondisk::load_stored_input;
 use crate::{
     bolts::{
         rands::Rand,
@@ -168,6 +171,11 @@ pub trait HasStartTime {
     fn start_time_mut(&mut self) -> &mut Duration;
 }
 
+/// Parses the contents of a seed's sidecar file into the metadata of its [`crate::corpus::Testcase`], see
+/// [`StdState::load_initial_inputs_with_sidecars`].
+#[cfg(feature = "std")]
+pub type SidecarLoaderFn<'a> = dyn FnMut(&mut SerdeAnyMap, &[u8]) -> Result<(), Error> + 'a;
+
 /// The state a fuzz run.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(bound = "
@@ -358,14 +366,23 @@ where
                 && !self.remaining_initial_files.as_ref().unwrap().is_empty())
     }
 
-    /// List initial inputs from a directory.
-    fn visit_initial_directory(files: &mut Vec<PathBuf>, in_dir: &Path) -> Result<(), Error> {
+    /// List initial inputs from a directory, skipping sidecar files with the given extension, if any.
+    fn visit_initial_directory(
+        files: &mut Vec<PathBuf>,
+        in_dir: &Path,
+        sidecar_extension: Option<&str>,
+    ) -> Result<(), Error> {
         for entry in fs::read_dir(in_dir)? {
             let entry = entry?;
             let path = entry.path();
             if path.file_name().unwrap().to_string_lossy().starts_with('.') {
                 continue;
             }
+            if sidecar_extension.is_some()
+                && path.extension().and_then(OsStr::to_str) == sidecar_extension
+            {
+                continue;
+            }
 
             let attributes = fs::metadata(&path);
 
@@ -378,7 +395,7 @@ where
             if attr.is_file() && attr.len() > 0 {
                 files.push(path);
             } else if attr.is_dir() {
-                Self::visit_initial_directory(files, in_dir)?;
+                Self::visit_initial_directory(files, &path, sidecar_extension)?;
             }
         }
 
@@ -387,6 +404,9 @@ where
 
     /// Loads initial inputs from the passed-in `in_dirs`.
     /// If `forced` is true, will add all testcases, no matter what.
+    /// If `progress` is set, it gets called after each file with the number of files processed so far, and in total.
+    /// If `load_errors` is set, files failing to load get collected there, instead of aborting the load.
+    #[allow(clippy::too_many_arguments)]
     fn load_initial_inputs_custom<E, EM, Z>(
         &mut self,
         fuzzer: &mut Z,
@@ -395,6 +415,9 @@ where
         in_dirs: &[PathBuf],
         forced: bool,
         loader: &mut dyn FnMut(&mut Z, &mut Self, &Path) -> Result<I, Error>,
+        mut sidecar: Option<(&str, &mut SidecarLoaderFn<'_>)>,
+        mut progress: Option<&mut dyn FnMut(usize, usize)>,
+        mut load_errors: Option<&mut Vec<(PathBuf, Error)>>,
     ) -> Result<(), Error>
     where
         E: UsesState<State = Self>,
@@ -407,26 +430,57 @@ where
                 return Ok(());
             }
         } else {
+            let sidecar_extension = sidecar.as_ref().map(|(extension, _)| *extension);
             let mut files = vec![];
             for in_dir in in_dirs {
-                Self::visit_initial_directory(&mut files, in_dir)?;
+                Self::visit_initial_directory(&mut files, in_dir, sidecar_extension)?;
             }
 
             self.remaining_initial_files = Some(files);
         }
 
+        let total = self.remaining_initial_files.as_ref().unwrap().len();
+        let mut processed = 0;
         // TODO option to shuffle the initial files
         while let Some(path) = self.remaining_initial_files.as_mut().unwrap().pop() {
             println!("Loading file {:?} ...", &path);
-            let input = loader(fuzzer, self, &path)?;
+            processed += 1;
+            let input = match (loader(fuzzer, self, &path), load_errors.as_mut()) {
+                (Ok(input), _) => input,
+                (Err(err), Some(load_errors)) => {
+                    load_errors.push((path, err));
+                    if let Some(progress) = progress.as_mut() {
+                        progress(processed, total);
+                    }
+                    continue;
+                }
+                (Err(err), None) => return Err(err),
+            };
+            let mut metadata = SerdeAnyMap::new();
+            if let Some((extension, sidecar_loader)) = sidecar.as_mut() {
+                let mut sidecar_path = path.clone().into_os_string();
+                sidecar_path.push(".");
+                sidecar_path.push(extension);
+                let sidecar_path = PathBuf::from(sidecar_path);
+                // Seeds without a sidecar are fine
+                if sidecar_path.is_file() {
+                    sidecar_loader(&mut metadata, &fs::read(&sidecar_path)?)?;
+                }
+            }
+
             if forced {
-                let _ = fuzzer.add_input(self, executor, manager, input)?;
+                let _ = fuzzer.add_input_with_metadata(self, executor, manager, input, metadata)?;
             } else {
-                let (res, _) = fuzzer.evaluate_input(self, executor, manager, input)?;
+                let (res, _) = fuzzer
+                    .evaluate_input_with_metadata(self, executor, manager, input, metadata)?;
                 if res == ExecuteInputResult::None {
                     println!("File {:?} was not interesting, skipped.", &path);
                 }
             }
+
+            if let Some(progress) = progress.as_mut() {
+                progress(processed, total);
+            }
         }
 
         manager.fire(
@@ -461,7 +515,10 @@ where
             manager,
             in_dirs,
             true,
-            &mut |_, _, path| I::from_file(path),
+            &mut |_, _, path| load_stored_input(path),
+            None,
+            None,
+            None,
         )
     }
 
@@ -484,9 +541,79 @@ where
             manager,
             in_dirs,
             false,
-            &mut |_, _, path| I::from_file(path),
+            &mut |_, _, path| load_stored_input(path),
+            None,
+            None,
+            None,
         )
     }
+
+    /// Loads initial inputs from the passed-in `in_dirs`, together with their sidecar files.
+    /// The sidecar of an input is the file next to it, named `<input>.<sidecar_extension>`.
+    /// For each input, the contents of its sidecar, if any, are passed to `sidecar_loader`,
+    /// which parses them into the metadata of the input's [`crate::corpus::Testcase`].
+    /// If the input gets added to the corpus, its entry holds this metadata before the scheduler sees it,
+    /// see [`Evaluator::evaluate_input_with_metadata`]. Sidecar files are never loaded as inputs.
+    pub fn load_initial_inputs_with_sidecars<E, EM, F, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        manager: &mut EM,
+        in_dirs: &[PathBuf],
+        sidecar_extension: &str,
+        mut sidecar_loader: F,
+    ) -> Result<(), Error>
+    where
+        E: UsesState<State = Self>,
+        EM: EventFirer<State = Self>,
+        F: FnMut(&mut SerdeAnyMap, &[u8]) -> Result<(), Error>,
+        Z: Evaluator<E, EM, State = Self>,
+    {
+        self.load_initial_inputs_custom(
+            fuzzer,
+            executor,
+            manager,
+            in_dirs,
+            false,
+            &mut |_, _, path| load_stored_input(path),
+            Some((sidecar_extension, &mut sidecar_loader)),
+            None,
+            None,
+        )
+    }
+
+    /// Loads initial inputs from the passed-in `in_dirs`, calling `progress` after each file
+    /// with the number of files processed so far and the number of files found, e.g. to render a progress bar.
+    /// All files get listed upfront. Files failing to load do not abort the load,
+    /// but get returned together with their error, once all other files are loaded.
+    pub fn load_initial_inputs_with_progress<E, EM, F, Z>(
+        &mut self,
+        fuzzer: &mut Z,
+        executor: &mut E,
+        manager: &mut EM,
+        in_dirs: &[PathBuf],
+        mut progress: F,
+    ) -> Result<Vec<(PathBuf, Error)>, Error>
+    where
+        E: UsesState<State = Self>,
+        EM: EventFirer<State = Self>,
+        F: FnMut(usize, usize),
+        Z: Evaluator<E, EM, State = Self>,
+    {
+        let mut load_errors = vec![];
+        self.load_initial_inputs_custom(
+            fuzzer,
+            executor,
+            manager,
+            in_dirs,
+            false,
+            &mut |_, _, path| load_stored_input(path),
+            None,
+            Some(&mut progress),
+            Some(&mut load_errors),
+        )?;
+        Ok(load_errors)
+    }
 }
 
 impl<C, I, R, SC> StdState<I, C, R, SC>
@@ -705,6 +832,221 @@ impl<I> HasClientPerfMonitor for NopState<I> {
 #[cfg(test)]
 impl<I> State for NopState<I> where I: Input {}
 
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use core::cell::RefCell;
+    use std::{env::temp_dir, fs, path::PathBuf, string::String, vec::Vec};
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{Corpus, CorpusId, InMemoryCorpus},
+        events::NopEventManager,
+        executors::{ExitKind, InProcessExecutor},
+        feedbacks::ConstFeedback,
+        fuzzer::HasScheduler,
+        inputs::{BytesInput, HasBytesVec, Input},
+        schedulers::{QueueScheduler, Scheduler},
+        state::{HasCorpus, HasMetadata, StdState, UsesState},
+        Error, StdFuzzer,
+    };
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct SeedInfoMetadata {
+        provenance: String,
+        weight: u64,
+    }
+
+    crate::impl_serdeany!(SeedInfoMetadata);
+
+    /// A [`QueueScheduler`] recording the seed weight each new entry has in `on_add`
+    #[derive(Debug)]
+    struct WeightRecordingScheduler {
+        base: QueueScheduler<TestState>,
+        weights: RefCell<Vec<Option<u64>>>,
+    }
+
+    impl UsesState for WeightRecordingScheduler {
+        type State = TestState;
+    }
+
+    impl Scheduler for WeightRecordingScheduler {
+        fn on_add(&self, state: &mut TestState, idx: CorpusId) -> Result<(), Error> {
+            let weight = state
+                .corpus()
+                .get(idx)?
+                .borrow()
+                .metadata()
+                .get::<SeedInfoMetadata>()
+                .map(|meta| meta.weight);
+            self.weights.borrow_mut().push(weight);
+            self.base.on_add(state, idx)
+        }
+
+        fn next(&self, state: &mut TestState) -> Result<CorpusId, Error> {
+            self.base.next(state)
+        }
+    }
+
+    #[test]
+    fn test_load_sidecars() {
+        let in_dir = temp_dir().join("libafl_test_load_sidecars");
+        // Seeds in subdirectories get their sidecars as well
+        let sub_dir = in_dir.join("sub");
+        fs::create_dir_all(&sub_dir).unwrap();
+        fs::write(sub_dir.join("with_sidecar"), b"a").unwrap();
+        fs::write(
+            sub_dir.join("with_sidecar.meta"),
+            br#"{"provenance": "crawler", "weight": 7}"#,
+        )
+        .unwrap();
+        fs::write(in_dir.join("without_sidecar"), b"b").unwrap();
+
+        let mut feedback = ConstFeedback::new(true);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let scheduler = WeightRecordingScheduler {
+            base: QueueScheduler::new(),
+            weights: RefCell::new(Vec::new()),
+        };
+        let mut fuzzer = StdFuzzer::new(scheduler, feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        state
+            .load_initial_inputs_with_sidecars(
+                &mut fuzzer,
+                &mut executor,
+                &mut mgr,
+                &[in_dir.clone()],
+                "meta",
+                |metadata, sidecar| {
+                    let meta: SeedInfoMetadata = serde_json::from_slice(sidecar)?;
+                    metadata.insert(meta);
+                    Ok(())
+                },
+            )
+            .unwrap();
+
+        // The sidecar itself was not loaded as an input
+        assert_eq!(state.corpus().count(), 2);
+        let mut with_sidecar = 0;
+        for idx in state.corpus().ids() {
+            let testcase = state.corpus().get(idx).unwrap().borrow();
+            match testcase.metadata().get::<SeedInfoMetadata>() {
+                Some(meta) => {
+                    assert_eq!(testcase.input().as_ref().unwrap().bytes(), b"a");
+                    assert_eq!(meta.provenance, "crawler");
+                    assert_eq!(meta.weight, 7);
+                    with_sidecar += 1;
+                }
+                None => assert_eq!(testcase.input().as_ref().unwrap().bytes(), b"b"),
+            }
+        }
+        assert_eq!(with_sidecar, 1);
+        // The scheduler saw the metadata of the sidecar when the seed got added
+        let mut weights = fuzzer.scheduler().weights.borrow().clone();
+        weights.sort_unstable();
+        assert_eq!(weights, [None, Some(7)]);
+
+        fs::remove_dir_all(&in_dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_with_progress() {
+        let in_dir = PathBuf::from("target/.test/progress");
+        fs::create_dir_all(&in_dir).unwrap();
+        for name in ["a", "b", "broken", "c"] {
+            fs::write(in_dir.join(name), name).unwrap();
+        }
+
+        let mut feedback = ConstFeedback::new(true);
+        let mut objective = ConstFeedback::new(false);
+        let mut state = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::<BytesInput>::new(),
+            InMemoryCorpus::new(),
+            &mut feedback,
+            &mut objective,
+        )
+        .unwrap();
+        let mut fuzzer = StdFuzzer::new(QueueScheduler::new(), feedback, objective);
+        let mut mgr = NopEventManager::new();
+        let mut harness = |_input: &BytesInput| ExitKind::Ok;
+        let mut executor = InProcessExecutor::new(
+            &mut harness,
+            tuple_list!(),
+            &mut fuzzer,
+            &mut state,
+            &mut mgr,
+        )
+        .unwrap();
+
+        // Failing files get collected, the others still get loaded
+        let mut calls = vec![];
+        let mut load_errors = vec![];
+        state
+            .load_initial_inputs_custom(
+                &mut fuzzer,
+                &mut executor,
+                &mut mgr,
+                &[in_dir.clone()],
+                false,
+                &mut |_, _, path| {
+                    if path.ends_with("broken") {
+                        Err(Error::illegal_argument("broken seed"))
+                    } else {
+                        BytesInput::from_file(path)
+                    }
+                },
+                None,
+                Some(&mut |processed, total| calls.push((processed, total))),
+                Some(&mut load_errors),
+            )
+            .unwrap();
+        assert_eq!(calls, [(1, 4), (2, 4), (3, 4), (4, 4)]);
+        assert_eq!(state.corpus().count(), 3);
+        assert_eq!(load_errors.len(), 1);
+        assert!(load_errors[0].0.ends_with("broken"));
+
+        // Everything was loaded, so nothing gets loaded again
+        let mut calls = 0;
+        let load_errors = state
+            .load_initial_inputs_with_progress(
+                &mut fuzzer,
+                &mut executor,
+                &mut mgr,
+                &[in_dir.clone()],
+                |_, _| calls += 1,
+            )
+            .unwrap();
+        assert_eq!(calls, 0);
+        assert!(load_errors.is_empty());
+
+        fs::remove_dir_all(&in_dir).unwrap();
+    }
+}
+
 #[cfg(feature = "python")]
 #[allow(missing_docs)]
 /// `State` Python bindings
@@ -153,6 +153,9 @@ size_t __libqasan_malloc_usable_size(void *ptr) {
   return ((struct chunk_begin *)p)->requested_size;
 }
 
+static void *malloc_chunk(size_t size);
+static void free_chunk(void *ptr);
+
 void *__libqasan_malloc(size_t size) {
   if (!__libqasan_malloc_initialized) {
     __libqasan_init_malloc();
@@ -170,6 +173,15 @@ void *__libqasan_malloc(size_t size) {
 #endif
   }
 
+  char *p = malloc_chunk(size);
+  if (!p) return NULL;
+
+  QASAN_ALLOC(p, p + size);
+  return p;
+}
+
+/* allocates a chunk, without reporting it to the host with QASAN_ALLOC */
+static void *malloc_chunk(size_t size) {
   int state = QASAN_SWAP(QASAN_DISABLED);  // disable qasan for this thread
 
   struct chunk_begin *p = backend_malloc(sizeof(struct chunk_struct) + size);
@@ -184,7 +196,6 @@ void *__libqasan_malloc(size_t size) {
   p->aligned_orig = NULL;
   p->next = p->prev = NULL;
 
-  QASAN_ALLOC(&p[1], (char *)&p[1] + size);
   QASAN_POISON(p->redzone, REDZONE_SIZE, ASAN_HEAP_LEFT_RZ);
   if (size & (ALLOC_ALIGN_SIZE - 1))
     QASAN_POISON((char *)&p[1] + size,
@@ -207,6 +218,12 @@ void __libqasan_free(void *ptr) {
     return;
 #endif
 
+  free_chunk(ptr);
+  QASAN_DEALLOC(ptr);
+}
+
+/* frees a chunk, without reporting it to the host with QASAN_DEALLOC */
+static void free_chunk(void *ptr) {
   struct chunk_begin *p = ptr;
   p -= 1;
 
@@ -231,7 +248,6 @@ void __libqasan_free(void *ptr) {
     n = (n & ~(ALLOC_ALIGN_SIZE - 1)) + ALLOC_ALIGN_SIZE;
 
   QASAN_POISON(ptr, n, ASAN_HEAP_FREED);
-  QASAN_DEALLOC(ptr);
 }
 
 void *__libqasan_calloc(size_t nmemb, size_t size) {
@@ -255,17 +271,19 @@ void *__libqasan_calloc(size_t nmemb, size_t size) {
 }
 
 void *__libqasan_realloc(void *ptr, size_t size) {
-  char *p = __libqasan_malloc(size);
-  if (!p) return NULL;
+  if (!ptr) return __libqasan_malloc(size);
 
-  if (!ptr) return p;
+  char *p = malloc_chunk(size);
+  if (!p) return NULL;
 
   size_t n = ((struct chunk_begin *)ptr)[-1].requested_size;
   if (size < n) n = size;
 
   __builtin_memcpy(p, ptr, n);
 
-  __libqasan_free(ptr);
+  free_chunk(ptr);
+  // moves the chunk on the host, keeping the context it was allocated at
+  QASAN_REALLOC(ptr, p, p + size);
   return p;
 }
 
@@ -44,8 +44,16 @@ enum {
   QASAN_ACTION_ENABLE,
   QASAN_ACTION_DISABLE,
   QASAN_ACTION_SWAP_STATE,
+  QASAN_ACTION_DUMP_HEAP,
+  QASAN_ACTION_REALLOC,
+  QASAN_ACTION_GET_CHUNK_BOUNDS,
+  QASAN_ACTION_MARK_READ_ONLY,
 };
 
+/* check actions with this bit set return whether the access is valid, without
+ * reporting it */
+#define QASAN_ACTION_DRY_RUN 0x80000000UL
+
 /* shadow map byte values */
 #define ASAN_VALID 0x00
 #define ASAN_PARTIAL1 0x01
@@ -68,6 +76,11 @@ enum {
 #define ASAN_HEAP_LEFT_RZ 0xfa
 #define ASAN_HEAP_RIGHT_RZ 0xfb
 #define ASAN_HEAP_FREED 0xfd
+/* loads are valid, stores are reported */
+#define ASAN_READ_ONLY 0xfe
+/* application-specific kinds, tag in [0, ASAN_CUSTOM_KINDS) */
+#define ASAN_CUSTOM_KINDS 0x2c
+#define ASAN_CUSTOM(tag) (0x80 + (tag))
 
 #define QASAN_ENABLED (0)
 #define QASAN_DISABLED (1)
@@ -87,16 +100,42 @@ enum {
 #define QASAN_LOAD(ptr, len) QASAN_CALL2(QASAN_ACTION_CHECK_LOAD, ptr, len)
 #define QASAN_STORE(ptr, len) QASAN_CALL2(QASAN_ACTION_CHECK_STORE, ptr, len)
 
+#define QASAN_CAN_LOAD(ptr, len) \
+  QASAN_CALL2(QASAN_ACTION_CHECK_LOAD | QASAN_ACTION_DRY_RUN, ptr, len)
+#define QASAN_CAN_STORE(ptr, len) \
+  QASAN_CALL2(QASAN_ACTION_CHECK_STORE | QASAN_ACTION_DRY_RUN, ptr, len)
+
 #define QASAN_POISON(ptr, len, poison_byte) \
   QASAN_CALL3(QASAN_ACTION_POISON, ptr, len, poison_byte)
 #define QASAN_USER_POISON(ptr, len) \
   QASAN_CALL3(QASAN_ACTION_POISON, ptr, len, ASAN_USER)
 #define QASAN_UNPOISON(ptr, len) QASAN_CALL2(QASAN_ACTION_UNPOISON, ptr, len)
+/* unpoisons only the blocks poisoned with poison_byte */
+#define QASAN_UNPOISON_KIND(ptr, len, poison_byte) \
+  QASAN_CALL3(QASAN_ACTION_UNPOISON, ptr, len, poison_byte)
+/* returns 0 if valid, the shadow value if poisoned with an ASAN_CUSTOM kind,
+ * 1 otherwise */
 #define QASAN_IS_POISON(ptr, len) QASAN_CALL2(QASAN_ACTION_IS_POISON, ptr, len)
 
 #define QASAN_ALLOC(start, end) QASAN_CALL2(QASAN_ACTION_ALLOC, start, end)
 #define QASAN_DEALLOC(ptr) QASAN_CALL1(QASAN_ACTION_DEALLOC, ptr)
+/* moves the chunk at old to [start, end), a plain alloc if old is NULL */
+#define QASAN_REALLOC(old, start, end) \
+  QASAN_CALL3(QASAN_ACTION_REALLOC, old, start, end)
+
+/* writes the start and end of the chunk containing ptr to bounds[0] and
+ * bounds[1], returns 0 if allocated, 1 if freed, -1 if ptr is in no chunk */
+#define QASAN_GET_CHUNK_BOUNDS(ptr, bounds) \
+  QASAN_CALL2(QASAN_ACTION_GET_CHUNK_BOUNDS, ptr, bounds)
+
+/* makes the 8 bytes blocks inside [ptr, ptr + len) read-only, until unpoisoned
+ */
+#define QASAN_MARK_READ_ONLY(ptr, len) \
+  QASAN_CALL2(QASAN_ACTION_MARK_READ_ONLY, ptr, len)
 
 #define QASAN_SWAP(state) QASAN_CALL1(QASAN_ACTION_SWAP_STATE, state)
 
+/* writes a summary of the heap to buf, or to stderr if buf is NULL */
+#define QASAN_DUMP_HEAP(buf, len) QASAN_CALL2(QASAN_ACTION_DUMP_HEAP, buf, len)
+
 #endif
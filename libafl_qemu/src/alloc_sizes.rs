@@ -0,0 +1,261 @@
+//! Tracks the allocation sizes of the target, to reward inputs reaching new ones.
+//! The [`crate::QemuAsanHelper`] records each allocation for the [`QemuAllocSizesObserver`],
+//! and the [`NewAllocSizeFeedback`] keeps the inputs with sizes it has not seen before.
+
+use std::{collections::HashSet, fmt::Debug, marker::PhantomData, sync::Mutex};
+
+use libafl::{
+    bolts::tuples::Named,
+    corpus::Testcase,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverName},
+    inputs::UsesInput,
+    observers::{Observer, ObserversTuple},
+    state::{HasClientPerfMonitor, HasNamedMetadata},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+/// The allocation sizes seen in the current run, `None` if no observer is tracking them
+static ALLOC_SIZES: Mutex<Option<HashSet<usize>>> = Mutex::new(None);
+
+/// Records the size of an allocation, if a [`QemuAllocSizesObserver`] is tracking them.
+/// Called by the [`crate::QemuAsanHelper`] for each allocation.
+pub fn record_alloc_size(size: usize) {
+    if let Some(sizes) = ALLOC_SIZES.lock().unwrap().as_mut() {
+        sizes.insert(size);
+    }
+}
+
+/// Observes the set of distinct allocation sizes seen by the [`crate::QemuAsanHelper`] during a run
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QemuAllocSizesObserver {
+    name: String,
+    sizes: Vec<usize>,
+}
+
+impl QemuAllocSizesObserver {
+    /// Creates a new [`QemuAllocSizesObserver`] and enables the tracking of allocation sizes
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        ALLOC_SIZES.lock().unwrap().get_or_insert_with(HashSet::new);
+        Self {
+            name: name.to_string(),
+            sizes: vec![],
+        }
+    }
+
+    /// The distinct allocation sizes of the last run, sorted
+    #[must_use]
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+}
+
+impl<S> Observer<S> for QemuAllocSizesObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.sizes.clear();
+        if let Some(sizes) = ALLOC_SIZES.lock().unwrap().as_mut() {
+            sizes.clear();
+        }
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &S::Input,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        if let Some(sizes) = ALLOC_SIZES.lock().unwrap().as_mut() {
+            self.sizes.extend(sizes.drain());
+        }
+        self.sizes.sort_unstable();
+        Ok(())
+    }
+}
+
+impl Named for QemuAllocSizesObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The cumulative set of allocation sizes seen by a [`NewAllocSizeFeedback`]
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct AllocSizesMetadata {
+    /// The allocation sizes of all the inputs kept so far
+    pub sizes: HashSet<usize>,
+}
+
+libafl::impl_serdeany!(AllocSizesMetadata);
+
+/// A [`Feedback`] reporting an input as interesting if it triggered a previously unseen allocation size.
+/// The new sizes only get added to the [`AllocSizesMetadata`] once the input is kept.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NewAllocSizeFeedback<S> {
+    name: String,
+    observer_name: String,
+    /// The new sizes of the last run, committed in `append_metadata`
+    new_sizes: Vec<usize>,
+    phantom: PhantomData<S>,
+}
+
+impl<S> NewAllocSizeFeedback<S> {
+    /// Creates a new [`NewAllocSizeFeedback`] for the given observer
+    #[must_use]
+    pub fn new(observer: &QemuAllocSizesObserver) -> Self {
+        Self {
+            name: "NewAllocSizeFeedback_".to_string() + observer.name(),
+            observer_name: observer.name().to_string(),
+            new_sizes: vec![],
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> Feedback<S> for NewAllocSizeFeedback<S>
+where
+    S: UsesInput + Debug + HasNamedMetadata + HasClientPerfMonitor,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        state.add_named_metadata(AllocSizesMetadata::default(), &self.name);
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let observer = observers
+            .match_name::<QemuAllocSizesObserver>(&self.observer_name)
+            .expect("A NewAllocSizeFeedback needs a QemuAllocSizesObserver");
+        let seen = state
+            .named_metadata()
+            .get::<AllocSizesMetadata>(&self.name)
+            .ok_or_else(|| Error::key_not_found("AllocSizesMetadata not found".to_string()))?;
+
+        self.new_sizes.clear();
+        self.new_sizes.extend(
+            observer
+                .sizes()
+                .iter()
+                .filter(|size| !seen.sizes.contains(size)),
+        );
+        Ok(!self.new_sizes.is_empty())
+    }
+
+    fn append_metadata(
+        &mut self,
+        state: &mut S,
+        _testcase: &mut Testcase<S::Input>,
+    ) -> Result<(), Error> {
+        let seen = state
+            .named_metadata_mut()
+            .get_mut::<AllocSizesMetadata>(&self.name)
+            .ok_or_else(|| Error::key_not_found("AllocSizesMetadata not found".to_string()))?;
+        seen.sizes.extend(self.new_sizes.drain(..));
+        Ok(())
+    }
+
+    fn discard_metadata(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.new_sizes.clear();
+        Ok(())
+    }
+}
+
+impl<S> Named for NewAllocSizeFeedback<S> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<S> HasObserverName for NewAllocSizeFeedback<S> {
+    fn observer_name(&self) -> &str {
+        &self.observer_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl::{
+        bolts::{rands::StdRand, tuples::tuple_list},
+        corpus::{InMemoryCorpus, Testcase},
+        events::NopEventManager,
+        executors::ExitKind,
+        feedbacks::Feedback,
+        inputs::BytesInput,
+        state::{HasNamedMetadata, StdState},
+    };
+
+    use super::{AllocSizesMetadata, NewAllocSizeFeedback, QemuAllocSizesObserver};
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    fn test_new_alloc_size_feedback() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut (),
+            &mut (),
+        )
+        .unwrap();
+        let mut mgr = NopEventManager::new();
+        let input = BytesInput::new(vec![0]);
+
+        let mut observer = QemuAllocSizesObserver::new("alloc_sizes");
+        let mut feedback = NewAllocSizeFeedback::new(&observer);
+        feedback.init_state(&mut state).unwrap();
+        observer.sizes = vec![8, 16];
+        let observers = tuple_list!(observer);
+
+        let seen = |state: &TestState| {
+            let mut sizes: Vec<usize> = state
+                .named_metadata()
+                .get::<AllocSizesMetadata>("NewAllocSizeFeedback_alloc_sizes")
+                .unwrap()
+                .sizes
+                .iter()
+                .copied()
+                .collect();
+            sizes.sort_unstable();
+            sizes
+        };
+
+        // A discarded input does not add its sizes
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        feedback.discard_metadata(&mut state, &input).unwrap();
+        assert!(seen(&state).is_empty());
+
+        // A kept one does
+        assert!(feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+        feedback
+            .append_metadata(&mut state, &mut Testcase::new(input.clone()))
+            .unwrap();
+        assert_eq!(seen(&state), [8, 16]);
+
+        assert!(!feedback
+            .is_interesting(&mut state, &mut mgr, &input, &observers, &ExitKind::Ok)
+            .unwrap());
+    }
+}
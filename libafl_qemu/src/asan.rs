@@ -1,23 +1,30 @@
 #![allow(clippy::cast_possible_wrap)]
 
+use core::ops::Range;
 use std::{
     collections::{HashMap, HashSet},
-    env, fs,
+    env,
+    ffi::OsStr,
+    fs,
+    path::Path,
     sync::Mutex,
 };
 
-use libafl::{inputs::UsesInput, state::HasMetadata};
+use libafl::{inputs::UsesInput, state::HasMetadata, Error};
 use libc::{
     c_void, MAP_ANON, MAP_FAILED, MAP_FIXED, MAP_NORESERVE, MAP_PRIVATE, PROT_READ, PROT_WRITE,
 };
 use meminterval::{Interval, IntervalTree};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    emu::{Emulator, MemAccessInfo, SyscallHookResult},
+    alloc_sizes,
+    emu::{Emulator, MemAccessInfo, MmapPerms, SyscallHookResult},
     helper::{QemuHelper, QemuHelperTuple, QemuInstrumentationFilter},
     hooks::QemuHooks,
-    GuestAddr,
+    stack_hash::StackHashRecorder,
+    GuestAddr, Regs,
 };
 
 // TODO at some point, merge parts with libafl_frida
@@ -34,10 +41,21 @@ pub const SHADOW_OFFSET: isize = 0x7fff8000;
 
 pub const QASAN_FAKESYS_NR: i32 = 0xa2a4;
 
+/// Flag bit of the QASan action number: a [`QasanAction::CheckLoad`] or [`QasanAction::CheckStore`]
+/// with this bit set only returns `1` if the access is valid, and `0` otherwise, without reporting it
+pub const QASAN_ACTION_DRY_RUN: u64 = 1 << 31;
+
+/// Returned by the QASan syscall for a request with an invalid argument, such as an unknown poison kind,
+/// as `-EINVAL`, like other syscalls do. The request gets ignored.
+pub const QASAN_INVALID_ARGUMENT: u64 = -(libc::EINVAL as i64) as u64;
+
 pub const SHADOW_PAGE_SIZE: usize = 4096;
 pub const SHADOW_PAGE_MASK: GuestAddr = !(SHADOW_PAGE_SIZE as GuestAddr - 1);
 
-#[derive(IntoPrimitive, TryFromPrimitive, Debug, Clone, Copy)]
+/// An action requested by `libqasan` through the QASan syscall.
+/// Serialized as its `u64` value, the same the C side uses.
+#[derive(IntoPrimitive, TryFromPrimitive, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(into = "u64", try_from = "u64")]
 #[repr(u64)]
 pub enum QasanAction {
     CheckLoad,
@@ -51,32 +69,171 @@ pub enum QasanAction {
     Enable,
     Disable,
     SwapState,
+    DumpHeap,
+    Realloc,
+    GetChunkBounds,
+    MarkReadOnly,
+}
+
+/// The number of application-specific poison kinds, see [`PoisonKind::Custom`]
+pub const CUSTOM_POISON_KINDS: u8 = 0x2c;
+/// The shadow value of [`PoisonKind::Custom`] `0`. The custom kinds use the unused shadow values `0x80..=0xab`.
+pub const CUSTOM_POISON_BASE: u8 = 0x80;
+
+/// The tag of a [`PoisonKind::Custom`] kind, always below [`CUSTOM_POISON_KINDS`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomPoisonTag(u8);
+
+impl CustomPoisonTag {
+    /// The tag, or `None` if it is not below [`CUSTOM_POISON_KINDS`]
+    #[must_use]
+    pub fn new(tag: u8) -> Option<Self> {
+        (tag < CUSTOM_POISON_KINDS).then_some(Self(tag))
+    }
+
+    /// The raw tag
+    #[must_use]
+    pub fn tag(self) -> u8 {
+        self.0
+    }
+
+    /// The shadow value of the tag, [`CUSTOM_POISON_BASE`] plus the tag
+    #[must_use]
+    pub fn shadow_value(self) -> u8 {
+        CUSTOM_POISON_BASE + self.0
+    }
 }
 
-#[derive(IntoPrimitive, TryFromPrimitive, Debug, Clone, Copy)]
-#[repr(i8)]
+impl TryFrom<u8> for CustomPoisonTag {
+    type Error = Error;
+
+    fn try_from(tag: u8) -> Result<Self, Error> {
+        Self::new(tag).ok_or_else(|| {
+            Error::illegal_argument(format!(
+                "Custom poison kind {tag} out of range, must be below {CUSTOM_POISON_KINDS}"
+            ))
+        })
+    }
+}
+
+/// The kind of a shadow byte. Serialized as its shadow value, the same the C side uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "i8", try_from = "i8")]
 pub enum PoisonKind {
-    Valid = 0,
-    Partial1 = 1,
-    Partial2 = 2,
-    Partial3 = 3,
-    Partial4 = 4,
-    Partial5 = 5,
-    Partial6 = 6,
-    Partial7 = 7,
-    ArrayCookie = -84,  // 0xac
-    StackRz = -16,      // 0xf0
-    StackLeftRz = -15,  // 0xf1
-    StackMidRz = -14,   // 0xf2
-    StackRightRz = -13, // 0xf3
-    StacKFreed = -11,   // 0xf5
-    StackOOScope = -8,  // 0xf8
-    GlobalRz = -7,      // 0xf9
-    HeapRz = -23,       // 0xe9
-    User = -9,          // 0xf7
-    HeapLeftRz = -6,    // 0xfa
-    HeapRightRz = -5,   // 0xfb
-    HeapFreed = -3,     // 0xfd
+    Valid,
+    Partial1,
+    Partial2,
+    Partial3,
+    Partial4,
+    Partial5,
+    Partial6,
+    Partial7,
+    ArrayCookie,
+    StackRz,
+    StackLeftRz,
+    StackMidRz,
+    StackRightRz,
+    StacKFreed,
+    StackOOScope,
+    GlobalRz,
+    HeapRz,
+    User,
+    HeapLeftRz,
+    HeapRightRz,
+    HeapFreed,
+    /// Valid to load from, but not to store to, see [`QemuAsanHelper::mark_read_only`]
+    ReadOnly,
+    /// An application-specific kind, tagged `0..CUSTOM_POISON_KINDS`.
+    /// It gets stored as the shadow value [`CUSTOM_POISON_BASE`] plus the tag, so that it survives the reverse lookup.
+    Custom(CustomPoisonTag),
+}
+
+impl PoisonKind {
+    /// The application-specific kind with the given tag, or `None` if the tag is not below [`CUSTOM_POISON_KINDS`]
+    #[must_use]
+    pub fn custom(tag: u8) -> Option<Self> {
+        CustomPoisonTag::new(tag).map(PoisonKind::Custom)
+    }
+
+    /// If the [`QemuAsanHelper`] poisons with this kind itself, to track the heap
+    fn is_heap(self) -> bool {
+        matches!(
+            self,
+            PoisonKind::HeapLeftRz | PoisonKind::HeapRightRz | PoisonKind::HeapFreed
+        )
+    }
+}
+
+impl From<PoisonKind> for i8 {
+    /// The shadow value of the poison kind
+    fn from(kind: PoisonKind) -> Self {
+        match kind {
+            PoisonKind::Valid => 0,
+            PoisonKind::Partial1 => 1,
+            PoisonKind::Partial2 => 2,
+            PoisonKind::Partial3 => 3,
+            PoisonKind::Partial4 => 4,
+            PoisonKind::Partial5 => 5,
+            PoisonKind::Partial6 => 6,
+            PoisonKind::Partial7 => 7,
+            PoisonKind::ArrayCookie => -84,  // 0xac
+            PoisonKind::StackRz => -16,      // 0xf0
+            PoisonKind::StackLeftRz => -15,  // 0xf1
+            PoisonKind::StackMidRz => -14,   // 0xf2
+            PoisonKind::StackRightRz => -13, // 0xf3
+            PoisonKind::StacKFreed => -11,   // 0xf5
+            PoisonKind::StackOOScope => -8,  // 0xf8
+            PoisonKind::GlobalRz => -7,      // 0xf9
+            PoisonKind::HeapRz => -23,       // 0xe9
+            PoisonKind::User => -9,          // 0xf7
+            PoisonKind::HeapLeftRz => -6,    // 0xfa
+            PoisonKind::HeapRightRz => -5,   // 0xfb
+            PoisonKind::HeapFreed => -3,     // 0xfd
+            PoisonKind::ReadOnly => -2,      // 0xfe
+            PoisonKind::Custom(tag) => tag.shadow_value() as i8,
+        }
+    }
+}
+
+impl TryFrom<i8> for PoisonKind {
+    type Error = Error;
+
+    /// The poison kind of the shadow value
+    fn try_from(value: i8) -> Result<Self, Error> {
+        Ok(match value {
+            0 => PoisonKind::Valid,
+            1 => PoisonKind::Partial1,
+            2 => PoisonKind::Partial2,
+            3 => PoisonKind::Partial3,
+            4 => PoisonKind::Partial4,
+            5 => PoisonKind::Partial5,
+            6 => PoisonKind::Partial6,
+            7 => PoisonKind::Partial7,
+            -84 => PoisonKind::ArrayCookie,
+            -16 => PoisonKind::StackRz,
+            -15 => PoisonKind::StackLeftRz,
+            -14 => PoisonKind::StackMidRz,
+            -13 => PoisonKind::StackRightRz,
+            -11 => PoisonKind::StacKFreed,
+            -8 => PoisonKind::StackOOScope,
+            -7 => PoisonKind::GlobalRz,
+            -23 => PoisonKind::HeapRz,
+            -9 => PoisonKind::User,
+            -6 => PoisonKind::HeapLeftRz,
+            -5 => PoisonKind::HeapRightRz,
+            -3 => PoisonKind::HeapFreed,
+            -2 => PoisonKind::ReadOnly,
+            _ => match CustomPoisonTag::new((value as u8).wrapping_sub(CUSTOM_POISON_BASE)) {
+                Some(tag) => PoisonKind::Custom(tag),
+                None => {
+                    return Err(Error::illegal_argument(format!(
+                        "Unknown shadow value {:#x}",
+                        value as u8
+                    )))
+                }
+            },
+        })
+    }
 }
 
 pub enum AsanError {
@@ -84,13 +241,71 @@ pub enum AsanError {
     Write(GuestAddr, usize),
     BadFree(GuestAddr, Option<Interval<GuestAddr>>),
     MemLeak(Interval<GuestAddr>),
+    /// Poisoning already poisoned bytes with another kind, reported if the [`QemuAsanHelper`] is strict
+    PoisonConflict(PoisonConflict),
 }
 
 pub type AsanErrorCallback = Box<dyn FnMut(&Emulator, AsanError)>;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsanAccessKind {
+    Read,
+    Write,
+}
+
+/// An invalid memory access, recorded instead of crashing if the [`QemuAsanHelper`] does not abort on errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AsanViolation {
+    pub addr: GuestAddr,
+    pub size: usize,
+    pub kind: AsanAccessKind,
+    pub pc: GuestAddr,
+}
+
+/// Poisoning of bytes already poisoned with another kind, recorded unless the [`QemuAsanHelper`] is strict,
+/// see [`QemuAsanHelper::set_strict_poison`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoisonConflict {
+    pub addr: GuestAddr,
+    pub size: usize,
+    /// The kind the bytes were poisoned with before
+    pub previous: PoisonKind,
+    pub kind: PoisonKind,
+}
+
+/// The return addresses of a call stack, innermost first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CallContext {
+    pub addresses: Vec<GuestAddr>,
+}
+
+/// The call contexts an allocation was made and freed at
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AllocTreeItem {
+    pub alloc_ctx: CallContext,
+    pub free_ctx: Option<CallContext>,
+}
+
+/// A chunk tracked by the allocator, as summarized by [`QasanAction::DumpHeap`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapChunk {
+    pub start: GuestAddr,
+    pub end: GuestAddr,
+    pub freed: bool,
+}
+
+/// The chunks tracked by the allocator at some point, with their call contexts, and the shadow of the
+/// pages poisoned so far, as taken by [`QemuAsanHelper::snapshot_allocations`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AllocSnapshot {
+    pub chunks: Vec<(Range<GuestAddr>, AllocTreeItem)>,
+    /// The shadow of each page poisoned so far, by page address. Only tracked if the shadow gets snapshotted.
+    pub shadow: HashMap<GuestAddr, Vec<i8>>,
+}
+
 pub struct AsanGiovese {
-    pub alloc_tree: Mutex<IntervalTree<GuestAddr, ()>>,
-    pub saved_tree: IntervalTree<GuestAddr, ()>,
+    pub alloc_tree: Mutex<IntervalTree<GuestAddr, AllocTreeItem>>,
+    pub saved_tree: IntervalTree<GuestAddr, AllocTreeItem>,
     pub error_callback: Option<AsanErrorCallback>,
     pub dirty_shadow: Mutex<HashSet<GuestAddr>>,
     pub saved_shadow: HashMap<GuestAddr, Vec<i8>>,
@@ -173,6 +388,32 @@ impl AsanGiovese {
         }
     }
 
+    /// Like [`Self::is_invalid_access`], but for a load, for which [`PoisonKind::ReadOnly`] blocks are valid.
+    /// Checks byte by byte, so it is only meant to double check the loads found invalid by the other checks.
+    #[must_use]
+    pub fn is_invalid_load(emu: &Emulator, addr: GuestAddr, n: usize) -> bool {
+        let read_only = i8::from(PoisonKind::ReadOnly);
+        (0..n as GuestAddr).any(|offset| {
+            let addr = addr.wrapping_add(offset);
+            match Self::shadow_byte(emu, addr) {
+                0 => false,
+                k @ 1..=7 => (addr & 7) as i8 >= k,
+                k => k != read_only,
+            }
+        })
+    }
+
+    /// The raw shadow byte of the 8 bytes aligned block `addr` is in
+    #[inline]
+    #[must_use]
+    pub fn shadow_byte(emu: &Emulator, addr: GuestAddr) -> i8 {
+        unsafe {
+            let h = emu.g2h::<*const c_void>(addr) as isize;
+            let shadow_addr = ((h >> 3) as *mut i8).offset(SHADOW_OFFSET);
+            *shadow_addr
+        }
+    }
+
     #[inline]
     #[must_use]
     pub fn is_invalid_access_8(emu: &Emulator, addr: GuestAddr) -> bool {
@@ -281,22 +522,44 @@ impl AsanGiovese {
         }
     }
 
+    /// Unpoisons `addr..addr + n`.
+    /// The shadow can only mark the first bytes of an 8 bytes block as valid, so if `addr` is unaligned,
+    /// the bytes of its block before `addr` get unpoisoned too. If `addr + n` is unaligned, its block only
+    /// gets partially unpoisoned, up to `addr + n`, unless more of it was valid already.
     #[inline]
     #[allow(clippy::must_use_candidate)]
     #[allow(clippy::cast_sign_loss)]
     pub fn unpoison(emu: &Emulator, addr: GuestAddr, n: usize) -> bool {
+        if n == 0 {
+            return true;
+        }
+        let end = addr.wrapping_add(n as GuestAddr);
+        let blocks = ((end >> 3) - (addr >> 3)) as usize + usize::from(end & 7 != 0);
         unsafe {
-            let n = n as isize;
-            let mut start = addr;
-            let end = start.wrapping_add(n as GuestAddr);
+            let h = emu.g2h::<*const c_void>(addr) as isize;
+            let shadow_addr = ((h >> 3) as *mut i8).offset(SHADOW_OFFSET);
+            Self::unpoison_shadow(std::slice::from_raw_parts_mut(shadow_addr, blocks), addr, n);
+        }
+        true
+    }
 
-            while start < end {
-                let h = emu.g2h::<*const c_void>(start) as isize;
-                let shadow_addr = ((h >> 3) as *mut i8).offset(SHADOW_OFFSET);
-                *shadow_addr = 0;
-                start = (start).wrapping_add(8);
+    /// Unpoisons `addr..addr + n` in `shadow`, the shadow bytes of the blocks from the one of `addr` on,
+    /// the same way as [`Self::unpoison`] does. A partially valid block gets the shadow value of its number of
+    /// valid bytes, i.e. [`PoisonKind::Partial1`] to [`PoisonKind::Partial7`].
+    fn unpoison_shadow(shadow: &mut [i8], addr: GuestAddr, n: usize) {
+        let end = addr.wrapping_add(n as GuestAddr);
+        let end_offset = (end & 7) as i8;
+        let full_blocks = ((end >> 3) - (addr >> 3)) as usize;
+
+        for shadow_byte in &mut shadow[..full_blocks] {
+            *shadow_byte = 0;
+        }
+        if end_offset > 0 {
+            // `addr` is followed by valid bytes up to `end` in the last block
+            let shadow_byte = &mut shadow[full_blocks];
+            if *shadow_byte != 0 {
+                *shadow_byte = (*shadow_byte).max(end_offset);
             }
-            true
         }
     }
 
@@ -344,15 +607,108 @@ impl AsanGiovese {
     }
 
     pub fn report_and_crash(&mut self, emu: &Emulator, error: AsanError) {
+        self.report_and_crash_with_context(emu, error, &CallContext::default());
+    }
+
+    /// Hands `error` to the error callback. Without one, prints a report with the call stack `ctx` of
+    /// the error, symbolized by the resolver set with [`register_symbol_resolver`], and aborts.
+    pub fn report_and_crash_with_context(
+        &mut self,
+        emu: &Emulator,
+        error: AsanError,
+        ctx: &CallContext,
+    ) {
         if let Some(cb) = self.error_callback.as_mut() {
             (cb)(emu, error);
         } else {
+            eprintln!("{}", format_report(&error, ctx));
             std::process::abort();
         }
     }
 
     pub fn alloc_insert(&mut self, start: GuestAddr, end: GuestAddr) {
-        self.alloc_tree.lock().unwrap().insert(start..end, ());
+        self.alloc_insert_with_context(start, end, CallContext::default());
+    }
+
+    /// Tracks the chunk `start..end`, allocated at `alloc_ctx`.
+    /// The freed chunks it overlaps got reused, so they are not tracked anymore.
+    pub fn alloc_insert_with_context(
+        &mut self,
+        start: GuestAddr,
+        end: GuestAddr,
+        alloc_ctx: CallContext,
+    ) {
+        let mut tree = self.alloc_tree.lock().unwrap();
+        if start < end {
+            let reused: Vec<_> = tree
+                .query(start..end)
+                .filter(|entry| entry.value.free_ctx.is_some())
+                .map(|entry| *entry.interval)
+                .collect();
+            for interval in reused {
+                tree.delete(interval);
+            }
+        }
+        tree.insert(
+            start..end,
+            AllocTreeItem {
+                alloc_ctx,
+                free_ctx: None,
+            },
+        );
+    }
+
+    /// The redzones of `redzone_size` bytes before and after the chunk `start..end`,
+    /// shrunk so that they do not cover any other live chunk
+    #[must_use]
+    pub fn clamped_redzones(
+        &self,
+        start: GuestAddr,
+        end: GuestAddr,
+        redzone_size: usize,
+    ) -> (Range<GuestAddr>, Range<GuestAddr>) {
+        if redzone_size == 0 {
+            return (start..start, end..end);
+        }
+        let tree = self.alloc_tree.lock().unwrap();
+        let live = |range: Range<GuestAddr>| {
+            tree.query(range.clone())
+                .map(|entry| (*entry.interval, entry.value.free_ctx.is_none()))
+                .filter(|(chunk, live)| *live && chunk.start < range.end && chunk.end > range.start)
+                .map(|(chunk, _)| chunk)
+                .collect::<Vec<_>>()
+        };
+        let mut left_start = start.saturating_sub(redzone_size as GuestAddr);
+        for chunk in live(left_start..start) {
+            left_start = left_start.max(chunk.end.min(start));
+        }
+        let mut right_end = end.saturating_add(redzone_size as GuestAddr);
+        for chunk in live(end..right_end) {
+            right_end = right_end.min(chunk.start.max(end));
+        }
+        (left_start..start, end..right_end)
+    }
+
+    /// Records the call context the chunk starting at `start` got freed at
+    pub fn alloc_set_free_context(&mut self, start: GuestAddr, free_ctx: CallContext) {
+        let mut tree = self.alloc_tree.lock().unwrap();
+        for entry in tree.query_mut(start..=start) {
+            if entry.interval.start == start {
+                entry.value.free_ctx = Some(free_ctx);
+                break;
+            }
+        }
+    }
+
+    /// The allocation and free call contexts of the chunk containing `query`, if any
+    #[must_use]
+    pub fn alloc_contexts(&self, query: GuestAddr) -> Option<AllocTreeItem> {
+        self.alloc_tree
+            .lock()
+            .unwrap()
+            .query(query..=query)
+            .next()
+            .map(|entry| entry.value.clone())
     }
 
     pub fn alloc_remove(&mut self, start: GuestAddr, end: GuestAddr) {
@@ -366,6 +722,83 @@ impl AsanGiovese {
         }
     }
 
+    /// Stops tracking the freed chunks, keeping the live ones
+    pub fn alloc_remove_freed(&mut self) {
+        let mut tree = self.alloc_tree.lock().unwrap();
+        let mut found = vec![];
+        for entry in tree.query(0..GuestAddr::MAX) {
+            if entry.value.free_ctx.is_some() {
+                found.push(*entry.interval);
+            }
+        }
+        for interval in found {
+            tree.delete(interval);
+        }
+    }
+
+    /// The chunks currently tracked by the allocator, sorted by start address
+    #[must_use]
+    pub fn heap_chunks(&self) -> Vec<HeapChunk> {
+        let mut chunks: Vec<HeapChunk> = self
+            .alloc_tree
+            .lock()
+            .unwrap()
+            .query(0..GuestAddr::MAX)
+            .map(|entry| HeapChunk {
+                start: entry.interval.start,
+                end: entry.interval.end,
+                freed: entry.value.free_ctx.is_some(),
+            })
+            .collect();
+        chunks.sort_by_key(|chunk| chunk.start);
+        chunks
+    }
+
+    /// The chunk containing `query`, if any, freed or not
+    #[must_use]
+    pub fn chunk_at(&self, query: GuestAddr) -> Option<HeapChunk> {
+        self.alloc_tree
+            .lock()
+            .unwrap()
+            .query(query..=query)
+            .next()
+            .map(|entry| HeapChunk {
+                start: entry.interval.start,
+                end: entry.interval.end,
+                freed: entry.value.free_ctx.is_some(),
+            })
+    }
+
+    /// Saves the whole chunk table, freed chunks included, as the `saved_tree`, and returns it
+    /// with the `saved_shadow`, see [`Self::save_shadow`]
+    pub fn alloc_snapshot(&mut self) -> AllocSnapshot {
+        self.saved_tree = self.alloc_tree.lock().unwrap().clone();
+        AllocSnapshot {
+            chunks: self
+                .saved_tree
+                .query(0..GuestAddr::MAX)
+                .map(|entry| {
+                    (
+                        entry.interval.start..entry.interval.end,
+                        entry.value.clone(),
+                    )
+                })
+                .collect(),
+            shadow: self.saved_shadow.clone(),
+        }
+    }
+
+    /// Replaces the whole chunk table with the one in `snapshot`, which becomes the `saved_tree`,
+    /// and its shadow pages become the `saved_shadow`, to be written back by [`Self::restore_shadow`]
+    pub fn alloc_restore(&mut self, snapshot: &AllocSnapshot) {
+        self.saved_tree = IntervalTree::new();
+        for (interval, item) in &snapshot.chunks {
+            self.saved_tree.insert(interval.clone(), item.clone());
+        }
+        *self.alloc_tree.lock().unwrap() = self.saved_tree.clone();
+        self.saved_shadow = snapshot.shadow.clone();
+    }
+
     #[must_use]
     pub fn alloc_search(&mut self, query: GuestAddr) -> Option<Interval<GuestAddr>> {
         self.alloc_tree
@@ -378,18 +811,44 @@ impl AsanGiovese {
 
     pub fn snapshot(&mut self, emu: &Emulator) {
         if self.snapshot_shadow {
-            let set = self.dirty_shadow.lock().unwrap();
-
-            for &page in set.iter() {
-                let data = Self::get_shadow_page(emu, page).to_vec();
-                self.saved_shadow.insert(page, data);
-            }
+            self.save_shadow(emu);
 
             let tree = self.alloc_tree.lock().unwrap();
             self.saved_tree = tree.clone();
         }
     }
 
+    /// Saves the shadow of the pages poisoned since the last rollback into the `saved_shadow`.
+    /// The pages only get tracked if the shadow gets snapshotted.
+    pub fn save_shadow(&mut self, emu: &Emulator) {
+        let set = self.dirty_shadow.lock().unwrap();
+
+        for &page in set.iter() {
+            let data = Self::get_shadow_page(emu, page).to_vec();
+            self.saved_shadow.insert(page, data);
+        }
+    }
+
+    /// Writes the `saved_shadow` back, and unpoisons the other pages poisoned since the last rollback.
+    /// The saved pages stay tracked, so that restoring another snapshot unpoisons them.
+    pub fn restore_shadow(&mut self, emu: &Emulator) {
+        let mut set = self.dirty_shadow.lock().unwrap();
+
+        for &page in set.iter() {
+            if !self.saved_shadow.contains_key(&page) {
+                Self::unpoison_page(emu, page);
+            }
+        }
+        set.clear();
+
+        for (&page, data) in &self.saved_shadow {
+            Self::get_shadow_page(emu, page).copy_from_slice(data);
+            if self.snapshot_shadow {
+                set.insert(page);
+            }
+        }
+    }
+
     pub fn rollback(&mut self, emu: &Emulator, detect_leaks: bool) {
         let mut leaks = vec![];
 
@@ -429,6 +888,212 @@ impl AsanGiovese {
     }
 }
 
+/// Reads a guest pointer from the guest memory
+fn read_guest_ptr(emu: &Emulator, addr: GuestAddr) -> GuestAddr {
+    let mut buf = [0; core::mem::size_of::<GuestAddr>()];
+    unsafe { emu.read_mem(addr, &mut buf) };
+    if cfg!(feature = "be") {
+        GuestAddr::from_be_bytes(buf)
+    } else {
+        GuestAddr::from_le_bytes(buf)
+    }
+}
+
+/// The bytes of the guest pointer `ptr`, in the byte order of the guest
+fn guest_ptr_bytes(ptr: GuestAddr) -> [u8; core::mem::size_of::<GuestAddr>()] {
+    if cfg!(feature = "be") {
+        ptr.to_be_bytes()
+    } else {
+        ptr.to_le_bytes()
+    }
+}
+
+/// If `addr..addr + len` lies in a single writable mapping, out of the `start..end` ranges and permissions
+fn is_writable<I>(mappings: I, addr: GuestAddr, len: usize) -> bool
+where
+    I: IntoIterator<Item = (Range<GuestAddr>, MmapPerms)>,
+{
+    let Some(end) = addr.checked_add(len as GuestAddr) else {
+        return false;
+    };
+    mappings
+        .into_iter()
+        .any(|(range, perms)| perms.is_w() && range.start <= addr && end <= range.end)
+}
+
+/// Walks the frame pointer chain, pushing up to `max_frames` return addresses.
+/// Only the readable mapping holding the stack pointer gets read.
+#[cfg(any(cpu_target = "x86_64", cpu_target = "i386", cpu_target = "aarch64"))]
+fn walk_frames(emu: &Emulator, max_frames: usize, addresses: &mut Vec<GuestAddr>) {
+    #[cfg(cpu_target = "x86_64")]
+    let fp: GuestAddr = emu.read_reg(Regs::Rbp).unwrap_or(0);
+    #[cfg(cpu_target = "i386")]
+    let fp: GuestAddr = emu.read_reg(Regs::Ebp).unwrap_or(0);
+    #[cfg(cpu_target = "aarch64")]
+    let fp: GuestAddr = emu.read_reg(Regs::Fp).unwrap_or(0);
+
+    let sp: GuestAddr = emu.read_reg(Regs::Sp).unwrap_or(0);
+    let Some(stack_end) = emu
+        .mappings()
+        .find(|map| map.flags().is_r() && (map.start()..map.end()).contains(&sp))
+        .map(|map| map.end())
+    else {
+        return;
+    };
+    walk_frame_chain(
+        sp..stack_end,
+        fp,
+        max_frames,
+        |addr| read_guest_ptr(emu, addr),
+        addresses,
+    );
+}
+
+/// Follows the frame pointer chain from `fp`, pushing up to `max_frames` return addresses.
+/// Frames live in `stack`, above the stack pointer, and each caller frame above its callee,
+/// so the walk stops at the first frame pointer that is not aligned, inside `stack`, and increasing.
+/// `read` only gets asked for the guest pointers inside `stack`.
+#[cfg(any(cpu_target = "x86_64", cpu_target = "i386", cpu_target = "aarch64"))]
+fn walk_frame_chain<F>(
+    stack: Range<GuestAddr>,
+    mut fp: GuestAddr,
+    max_frames: usize,
+    read: F,
+    addresses: &mut Vec<GuestAddr>,
+) where
+    F: Fn(GuestAddr) -> GuestAddr,
+{
+    let ptr_size = core::mem::size_of::<GuestAddr>() as GuestAddr;
+    let try_read = |addr: GuestAddr| {
+        let end = addr.checked_add(ptr_size)?;
+        (stack.start <= addr && end <= stack.end).then(|| read(addr))
+    };
+    while addresses.len() < max_frames && fp != 0 && fp % ptr_size == 0 {
+        let Some(ret_addr) = fp.checked_add(ptr_size).and_then(try_read) else {
+            break;
+        };
+        let Some(next_fp) = try_read(fp) else {
+            break;
+        };
+        if ret_addr == 0 {
+            break;
+        }
+        addresses.push(ret_addr);
+        if next_fp <= fp {
+            break;
+        }
+        fp = next_fp;
+    }
+}
+
+/// Without a usable frame pointer chain, only the return address in the link register is captured
+#[cfg(any(cpu_target = "arm", cpu_target = "mips"))]
+fn walk_frames(emu: &Emulator, max_frames: usize, addresses: &mut Vec<GuestAddr>) {
+    #[cfg(cpu_target = "arm")]
+    let ret_addr: GuestAddr = emu.read_reg(Regs::Lr).unwrap_or(0);
+    #[cfg(cpu_target = "mips")]
+    let ret_addr: GuestAddr = emu.read_reg(Regs::Ra).unwrap_or(0);
+
+    if max_frames > 0 && ret_addr != 0 {
+        addresses.push(ret_addr);
+    }
+}
+
+/// A symbol resolver, turning a guest address into a printable location
+pub type SymbolResolver = Box<dyn Fn(u64) -> Option<String> + Send>;
+
+static SYMBOL_RESOLVER: Mutex<Option<SymbolResolver>> = Mutex::new(None);
+
+/// Registers the resolver used to symbolize addresses in ASan reports, replacing the previous one
+pub fn register_symbol_resolver(resolver: SymbolResolver) {
+    *SYMBOL_RESOLVER.lock().unwrap() = Some(resolver);
+}
+
+/// Resolves `addr` with the registered resolver, if any
+#[must_use]
+pub fn resolve_symbol(addr: u64) -> Option<String> {
+    SYMBOL_RESOLVER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|resolver| resolver(addr))
+}
+
+/// A resolver printing addresses as `module+0x...`, relative to the lowest mapping of their module.
+/// Only the modules mapped in the guest at the time of the call are known to it.
+#[must_use]
+#[allow(clippy::unnecessary_cast)]
+pub fn module_offset_resolver(emulator: &Emulator) -> SymbolResolver {
+    let maps: Vec<(u64, u64, String)> = emulator
+        .mappings()
+        .filter_map(|map| {
+            let path = map.path().filter(|path| !path.is_empty())?;
+            Some((map.start() as u64, map.end() as u64, path.to_string()))
+        })
+        .collect();
+    let modules: Vec<(u64, u64, u64, String)> = maps
+        .iter()
+        .map(|(start, end, path)| {
+            let base = maps
+                .iter()
+                .filter(|(_, _, other)| other == path)
+                .map(|(start, _, _)| *start)
+                .min()
+                .unwrap();
+            let name = Path::new(path)
+                .file_name()
+                .map_or_else(|| path.clone(), |name| name.to_string_lossy().into_owned());
+            (*start, *end, base, name)
+        })
+        .collect();
+
+    Box::new(move |addr| {
+        modules
+            .iter()
+            .find(|(start, end, _, _)| (*start..*end).contains(&addr))
+            .map(|(_, _, base, name)| format!("{name}+{:#x}", addr - base))
+    })
+}
+
+/// Formats `addr` for ASan reports, followed by its symbol if the registered resolver knows it
+#[must_use]
+#[allow(clippy::unnecessary_cast)]
+pub fn format_addr(addr: GuestAddr) -> String {
+    match resolve_symbol(addr as u64) {
+        Some(symbol) => format!("{addr:#x} ({symbol})"),
+        None => format!("{addr:#x}"),
+    }
+}
+
+/// Formats the report printed for `error`, with the symbolized call stack `ctx`
+#[must_use]
+pub fn format_report(error: &AsanError, ctx: &CallContext) -> String {
+    let mut report = match error {
+        AsanError::Read(addr, size) => {
+            format!("ERROR: AddressSanitizer: invalid read of size {size} at {addr:#x}")
+        }
+        AsanError::Write(addr, size) => {
+            format!("ERROR: AddressSanitizer: invalid write of size {size} at {addr:#x}")
+        }
+        AsanError::BadFree(addr, _) => {
+            format!("ERROR: AddressSanitizer: bad free of {addr:#x}")
+        }
+        AsanError::MemLeak(interval) => format!(
+            "ERROR: AddressSanitizer: leak of {} bytes at {:#x}",
+            interval.end - interval.start,
+            interval.start
+        ),
+        AsanError::PoisonConflict(conflict) => format!(
+            "ERROR: AddressSanitizer: poisoning {} bytes at {:#x} as {:?}, already poisoned as {:?}",
+            conflict.size, conflict.addr, conflict.kind, conflict.previous
+        ),
+    };
+    for (idx, addr) in ctx.addresses.iter().enumerate() {
+        report.push_str(&format!("\n    #{idx} {}", format_addr(*addr)));
+    }
+    report
+}
+
 static mut ASAN_INITED: bool = false;
 
 pub fn init_with_asan(args: &mut Vec<String>, env: &mut [(String, String)]) -> Emulator {
@@ -496,26 +1161,191 @@ pub struct QemuAsanHelper {
     empty: bool,
     rt: AsanGiovese,
     filter: QemuInstrumentationFilter,
+    max_frames: usize,
+    redzone_size: usize,
+    abort_on_error: bool,
+    violations: Vec<AsanViolation>,
+    /// The pc of the memory access currently being checked
+    access_pc: GuestAddr,
+    /// If `post_exec` only forgets the freed chunks, see [`QemuAsanHelper::reset_freed_only`]
+    keep_live_chunks: bool,
+    /// If poisoning bytes already poisoned with another kind gets reported, see [`QemuAsanHelper::set_strict_poison`]
+    strict_poison: bool,
+    poison_conflicts: Vec<PoisonConflict>,
+    /// Where the stack hashes of the reports get recorded, see [`QemuAsanHelper::set_stack_hash_recorder`]
+    stack_hash_recorder: Option<StackHashRecorder>,
 }
 
-impl QemuAsanHelper {
+/// Builds a [`QemuAsanHelper`], combining its options
+pub struct QemuAsanHelperBuilder {
+    filter: QemuInstrumentationFilter,
+    options: QemuAsanOptions,
+    error_callback: Option<AsanErrorCallback>,
+    enabled: bool,
+    abort_on_error: bool,
+    redzone_size: usize,
+    max_frames: usize,
+    keep_live_chunks: bool,
+    strict_poison: bool,
+    stack_hash_recorder: Option<StackHashRecorder>,
+}
+
+impl QemuAsanHelperBuilder {
+    /// Creates a new builder, instrumenting everything with [`QemuAsanOptions::Snapshot`]
     #[must_use]
-    pub fn new(filter: QemuInstrumentationFilter, options: QemuAsanOptions) -> Self {
+    pub fn new() -> Self {
+        Self {
+            filter: QemuInstrumentationFilter::None,
+            options: QemuAsanOptions::Snapshot,
+            error_callback: None,
+            enabled: true,
+            abort_on_error: true,
+            redzone_size: 0,
+            max_frames: 0,
+            keep_live_chunks: false,
+            strict_poison: false,
+            stack_hash_recorder: None,
+        }
+    }
+
+    /// The [`QemuInstrumentationFilter`] of the helper
+    #[must_use]
+    pub fn filter(mut self, filter: QemuInstrumentationFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// The [`QemuAsanOptions`] of the helper
+    #[must_use]
+    pub fn options(mut self, options: QemuAsanOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Calls `error_callback` on errors, instead of the default report
+    #[must_use]
+    pub fn error_callback(mut self, error_callback: AsanErrorCallback) -> Self {
+        self.error_callback = Some(error_callback);
+        self
+    }
+
+    /// If the helper starts enabled, see [`QemuAsanHelper::set_enabled`]
+    #[must_use]
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// If the helper aborts on invalid accesses, see [`QemuAsanHelper::set_abort_on_error`]
+    #[must_use]
+    pub fn abort_on_error(mut self, abort_on_error: bool) -> Self {
+        self.abort_on_error = abort_on_error;
+        self
+    }
+
+    /// The size of the redzones around allocations, see [`QemuAsanHelper::with_redzone`]
+    #[must_use]
+    pub fn redzone(mut self, redzone_size: usize) -> Self {
+        self.redzone_size = redzone_size;
+        self
+    }
+
+    /// The maximum number of frames of the call contexts, see [`QemuAsanHelper::set_max_frames`]
+    #[must_use]
+    pub fn max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    /// If live chunks are kept across runs, see [`QemuAsanHelper::with_keep_live_chunks`]
+    #[must_use]
+    pub fn keep_live_chunks(mut self, keep_live_chunks: bool) -> Self {
+        self.keep_live_chunks = keep_live_chunks;
+        self
+    }
+
+    /// If poisoning already poisoned bytes with another kind gets reported, see [`QemuAsanHelper::set_strict_poison`]
+    #[must_use]
+    pub fn strict_poison(mut self, strict_poison: bool) -> Self {
+        self.strict_poison = strict_poison;
+        self
+    }
+
+    /// Records the stack hashes of the reports, see [`QemuAsanHelper::set_stack_hash_recorder`]
+    #[must_use]
+    pub fn stack_hash_recorder(mut self, recorder: StackHashRecorder) -> Self {
+        self.stack_hash_recorder = Some(recorder);
+        self
+    }
+
+    /// Builds the [`QemuAsanHelper`]. Panics if the `ASan` runtime was not initialized using [`init_with_asan`].
+    #[must_use]
+    pub fn build(self) -> QemuAsanHelper {
         assert!(unsafe { ASAN_INITED }, "The ASan runtime is not initialized, use init_with_asan(...) instead of just Emulator::new(...)");
-        let (snapshot, detect_leaks) = match options {
+        self.build_unchecked()
+    }
+
+    /// Builds the [`QemuAsanHelper`] without asserting that the `ASan` runtime was initialized
+    fn build_unchecked(self) -> QemuAsanHelper {
+        let (snapshot, detect_leaks) = match self.options {
             QemuAsanOptions::None => (false, false),
             QemuAsanOptions::Snapshot => (true, false),
             QemuAsanOptions::DetectLeaks => (false, true),
             QemuAsanOptions::SnapshotDetectLeaks => (true, true),
         };
-        Self {
-            enabled: true,
+        let rt = match self.error_callback {
+            Some(error_callback) => AsanGiovese::with_error_callback(snapshot, error_callback),
+            None => AsanGiovese::new(snapshot),
+        };
+        QemuAsanHelper {
+            enabled: self.enabled,
             detect_leaks,
             empty: true,
-            rt: AsanGiovese::new(snapshot),
-            filter,
+            rt,
+            filter: self.filter,
+            max_frames: self.max_frames,
+            redzone_size: self.redzone_size,
+            abort_on_error: self.abort_on_error,
+            violations: vec![],
+            access_pc: 0,
+            keep_live_chunks: self.keep_live_chunks,
+            strict_poison: self.strict_poison,
+            poison_conflicts: vec![],
+            stack_hash_recorder: self.stack_hash_recorder,
         }
     }
+}
+
+impl Default for QemuAsanHelperBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QemuAsanHelper {
+    /// A [`QemuAsanHelperBuilder`] to combine the options of a new [`QemuAsanHelper`]
+    #[must_use]
+    pub fn builder() -> QemuAsanHelperBuilder {
+        QemuAsanHelperBuilder::new()
+    }
+
+    #[must_use]
+    pub fn new(filter: QemuInstrumentationFilter, options: QemuAsanOptions) -> Self {
+        Self::builder().filter(filter).options(options).build()
+    }
+
+    /// Creates a new, disabled [`QemuAsanHelper`] without asserting that the `ASan` runtime was initialized
+    /// using [`init_with_asan`], for tests and for runtimes initialized out-of-band.
+    ///
+    /// Enabling it and running the read/write hooks without a real `ASan` runtime is undefined behavior.
+    #[must_use]
+    pub fn new_unchecked(filter: QemuInstrumentationFilter, options: QemuAsanOptions) -> Self {
+        Self::builder()
+            .filter(filter)
+            .options(options)
+            .enabled(false)
+            .build_unchecked()
+    }
 
     #[must_use]
     pub fn with_error_callback(
@@ -523,20 +1353,167 @@ impl QemuAsanHelper {
         error_callback: AsanErrorCallback,
         options: QemuAsanOptions,
     ) -> Self {
-        assert!(unsafe { ASAN_INITED }, "The ASan runtime is not initialized, use init_with_asan(...) instead of just Emulator::new(...)");
-        let (snapshot, detect_leaks) = match options {
-            QemuAsanOptions::None => (false, false),
-            QemuAsanOptions::Snapshot => (true, false),
-            QemuAsanOptions::DetectLeaks => (false, true),
-            QemuAsanOptions::SnapshotDetectLeaks => (true, true),
-        };
-        Self {
-            enabled: true,
-            detect_leaks,
-            empty: true,
-            rt: AsanGiovese::with_error_callback(snapshot, error_callback),
-            filter,
+        Self::builder()
+            .filter(filter)
+            .options(options)
+            .error_callback(error_callback)
+            .build()
+    }
+
+    /// Creates a new [`QemuAsanHelper`] only instrumenting the module `name`, a path or a file name,
+    /// as found in the guest's memory mappings. Returns an error if no mapping belongs to the module.
+    #[allow(clippy::unnecessary_cast)]
+    pub fn for_module(
+        emulator: &Emulator,
+        name: &str,
+        options: QemuAsanOptions,
+    ) -> Result<Self, Error> {
+        let mut filter = QemuInstrumentationFilter::AllowList(vec![]);
+        filter.extend(
+            emulator
+                .mappings()
+                .filter(|map| {
+                    map.path().map_or(false, |path| {
+                        path == name || Path::new(path).file_name() == Some(OsStr::new(name))
+                    })
+                })
+                .map(|map| map.start() as u64..map.end() as u64),
+        );
+        if matches!(&filter, QemuInstrumentationFilter::AllowList(l) if l.is_empty()) {
+            return Err(Error::illegal_state(format!(
+                "The module {name} is not mapped in the guest"
+            )));
+        }
+        Ok(Self::new(filter, options))
+    }
+
+    /// Poisons `redzone_size` bytes before and after each allocation, to catch linear overflows,
+    /// and the whole chunk once it gets freed
+    #[must_use]
+    pub fn with_redzone(mut self, redzone_size: usize) -> Self {
+        self.redzone_size = redzone_size;
+        self
+    }
+
+    /// Replaces the [`QemuInstrumentationFilter`] of this helper, checking it against the guest's
+    /// memory mappings first if `validate` is set. See [`QemuInstrumentationFilter::validate_against`].
+    #[allow(clippy::unnecessary_cast)]
+    pub fn with_instrumentation_filter(
+        mut self,
+        emulator: &Emulator,
+        filter: QemuInstrumentationFilter,
+        validate: bool,
+    ) -> Result<Self, Error> {
+        if validate {
+            filter.validate_against(
+                emulator
+                    .mappings()
+                    .map(|map| map.start() as u64..map.end() as u64),
+            )?;
+        }
+        self.filter = filter;
+        Ok(self)
+    }
+
+    /// If `true`, `post_exec` calls [`Self::reset_freed_only`] instead of [`Self::reset`],
+    /// for persistent-mode harnesses that keep their heap across runs
+    #[must_use]
+    pub fn with_keep_live_chunks(mut self, keep_live_chunks: bool) -> Self {
+        self.keep_live_chunks = keep_live_chunks;
+        self
+    }
+
+    #[must_use]
+    pub fn keep_live_chunks(&self) -> bool {
+        self.keep_live_chunks
+    }
+
+    #[must_use]
+    pub fn redzone_size(&self) -> usize {
+        self.redzone_size
+    }
+
+    #[must_use]
+    pub fn abort_on_error(&self) -> bool {
+        self.abort_on_error
+    }
+
+    /// If `false`, invalid reads and writes get recorded as [`AsanViolation`]s instead of crashing,
+    /// so that a single run can surface multiple bugs. Drain them using [`Self::take_violations`].
+    pub fn set_abort_on_error(&mut self, abort_on_error: bool) {
+        self.abort_on_error = abort_on_error;
+    }
+
+    /// The violations recorded so far, if not aborting on errors
+    #[must_use]
+    pub fn violations(&self) -> &[AsanViolation] {
+        &self.violations
+    }
+
+    /// Drains the violations recorded during the current run, for example in `post_exec`.
+    /// Violations not drained get discarded at the start of the next run.
+    pub fn take_violations(&mut self) -> Vec<AsanViolation> {
+        core::mem::take(&mut self.violations)
+    }
+
+    #[must_use]
+    pub fn strict_poison(&self) -> bool {
+        self.strict_poison
+    }
+
+    /// If `true`, [`Self::poison`] reports an [`AsanError::PoisonConflict`] like any other error when poisoning
+    /// bytes already poisoned with another [`PoisonKind`], as when two helpers, or nested calls, poison the same
+    /// region. Else, the conflict gets recorded as [`PoisonConflict`] and the later kind wins. Drain them using [`Self::take_poison_conflicts`].
+    /// The heap redzones and freed chunks the helper poisons itself may overlap each other without conflict.
+    pub fn set_strict_poison(&mut self, strict_poison: bool) {
+        self.strict_poison = strict_poison;
+    }
+
+    /// The poison conflicts recorded so far, if not strict
+    #[must_use]
+    pub fn poison_conflicts(&self) -> &[PoisonConflict] {
+        &self.poison_conflicts
+    }
+
+    /// Drains the poison conflicts recorded during the current run.
+    /// Conflicts not drained get discarded at the start of the next run.
+    pub fn take_poison_conflicts(&mut self) -> Vec<PoisonConflict> {
+        core::mem::take(&mut self.poison_conflicts)
+    }
+
+    /// Reports an error to the runtime, first recording the hash of the call stack at `pc`
+    /// for a [`crate::stack_hash::QemuAsanStackHashObserver`]. Bad frees have no `pc`, only the call stack.
+    fn report_error(&mut self, emulator: &Emulator, pc: GuestAddr, error: AsanError) {
+        let mut ctx = self.call_context(emulator);
+        if let Some(recorder) = &self.stack_hash_recorder {
+            recorder.record(pc, &ctx.addresses);
+        }
+        if pc != 0 {
+            ctx.addresses.insert(0, pc);
+        }
+        self.rt.report_and_crash_with_context(emulator, error, &ctx);
+    }
+
+    /// Reports an invalid read or write, recording it as [`AsanViolation`] unless aborting on errors
+    fn report_access(&mut self, emulator: &Emulator, error: AsanError) {
+        if self.abort_on_error {
+            self.report_error(emulator, self.access_pc, error);
+            return;
         }
+        let (addr, size, kind) = match error {
+            AsanError::Read(addr, size) => (addr, size, AsanAccessKind::Read),
+            AsanError::Write(addr, size) => (addr, size, AsanAccessKind::Write),
+            _ => {
+                self.report_error(emulator, self.access_pc, error);
+                return;
+            }
+        };
+        self.violations.push(AsanViolation {
+            addr,
+            size,
+            kind,
+            pc: self.access_pc,
+        });
     }
 
     #[must_use]
@@ -553,97 +1530,358 @@ impl QemuAsanHelper {
         self.enabled = enabled;
     }
 
-    pub fn alloc(&mut self, _emulator: &Emulator, start: GuestAddr, end: GuestAddr) {
-        self.rt.alloc_insert(start, end);
+    /// The maximum number of return addresses captured for the allocation and free call contexts
+    #[must_use]
+    pub fn max_frames(&self) -> usize {
+        self.max_frames
+    }
+
+    /// Sets the maximum number of return addresses captured for the allocation and free call contexts.
+    /// With `0`, the default, no call stack is walked and the contexts stay empty.
+    pub fn set_max_frames(&mut self, max_frames: usize) {
+        self.max_frames = max_frames;
+    }
+
+    /// Records the hash of the call stack of each report into `recorder`, the one of a
+    /// [`crate::stack_hash::QemuAsanStackHashObserver`], or stops recording them with `None`
+    pub fn set_stack_hash_recorder(&mut self, recorder: Option<StackHashRecorder>) {
+        self.stack_hash_recorder = recorder;
+    }
+
+    /// The allocation and free call contexts of the chunk containing `addr`, if any
+    #[must_use]
+    pub fn alloc_contexts(&self, addr: GuestAddr) -> Option<AllocTreeItem> {
+        self.rt.alloc_contexts(addr)
+    }
+
+    /// Captures the current call context, walking up to `max_frames` frames
+    #[must_use]
+    pub fn call_context(&self, emulator: &Emulator) -> CallContext {
+        let mut addresses = vec![];
+        if self.max_frames > 0 {
+            walk_frames(emulator, self.max_frames, &mut addresses);
+        }
+        CallContext { addresses }
+    }
+
+    /// Tracks the chunk `start..end`. It gets unpoisoned, as it may reuse the memory of a freed chunk,
+    /// before poisoning its redzones.
+    pub fn alloc(&mut self, emulator: &Emulator, start: GuestAddr, end: GuestAddr) {
+        alloc_sizes::record_alloc_size((end - start) as usize);
+        let ctx = self.call_context(emulator);
+        self.rt.alloc_insert_with_context(start, end, ctx);
+        self.unpoison(emulator, start, (end - start) as usize);
+        self.poison_redzones(emulator, start, end);
+    }
+
+    /// Poisons the redzones around the chunk `start..end`, if enabled, without covering other live chunks
+    fn poison_redzones(&mut self, emulator: &Emulator, start: GuestAddr, end: GuestAddr) {
+        if self.redzone_size == 0 {
+            return;
+        }
+        let (left, right) = self.rt.clamped_redzones(start, end, self.redzone_size);
+        self.poison(
+            emulator,
+            left.start,
+            (left.end - left.start) as usize,
+            PoisonKind::HeapLeftRz,
+        );
+        self.poison(
+            emulator,
+            right.start,
+            (right.end - right.start) as usize,
+            PoisonKind::HeapRightRz,
+        );
+    }
+
+    /// The chunks currently tracked by the allocator, sorted by start address
+    #[must_use]
+    pub fn heap_chunks(&self) -> Vec<HeapChunk> {
+        self.rt.heap_chunks()
+    }
+
+    /// Captures the chunks currently tracked by the allocator, freed ones included, to get back to them
+    /// with [`Self::restore_allocations`], e.g., to return to a known heap baseline in persistent mode.
+    /// If the helper snapshots the shadow memory, the shadow of the poisoned pages gets captured too.
+    /// The capture also becomes the baseline the helper rolls back to. Costs O(number of chunks).
+    pub fn snapshot_allocations(&mut self, emulator: &Emulator) -> AllocSnapshot {
+        if self.rt.snapshot_shadow {
+            self.rt.save_shadow(emulator);
+        }
+        self.rt.alloc_snapshot()
+    }
+
+    /// Makes the allocator track exactly the chunks in `snapshot`, forgetting all others,
+    /// and restores the shadow memory it captured. Costs O(number of chunks).
+    pub fn restore_allocations(&mut self, emulator: &Emulator, snapshot: &AllocSnapshot) {
+        self.rt.alloc_restore(snapshot);
+        self.rt.restore_shadow(emulator);
+    }
+
+    /// The tracked chunk containing `addr`, if any. Freed chunks stay tracked until they get reused.
+    #[must_use]
+    pub fn chunk_bounds(&self, addr: GuestAddr) -> Option<HeapChunk> {
+        self.rt.chunk_at(addr)
+    }
+
+    /// Dumps a summary of the heap, one `start-end allocated|freed` line per chunk.
+    /// The summary gets written to the guest buffer at `buf`, truncated to `len` bytes,
+    /// or to stderr if `buf` is null. Returns the length of the whole summary.
+    pub fn dump_heap(&self, emulator: &Emulator, buf: GuestAddr, len: usize) -> usize {
+        let mut summary = String::new();
+        for chunk in self.heap_chunks() {
+            summary.push_str(&format!(
+                "{:#x}-{:#x} {}\n",
+                chunk.start,
+                chunk.end,
+                if chunk.freed { "freed" } else { "allocated" }
+            ));
+        }
+        if buf == 0 {
+            eprint!("{summary}");
+        } else {
+            let n = summary.len().min(len);
+            unsafe { emulator.write_mem(buf, &summary.as_bytes()[..n]) };
+        }
+        summary.len()
     }
 
     pub fn dealloc(&mut self, emulator: &Emulator, addr: GuestAddr) {
         let chunk = self.rt.alloc_search(addr);
         if let Some(ck) = chunk {
-            if ck.start != addr {
+            if ck.start == addr {
+                let ctx = self.call_context(emulator);
+                self.rt.alloc_set_free_context(addr, ctx);
+                if self.redzone_size > 0 {
+                    self.poison(
+                        emulator,
+                        ck.start,
+                        (ck.end - ck.start) as usize,
+                        PoisonKind::HeapFreed,
+                    );
+                }
+            } else {
                 // Free not the start of the chunk
-                self.rt
-                    .report_and_crash(emulator, AsanError::BadFree(addr, Some(ck)));
+                self.report_error(emulator, 0, AsanError::BadFree(addr, Some(ck)));
             }
         } else {
             // Free of wild ptr
-            self.rt
-                .report_and_crash(emulator, AsanError::BadFree(addr, None));
+            self.report_error(emulator, 0, AsanError::BadFree(addr, None));
         }
     }
 
+    /// Moves the chunk starting at `old` to `start..end`, keeping the call context it was allocated at.
+    /// If the chunk moved, the old region gets poisoned as freed, with redzones enabled as in [`QemuAsanHelper::dealloc`]. Else, the region it shrank by gets poisoned,
+    /// and the region it grew by unpoisoned. A null `old` is a plain [`QemuAsanHelper::alloc`].
+    pub fn realloc(
+        &mut self,
+        emulator: &Emulator,
+        old: GuestAddr,
+        start: GuestAddr,
+        end: GuestAddr,
+    ) {
+        if old == 0 {
+            self.alloc(emulator, start, end);
+            return;
+        }
+        let Some(ck) = self.rt.alloc_search(old) else {
+            // Realloc of wild ptr
+            self.report_error(emulator, 0, AsanError::BadFree(old, None));
+            return;
+        };
+        if ck.start != old {
+            // Realloc not the start of the chunk
+            self.report_error(emulator, 0, AsanError::BadFree(old, Some(ck)));
+            return;
+        }
+
+        alloc_sizes::record_alloc_size((end - start) as usize);
+        let alloc_ctx = self
+            .rt
+            .alloc_contexts(old)
+            .map(|item| item.alloc_ctx)
+            .unwrap_or_default();
+        if start == old {
+            self.rt.alloc_remove(ck.start, ck.end);
+            if end < ck.end {
+                self.poison(
+                    emulator,
+                    end,
+                    (ck.end - end) as usize,
+                    PoisonKind::HeapRightRz,
+                );
+            } else if end > ck.end {
+                self.unpoison(emulator, ck.end, (end - ck.end) as usize);
+            }
+        } else {
+            let free_ctx = self.call_context(emulator);
+            self.rt.alloc_set_free_context(old, free_ctx);
+            if self.redzone_size > 0 {
+                self.poison(
+                    emulator,
+                    ck.start,
+                    (ck.end - ck.start) as usize,
+                    PoisonKind::HeapFreed,
+                );
+            }
+            self.unpoison(emulator, start, (end - start) as usize);
+        }
+        self.rt.alloc_insert_with_context(start, end, alloc_ctx);
+        self.poison_redzones(emulator, start, end);
+    }
+
     #[allow(clippy::unused_self)]
     #[must_use]
     pub fn is_poisoned(&self, emulator: &Emulator, addr: GuestAddr, size: usize) -> bool {
         AsanGiovese::is_invalid_access(emulator, addr, size)
     }
 
+    /// Returns `true` if loading `size` bytes at `addr` is valid, without ever reporting the access.
+    /// Unlike [`Self::is_poisoned`], this takes the fast checks for accesses of `1`, `2`, `4` or `8` bytes.
+    /// The check does not depend on the helper being enabled. [`PoisonKind::ReadOnly`] blocks are valid.
+    #[must_use]
+    pub fn check_load(&self, emulator: &Emulator, addr: GuestAddr, size: usize) -> bool {
+        self.check_store(emulator, addr, size)
+            || !AsanGiovese::is_invalid_load(emulator, addr, size)
+    }
+
+    /// Returns `true` if storing `size` bytes at `addr` is valid, without ever reporting the access.
+    /// Unlike for [`Self::check_load`], [`PoisonKind::ReadOnly`] blocks are invalid.
+    #[must_use]
+    #[allow(clippy::unused_self)]
+    pub fn check_store(&self, emulator: &Emulator, addr: GuestAddr, size: usize) -> bool {
+        !match size {
+            1 => AsanGiovese::is_invalid_access_1(emulator, addr),
+            2 => AsanGiovese::is_invalid_access_2(emulator, addr),
+            4 => AsanGiovese::is_invalid_access_4(emulator, addr),
+            8 => AsanGiovese::is_invalid_access_8(emulator, addr),
+            _ => AsanGiovese::is_invalid_access(emulator, addr, size),
+        }
+    }
+
+    /// Why the byte at `addr` is poisoned, or `None` if it is valid.
+    /// For partially addressable blocks, `addr` is only poisoned past the valid bytes at the start of the block.
+    /// Shadow values not known as a [`PoisonKind`] also return `None`.
+    #[allow(clippy::unused_self)]
+    #[must_use]
+    pub fn poison_kind(&self, emulator: &Emulator, addr: GuestAddr) -> Option<PoisonKind> {
+        let k = AsanGiovese::shadow_byte(emulator, addr);
+        match k {
+            0 => None,
+            1..=7 if ((addr & 7) as i8) < k => None,
+            _ => PoisonKind::try_from(k).ok(),
+        }
+    }
+
+    /// The [`PoisonKind`] of the first poisoned byte in `addr..addr + size`, if any is poisoned with a known kind
+    #[must_use]
+    pub fn first_poison_kind(
+        &self,
+        emulator: &Emulator,
+        addr: GuestAddr,
+        size: usize,
+    ) -> Option<PoisonKind> {
+        (0..size as GuestAddr).find_map(|offset| self.poison_kind(emulator, addr + offset))
+    }
+
+    /// Unpoisons the 8 bytes aligned blocks in `addr..addr + size` poisoned with `kind`, keeping the others.
+    /// Useful to lift the application-specific [`PoisonKind::Custom`] kinds only.
+    pub fn unpoison_kind(
+        &mut self,
+        emulator: &Emulator,
+        addr: GuestAddr,
+        size: usize,
+        kind: PoisonKind,
+    ) {
+        let value = i8::from(kind);
+        let end = addr + size as GuestAddr;
+        let mut block = addr & !7;
+        while block < end {
+            if AsanGiovese::shadow_byte(emulator, block) == value {
+                AsanGiovese::unpoison(emulator, block, 8);
+            }
+            block += 8;
+        }
+    }
+
     pub fn read_1(&mut self, emulator: &Emulator, addr: GuestAddr) {
-        if self.enabled() && AsanGiovese::is_invalid_access_1(emulator, addr) {
-            self.rt.report_and_crash(emulator, AsanError::Read(addr, 1));
+        if self.enabled()
+            && AsanGiovese::is_invalid_access_1(emulator, addr)
+            && AsanGiovese::is_invalid_load(emulator, addr, 1)
+        {
+            self.report_access(emulator, AsanError::Read(addr, 1));
         }
     }
 
     pub fn read_2(&mut self, emulator: &Emulator, addr: GuestAddr) {
-        if self.enabled() && AsanGiovese::is_invalid_access_2(emulator, addr) {
-            self.rt.report_and_crash(emulator, AsanError::Read(addr, 2));
+        if self.enabled()
+            && AsanGiovese::is_invalid_access_2(emulator, addr)
+            && AsanGiovese::is_invalid_load(emulator, addr, 2)
+        {
+            self.report_access(emulator, AsanError::Read(addr, 2));
         }
     }
 
     pub fn read_4(&mut self, emulator: &Emulator, addr: GuestAddr) {
-        if self.enabled() && AsanGiovese::is_invalid_access_4(emulator, addr) {
-            self.rt.report_and_crash(emulator, AsanError::Read(addr, 4));
+        if self.enabled()
+            && AsanGiovese::is_invalid_access_4(emulator, addr)
+            && AsanGiovese::is_invalid_load(emulator, addr, 4)
+        {
+            self.report_access(emulator, AsanError::Read(addr, 4));
         }
     }
 
     pub fn read_8(&mut self, emulator: &Emulator, addr: GuestAddr) {
-        if self.enabled() && AsanGiovese::is_invalid_access_8(emulator, addr) {
-            self.rt.report_and_crash(emulator, AsanError::Read(addr, 8));
+        if self.enabled()
+            && AsanGiovese::is_invalid_access_8(emulator, addr)
+            && AsanGiovese::is_invalid_load(emulator, addr, 8)
+        {
+            self.report_access(emulator, AsanError::Read(addr, 8));
         }
     }
 
     pub fn read_n(&mut self, emulator: &Emulator, addr: GuestAddr, size: usize) {
-        if self.enabled() && AsanGiovese::is_invalid_access(emulator, addr, size) {
-            self.rt
-                .report_and_crash(emulator, AsanError::Read(addr, size));
+        if self.enabled()
+            && AsanGiovese::is_invalid_access(emulator, addr, size)
+            && AsanGiovese::is_invalid_load(emulator, addr, size)
+        {
+            self.report_access(emulator, AsanError::Read(addr, size));
         }
     }
 
     pub fn write_1(&mut self, emulator: &Emulator, addr: GuestAddr) {
         if self.enabled() && AsanGiovese::is_invalid_access_1(emulator, addr) {
-            self.rt
-                .report_and_crash(emulator, AsanError::Write(addr, 1));
+            self.report_access(emulator, AsanError::Write(addr, 1));
         }
     }
 
     pub fn write_2(&mut self, emulator: &Emulator, addr: GuestAddr) {
         if self.enabled() && AsanGiovese::is_invalid_access_2(emulator, addr) {
-            self.rt
-                .report_and_crash(emulator, AsanError::Write(addr, 2));
+            self.report_access(emulator, AsanError::Write(addr, 2));
         }
     }
 
     pub fn write_4(&mut self, emulator: &Emulator, addr: GuestAddr) {
         if self.enabled() && AsanGiovese::is_invalid_access_4(emulator, addr) {
-            self.rt
-                .report_and_crash(emulator, AsanError::Write(addr, 4));
+            self.report_access(emulator, AsanError::Write(addr, 4));
         }
     }
 
     pub fn write_8(&mut self, emulator: &Emulator, addr: GuestAddr) {
         if self.enabled() && AsanGiovese::is_invalid_access_8(emulator, addr) {
-            self.rt
-                .report_and_crash(emulator, AsanError::Write(addr, 8));
+            self.report_access(emulator, AsanError::Write(addr, 8));
         }
     }
 
     pub fn write_n(&mut self, emulator: &Emulator, addr: GuestAddr, size: usize) {
         if self.enabled() && AsanGiovese::is_invalid_access(emulator, addr, size) {
-            self.rt
-                .report_and_crash(emulator, AsanError::Write(addr, size));
+            self.report_access(emulator, AsanError::Write(addr, size));
         }
     }
 
+    /// Poisons `addr..addr + size` with the kind `poison`.
+    /// Poisoning bytes already poisoned with another kind gets reported as [`AsanError::PoisonConflict`]
+    /// in strict mode, see [`Self::set_strict_poison`].
     pub fn poison(
         &mut self,
         emulator: &Emulator,
@@ -651,9 +1889,66 @@ impl QemuAsanHelper {
         size: usize,
         poison: PoisonKind,
     ) {
+        if let Some(previous) = self.find_poison_conflict(emulator, addr, size, poison) {
+            let conflict = PoisonConflict {
+                addr,
+                size,
+                previous,
+                kind: poison,
+            };
+            if self.strict_poison {
+                // The guest decides what gets poisoned, so this is an error of the target, not of the fuzzer
+                self.report_error(emulator, 0, AsanError::PoisonConflict(conflict));
+            } else {
+                self.poison_conflicts.push(conflict);
+            }
+        }
         self.rt.poison(emulator, addr, size, poison.into());
     }
 
+    /// The kind of the first 8 bytes block in `addr..addr + size` poisoned with another kind than `kind`, if any
+    fn find_poison_conflict(
+        &self,
+        emulator: &Emulator,
+        addr: GuestAddr,
+        size: usize,
+        kind: PoisonKind,
+    ) -> Option<PoisonKind> {
+        if size == 0 || !self.is_poisoned(emulator, addr, size) {
+            return None;
+        }
+        let end = addr + size as GuestAddr;
+        let mut block = addr & !7;
+        while block < end {
+            if let Some(previous) = self.poison_kind(emulator, block) {
+                if previous != kind && !(previous.is_heap() && kind.is_heap()) {
+                    return Some(previous);
+                }
+            }
+            block += 8;
+        }
+        None
+    }
+
+    /// Makes `addr..addr + size` read-only: loads stay valid, while stores get reported like invalid accesses.
+    /// The shadow tracks 8 bytes blocks, so only the blocks fully inside the range become read-only.
+    /// [`Self::unpoison`] makes them writable again.
+    pub fn mark_read_only(&mut self, emulator: &Emulator, addr: GuestAddr, size: usize) {
+        let start = (addr.wrapping_add(7)) & !7;
+        let end = addr.wrapping_add(size as GuestAddr) & !7;
+        if start < end {
+            self.poison(
+                emulator,
+                start,
+                (end - start) as usize,
+                PoisonKind::ReadOnly,
+            );
+        }
+    }
+
+    /// Unpoisons `addr..addr + size`. If `addr + size` is unaligned, the last 8 bytes block becomes
+    /// partially addressable, e.g. [`PoisonKind::Partial3`], instead of fully valid.
+    /// The block of an unaligned `addr` becomes valid from its start, see [`AsanGiovese::unpoison`].
     #[allow(clippy::unused_self)]
     pub fn unpoison(&mut self, emulator: &Emulator, addr: GuestAddr, size: usize) {
         AsanGiovese::unpoison(emulator, addr, size);
@@ -662,6 +1957,13 @@ impl QemuAsanHelper {
     pub fn reset(&mut self, emulator: &Emulator) {
         self.rt.rollback(emulator, self.detect_leaks);
     }
+
+    /// Forgets the freed chunks only, keeping the allocations still live at the end of the run.
+    /// Neither the shadow memory gets rolled back nor leaks detected,
+    /// as the live chunks are expected to be used by the next runs.
+    pub fn reset_freed_only(&mut self, _emulator: &Emulator) {
+        self.rt.alloc_remove_freed();
+    }
 }
 
 impl Default for QemuAsanHelper {
@@ -707,6 +2009,8 @@ where
     }
 
     fn pre_exec(&mut self, emulator: &Emulator, _input: &S::Input) {
+        self.violations.clear();
+        self.poison_conflicts.clear();
         if self.empty {
             self.rt.snapshot(emulator);
             self.empty = false;
@@ -714,7 +2018,11 @@ where
     }
 
     fn post_exec(&mut self, emulator: &Emulator, _input: &S::Input) {
-        self.reset(emulator);
+        if self.keep_live_chunks {
+            self.reset_freed_only(emulator);
+        } else {
+            self.reset(emulator);
+        }
     }
 }
 
@@ -739,7 +2047,7 @@ where
 pub fn trace_read1_asan<QT, S>(
     hooks: &mut QemuHooks<'_, QT, S>,
     _state: Option<&mut S>,
-    _id: u64,
+    id: u64,
     addr: GuestAddr,
 ) where
     S: UsesInput,
@@ -747,13 +2055,14 @@ pub fn trace_read1_asan<QT, S>(
 {
     let emulator = hooks.emulator().clone();
     let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
+    h.access_pc = id as GuestAddr;
     h.read_1(&emulator, addr);
 }
 
 pub fn trace_read2_asan<QT, S>(
     hooks: &mut QemuHooks<'_, QT, S>,
     _state: Option<&mut S>,
-    _id: u64,
+    id: u64,
     addr: GuestAddr,
 ) where
     S: UsesInput,
@@ -761,13 +2070,14 @@ pub fn trace_read2_asan<QT, S>(
 {
     let emulator = hooks.emulator().clone();
     let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
+    h.access_pc = id as GuestAddr;
     h.read_2(&emulator, addr);
 }
 
 pub fn trace_read4_asan<QT, S>(
     hooks: &mut QemuHooks<'_, QT, S>,
     _state: Option<&mut S>,
-    _id: u64,
+    id: u64,
     addr: GuestAddr,
 ) where
     S: UsesInput,
@@ -775,13 +2085,14 @@ pub fn trace_read4_asan<QT, S>(
 {
     let emulator = hooks.emulator().clone();
     let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
+    h.access_pc = id as GuestAddr;
     h.read_4(&emulator, addr);
 }
 
 pub fn trace_read8_asan<QT, S>(
     hooks: &mut QemuHooks<'_, QT, S>,
     _state: Option<&mut S>,
-    _id: u64,
+    id: u64,
     addr: GuestAddr,
 ) where
     S: UsesInput,
@@ -789,13 +2100,14 @@ pub fn trace_read8_asan<QT, S>(
 {
     let emulator = hooks.emulator().clone();
     let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
+    h.access_pc = id as GuestAddr;
     h.read_8(&emulator, addr);
 }
 
 pub fn trace_read_n_asan<QT, S>(
     hooks: &mut QemuHooks<'_, QT, S>,
     _state: Option<&mut S>,
-    _id: u64,
+    id: u64,
     addr: GuestAddr,
     size: usize,
 ) where
@@ -804,13 +2116,14 @@ pub fn trace_read_n_asan<QT, S>(
 {
     let emulator = hooks.emulator().clone();
     let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
+    h.access_pc = id as GuestAddr;
     h.read_n(&emulator, addr, size);
 }
 
 pub fn trace_write1_asan<QT, S>(
     hooks: &mut QemuHooks<'_, QT, S>,
     _state: Option<&mut S>,
-    _id: u64,
+    id: u64,
     addr: GuestAddr,
 ) where
     S: UsesInput,
@@ -818,13 +2131,14 @@ pub fn trace_write1_asan<QT, S>(
 {
     let emulator = hooks.emulator().clone();
     let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
+    h.access_pc = id as GuestAddr;
     h.write_1(&emulator, addr);
 }
 
 pub fn trace_write2_asan<QT, S>(
     hooks: &mut QemuHooks<'_, QT, S>,
     _state: Option<&mut S>,
-    _id: u64,
+    id: u64,
     addr: GuestAddr,
 ) where
     S: UsesInput,
@@ -832,13 +2146,14 @@ pub fn trace_write2_asan<QT, S>(
 {
     let emulator = hooks.emulator().clone();
     let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
+    h.access_pc = id as GuestAddr;
     h.write_2(&emulator, addr);
 }
 
 pub fn trace_write4_asan<QT, S>(
     hooks: &mut QemuHooks<'_, QT, S>,
     _state: Option<&mut S>,
-    _id: u64,
+    id: u64,
     addr: GuestAddr,
 ) where
     S: UsesInput,
@@ -846,13 +2161,14 @@ pub fn trace_write4_asan<QT, S>(
 {
     let emulator = hooks.emulator().clone();
     let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
+    h.access_pc = id as GuestAddr;
     h.write_4(&emulator, addr);
 }
 
 pub fn trace_write8_asan<QT, S>(
     hooks: &mut QemuHooks<'_, QT, S>,
     _state: Option<&mut S>,
-    _id: u64,
+    id: u64,
     addr: GuestAddr,
 ) where
     S: UsesInput,
@@ -860,13 +2176,14 @@ pub fn trace_write8_asan<QT, S>(
 {
     let emulator = hooks.emulator().clone();
     let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
+    h.access_pc = id as GuestAddr;
     h.write_8(&emulator, addr);
 }
 
 pub fn trace_write_n_asan<QT, S>(
     hooks: &mut QemuHooks<'_, QT, S>,
     _state: Option<&mut S>,
-    _id: u64,
+    id: u64,
     addr: GuestAddr,
     size: usize,
 ) where
@@ -875,7 +2192,18 @@ pub fn trace_write_n_asan<QT, S>(
 {
     let emulator = hooks.emulator().clone();
     let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
-    h.read_n(&emulator, addr, size);
+    h.access_pc = id as GuestAddr;
+    h.write_n(&emulator, addr, size);
+}
+
+/// The [`PoisonKind`] of the shadow value the guest passed to the QASan syscall, zero or sign extended,
+/// or `None` if it is no valid shadow value
+fn guest_poison_kind(arg: u64) -> Option<PoisonKind> {
+    let shadow = match u8::try_from(arg) {
+        Ok(shadow) => shadow as i8,
+        Err(_) => i8::try_from(arg as i64).ok()?,
+    };
+    PoisonKind::try_from(shadow).ok()
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -900,30 +2228,46 @@ where
         let emulator = hooks.emulator().clone();
         let h = hooks.match_helper_mut::<QemuAsanHelper>().unwrap();
         let mut r = 0;
-        match QasanAction::try_from(a0).expect("Invalid QASan action number") {
+        let dry_run = a0 & QASAN_ACTION_DRY_RUN != 0;
+        match QasanAction::try_from(a0 & !QASAN_ACTION_DRY_RUN)
+            .expect("Invalid QASan action number")
+        {
+            QasanAction::CheckLoad if dry_run => {
+                r = u64::from(h.check_load(&emulator, a1 as GuestAddr, a2 as usize));
+            }
             QasanAction::CheckLoad => {
                 h.read_n(&emulator, a1 as GuestAddr, a2 as usize);
             }
+            QasanAction::CheckStore if dry_run => {
+                r = u64::from(h.check_store(&emulator, a1 as GuestAddr, a2 as usize));
+            }
             QasanAction::CheckStore => {
                 h.write_n(&emulator, a1 as GuestAddr, a2 as usize);
             }
-            QasanAction::Poison => {
-                h.poison(
-                    &emulator,
-                    a1 as GuestAddr,
-                    a2 as usize,
-                    PoisonKind::try_from(a3 as i8).unwrap(),
-                );
-            }
+            QasanAction::Poison => match guest_poison_kind(a3) {
+                Some(kind) => h.poison(&emulator, a1 as GuestAddr, a2 as usize, kind),
+                None => r = QASAN_INVALID_ARGUMENT,
+            },
             QasanAction::UserPoison => {
                 h.poison(&emulator, a1 as GuestAddr, a2 as usize, PoisonKind::User);
             }
+            QasanAction::UnPoison if a3 != 0 => {
+                // Only unpoison the blocks poisoned with the given kind
+                match guest_poison_kind(a3) {
+                    Some(kind) => h.unpoison_kind(&emulator, a1 as GuestAddr, a2 as usize, kind),
+                    None => r = QASAN_INVALID_ARGUMENT,
+                }
+            }
             QasanAction::UnPoison => {
                 h.unpoison(&emulator, a1 as GuestAddr, a2 as usize);
             }
             QasanAction::IsPoison => {
+                // Returns the shadow value of custom kinds, to preserve the tag, else 1
                 if h.is_poisoned(&emulator, a1 as GuestAddr, a2 as usize) {
-                    r = 1;
+                    r = match h.first_poison_kind(&emulator, a1 as GuestAddr, a2 as usize) {
+                        Some(PoisonKind::Custom(tag)) => u64::from(tag.shadow_value()),
+                        _ => 1,
+                    };
                 }
             }
             QasanAction::Alloc => {
@@ -941,9 +2285,337 @@ where
             QasanAction::SwapState => {
                 h.set_enabled(!h.enabled());
             }
+            QasanAction::DumpHeap => {
+                r = h.dump_heap(&emulator, a1 as GuestAddr, a2 as usize) as u64;
+            }
+            QasanAction::Realloc => {
+                h.realloc(&emulator, a1 as GuestAddr, a2 as GuestAddr, a3 as GuestAddr);
+            }
+            QasanAction::GetChunkBounds => {
+                // Writes the start and end of the chunk to the two guest pointers at `a2`.
+                // Returns 0 for an allocated chunk, 1 for a freed one, -1 if `a1` is in no chunk,
+                // and `QASAN_INVALID_ARGUMENT` if `a2` is not writable.
+                r = match h.chunk_bounds(a1 as GuestAddr) {
+                    Some(chunk) => {
+                        let mut bounds = guest_ptr_bytes(chunk.start).to_vec();
+                        bounds.extend_from_slice(&guest_ptr_bytes(chunk.end));
+                        let mappings = emulator
+                            .mappings()
+                            .map(|map| (map.start()..map.end(), map.flags()));
+                        if is_writable(mappings, a2 as GuestAddr, bounds.len()) {
+                            unsafe { emulator.write_mem(a2 as GuestAddr, &bounds) };
+                            u64::from(chunk.freed)
+                        } else {
+                            QASAN_INVALID_ARGUMENT
+                        }
+                    }
+                    None => u64::MAX,
+                };
+            }
+            QasanAction::MarkReadOnly => {
+                h.mark_read_only(&emulator, a1 as GuestAddr, a2 as usize);
+            }
         }
         SyscallHookResult::new(Some(r))
     } else {
         SyscallHookResult::new(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    #[cfg(any(cpu_target = "x86_64", cpu_target = "i386", cpu_target = "aarch64"))]
+    use super::walk_frame_chain;
+    use super::{
+        format_report, guest_poison_kind, guest_ptr_bytes, is_writable, register_symbol_resolver,
+        AllocSnapshot, AsanError, AsanGiovese, CallContext, CustomPoisonTag, PoisonConflict,
+        PoisonKind, QasanAction, CUSTOM_POISON_BASE, CUSTOM_POISON_KINDS,
+    };
+    use crate::{emu::MmapPerms, GuestAddr};
+
+    #[test]
+    fn test_unpoison_shadow_partial() {
+        let freed = i8::from(PoisonKind::HeapFreed);
+
+        // An unaligned end leaves the last block partially valid
+        let mut shadow = [freed; 3];
+        AsanGiovese::unpoison_shadow(&mut shadow, 0x1000, 13);
+        assert_eq!(shadow, [0, i8::from(PoisonKind::Partial5), freed]);
+
+        // An unaligned start unpoisons its whole block, the end stays partial
+        let mut shadow = [freed; 2];
+        AsanGiovese::unpoison_shadow(&mut shadow, 0x1003, 10);
+        assert_eq!(shadow, [0, i8::from(PoisonKind::Partial5)]);
+
+        // Within a single block
+        let mut shadow = [freed];
+        AsanGiovese::unpoison_shadow(&mut shadow, 0x1001, 2);
+        assert_eq!(shadow, [i8::from(PoisonKind::Partial3)]);
+
+        // More valid bytes than unpoisoned, or an already valid block, are kept
+        let mut shadow = [i8::from(PoisonKind::Partial6), 0];
+        AsanGiovese::unpoison_shadow(&mut shadow[..1], 0x1000, 2);
+        AsanGiovese::unpoison_shadow(&mut shadow[1..], 0x1008, 2);
+        assert_eq!(shadow, [i8::from(PoisonKind::Partial6), 0]);
+
+        // Aligned ranges do not touch the next block
+        let mut shadow = [freed; 2];
+        AsanGiovese::unpoison_shadow(&mut shadow, 0x1000, 8);
+        assert_eq!(shadow, [0, freed]);
+    }
+
+    #[test]
+    fn test_alloc_snapshot() {
+        let mut rt = AsanGiovese::new(true);
+        rt.alloc_insert(0x1000, 0x1010);
+        rt.alloc_insert(0x2000, 0x2010);
+        rt.alloc_set_free_context(
+            0x2000,
+            CallContext {
+                addresses: vec![0x4000],
+            },
+        );
+        rt.saved_shadow.insert(0x1000, vec![-3; 4]);
+
+        // The snapshot serializes, and becomes the saved tree
+        let snapshot = rt.alloc_snapshot();
+        let serialized = postcard::to_allocvec(&snapshot).unwrap();
+        let snapshot: AllocSnapshot = postcard::from_bytes(&serialized).unwrap();
+        assert_eq!(rt.saved_tree.query(0..GuestAddr::MAX).count(), 2);
+        assert_eq!(snapshot.shadow[&0x1000], [-3; 4]);
+
+        rt.alloc_set_free_context(0x1000, CallContext::default());
+        rt.alloc_insert(0x3000, 0x3010);
+        rt.saved_shadow.clear();
+
+        // Restoring brings back the chunks, their contexts, and the shadow to write back
+        rt.alloc_restore(&snapshot);
+        let chunks = rt.heap_chunks();
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| (chunk.start, chunk.end, chunk.freed))
+                .collect::<Vec<_>>(),
+            [(0x1000, 0x1010, false), (0x2000, 0x2010, true)]
+        );
+        assert_eq!(
+            rt.alloc_tree
+                .lock()
+                .unwrap()
+                .query(0x2000..=0x2000)
+                .next()
+                .unwrap()
+                .value
+                .free_ctx
+                .as_ref()
+                .unwrap()
+                .addresses,
+            [0x4000]
+        );
+        assert_eq!(rt.saved_tree.query(0..GuestAddr::MAX).count(), 2);
+        assert_eq!(rt.saved_shadow, snapshot.shadow);
+    }
+
+    #[test]
+    fn test_alloc_reuse() {
+        let mut rt = AsanGiovese::new(false);
+        rt.alloc_insert(0x1000, 0x1010);
+        assert_eq!(
+            rt.clamped_redzones(0x1000, 0x1010, 0x10),
+            (0xff0..0x1000, 0x1010..0x1020)
+        );
+
+        // Freed, then allocated again at the same address: only the new live chunk is tracked,
+        // and its stale freed shadow gets unpoisoned
+        rt.alloc_set_free_context(0x1000, CallContext::default());
+        assert!(rt.chunk_at(0x1000).unwrap().freed);
+        rt.alloc_insert(0x1000, 0x100c);
+        let chunks = rt.heap_chunks();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!((chunks[0].start, chunks[0].end), (0x1000, 0x100c));
+        assert!(!chunks[0].freed);
+        let mut shadow = [i8::from(PoisonKind::HeapFreed); 2];
+        AsanGiovese::unpoison_shadow(&mut shadow, 0x1000, 0xc);
+        assert_eq!(shadow, [0, i8::from(PoisonKind::Partial4)]);
+
+        // The redzones stop at live neighbours, but may cover freed ones
+        rt.alloc_insert(0x1014, 0x1020);
+        rt.alloc_insert(0xfe0, 0xff8);
+        rt.alloc_set_free_context(0xfe0, CallContext::default());
+        assert_eq!(
+            rt.clamped_redzones(0x1000, 0x100c, 0x10),
+            (0xff0..0x1000, 0x100c..0x1014)
+        );
+        rt.alloc_insert(0xff8, 0x1000);
+        assert_eq!(
+            rt.clamped_redzones(0x1000, 0x100c, 0x10),
+            (0x1000..0x1000, 0x100c..0x1014)
+        );
+    }
+
+    #[test]
+    #[cfg(any(cpu_target = "x86_64", cpu_target = "i386", cpu_target = "aarch64"))]
+    fn test_walk_frame_chain() {
+        let ptr_size = core::mem::size_of::<GuestAddr>() as GuestAddr;
+        let stack = 0x1000..0x1000 + 16 * ptr_size;
+        let slot = |idx: GuestAddr| 0x1000 + idx * ptr_size;
+        // Each frame holds the caller frame pointer, then the return address
+        let memory: HashMap<GuestAddr, GuestAddr> = [
+            (slot(2), slot(6)),
+            (slot(3), 0x4001),
+            (slot(6), slot(10)),
+            (slot(7), 0x4002),
+            // The outermost frame points out of the stack
+            (slot(10), 0x8000_0000),
+            (slot(11), 0x4003),
+        ]
+        .into_iter()
+        .collect();
+        let read = |addr: GuestAddr| {
+            assert!(stack.contains(&addr));
+            memory.get(&addr).copied().unwrap_or(0)
+        };
+
+        let mut addresses = vec![];
+        walk_frame_chain(stack.clone(), slot(2), 8, read, &mut addresses);
+        assert_eq!(addresses, [0x4001, 0x4002, 0x4003]);
+
+        // Bounded by `max_frames`
+        let mut addresses = vec![];
+        walk_frame_chain(stack.clone(), slot(2), 2, read, &mut addresses);
+        assert_eq!(addresses, [0x4001, 0x4002]);
+
+        // Unaligned, out of the stack, or at its very end, nothing gets read
+        for fp in [
+            slot(2) + 1,
+            0x10,
+            slot(15),
+            GuestAddr::MAX & !(ptr_size - 1),
+        ] {
+            let mut addresses = vec![];
+            walk_frame_chain(stack.clone(), fp, 8, read, &mut addresses);
+            assert!(addresses.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_custom_poison_tag() {
+        let last = PoisonKind::custom(CUSTOM_POISON_KINDS - 1).unwrap();
+        assert_eq!(
+            i8::from(last) as u8,
+            CUSTOM_POISON_BASE + CUSTOM_POISON_KINDS - 1
+        );
+        assert_eq!(PoisonKind::try_from(i8::from(last)).unwrap(), last);
+
+        // Out of range tags can not be constructed
+        assert!(PoisonKind::custom(CUSTOM_POISON_KINDS).is_none());
+        assert!(CustomPoisonTag::try_from(CUSTOM_POISON_KINDS).is_err());
+    }
+
+    #[test]
+    fn test_chunk_bounds_write() {
+        let ptr_size = core::mem::size_of::<GuestAddr>();
+        let bytes = guest_ptr_bytes(0x1234);
+        assert_eq!(bytes.len(), ptr_size);
+        if cfg!(feature = "be") {
+            assert_eq!(bytes[ptr_size - 2..], [0x12, 0x34]);
+        } else {
+            assert_eq!(bytes[..2], [0x34, 0x12]);
+        }
+
+        // Both bounds have to fit in a single writable mapping
+        let mappings = || {
+            [
+                (0x1000..0x2000, MmapPerms::Read),
+                (0x2000..0x3000, MmapPerms::ReadWrite),
+            ]
+        };
+        let len = 2 * ptr_size;
+        assert!(is_writable(mappings(), 0x2000, len));
+        assert!(is_writable(mappings(), 0x3000 - len as GuestAddr, len));
+        assert!(!is_writable(mappings(), 0x1000, len));
+        assert!(!is_writable(
+            mappings(),
+            0x3000 - ptr_size as GuestAddr,
+            len
+        ));
+        assert!(!is_writable(mappings(), 0, len));
+        assert!(!is_writable(mappings(), GuestAddr::MAX - 1, len));
+    }
+
+    #[test]
+    fn test_guest_poison_kind() {
+        // The shadow value may come zero or sign extended
+        assert_eq!(guest_poison_kind(0xfd), Some(PoisonKind::HeapFreed));
+        assert_eq!(
+            guest_poison_kind(-3_i64 as u64),
+            Some(PoisonKind::HeapFreed)
+        );
+        assert_eq!(
+            guest_poison_kind(u64::from(CUSTOM_POISON_BASE)),
+            PoisonKind::custom(0)
+        );
+        // Unknown shadow values and out of range arguments get rejected instead of panicking
+        assert_eq!(guest_poison_kind(0x7f), None);
+        assert_eq!(guest_poison_kind(0x1fd), None);
+        assert_eq!(guest_poison_kind(u64::MAX << 8), None);
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        // Every action round trips, serialized as its number
+        let mut actions = 0;
+        while let Ok(action) = QasanAction::try_from(actions) {
+            let serialized = postcard::to_allocvec(&action).unwrap();
+            assert_eq!(serialized, postcard::to_allocvec(&actions).unwrap());
+            let deserialized: QasanAction = postcard::from_bytes(&serialized).unwrap();
+            assert_eq!(u64::from(deserialized), actions);
+            actions += 1;
+        }
+        assert_eq!(actions, u64::from(QasanAction::MarkReadOnly) + 1);
+        let unknown = postcard::to_allocvec(&actions).unwrap();
+        assert!(postcard::from_bytes::<QasanAction>(&unknown).is_err());
+
+        // Every poison kind round trips, serialized as its shadow value
+        for value in i8::MIN..=i8::MAX {
+            let serialized = postcard::to_allocvec(&value).unwrap();
+            match PoisonKind::try_from(value) {
+                Ok(kind) => {
+                    assert_eq!(postcard::to_allocvec(&kind).unwrap(), serialized);
+                    assert_eq!(
+                        postcard::from_bytes::<PoisonKind>(&serialized).unwrap(),
+                        kind
+                    );
+                }
+                Err(_) => assert!(postcard::from_bytes::<PoisonKind>(&serialized).is_err()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_report() {
+        register_symbol_resolver(Box::new(|addr| {
+            (addr == 0x1010).then(|| "target+0x10".to_string())
+        }));
+        let ctx = CallContext {
+            addresses: vec![0x1010, 0x2000],
+        };
+        assert_eq!(
+            format_report(&AsanError::Write(0x4000, 4), &ctx),
+            "ERROR: AddressSanitizer: invalid write of size 4 at 0x4000\n    \
+             #0 0x1010 (target+0x10)\n    #1 0x2000"
+        );
+        let conflict = PoisonConflict {
+            addr: 0x4000,
+            size: 16,
+            previous: PoisonKind::HeapFreed,
+            kind: PoisonKind::User,
+        };
+        assert_eq!(
+            format_report(&AsanError::PoisonConflict(conflict), &CallContext::default()),
+            "ERROR: AddressSanitizer: poisoning 16 bytes at 0x4000 as User, already poisoned as HeapFreed"
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use core::{fmt::Debug, ops::Range};
 
-use libafl::{bolts::tuples::MatchFirstType, inputs::UsesInput};
+use libafl::{bolts::tuples::MatchFirstType, inputs::UsesInput, Error};
 
 use crate::{emu::Emulator, hooks::QemuHooks};
 
@@ -136,6 +136,105 @@ impl QemuInstrumentationFilter {
             QemuInstrumentationFilter::None => true,
         }
     }
+
+    /// Adds the given ranges to the list of this filter, for example the ranges of the
+    /// [`crate::emu::MapInfo`]s of a module, as returned by `Emulator::mappings`.
+    /// A `None` filter has no list, and stays unchanged.
+    pub fn extend<I>(&mut self, ranges: I)
+    where
+        I: IntoIterator<Item = Range<u64>>,
+    {
+        match self {
+            QemuInstrumentationFilter::AllowList(l) | QemuInstrumentationFilter::DenyList(l) => {
+                l.extend(ranges);
+            }
+            QemuInstrumentationFilter::None => {}
+        }
+    }
+
+    /// Composes this filter with `other`.
+    /// Lists of the same kind get joined, while the ranges of a `DenyList` get cut out of an `AllowList`.
+    /// `None` filters do not change the other filter.
+    #[must_use]
+    pub fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (QemuInstrumentationFilter::None, f) | (f, QemuInstrumentationFilter::None) => f,
+            (
+                QemuInstrumentationFilter::AllowList(mut l),
+                QemuInstrumentationFilter::AllowList(o),
+            ) => {
+                l.extend(o);
+                QemuInstrumentationFilter::AllowList(l)
+            }
+            (
+                QemuInstrumentationFilter::DenyList(mut l),
+                QemuInstrumentationFilter::DenyList(o),
+            ) => {
+                l.extend(o);
+                QemuInstrumentationFilter::DenyList(l)
+            }
+            (
+                QemuInstrumentationFilter::AllowList(mut allowed),
+                QemuInstrumentationFilter::DenyList(deny),
+            )
+            | (
+                QemuInstrumentationFilter::DenyList(deny),
+                QemuInstrumentationFilter::AllowList(mut allowed),
+            ) => {
+                for denied in &deny {
+                    allowed = allowed
+                        .into_iter()
+                        .flat_map(|rng| {
+                            [
+                                rng.start..rng.end.min(denied.start),
+                                rng.start.max(denied.end)..rng.end,
+                            ]
+                        })
+                        .filter(|rng| !rng.is_empty())
+                        .collect();
+                }
+                QemuInstrumentationFilter::AllowList(allowed)
+            }
+        }
+    }
+
+    /// Checks the ranges of this filter against the mapped regions of the guest, for example the ranges of
+    /// the [`crate::emu::MapInfo`]s returned by `Emulator::mappings`, before any run.
+    /// Prints a warning for each range not overlapping any mapped region, as it filters nothing.
+    /// Returns an error if this is an `AllowList` none of whose ranges overlaps a mapped region,
+    /// as nothing would get instrumented, e.g., because of a wrong base address.
+    pub fn validate_against<I>(&self, mappings: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = Range<u64>>,
+    {
+        let l = match self {
+            QemuInstrumentationFilter::AllowList(l) | QemuInstrumentationFilter::DenyList(l) => l,
+            QemuInstrumentationFilter::None => return Ok(()),
+        };
+        let mappings: Vec<Range<u64>> = mappings.into_iter().collect();
+
+        let mut any_mapped = false;
+        for rng in l {
+            if mappings
+                .iter()
+                .any(|map| rng.start < map.end && map.start < rng.end)
+            {
+                any_mapped = true;
+            } else {
+                eprintln!(
+                    "Warning: the filter range {:#x}..{:#x} does not overlap any mapped region",
+                    rng.start, rng.end
+                );
+            }
+        }
+
+        if !any_mapped && matches!(self, QemuInstrumentationFilter::AllowList(_)) {
+            return Err(Error::illegal_argument(
+                "No range of the allow list overlaps a mapped region, nothing would get instrumented",
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[must_use]
@@ -73,6 +73,22 @@ pub use snapshot::QemuSnapshotHelper;
 pub mod asan;
 #[cfg(emulation_mode = "usermode")]
 pub use asan::{init_with_asan, QemuAsanHelper};
+#[cfg(emulation_mode = "usermode")]
+pub mod alloc_sizes;
+#[cfg(emulation_mode = "usermode")]
+pub use alloc_sizes::{NewAllocSizeFeedback, QemuAllocSizesObserver};
+#[cfg(emulation_mode = "usermode")]
+pub mod stack_hash;
+#[cfg(emulation_mode = "usermode")]
+pub use stack_hash::{QemuAsanStackHashObserver, StackHashDedupFeedback, StackHashRecorder};
+#[cfg(emulation_mode = "usermode")]
+pub mod rss;
+#[cfg(emulation_mode = "usermode")]
+pub use rss::{MaxRssFeedback, MaxRssObserver};
+#[cfg(emulation_mode = "usermode")]
+pub mod syscall_trace;
+#[cfg(emulation_mode = "usermode")]
+pub use syscall_trace::QemuSyscallTraceHelper;
 
 pub mod blocks;
 
@@ -0,0 +1,248 @@
+use std::{fmt::Debug, fs, marker::PhantomData};
+
+use libafl::{
+    bolts::tuples::Named,
+    events::EventFirer,
+    executors::ExitKind,
+    feedbacks::{Feedback, HasObserverName},
+    inputs::UsesInput,
+    observers::{Observer, ObserversTuple},
+    state::{HasClientPerfMonitor, HasNamedMetadata},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+/// Resets the peak resident set size of this process to its current one.
+/// Needs Linux 4.0 or later, returns `false` if the reset failed.
+fn reset_peak_rss() -> bool {
+    fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+/// The value of a `kB` field of `/proc/self/status`, like `VmHWM:`, in bytes
+fn status_bytes(status: &str, field: &str) -> Option<usize> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix(field))
+        .and_then(|kb| {
+            kb.trim()
+                .trim_end_matches("kB")
+                .trim()
+                .parse::<usize>()
+                .ok()
+        })
+        .map(|kb| kb * 1024)
+}
+
+/// The peak resident set size of this process, in bytes, or the current one if `sampled`.
+/// In usermode, the guest shares the process of QEMU, so this includes the memory of the guest.
+fn rss(sampled: bool) -> Result<usize, Error> {
+    let field = if sampled { "VmRSS:" } else { "VmHWM:" };
+    status_bytes(&fs::read_to_string("/proc/self/status")?, field)
+        .ok_or_else(|| Error::unknown(format!("No {field} in /proc/self/status")))
+}
+
+/// Observes the peak resident set size of the target during a run, read from `/proc/self/status`.
+/// The peak gets reset before each run. If the kernel does not support resetting it, the peak would only
+/// ever grow, so the observer falls back to sampling the resident set size at the end of each run instead.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MaxRssObserver {
+    name: String,
+    max_rss: Option<usize>,
+    /// If the peak can not be reset, see [`MaxRssObserver::sampled`]
+    sampled: bool,
+}
+
+impl MaxRssObserver {
+    /// Creates a new [`MaxRssObserver`]
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            max_rss: None,
+            sampled: false,
+        }
+    }
+
+    /// The peak resident set size of the last run, in bytes
+    #[must_use]
+    pub fn max_rss(&self) -> Option<usize> {
+        self.max_rss
+    }
+
+    /// If the peak could not be reset, so [`MaxRssObserver::max_rss`] is the resident set size at the end of the run
+    #[must_use]
+    pub fn sampled(&self) -> bool {
+        self.sampled
+    }
+}
+
+impl<S> Observer<S> for MaxRssObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.max_rss = None;
+        if !self.sampled && !reset_peak_rss() {
+            eprintln!(
+                "Warning: can not reset the peak RSS, {} samples the RSS at the end of each run instead",
+                self.name
+            );
+            self.sampled = true;
+        }
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &S::Input,
+        _exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        self.max_rss = Some(rss(self.sampled)?);
+        Ok(())
+    }
+}
+
+impl Named for MaxRssObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The highest peak resident set size seen by a [`MaxRssFeedback`], in bytes
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct MaxRssMetadata {
+    pub max_rss: usize,
+}
+
+libafl::impl_serdeany!(MaxRssMetadata);
+
+/// A [`Feedback`] reporting an input as interesting if it set a new high-water mark
+/// of the peak resident set size observed by a [`MaxRssObserver`]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MaxRssFeedback<S> {
+    name: String,
+    observer_name: String,
+    phantom: PhantomData<S>,
+}
+
+impl<S> MaxRssFeedback<S> {
+    /// Creates a new [`MaxRssFeedback`] for the given observer
+    #[must_use]
+    pub fn new(observer: &MaxRssObserver) -> Self {
+        Self {
+            name: "MaxRssFeedback_".to_string() + observer.name(),
+            observer_name: observer.name().to_string(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> Feedback<S> for MaxRssFeedback<S>
+where
+    S: UsesInput + Debug + HasNamedMetadata + HasClientPerfMonitor,
+{
+    fn init_state(&mut self, state: &mut S) -> Result<(), Error> {
+        state.add_named_metadata(MaxRssMetadata::default(), &self.name);
+        Ok(())
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn is_interesting<EM, OT>(
+        &mut self,
+        state: &mut S,
+        _manager: &mut EM,
+        _input: &S::Input,
+        observers: &OT,
+        _exit_kind: &ExitKind,
+    ) -> Result<bool, Error>
+    where
+        EM: EventFirer<State = S>,
+        OT: ObserversTuple<S>,
+    {
+        let observer = observers
+            .match_name::<MaxRssObserver>(&self.observer_name)
+            .expect("A MaxRssFeedback needs a MaxRssObserver");
+        let Some(max_rss) = observer.max_rss() else {
+            return Ok(false);
+        };
+        let seen = state
+            .named_metadata_mut()
+            .get_mut::<MaxRssMetadata>(&self.name)
+            .unwrap();
+
+        if max_rss > seen.max_rss {
+            seen.max_rss = max_rss;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+impl<S> Named for MaxRssFeedback<S> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<S> HasObserverName for MaxRssFeedback<S> {
+    fn observer_name(&self) -> &str {
+        &self.observer_name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libafl::{
+        bolts::rands::StdRand, corpus::InMemoryCorpus, executors::ExitKind, inputs::BytesInput,
+        observers::Observer, state::StdState,
+    };
+
+    use super::{rss, status_bytes, MaxRssObserver};
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    fn test_status_bytes() {
+        let status = "Name:\tqemu\nVmHWM:\t   2048 kB\nVmRSS:\t    512 kB\n";
+        assert_eq!(status_bytes(status, "VmHWM:"), Some(2048 * 1024));
+        assert_eq!(status_bytes(status, "VmRSS:"), Some(512 * 1024));
+        assert_eq!(status_bytes(status, "VmSwap:"), None);
+    }
+
+    #[test]
+    fn test_sampled_rss() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut (),
+            &mut (),
+        )
+        .unwrap();
+        let input = BytesInput::new(vec![0]);
+
+        // Without a resettable peak, the observer reports the current RSS, not the peak since the start
+        let mut observer = MaxRssObserver::new("rss");
+        observer.sampled = true;
+        observer.pre_exec(&mut state, &input).unwrap();
+        assert_eq!(observer.max_rss(), None);
+        observer
+            .post_exec(&mut state, &input, &ExitKind::Ok)
+            .unwrap();
+        assert!(observer.sampled());
+        let sampled = observer.max_rss().unwrap();
+        assert!(sampled > 0);
+        assert!(sampled <= rss(false).unwrap());
+
+        // Whether or not the peak can be reset here, each run reports a size
+        let mut observer = MaxRssObserver::new("rss");
+        observer.pre_exec(&mut state, &input).unwrap();
+        observer
+            .post_exec(&mut state, &input, &ExitKind::Ok)
+            .unwrap();
+        assert!(observer.max_rss().unwrap() > 0);
+    }
+}
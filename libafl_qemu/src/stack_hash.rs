@@ -0,0 +1,187 @@
+use std::sync::{Arc, Mutex};
+
+use libafl::{
+    bolts::{tuples::Named, xxh3_rrmxmx_mixer},
+    executors::ExitKind,
+    feedbacks::NewHashFeedback,
+    inputs::UsesInput,
+    observers::{Observer, ObserverWithHashField},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::GuestAddr;
+
+/// The hash of the given `pc` and return addresses, innermost first.
+/// Bad frees have no `pc`, so a `pc` of `0` is left out and only the call stack gets hashed.
+#[must_use]
+#[allow(clippy::unnecessary_cast)]
+pub fn stack_hash(pc: GuestAddr, addresses: &[GuestAddr]) -> u64 {
+    let mut addresses = addresses.iter();
+    let mut hash = if pc == 0 {
+        match addresses.next() {
+            Some(addr) => xxh3_rrmxmx_mixer(*addr as u64),
+            None => 0,
+        }
+    } else {
+        xxh3_rrmxmx_mixer(pc as u64)
+    };
+    for addr in addresses {
+        hash = xxh3_rrmxmx_mixer(hash ^ *addr as u64);
+    }
+    hash
+}
+
+/// The stack hash of the `ASan` report of the current run, shared by a [`QemuAsanStackHashObserver`]
+/// and the [`crate::QemuAsanHelper`] recording into it, see [`crate::QemuAsanHelperBuilder::stack_hash_recorder`].
+/// Each observer has its own, so that several helpers and observers do not interfere.
+#[derive(Debug, Clone, Default)]
+pub struct StackHashRecorder {
+    hash: Arc<Mutex<Option<u64>>>,
+}
+
+impl StackHashRecorder {
+    /// Records the hash of the given `pc` and return addresses, see [`stack_hash`].
+    /// Called by the [`crate::QemuAsanHelper`] right before reporting an error.
+    pub fn record(&self, pc: GuestAddr, addresses: &[GuestAddr]) {
+        *self.hash.lock().unwrap() = Some(stack_hash(pc, addresses));
+    }
+
+    /// Takes the recorded hash, if any
+    pub fn take(&self) -> Option<u64> {
+        self.hash.lock().unwrap().take()
+    }
+}
+
+/// Observes the hash of the call stack of the `ASan` report that crashed the current run.
+/// The stack includes up to [`crate::QemuAsanHelper::max_frames`] return addresses, set it to dedup by more than the faulting pc.
+/// The helper records the hash into the [`StackHashRecorder`] of the observer, hand it over with
+/// [`crate::QemuAsanHelperBuilder::stack_hash_recorder`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QemuAsanStackHashObserver {
+    name: String,
+    hash: Option<u64>,
+    /// Not serialized, a deserialized observer gets a new recorder no helper records into
+    #[serde(skip)]
+    recorder: StackHashRecorder,
+}
+
+impl QemuAsanStackHashObserver {
+    /// Creates a new [`QemuAsanStackHashObserver`], with its own [`StackHashRecorder`]
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            hash: None,
+            recorder: StackHashRecorder::default(),
+        }
+    }
+
+    /// The [`StackHashRecorder`] of this observer, for the [`crate::QemuAsanHelper`] to record into
+    #[must_use]
+    pub fn recorder(&self) -> StackHashRecorder {
+        self.recorder.clone()
+    }
+}
+
+impl ObserverWithHashField for QemuAsanStackHashObserver {
+    /// The hash of the stack of the last `ASan` report
+    fn hash(&self) -> Option<u64> {
+        self.hash
+    }
+}
+
+impl<S> Observer<S> for QemuAsanStackHashObserver
+where
+    S: UsesInput,
+{
+    fn pre_exec(&mut self, _state: &mut S, _input: &S::Input) -> Result<(), Error> {
+        self.hash = None;
+        self.recorder.take();
+        Ok(())
+    }
+
+    fn post_exec(
+        &mut self,
+        _state: &mut S,
+        _input: &S::Input,
+        exit_kind: &ExitKind,
+    ) -> Result<(), Error> {
+        let hash = self.recorder.take();
+        if exit_kind == &ExitKind::Crash {
+            self.hash = hash;
+        }
+        Ok(())
+    }
+}
+
+impl Named for QemuAsanStackHashObserver {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A [`NewHashFeedback`] on the stack hash of a [`QemuAsanStackHashObserver`].
+/// Used as objective, it only saves the first crash of each `ASan` report stack,
+/// keeping the seen hashes in its (serialized) named metadata, so that they survive restarts.
+pub type StackHashDedupFeedback<S> = NewHashFeedback<QemuAsanStackHashObserver, S>;
+
+#[cfg(test)]
+mod tests {
+    use libafl::{
+        bolts::rands::StdRand,
+        corpus::InMemoryCorpus,
+        executors::ExitKind,
+        inputs::BytesInput,
+        observers::{Observer, ObserverWithHashField},
+        state::StdState,
+    };
+
+    use super::{stack_hash, QemuAsanStackHashObserver};
+
+    type TestState =
+        StdState<BytesInput, InMemoryCorpus<BytesInput>, StdRand, InMemoryCorpus<BytesInput>>;
+
+    #[test]
+    fn test_stack_hash_observers() {
+        let mut state: TestState = StdState::new(
+            StdRand::with_seed(0),
+            InMemoryCorpus::new(),
+            InMemoryCorpus::new(),
+            &mut (),
+            &mut (),
+        )
+        .unwrap();
+        let input = BytesInput::new(vec![0]);
+
+        // Bad frees without a pc hash like their call stack, and differ by it
+        assert_eq!(stack_hash(0, &[0x10, 0x20]), stack_hash(0x10, &[0x20]));
+        assert_ne!(stack_hash(0, &[0x10, 0x20]), stack_hash(0, &[0x30, 0x20]));
+
+        // Each observer only sees the hashes recorded into its own recorder
+        let mut first = QemuAsanStackHashObserver::new("first");
+        let mut second = QemuAsanStackHashObserver::new("second");
+        for observer in [&mut first, &mut second] {
+            observer.pre_exec(&mut state, &input).unwrap();
+        }
+        first.recorder().record(0x1000, &[0x2000]);
+        for observer in [&mut first, &mut second] {
+            observer
+                .post_exec(&mut state, &input, &ExitKind::Crash)
+                .unwrap();
+        }
+        assert_eq!(first.hash(), Some(stack_hash(0x1000, &[0x2000])));
+        assert_eq!(second.hash(), None);
+
+        // A hash recorded in a run that did not crash does not leak into the next run
+        first.pre_exec(&mut state, &input).unwrap();
+        first.recorder().record(0x1000, &[]);
+        first.post_exec(&mut state, &input, &ExitKind::Ok).unwrap();
+        assert_eq!(first.hash(), None);
+        first.pre_exec(&mut state, &input).unwrap();
+        first
+            .post_exec(&mut state, &input, &ExitKind::Crash)
+            .unwrap();
+        assert_eq!(first.hash(), None);
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::VecDeque;
+
+use libafl::inputs::UsesInput;
+
+use crate::{
+    emu::{Emulator, SyscallHookResult},
+    helper::{QemuHelper, QemuHelperTuple},
+    hooks::QemuHooks,
+};
+
+/// The default number of syscalls a [`QemuSyscallTraceHelper`] keeps per run
+pub const DEFAULT_SYSCALL_TRACE_SIZE: usize = 4096;
+
+/// A syscall issued by the target, as seen before its execution
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SyscallTraceEntry {
+    /// The syscall number
+    pub sys_num: i32,
+    /// The arguments `a0` to `a7`, as passed by the target
+    pub args: [u64; 8],
+}
+
+/// Traces the syscalls of the target in a ring buffer, for each run.
+/// Once the buffer is full, the oldest syscalls get dropped.
+/// The syscalls still get executed, the helper only looks at them.
+#[derive(Debug)]
+pub struct QemuSyscallTraceHelper {
+    trace: VecDeque<SyscallTraceEntry>,
+    capacity: usize,
+}
+
+impl QemuSyscallTraceHelper {
+    /// Creates a new [`QemuSyscallTraceHelper`], keeping the last [`DEFAULT_SYSCALL_TRACE_SIZE`] syscalls
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            trace: VecDeque::new(),
+            capacity: DEFAULT_SYSCALL_TRACE_SIZE,
+        }
+    }
+
+    /// Sets the number of syscalls kept per run. A capacity of `0` disables the tracing.
+    #[must_use]
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self.trace.truncate(capacity);
+        self
+    }
+
+    /// The number of syscalls kept per run
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The syscalls traced in the current run so far, oldest first
+    #[must_use]
+    pub fn trace(&self) -> &VecDeque<SyscallTraceEntry> {
+        &self.trace
+    }
+
+    /// Takes the syscalls traced in the last run, oldest first, leaving the trace empty
+    pub fn take_trace(&mut self) -> Vec<SyscallTraceEntry> {
+        self.trace.drain(..).collect()
+    }
+
+    /// Appends a syscall to the trace, dropping the oldest one if the trace is full
+    pub fn record(&mut self, entry: SyscallTraceEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.trace.len() >= self.capacity {
+            self.trace.pop_front();
+        }
+        self.trace.push_back(entry);
+    }
+}
+
+impl Default for QemuSyscallTraceHelper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> QemuHelper<S> for QemuSyscallTraceHelper
+where
+    S: UsesInput,
+{
+    const HOOKS_DO_SIDE_EFFECTS: bool = false;
+
+    fn init_hooks<QT>(&self, hooks: &QemuHooks<'_, QT, S>)
+    where
+        QT: QemuHelperTuple<S>,
+    {
+        hooks.syscalls(trace_syscall::<QT, S>);
+    }
+
+    fn pre_exec(&mut self, _emulator: &Emulator, _input: &S::Input) {
+        self.trace.clear();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn trace_syscall<QT, S>(
+    hooks: &mut QemuHooks<'_, QT, S>,
+    _state: Option<&mut S>,
+    sys_num: i32,
+    a0: u64,
+    a1: u64,
+    a2: u64,
+    a3: u64,
+    a4: u64,
+    a5: u64,
+    a6: u64,
+    a7: u64,
+) -> SyscallHookResult
+where
+    S: UsesInput,
+    QT: QemuHelperTuple<S>,
+{
+    if let Some(h) = hooks.match_helper_mut::<QemuSyscallTraceHelper>() {
+        h.record(SyscallTraceEntry {
+            sys_num,
+            args: [a0, a1, a2, a3, a4, a5, a6, a7],
+        });
+    }
+    SyscallHookResult::new(None)
+}
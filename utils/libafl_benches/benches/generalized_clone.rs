@@ -0,0 +1,24 @@
+//! Compare cloning a generalized 1 MB input to cloning its bytes
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use libafl::inputs::GeneralizedInputMetadata;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    // A 1 MB input with a gap every 4 KB, as left behind by the generalization
+    let options: Vec<Option<u8>> = (0..1 << 20)
+        .map(|i| if i % 4096 == 0 { None } else { Some(0x41) })
+        .collect();
+    let bytes: Vec<u8> = options.iter().flatten().copied().collect();
+    let meta = GeneralizedInputMetadata::generalized_from_options(&options);
+
+    // The byte runs are shared, so this only copies the items, not the 1 MB
+    c.bench_function("generalized_clone", |b| {
+        b.iter(|| black_box(&meta).clone());
+    });
+    c.bench_function("bytes_clone", |b| {
+        b.iter(|| black_box(&bytes).clone());
+    });
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);